@@ -1,9 +1,10 @@
 use std::{
     fmt,
     io::Read,
+    collections::BTreeSet,
     sync::{Arc, Mutex, PoisonError},
 };
-use glium::{SwapBuffersError, glutin::Event};
+use glium::{SwapBuffersError, glutin::{Event, KeyboardInput}};
 use glium::glutin::dpi::{LogicalPosition, LogicalSize};
 use webrender::api::{HitTestFlags, DevicePixel};
 use webrender::PipelineInfo;
@@ -14,17 +15,19 @@ use log::LevelFilter;
 use {
     images::ImageType,
     errors::{FontError, ClipboardError},
+    font::TextRenderingOptions,
     window::{Window, WindowCreateOptions, WindowCreateError, WindowId},
-    css_parser::{FontId, PixelValue},
+    css_parser::{FontId, FontWeight, FontStyle, PixelValue},
     text_cache::TextId,
-    dom::UpdateScreen,
+    dom::{UpdateScreen, On, Callback},
     window::FakeWindow,
     css::{Css, FakeCss},
-    resources::AppResources,
+    resources::{AppResources, DEFAULT_FONT_SIZE_QUANTIZATION},
     app_state::AppState,
     traits::Layout,
     ui_state::UiState,
     ui_description::UiDescription,
+    synthetic_event::{SyntheticEvent, DispatchedEvents, dispatch_synthetic_event},
 };
 
 /// Graphical application that maintains some kind of application state
@@ -104,6 +107,24 @@ pub struct AppConfig {
     /// file (if logging is enabled).
     #[cfg(feature = "logging")]
     pub enable_visual_panic_hook: bool,
+    /// Maximum number of bytes of image data that may be uploaded to the GPU at
+    /// the same time. If this budget is exceeded, the least-recently-displayed images
+    /// are deleted from the GPU (and re-uploaded if they are displayed again).
+    ///
+    /// Default is `None`, meaning that there is no limit
+    pub max_texture_cache_bytes: Option<usize>,
+    /// Gamma and contrast used for rendering text, to tune the weight of the
+    /// text to the background colors of the application. Only has an effect on
+    /// Windows, webrender doesn't support these options on other platforms.
+    ///
+    /// Default is `None`, meaning that the defaults of webrender are used
+    pub text_rendering: Option<TextRenderingOptions>,
+    /// Font sizes are rounded to the nearest multiple of this size (in pixels) before
+    /// the font is instantiated. Otherwise, animating the `font-size` would create a new
+    /// font instance for every slightly different size.
+    ///
+    /// Default is `Some(0.25)`, `None` uses the exact font sizes
+    pub font_size_quantization: Option<f32>,
 }
 
 impl Default for AppConfig {
@@ -115,6 +136,9 @@ impl Default for AppConfig {
             log_file_path: None,
             #[cfg(feature = "logging")]
             enable_visual_panic_hook: true,
+            max_texture_cache_bytes: None,
+            text_rendering: None,
+            font_size_quantization: Some(DEFAULT_FONT_SIZE_QUANTIZATION),
         }
     }
 }
@@ -134,9 +158,14 @@ impl<'a, T: Layout> App<'a, T> {
             }
         }
 
+        let mut app_state = AppState::new(initial_data);
+        app_state.resources.texture_cache.max_bytes = config.max_texture_cache_bytes;
+        app_state.resources.text_rendering = config.text_rendering;
+        app_state.resources.font_size_quantization = config.font_size_quantization;
+
         Self {
             windows: Vec::new(),
-            app_state: AppState::new(initial_data),
+            app_state: app_state,
         }
     }
 
@@ -148,11 +177,48 @@ impl<'a, T: Layout> App<'a, T> {
             state: window.state.clone(),
             css: FakeCss::default(),
             read_only_window: window.display.clone(),
+            frame_stats: window.frame_stats,
+            dirty_rect: None,
         });
         self.windows.push(window);
         Ok(())
     }
 
+    /// Injects a synthetic input event (a click, a key press) into the window with
+    /// the given ID and runs one dispatch cycle, i.e. hit-tests the nodes and invokes
+    /// their callbacks. Useful for testing interactive behaviour without real user input.
+    ///
+    /// Returns which callbacks were invoked and if any of them requested a redraw.
+    ///
+    /// The nodes are hit-tested at their bounds in the last solved layout of the window,
+    /// nodes that haven't been laid out yet can't be hit.
+    pub fn inject_event(&mut self, window_id: WindowId, event: SyntheticEvent) -> DispatchedEvents {
+        use webrender::api::LayoutRect;
+        use window::ReadOnlyWindow;
+
+        let window = &self.windows[window_id.id];
+        let read_only_window = ReadOnlyWindow { inner: window.display.clone() };
+        let ui_state = UiState::from_app_state(&self.app_state, window_id, read_only_window);
+
+        let hit_test_rects: Vec<(u64, LayoutRect)> = {
+            let arena = ui_state.dom.arena.borrow();
+            arena.linear_iter().filter_map(|node_id| {
+                let tag = arena[node_id].data.tag?;
+                if !ui_state.node_ids_to_callbacks_list.contains_key(&tag) {
+                    return None;
+                }
+                window.solver.query_bounds_of_rect(node_id).map(|bounds| (tag, bounds))
+            }).collect()
+        };
+
+        let focus = self.app_state.focus.clone();
+        let app_state = &mut self.app_state;
+
+        dispatch_synthetic_event(&event, window_id, &hit_test_rects, &focus, &ui_state, |Callback(callback_func), window_event| {
+            (callback_func)(app_state, window_event)
+        })
+    }
+
     /// Start the rendering loop for the currently open windows
     /// This is the "main app loop", "main game loop" or whatever you want to call it.
     /// Usually this is the last function you call in your `main()` function, since exiting
@@ -184,6 +250,7 @@ impl<'a, T: Layout> App<'a, T> {
 
     fn run_inner(&mut self) -> Result<(), RuntimeError<T>> {
         use std::{thread, time::{Duration, Instant}};
+        use glium::glutin::WindowEvent;
         use window::ReadOnlyWindow;
 
         let mut ui_state_cache = Self::initialize_ui_state(&self.windows, &self.app_state);
@@ -195,6 +262,15 @@ impl<'a, T: Layout> App<'a, T> {
             let time_start = Instant::now();
             let mut closed_windows = Vec::<usize>::new();
 
+            // Focus changes (i.e. from `AppState::focus_node`) from the last frame
+            let focus_events = self.app_state.focus.take_pending_events();
+
+            // Images that finished decoding on a background thread (see `AppState::add_image_path`)
+            let images_finished_decoding = self.app_state.resources.update_decoding_images();
+
+            // The caret of the focused node blinks, only redraw when it toggles
+            let caret_toggled = self.app_state.focus.tick_caret(time_start) == UpdateScreen::Redraw;
+
             'window_loop: for (idx, ref mut window) in self.windows.iter_mut().enumerate() {
 
                 let window_id = WindowId { id: idx };
@@ -203,6 +279,11 @@ impl<'a, T: Layout> App<'a, T> {
                 let mut events = Vec::new();
                 window.events_loop.poll_events(|e| events.push(e));
 
+                // Enter / Space invokes the `On::MouseUp` callback of the focused node
+                let mut activation_events = Vec::new();
+                // `On::KeyDown` / `On::KeyUp` events of the focused node, with the key
+                let mut key_events = Vec::new();
+
                 for event in &events {
                     if preprocess_event(event, &mut frame_event_info) == WindowCloseEvent::AboutToClose {
                         closed_windows.push(idx);
@@ -211,6 +292,48 @@ impl<'a, T: Layout> App<'a, T> {
                     window.state.update_mouse_cursor_position(event);
                     window.state.update_keyboard_modifiers(event);
                     window.state.update_keyboard_pressed_chars(event);
+                    if let Event::WindowEvent { event: WindowEvent::KeyboardInput { input, .. }, .. } = event {
+                        // Global shortcuts take precedence over the focused node
+                        if let Some(update_screen) = self.app_state.dispatch_shortcut(input, window_id) {
+                            if update_screen == UpdateScreen::Redraw {
+                                frame_event_info.should_redraw_window = true;
+                            }
+                            continue;
+                        }
+
+                        if let Some(key_event) = self.app_state.focus.keyboard_event(input) {
+                            key_events.push((key_event, *input));
+                        }
+
+                        activation_events.extend(self.app_state.focus.keyboard_activation_event(input));
+
+                        // Tab / Shift+Tab move the focus, inside of the topmost modal (if any)
+                        if let Some(direction) = self.app_state.focus.tab_navigation(input) {
+                            let tab_order = ::focus::get_tab_order(&ui_description_cache[idx]);
+                            self.app_state.focus.focus_in_tab_order(&tab_order, direction);
+                        }
+
+                        // Ctrl+A / Ctrl+C select / copy the text of the focused node
+                        if let Some(shortcut) = self.app_state.focus.selection_shortcut(input) {
+                            let focused_text = self.app_state.focus.focused_tag
+                                .and_then(|tag| ::focus::find_selectable_text(&ui_description_cache[idx], tag));
+                            if let Some(text) = focused_text {
+                                match self.app_state.focus.handle_selection_shortcut(shortcut, &text, &mut self.app_state.resources) {
+                                    Ok(UpdateScreen::Redraw) => frame_event_info.should_redraw_window = true,
+                                    Ok(UpdateScreen::DontRedraw) => { },
+                                    Err(e) => error!("Error copying the selected text to the clipboard: {:?}", e),
+                                }
+                            }
+                        }
+                    }
+                    if let Event::WindowEvent { event: WindowEvent::CursorLeft { .. }, .. } = event {
+                        if window.tooltip.hide() == UpdateScreen::Redraw {
+                            frame_event_info.should_redraw_window = true;
+                        }
+                        if window.css.set_hovered_tags(BTreeSet::new()) {
+                            frame_event_info.should_redraw_window = true;
+                        }
+                    }
                 }
 
                 if frame_event_info.should_hittest {
@@ -221,10 +344,48 @@ impl<'a, T: Layout> App<'a, T> {
                             window_id,
                             &mut frame_event_info,
                             &ui_state_cache,
+                            &ui_description_cache[idx],
                             &mut self.app_state);
                     }
                 }
 
+                if !focus_events.is_empty() {
+                    dispatch_node_events(&focus_events, window_id, &ui_state_cache, &mut self.app_state, None);
+                    // The focused node may be styled differently, so always redraw. The layout
+                    // is only re-solved if a `:focus` rule affects the layout.
+                    window.css.set_focused_tag(self.app_state.focus.focused_tag);
+                    frame_event_info.should_redraw_window = true;
+                }
+
+                if images_finished_decoding || caret_toggled {
+                    frame_event_info.should_redraw_window = true;
+                }
+
+                // Show the tooltip once the mouse hovered over a node for long enough
+                if window.tooltip.tick(time_start) == UpdateScreen::Redraw {
+                    frame_event_info.should_redraw_window = true;
+                }
+
+                // The key events are dispatched before the node is activated by the same key
+                let mut node_events_redraw = false;
+                for (key_event, input) in &key_events {
+                    if dispatch_node_events(&[*key_event], window_id, &ui_state_cache, &mut self.app_state, Some(*input)) == UpdateScreen::Redraw {
+                        node_events_redraw = true;
+                    }
+                }
+
+                if !activation_events.is_empty() &&
+                   dispatch_node_events(&activation_events, window_id, &ui_state_cache, &mut self.app_state, None) == UpdateScreen::Redraw {
+                    node_events_redraw = true;
+                }
+
+                if node_events_redraw {
+                    frame_event_info.should_redraw_window = true;
+                    // Same as in `do_hit_test_and_call_callbacks`: copy the fake CSS changes to the real CSS
+                    window.css.set_dynamic_css_overrides(self.app_state.windows[idx].css.dynamic_css_overrides.clone());
+                    self.app_state.windows[idx].css.clear();
+                }
+
                 if frame_event_info.should_swap_window || frame_event_info.is_resize_event {
                     window.display.swap_buffers()?;
                     if let Some(i) = force_redraw_cache.get_mut(idx) {
@@ -260,12 +421,25 @@ impl<'a, T: Layout> App<'a, T> {
                         &self.app_state, window_id, read_only_window
                     );
 
-                    // Style the DOM
+                    // Switch the theme if a callback set one, before the new DOM is styled with it
+                    if let Some(theme) = self.app_state.windows[idx].css.theme.take() {
+                        window.css.switch_theme(theme, &ui_description_cache[idx]);
+                    }
+
+                    // Style the DOM, `env(safe-area-inset-*)` resolves to the insets of the window
+                    window.css.set_safe_area_insets(window.state.size.safe_area_insets);
                     ui_description_cache[idx] = UiDescription::from_ui_state(&ui_state_cache[idx], &mut window.css);
                     // send webrender the size and buffer of the display
                     Self::update_display(&window);
                     // render the window (webrender will send an Awakened event when the frame is done)
-                    render(window, &WindowId { id: idx }, &ui_description_cache[idx], &mut self.app_state.resources, true);
+                    let has_running_animations = render(window, &WindowId { id: idx }, &ui_description_cache[idx], &mut self.app_state.resources, self.app_state.focus.is_caret_visible(), true);
+                    self.app_state.windows[idx].frame_stats = window.frame_stats;
+                    // Only reported to the application, the whole window was rendered
+                    self.app_state.windows[idx].dirty_rect = window.solver.dirty_rect;
+                    if has_running_animations {
+                        // keep redrawing until all CSS transitions are finished and no spinner is rotating
+                        force_redraw_cache[idx] = 2;
+                    }
                 }
             }
 
@@ -362,6 +536,16 @@ impl<'a, T: Layout> App<'a, T> {
         self.app_state.add_font(id, data)
     }
 
+    /// Registers the already added font `font_id` as a face of the font `family`,
+    /// see [`AppState::add_font_face`]
+    ///
+    /// [`AppState::add_font_face`]: ../app_state/struct.AppState.html#method.add_font_face
+    pub fn add_font_face<S: Into<String>, F: Into<String>>(&mut self, family: S, font_id: F, weight: FontWeight, style: FontStyle)
+        -> Option<()>
+    {
+        self.app_state.add_font_face(family, font_id, weight, style)
+    }
+
     /// Checks if a font is currently registered and ready-to-use
     pub fn has_font<S: Into<String>>(&mut self, id: S)
         -> bool
@@ -562,6 +746,7 @@ fn do_hit_test_and_call_callbacks<T: Layout>(
     window_id: WindowId,
     info: &mut FrameEventInfo,
     ui_state_cache: &[UiState<T>],
+    ui_description: &UiDescription<T>,
     app_state: &mut AppState<T>)
 {
     use dom::UpdateScreen;
@@ -569,6 +754,10 @@ fn do_hit_test_and_call_callbacks<T: Layout>(
     use window::WindowEvent;
     use dom::Callback;
     use window_state::{KeyboardState, MouseState};
+    use glium::glutin::WindowEvent as GlutinWindowEvent;
+    use std::time::Instant;
+    use tooltip;
+    use scroll;
 
     let (cursor_x, cursor_y) = window.state.mouse_state.cursor_pos
         .and_then(|pos| {
@@ -586,11 +775,90 @@ fn do_hit_test_and_call_callbacks<T: Layout>(
 
     let mut should_update_screen = UpdateScreen::DontRedraw;
 
-    let callbacks_filter_list = window.state.determine_callbacks(event);
+    // Update the tooltip: the topmost node under the cursor that has a title is hovered
+    if let Event::WindowEvent { event: GlutinWindowEvent::CursorMoved { position, .. }, .. } = event {
+        let dom = &ui_state_cache[window_id.id].dom;
+        let hovered = hit_test_results.items.iter().filter_map(|item| {
+            let tag = item.tag.0;
+            tooltip::find_title(dom, tag).map(|title| (tag, title))
+        }).next();
+        if window.tooltip.on_mouse_move(hovered, (position.x as f32, position.y as f32), Instant::now()) == UpdateScreen::Redraw {
+            info.should_redraw_window = true;
+        }
+
+        // Restyle the nodes that the mouse entered or left, for the `:hover` rules
+        let hovered_tags = hit_test_results.items.iter().map(|item| item.tag.0).collect::<BTreeSet<u64>>();
+        if window.css.set_hovered_tags(hovered_tags) {
+            info.should_redraw_window = true;
+        }
+    }
+
+    // Drag the resize grips of `resize: both / horizontal / vertical` boxes
+    if let Event::WindowEvent { event: ref window_event, .. } = event {
+        use glium::glutin::{ElementState, MouseButton};
+        use css::RelayoutCause;
+        use webrender::api::LayoutPoint;
+
+        let cursor = window.state.mouse_state.cursor_pos
+            .map(|pos| LayoutPoint::new(pos.x as f32, pos.y as f32))
+            .unwrap_or(LayoutPoint::zero());
+
+        match window_event {
+            GlutinWindowEvent::MouseInput { state: ElementState::Pressed, button: MouseButton::Left, .. } => {
+                window.resize_states.begin_drag(&window.solver.solved_layout.resize_grips, cursor);
+
+                // Clicking a focusable node focuses it, clicking anywhere else blurs the focused
+                // node. The focus events are dispatched at the beginning of the next frame.
+                let hit_tags = hit_test_results.items.iter().map(|item| item.tag.0).collect::<Vec<u64>>();
+                let clicked = ::focus::find_focusable_tag(&ui_state_cache[window_id.id].dom, &hit_tags);
+                if app_state.focus.focus_clicked(clicked) == UpdateScreen::Redraw {
+                    info.should_redraw_window = true;
+                }
+            },
+            GlutinWindowEvent::MouseInput { state: ElementState::Released, button: MouseButton::Left, .. } => {
+                window.resize_states.end_drag();
+            },
+            GlutinWindowEvent::CursorMoved { .. } => {
+                if window.resize_states.drag_to(cursor) {
+                    window.css.mark_relayout(RelayoutCause::Resize);
+                    info.should_redraw_window = true;
+                }
+            },
+            _ => { },
+        }
+    }
+
+    let mut callbacks_filter_list = window.state.determine_callbacks(event);
+
+    // The second click on the same node within the double click interval is a double click
+    if callbacks_filter_list.contains(&On::LeftMouseUp) {
+        let clicked = hit_test_results.items.first().map(|item| item.tag.0);
+        if window.double_click.on_click(clicked, (cursor_x, cursor_y), Instant::now()) {
+            callbacks_filter_list.push(On::DoubleClick);
+        }
+    }
+
     // TODO: this should be refactored - currently very stateful and error-prone!
     app_state.windows[window_id.id].set_keyboard_state(&window.state.keyboard_state);
     app_state.windows[window_id.id].set_mouse_state(&window.state.mouse_state);
 
+    // Scroll events are routed to the scroll containers that contain the topmost node under the
+    // cursor instead of all hit nodes: the innermost container is scrolled until it reaches its
+    // boundary, then the rest of the scroll delta is passed on to the next outer container
+    // (unless the container has `overscroll-behavior: contain`)
+    let scroll_chain = if callbacks_filter_list.contains(&On::Scroll) {
+        hit_test_results.items.first()
+            .map(|item| scroll::find_scroll_chain(ui_description, item.tag.0))
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
+    let wheel_delta = match event {
+        Event::WindowEvent { event: GlutinWindowEvent::MouseWheel { delta, .. }, .. } => Some(*delta),
+        _ => None,
+    };
+
     // NOTE: for some reason hit_test_results is empty...
     // ... but only when the mouse is relased - possible timing issue?
     for (item, callback_list) in hit_test_results.items.iter().filter_map(|item|
@@ -604,10 +872,46 @@ fn do_hit_test_and_call_callbacks<T: Layout>(
             number_of_previous_siblings: None,
             cursor_relative_to_item: (item.point_in_viewport.x, item.point_in_viewport.y),
             cursor_in_viewport: (item.point_in_viewport.x, item.point_in_viewport.y),
+            keyboard_input: None,
+            scroll_delta: wheel_delta,
+        };
+
+        // Invoke callback if necessary (scroll events that are routed
+        // to the scroll containers are dispatched below)
+        for callback_id in callbacks_filter_list.iter()
+            .filter(|on| **on != On::Scroll || scroll_chain.is_empty())
+            .filter_map(|on| callback_list.get(on))
+        {
+            let Callback(callback_func) = ui_state_cache[window_id.id].callback_list[callback_id];
+            if (callback_func)(app_state, window_event) == UpdateScreen::Redraw {
+                should_update_screen = UpdateScreen::Redraw;
+            }
+        }
+    }
+
+    if !scroll_chain.is_empty() {
+        let mouse_state = window.state.mouse_state;
+        let scroll_delta = (mouse_state.scroll_x as f32, mouse_state.scroll_y as f32);
+        let window_event = WindowEvent {
+            window: window_id.id,
+            number_of_previous_siblings: None,
+            cursor_relative_to_item: (cursor_x, cursor_y),
+            cursor_in_viewport: (cursor_x, cursor_y),
+            keyboard_input: None,
+            scroll_delta: wheel_delta,
         };
 
-        // Invoke callback if necessary
-        for callback_id in callbacks_filter_list.iter().filter_map(|on| callback_list.get(on)) {
+        for (tag, (scrolled_x, scrolled_y)) in window.scroll_states.scroll(&scroll_chain, scroll_delta) {
+            let callback_id = match ui_state_cache[window_id.id].node_ids_to_callbacks_list.get(&tag).and_then(|ids| ids.get(&On::Scroll)) {
+                Some(id) => id,
+                None => continue,
+            };
+            // The callback only sees the part of the scroll delta that scrolled its container
+            let mut container_mouse_state = mouse_state;
+            container_mouse_state.scroll_x = scrolled_x as f64;
+            container_mouse_state.scroll_y = scrolled_y as f64;
+            app_state.windows[window_id.id].set_mouse_state(&container_mouse_state);
+
             let Callback(callback_func) = ui_state_cache[window_id.id].callback_list[callback_id];
             if (callback_func)(app_state, window_event) == UpdateScreen::Redraw {
                 should_update_screen = UpdateScreen::Redraw;
@@ -623,25 +927,81 @@ fn do_hit_test_and_call_callbacks<T: Layout>(
         // TODO: THIS IS PROBABLY THE WRONG PLACE TO DO THIS!!!
         // Copy the current fake CSS changes to the real CSS, then clear the fake CSS again
         // TODO: .clone() and .clear() can be one operation
-        window.css.dynamic_css_overrides = app_state.windows[window_id.id].css.dynamic_css_overrides.clone();
+        window.css.set_dynamic_css_overrides(app_state.windows[window_id.id].css.dynamic_css_overrides.clone());
         // clear the dynamic CSS overrides
         app_state.windows[window_id.id].css.clear();
     }
 }
 
+/// Calls the callbacks of events that target a specific node (identified by its tag)
+/// instead of being determined by hit-testing, i.e. `On::FocusReceived` / `On::FocusLost`,
+/// `On::KeyDown` / `On::KeyUp` of the focused node (with the `keyboard_input`)
+/// or the `On::MouseUp` callback of a node that was activated via the keyboard.
+///
+/// Returns `UpdateScreen::Redraw` if any of the callbacks requested a redraw
+fn dispatch_node_events<T: Layout>(
+    node_events: &[(u64, On)],
+    window_id: WindowId,
+    ui_state_cache: &[UiState<T>],
+    app_state: &mut AppState<T>,
+    keyboard_input: Option<KeyboardInput>)
+-> UpdateScreen
+{
+    use window::WindowEvent;
+
+    let window_event = WindowEvent {
+        window: window_id.id,
+        number_of_previous_siblings: None,
+        cursor_relative_to_item: (0.0, 0.0),
+        cursor_in_viewport: (0.0, 0.0),
+        keyboard_input: keyboard_input,
+        scroll_delta: None,
+    };
+
+    let mut should_update_screen = UpdateScreen::DontRedraw;
+
+    for Callback(callback_func) in get_node_event_callbacks(node_events, &ui_state_cache[window_id.id]) {
+        if (callback_func)(app_state, window_event) == UpdateScreen::Redraw {
+            should_update_screen = UpdateScreen::Redraw;
+        }
+    }
+
+    should_update_screen
+}
+
+/// Looks up the callbacks for the `(tag, event)` pairs, nodes that don't
+/// have a callback for the event are skipped
+fn get_node_event_callbacks<T: Layout>(node_events: &[(u64, On)], ui_state: &UiState<T>) -> Vec<Callback<T>> {
+    node_events.iter().filter_map(|(tag, on)| {
+        ui_state.node_ids_to_callbacks_list.get(tag)
+            .and_then(|callback_ids| callback_ids.get(on))
+            .map(|callback_id| ui_state.callback_list[callback_id].clone())
+    }).collect()
+}
+
 fn render<T: Layout>(
     window: &mut Window<T>,
     _window_id: &WindowId,
     ui_description: &UiDescription<T>,
     app_resources: &mut AppResources,
+    is_caret_visible: bool,
     has_window_size_changed: bool)
+-> bool
 {
     use webrender::api::*;
-    use display_list::DisplayList;
+    use display_list::{DisplayList, push_tooltip};
     use euclid::TypedSize2D;
-    use std::u32;
-
-    let display_list = DisplayList::new_from_ui_description(ui_description);
+    use std::{u32, time::Instant};
+
+    let now = Instant::now();
+    let mut display_list = DisplayList::new_from_ui_description(ui_description);
+    let has_running_transitions = display_list.apply_transitions(&mut window.transitions, now);
+    let has_running_spinners = display_list.apply_spinner_animation(&window.spinner_clock, now);
+    display_list.apply_resized_sizes(&window.resize_states);
+    display_list.set_caret_visibility(is_caret_visible);
+    display_list.set_render_cache(&window.render_cache);
+    display_list.set_scroll_states(&window.scroll_states);
+    let clear_color = display_list.get_clear_color();
     let builder = display_list.into_display_list_builder(
         window.internal.pipeline_id,
         window.internal.epoch,
@@ -652,11 +1012,17 @@ fn render<T: Layout>(
         has_window_size_changed,
         &window.state.size);
 
-    if let Some(new_builder) = builder {
+    if let Some(mut new_builder) = builder {
+        let LogicalSize { width, height } = window.state.size.dimensions;
+        if let Some(tooltip) = window.tooltip.overlay(TypedSize2D::new(width as f32, height as f32)) {
+            push_tooltip(&mut new_builder, &tooltip, app_resources, &window.internal.api);
+        }
         // only finalize the list if we actually need to. Otherwise just redraw the last display list
         window.internal.last_display_list_builder = new_builder.finalize().2;
     }
 
+    window.frame_stats.record_frame(now.elapsed());
+
     let mut txn = Transaction::new();
 
     let LogicalSize { width, height } = window.state.size.dimensions;
@@ -666,7 +1032,7 @@ fn render<T: Layout>(
 
     txn.set_display_list(
         window.internal.epoch,
-        None,
+        clear_color,
         layout_size,
         (window.internal.pipeline_id, layout_size, window.internal.last_display_list_builder.clone()),
         true,
@@ -686,6 +1052,54 @@ fn render<T: Layout>(
     window.internal.api.send_transaction(window.internal.document_id, txn);
     window.renderer.as_mut().unwrap().update();
     render_inner(window, framebuffer_size);
+    update_render_cache(window, framebuffer_size);
+
+    has_running_transitions || has_running_spinners
+}
+
+/// Captures the cached subtrees (`Dom::with_cached_rendering`) that were painted node by node
+/// from the rendered frame and deletes the images of the subtrees that aren't in the DOM anymore
+fn update_render_cache<T: Layout>(window: &mut Window<T>, framebuffer_size: TypedSize2D<u32, DevicePixel>) {
+
+    use webrender::api::{AddImage, ResourceUpdate};
+    use window::get_gl_context;
+    use render_cache::read_framebuffer_region;
+
+    let mut resource_updates = Vec::<ResourceUpdate>::new();
+
+    let deleted_images = window.render_cache.retain(&window.solver.solved_layout.cached_subtrees);
+    resource_updates.extend(deleted_images.into_iter().map(ResourceUpdate::DeleteImage));
+
+    // The display list is rendered asynchronously, the subtrees can only be
+    // captured once the frame that they were painted in is on the screen
+    let rendered_epoch = window.renderer.as_ref().and_then(|renderer| renderer.current_epoch(window.internal.pipeline_id));
+    let has_rendered_captures = window.solver.solved_layout.render_cache_captures.iter()
+        .any(|capture| Some(capture.epoch) == rendered_epoch);
+
+    if has_rendered_captures {
+        match get_gl_context(&window.display) {
+            Ok(gl) => {
+                let hidpi_factor = window.state.size.hidpi_factor as f32;
+                let captures = ::std::mem::replace(&mut window.solver.solved_layout.render_cache_captures, Vec::new());
+                for capture in captures {
+                    let (descriptor, data) = match read_framebuffer_region(&*gl, &capture.bounds, hidpi_factor, (framebuffer_size.width, framebuffer_size.height)) {
+                        Some(image) => image,
+                        None => continue,
+                    };
+                    let key = window.internal.api.generate_image_key();
+                    resource_updates.push(ResourceUpdate::AddImage(AddImage { key, descriptor, data, tiling: None }));
+                    if let Some(outdated_key) = window.render_cache.insert(capture, key) {
+                        resource_updates.push(ResourceUpdate::DeleteImage(outdated_key));
+                    }
+                }
+            },
+            Err(e) => error!("Error capturing the cached subtrees: {:?}", e),
+        }
+    }
+
+    if !resource_updates.is_empty() {
+        window.internal.api.update_resources(resource_updates);
+    }
 }
 
 fn clean_up_unused_opengl_textures(pipeline_info: PipelineInfo) {
@@ -742,3 +1156,132 @@ fn render_inner<T: Layout>(window: &mut Window<T>, framebuffer_size: TypedSize2D
 fn __codecov_test_app_file() {
 
 }
+
+
+#[test]
+fn test_keyboard_activation_calls_mouse_up_callback() {
+
+    use std::collections::BTreeMap;
+    use glium::glutin::{KeyboardInput, ElementState, VirtualKeyCode, ModifiersState};
+    use dom::{Dom, NodeType};
+    use focus::FocusState;
+    use window::WindowEvent;
+    use test_utils::TestLayout;
+
+    fn on_mouse_down(_: &mut AppState<TestLayout>, _: WindowEvent) -> UpdateScreen { UpdateScreen::DontRedraw }
+    fn on_click(_: &mut AppState<TestLayout>, _: WindowEvent) -> UpdateScreen { UpdateScreen::Redraw }
+
+    let button = Dom::new(NodeType::Div)
+        .with_callback(On::MouseDown, Callback(on_mouse_down))
+        .with_callback(On::MouseUp, Callback(on_click));
+
+    let mut callback_list = BTreeMap::new();
+    let mut node_ids_to_callbacks_list = BTreeMap::new();
+    button.collect_callbacks(&mut callback_list, &mut node_ids_to_callbacks_list);
+    let button_tag = button.arena.borrow()[button.root].data.tag.unwrap();
+
+    let ui_state = UiState {
+        dom: button,
+        callback_list: callback_list,
+        node_ids_to_callbacks_list: node_ids_to_callbacks_list,
+    };
+
+    let mut focus = FocusState::default();
+    focus.focus(button_tag);
+
+    let space_released = KeyboardInput {
+        scancode: 0,
+        state: ElementState::Released,
+        virtual_keycode: Some(VirtualKeyCode::Space),
+        modifiers: ModifiersState::default(),
+    };
+
+    let activation_events: Vec<(u64, On)> = focus.keyboard_activation_event(&space_released).into_iter().collect();
+    assert_eq!(get_node_event_callbacks(&activation_events, &ui_state), vec![Callback(on_click)]);
+}
+
+#[test]
+fn test_key_down_is_sent_to_the_focused_text_field() {
+
+    use std::collections::BTreeMap;
+    use glium::glutin::{ElementState, VirtualKeyCode, ModifiersState};
+    use dom::{Dom, NodeType};
+    use window::WindowEvent;
+    use test_utils::TestLayout;
+
+    #[derive(Default)]
+    struct TextFields {
+        text: String,
+        other_key_presses: usize,
+    }
+
+    // A minimal text field: letters are appended, backspace removes the last letter
+    fn on_text_field_key_down(app_state: &mut AppState<TestLayout<TextFields>>, event: WindowEvent) -> UpdateScreen {
+        let key = match event.keyboard_input.and_then(|input| input.virtual_keycode) {
+            Some(key) => key,
+            None => return UpdateScreen::DontRedraw,
+        };
+        let mut data = app_state.data.lock().unwrap();
+        let text = &mut data.data.text;
+        match key {
+            VirtualKeyCode::A => text.push('a'),
+            VirtualKeyCode::B => text.push('b'),
+            VirtualKeyCode::Back => { text.pop(); },
+            _ => return UpdateScreen::DontRedraw,
+        }
+        UpdateScreen::Redraw
+    }
+
+    fn on_other_key_down(app_state: &mut AppState<TestLayout<TextFields>>, _: WindowEvent) -> UpdateScreen {
+        app_state.data.lock().unwrap().data.other_key_presses += 1;
+        UpdateScreen::Redraw
+    }
+
+    let dom = Dom::new(NodeType::Div)
+        .with_child(Dom::new(NodeType::Label(String::new())).with_callback(On::KeyDown, Callback(on_text_field_key_down)))
+        .with_child(Dom::new(NodeType::Label(String::new())).with_callback(On::KeyDown, Callback(on_other_key_down)));
+
+    let mut callback_list = BTreeMap::new();
+    let mut node_ids_to_callbacks_list = BTreeMap::new();
+    dom.collect_callbacks(&mut callback_list, &mut node_ids_to_callbacks_list);
+    let text_field_tag = {
+        let arena = dom.arena.borrow();
+        arena[arena[dom.root].first_child().unwrap()].data.tag.unwrap()
+    };
+
+    let ui_state_cache = vec![UiState {
+        dom: dom,
+        callback_list: callback_list,
+        node_ids_to_callbacks_list: node_ids_to_callbacks_list,
+    }];
+
+    let mut app_state = AppState::new(TestLayout::<TextFields>::default());
+
+    let type_key = |app_state: &mut AppState<TestLayout<TextFields>>, key: VirtualKeyCode| {
+        for state in &[ElementState::Pressed, ElementState::Released] {
+            let input = KeyboardInput {
+                scancode: 0,
+                state: *state,
+                virtual_keycode: Some(key),
+                modifiers: ModifiersState::default(),
+            };
+            if let Some(key_event) = app_state.focus.keyboard_event(&input) {
+                dispatch_node_events(&[key_event], WindowId { id: 0 }, &ui_state_cache, app_state, Some(input));
+            }
+        }
+    };
+
+    // Without a focused node, the key events are not sent to any node
+    type_key(&mut app_state, VirtualKeyCode::A);
+    assert_eq!(app_state.data.lock().unwrap().data.text, "");
+
+    app_state.focus.focus(text_field_tag);
+    type_key(&mut app_state, VirtualKeyCode::A);
+    type_key(&mut app_state, VirtualKeyCode::B);
+    type_key(&mut app_state, VirtualKeyCode::B);
+    type_key(&mut app_state, VirtualKeyCode::Back);
+
+    // Only the focused text field received the keys (once per key press, not on release)
+    assert_eq!(app_state.data.lock().unwrap().data.text, "ab");
+    assert_eq!(app_state.data.lock().unwrap().data.other_key_presses, 0);
+}
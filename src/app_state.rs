@@ -1,21 +1,25 @@
 use std::{
     io::Read,
-    collections::hash_map::Entry::*,
+    path::Path,
+    collections::{HashMap, hash_map::Entry::*},
     sync::{Arc, Mutex},
 };
 use image::ImageError;
 use rusttype::Font;
+use glium::glutin::KeyboardInput;
 use {
     FastHashMap,
     text_cache::TextId,
-    window::FakeWindow,
+    window::{FakeWindow, WindowEvent, WindowId},
     task::Task,
-    dom::UpdateScreen,
+    dom::{UpdateScreen, Callback},
+    focus::FocusState,
+    shortcut::{KeyCombo, ShortcutRegistry},
     traits::Layout,
     resources::AppResources,
-    images::ImageType,
+    images::{ImageType, ImageRegion, AtlasId},
     font::FontError,
-    css_parser::{FontId, FontSize, PixelValue},
+    css_parser::{FontId, FontSize, FontWeight, FontStyle, PixelValue},
     errors::ClipboardError,
 };
 
@@ -40,6 +44,10 @@ pub struct AppState<'a, T: Layout> {
     pub(crate) deamons: FastHashMap<String, fn(&mut T) -> UpdateScreen>,
     /// Currently running tasks (asynchronous functions running on a different thread)
     pub(crate) tasks: Vec<Task>,
+    /// Which node currently has the keyboard focus
+    pub(crate) focus: FocusState,
+    /// Global keyboard shortcuts, see `add_shortcut`
+    pub(crate) shortcuts: ShortcutRegistry<T>,
 }
 
 impl<'a, T: Layout> AppState<'a, T> {
@@ -52,6 +60,8 @@ impl<'a, T: Layout> AppState<'a, T> {
             resources: AppResources::default(),
             deamons: FastHashMap::default(),
             tasks: Vec::new(),
+            focus: FocusState::default(),
+            shortcuts: ShortcutRegistry::default(),
         }
     }
 
@@ -83,6 +93,58 @@ impl<'a, T: Layout> AppState<'a, T> {
         self.resources.add_image(id, data, image_type)
     }
 
+    /// Add an image from a file path to the internal resources. Unlike `add_image`,
+    /// this function doesn't block: the file is loaded and decoded on a background thread.
+    /// Until the image is decoded, a placeholder is drawn instead of the image and
+    /// once decoding has finished, the screen is redrawn.
+    ///
+    /// The type of the image is determined from the file extension (if possible).
+    /// If the image can't be decoded, the error is logged and the image is removed again.
+    ///
+    /// ## Returns
+    ///
+    /// - `Some(())` if the image was added
+    /// - `None` if an image with the same ID already exists
+    pub fn add_image_path<S: Into<String>, P: AsRef<Path>>(&mut self, id: S, path: P)
+        -> Option<()>
+    {
+        self.resources.add_image_path(id, path)
+    }
+
+    /// Registers a part of an already added image as a new image, i.e. a single
+    /// icon of a sprite sheet. The region can be used like any other image (by its `id`),
+    /// but only the `region` of the source image is drawn, stretched to the bounds
+    /// of the node. The source image is only uploaded to the GPU once.
+    ///
+    /// ## Returns
+    ///
+    /// - `Some(())` if the region was added
+    /// - `None` if the source image doesn't exist or an image with the same ID already exists
+    pub fn add_image_region<S: Into<String>, R: AsRef<str>>(&mut self, id: S, source_id: R, region: ImageRegion)
+        -> Option<()>
+    {
+        self.resources.add_image_region(id, source_id, region)
+    }
+
+    /// Adds a sprite atlas: a single image that contains many named `regions`, i.e. the
+    /// sprites of a game UI. Every region can be used like any other image, via
+    /// `atlas.region("button_hover")`, but the atlas is only uploaded to the GPU once.
+    ///
+    /// ## Returns
+    ///
+    /// - `Ok(atlas)` if the atlas was added
+    /// - `Err(e)` if the image couldn't be decoded
+    pub fn add_sprite_atlas<R: Read>(&mut self, data: &mut R, image_type: ImageType, regions: HashMap<String, ImageRegion>)
+        -> Result<AtlasId, ImageError>
+    {
+        self.resources.add_sprite_atlas(data, image_type, regions)
+    }
+
+    /// Removes a sprite atlas (and all of its regions) from the internal app resources
+    pub fn delete_sprite_atlas(&mut self, atlas: &AtlasId) {
+        self.resources.delete_sprite_atlas(atlas)
+    }
+
     /// Checks if an image is currently registered and ready-to-use
     pub fn has_image<S: AsRef<str>>(&mut self, id: S)
         -> bool
@@ -145,6 +207,22 @@ impl<'a, T: Layout> AppState<'a, T> {
         self.resources.add_font(id, data)
     }
 
+    /// Registers the already added font `font_id` as the face of the font `family` with the
+    /// given `weight` and `style`, so that i.e. `font-family: "Sleek"; font-weight: bold;`
+    /// selects the bold face of the "Sleek" family. A face with the same weight and style
+    /// is replaced. If a family has no face for the requested weight / style, the closest
+    /// face is used and bold / italic is synthesized by the renderer.
+    ///
+    /// ## Returns
+    ///
+    /// - `Some(())` if the face was registered
+    /// - `None` if no font with the ID `font_id` exists
+    pub fn add_font_face<S: Into<String>, F: Into<String>>(&mut self, family: S, font_id: F, weight: FontWeight, style: FontStyle)
+        -> Option<()>
+    {
+        self.resources.add_font_face(family, font_id, weight, style)
+    }
+
     /// Checks if a font is currently registered and ready-to-use
     pub fn has_font<S: Into<String>>(&mut self, id: S)
         -> bool
@@ -253,6 +331,68 @@ impl<'a, T: Layout> AppState<'a, T> {
     {
         self.resources.set_clipboard_string(contents)
     }
+
+    /// Gives the keyboard focus to the node with the given tag, i.e. to focus
+    /// a text field when a dialog opens.
+    ///
+    /// The previously focused node (if any) receives an `On::FocusLost` event,
+    /// the newly focused node an `On::FocusReceived` event. The events are dispatched
+    /// at the beginning of the next frame and the screen is redrawn afterwards.
+    /// Does nothing if the node is already focused.
+    pub fn focus_node(&mut self, tag: u64)
+    -> UpdateScreen
+    {
+        self.focus.focus(tag)
+    }
+
+    /// Removes the keyboard focus from the currently focused node, which
+    /// receives an `On::FocusLost` event. Does nothing if no node is focused.
+    pub fn blur(&mut self)
+    -> UpdateScreen
+    {
+        self.focus.blur()
+    }
+
+    /// Returns the tag of the node that currently has the keyboard focus, if any
+    pub fn get_focused_node(&self)
+    -> Option<u64>
+    {
+        self.focus.focused_tag
+    }
+
+    /// Registers a global keyboard shortcut, i.e. Ctrl+S to save or Escape to close a dialog.
+    ///
+    /// The callback is invoked whenever the key combo is pressed, regardless of which
+    /// node is focused (or if any node is focused at all). Shortcuts are checked before the
+    /// keyboard input is dispatched to the focused node, so a key press that triggers a
+    /// shortcut doesn't reach the focused node. Registering a combo a second time
+    /// replaces the previous callback.
+    pub fn add_shortcut(&mut self, combo: KeyCombo, callback: Callback<T>) {
+        self.shortcuts.add(combo, callback);
+    }
+
+    /// Removes a global keyboard shortcut, returns whether the combo was registered
+    pub fn remove_shortcut(&mut self, combo: &KeyCombo) -> bool {
+        self.shortcuts.remove(combo)
+    }
+
+    /// Invokes the callback of the global shortcut that the `input` triggers.
+    ///
+    /// Returns `None` if the `input` isn't a shortcut, otherwise what the callback returned
+    pub(crate) fn dispatch_shortcut(&mut self, input: &KeyboardInput, window_id: WindowId)
+    -> Option<UpdateScreen>
+    {
+        let Callback(callback_func) = self.shortcuts.find(input)?;
+        let window_event = WindowEvent {
+            window: window_id.id,
+            number_of_previous_siblings: None,
+            cursor_relative_to_item: (0.0, 0.0),
+            cursor_in_viewport: (0.0, 0.0),
+            keyboard_input: Some(*input),
+            scroll_delta: None,
+        };
+        Some((callback_func)(self, window_event))
+    }
 }
 
 impl<'a, T: Layout + Send + 'static> AppState<'a, T> {
@@ -0,0 +1,136 @@
+//! Checkboxes, created with `Dom::checkbox()`
+//!
+//! The mark of a checkbox is painted inside of the node, in the text color of the node
+//! (the box itself can be styled with the `border` / `background` of the node). A checked
+//! checkbox shows a checkmark, an indeterminate checkbox (i.e. a "select all" checkbox
+//! where only some of the items are selected) shows a horizontal dash.
+//!
+//! The state is part of the DOM, so clicking a checkbox doesn't change it: the
+//! `On::MouseUp` callback of the checkbox has to store `CheckBoxState::toggled()`
+//! in the app data, so that the next DOM contains the new state.
+
+use webrender::api::{LayoutRect, LayoutPoint, LayoutSize};
+
+/// Width of the strokes of the mark, relative to the size of the checkbox
+const CHECKBOX_STROKE_WIDTH: f32 = 0.125;
+/// Width of the dash of an indeterminate checkbox, relative to the width of the checkbox
+const CHECKBOX_DASH_WIDTH: f32 = 0.5;
+/// Number of segments that the strokes of the checkmark are made of
+const CHECKMARK_SEGMENTS: usize = 12;
+/// The checkmark goes from the start down to the corner, then up to the end,
+/// relative to the size of the checkbox
+const CHECKMARK_START: (f32, f32) = (0.2, 0.5);
+const CHECKMARK_CORNER: (f32, f32) = (0.4, 0.7);
+const CHECKMARK_END: (f32, f32) = (0.8, 0.3);
+
+/// State of a checkbox
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum CheckBoxState {
+    Unchecked,
+    Checked,
+    /// Neither checked nor unchecked, rendered as a dash
+    Indeterminate,
+}
+
+impl Default for CheckBoxState {
+    fn default() -> Self {
+        CheckBoxState::Unchecked
+    }
+}
+
+impl CheckBoxState {
+
+    /// Returns the state after the checkbox was clicked: a checked checkbox gets unchecked,
+    /// an unchecked or indeterminate checkbox gets checked. The indeterminate state can only
+    /// be set by the application, never by clicking.
+    pub fn toggled(&self) -> Self {
+        use self::CheckBoxState::*;
+        match *self {
+            Checked => Unchecked,
+            Unchecked | Indeterminate => Checked,
+        }
+    }
+
+    pub fn is_checked(&self) -> bool {
+        *self == CheckBoxState::Checked
+    }
+}
+
+impl From<bool> for CheckBoxState {
+    fn from(checked: bool) -> Self {
+        if checked { CheckBoxState::Checked } else { CheckBoxState::Unchecked }
+    }
+}
+
+/// Returns the rectangles that make up the mark of a checkbox with the `bounds`:
+/// nothing for an unchecked checkbox, a single horizontal dash for an indeterminate
+/// checkbox and the segments of the checkmark for a checked checkbox.
+///
+/// Webrender can't draw lines, so the checkmark is approximated by small squares along
+/// its two strokes, which overlap each other so that the strokes look continuous.
+pub(crate) fn get_checkbox_mark(bounds: &LayoutRect, state: CheckBoxState) -> Vec<LayoutRect> {
+    let size = bounds.size.width.min(bounds.size.height);
+    let stroke_width = size * CHECKBOX_STROKE_WIDTH;
+
+    match state {
+        CheckBoxState::Unchecked => Vec::new(),
+        CheckBoxState::Indeterminate => {
+            let dash_width = bounds.size.width * CHECKBOX_DASH_WIDTH;
+            vec![LayoutRect::new(
+                LayoutPoint::new(
+                    bounds.origin.x + (bounds.size.width - dash_width) / 2.0,
+                    bounds.origin.y + (bounds.size.height - stroke_width) / 2.0),
+                LayoutSize::new(dash_width, stroke_width))]
+        },
+        CheckBoxState::Checked => {
+            let point = |(x, y): (f32, f32)| LayoutPoint::new(bounds.origin.x + x * bounds.size.width, bounds.origin.y + y * bounds.size.height);
+            let (start, corner, end) = (point(CHECKMARK_START), point(CHECKMARK_CORNER), point(CHECKMARK_END));
+
+            // The short stroke gets a third of the segments, the long stroke the rest
+            let short_segments = CHECKMARK_SEGMENTS / 3;
+            let strokes = [(start, corner, short_segments), (corner, end, CHECKMARK_SEGMENTS - short_segments)];
+
+            strokes.iter().flat_map(|&(from, to, segment_count)| (0..=segment_count).map(move |segment| {
+                let t = segment as f32 / segment_count as f32;
+                let x = from.x + (to.x - from.x) * t;
+                let y = from.y + (to.y - from.y) * t;
+                LayoutRect::new(
+                    LayoutPoint::new(x - stroke_width / 2.0, y - stroke_width / 2.0),
+                    LayoutSize::new(stroke_width, stroke_width))
+            })).collect()
+        },
+    }
+}
+
+#[test]
+fn test_checkbox_click_cycle() {
+    use self::CheckBoxState::*;
+    assert_eq!(Unchecked.toggled(), Checked);
+    assert_eq!(Checked.toggled(), Unchecked);
+    // Clicking an indeterminate checkbox checks it, it never gets indeterminate again
+    assert_eq!(Indeterminate.toggled(), Checked);
+    assert_eq!(Indeterminate.toggled().toggled(), Unchecked);
+    assert_eq!(CheckBoxState::from(true), Checked);
+    assert!(!Indeterminate.is_checked());
+}
+
+#[test]
+fn test_checkbox_mark() {
+    let bounds = LayoutRect::new(LayoutPoint::new(10.0, 10.0), LayoutSize::new(100.0, 100.0));
+
+    assert!(get_checkbox_mark(&bounds, CheckBoxState::Unchecked).is_empty());
+
+    // A single horizontal dash in the center of the checkbox
+    assert_eq!(get_checkbox_mark(&bounds, CheckBoxState::Indeterminate), vec![
+        LayoutRect::new(LayoutPoint::new(35.0, 53.75), LayoutSize::new(50.0, 12.5)),
+    ]);
+
+    // The checkmark is made of small squares, the corner is the lowest point
+    let checkmark = get_checkbox_mark(&bounds, CheckBoxState::Checked);
+    assert_eq!(checkmark.len(), CHECKMARK_SEGMENTS + 2);
+    assert!(checkmark.iter().all(|segment| segment.size == LayoutSize::new(12.5, 12.5)));
+    let lowest = checkmark.iter().map(|segment| segment.origin.y).fold(0.0, f32::max);
+    assert_eq!(checkmark[CHECKMARK_SEGMENTS / 3].origin.y, lowest);
+    assert!(checkmark.first().unwrap().origin.y < lowest);
+    assert!(checkmark.last().unwrap().origin.y < checkmark.first().unwrap().origin.y);
+}
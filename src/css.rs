@@ -1,8 +1,10 @@
 //! CSS parsing and styling
+use std::{fmt, collections::{BTreeMap, BTreeSet}};
 use {
     FastHashMap,
-    traits::IntoParsedCssProperty,
-    css_parser::{ParsedCssProperty, CssParsingError},
+    traits::{IntoParsedCssProperty, Layout},
+    ui_description::{UiDescription, StyleChanges},
+    css_parser::{ParsedCssProperty, CssParsingError, CssWideKeyword, LayoutBoxSizing, SafeAreaInsets, is_supported_css_key},
     errors::CssSyntaxError,
 };
 
@@ -20,6 +22,10 @@ const RELAYOUT_RULES: [&str; 13] = [
     "order"
 ];
 
+/// How many frames in a row have to be re-laid out until a layout thrashing warning is logged
+/// (in debug builds), see `LayoutThrashingDetector`
+const LAYOUT_THRASHING_FRAMES: usize = 60;
+
 /// Wrapper for a `Vec<CssRule>` - the CSS is immutable at runtime, it can only be
 /// created once. Animations / conditional styling is implemented using dynamic fields
 #[derive(Debug, Clone, PartialEq)]
@@ -35,15 +41,130 @@ pub struct Css {
     /// Ex. if only a background color has changed, we need to redraw, but we
     /// don't need to re-layout the frame
     pub(crate) needs_relayout: bool,
+    /// What caused the `needs_relayout`, for detecting layout thrashing
+    pub(crate) relayout_causes: BTreeSet<RelayoutCause>,
+    /// Counts the frames in a row that had to be re-laid out
+    pub(crate) thrashing_detector: LayoutThrashingDetector,
+    /// Tags of the nodes that the mouse is currently over, matched by `:hover` rules
+    pub(crate) hovered_tags: BTreeSet<u64>,
+    /// Tag of the node that has the keyboard focus, matched by `:focus` rules
+    pub(crate) focused_tag: Option<u64>,
+    /// Insets of the window for `env(safe-area-inset-*)`, zero on desktop platforms
+    pub(crate) safe_area_insets: SafeAreaInsets,
+}
+
+/// Which of two stylesheets wins if both of them set the same
+/// property on a node with the same specificity, see `Css::merge`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Precedence {
+    /// The rules of the merged stylesheet override the existing rules
+    Higher,
+    /// The existing rules override the rules of the merged stylesheet
+    Lower,
+}
+
+/// Why a frame has to be re-laid out
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum RelayoutCause {
+    /// The stylesheet was replaced or merged with another stylesheet
+    Stylesheet,
+    /// The override of the dynamic CSS property with this ID changed
+    DynamicOverride(String),
+    /// The hovered nodes changed and a `:hover` rule affects the layout
+    Hover,
+    /// The focused node changed and a `:focus` rule affects the layout
+    Focus,
+    /// A box was resized by dragging its resize grip
+    Resize,
+    /// The safe area insets of the window changed, see `SafeAreaInsets`
+    SafeAreaInsets,
+}
+
+impl fmt::Display for RelayoutCause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::RelayoutCause::*;
+        match self {
+            Stylesheet => write!(f, "stylesheet change"),
+            DynamicOverride(id) => write!(f, "override of the dynamic CSS property \"{}\"", id),
+            Hover => write!(f, ":hover rule"),
+            Focus => write!(f, ":focus rule"),
+            Resize => write!(f, "resize grip drag"),
+            SafeAreaInsets => write!(f, "safe area insets change"),
+        }
+    }
+}
+
+/// Logged (in debug builds) if the layout had to be solved again in too many frames in a row,
+/// i.e. because a callback changes a `width` override on every frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LayoutThrashingWarning {
+    /// How many frames in a row were re-laid out
+    pub(crate) frames: usize,
+    /// What caused the re-layouts, the most frequent cause first
+    pub(crate) causes: Vec<RelayoutCause>,
+}
+
+impl fmt::Display for LayoutThrashingWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let causes = self.causes.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(", ");
+        write!(f, "layout thrashing: the layout was solved again in {} frames in a row, caused by: {}", self.frames, causes)
+    }
+}
+
+/// Counts the consecutive frames that had to be re-laid out and what caused them
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct LayoutThrashingDetector {
+    consecutive_frames: usize,
+    /// How many of the consecutive frames were caused by each cause
+    causes: BTreeMap<RelayoutCause, usize>,
+}
+
+impl LayoutThrashingDetector {
+
+    /// Records the `causes` of the re-layout of the current frame (empty if the frame wasn't
+    /// re-laid out). Returns a warning once `LAYOUT_THRASHING_FRAMES` frames in a row were
+    /// re-laid out - only once, until a frame doesn't have to be re-laid out anymore.
+    pub(crate) fn record_frame(&mut self, causes: &BTreeSet<RelayoutCause>) -> Option<LayoutThrashingWarning> {
+        if causes.is_empty() {
+            *self = Self::default();
+            return None;
+        }
+
+        self.consecutive_frames += 1;
+        for cause in causes {
+            *self.causes.entry(cause.clone()).or_insert(0) += 1;
+        }
+
+        if self.consecutive_frames != LAYOUT_THRASHING_FRAMES {
+            return None;
+        }
+
+        let mut causes = self.causes.iter().collect::<Vec<_>>();
+        causes.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        Some(LayoutThrashingWarning {
+            frames: self.consecutive_frames,
+            causes: causes.into_iter().map(|(cause, _)| cause.clone()).collect(),
+        })
+    }
 }
 
 /// Fake CSS that can be changed by the user
 #[derive(Debug, Default, Clone)]
 pub struct FakeCss {
     pub dynamic_css_overrides: FastHashMap<String, ParsedCssProperty>,
+    /// The theme that the window switches to in the next frame that is redrawn
+    pub(crate) theme: Option<Css>,
 }
 
 impl FakeCss {
+    /// Switches the stylesheet of the window to the `theme` (i.e. from a light to a dark theme),
+    /// in the next frame that is redrawn. See `Css::switch_theme`: only the nodes whose styles
+    /// changed are repainted and the frame is only re-laid out if the layout changed.
+    pub fn set_theme(&mut self, theme: Css) {
+        self.theme = Some(theme);
+    }
+
     /// Set a dynamic CSS property for the duration of one frame
     pub fn set_dynamic_property<'a, S, T>(&mut self, id: S, css_value: T)
     -> Result<(), CssParsingError<'a>>
@@ -72,6 +193,9 @@ pub enum CssParseError<'a> {
     UnclosedBlock,
     /// Invalid syntax, such as `#div { #div: "my-value" }`
     MalformedCss,
+    /// Attribute selector that azul doesn't support, such as `[lang|="en"]`.
+    /// Only `[name]` and `[name="value"]` are supported.
+    UnsupportedAttributeSelector(&'a str),
     /// Error parsing dynamic CSS property, such as
     /// `#div { width: {{ my_id }} /* no default case */ }`
     DynamicCssParseError(DynamicCssParseError<'a>),
@@ -106,14 +230,98 @@ pub(crate) struct CssRule {
     pub id: Option<String>,
     /// `.myclass .myotherclass` (vec![] by default)
     pub classes: Vec<String>,
+    /// `:disabled` (`None` by default)
+    pub pseudo_class: Option<CssPseudoClass>,
+    /// `[data-state="open"]` (vec![] by default)
+    pub attributes: Vec<CssAttributeSelector>,
     /// `("justify-content", "center")`
     pub declaration: (String, CssDeclaration),
+    /// Whether the rule is part of the default styling of the built-in widgets (see
+    /// `Css::native`), which doesn't apply to nodes with `appearance: none`
+    pub is_native: bool,
+}
+
+/// Pseudo-class of a CSS rule, i.e. the `:disabled` in `button:disabled`.
+/// Rules with a pseudo-class only apply to nodes in the given state.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum CssPseudoClass {
+    /// Matches nodes that were disabled via `Dom::with_disabled(true)`
+    Disabled,
+    /// Matches the nodes under the mouse cursor. Only nodes that are hit-tested
+    /// (i.e. nodes with a callback) can be hovered.
+    Hover,
+    /// Matches the node that has the keyboard focus
+    Focus,
+}
+
+impl CssPseudoClass {
+    fn from_str(input: &str) -> Option<Self> {
+        match input {
+            "disabled" => Some(CssPseudoClass::Disabled),
+            "hover" => Some(CssPseudoClass::Hover),
+            "focus" => Some(CssPseudoClass::Focus),
+            _ => None,
+        }
+    }
+}
+
+/// Attribute selector of a CSS rule, i.e. the `[data-state="open"]` in `div[data-state="open"]`.
+/// Matched against the attributes of a node (see `Dom::with_attribute`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CssAttributeSelector {
+    /// `data-state`
+    pub name: String,
+    /// `Some("open")` for `[data-state="open"]`, `None` for `[data-state]`
+    /// (matches any node that has the attribute, regardless of its value)
+    pub value: Option<String>,
+}
+
+impl CssAttributeSelector {
+    /// Parses the inside of the brackets, i.e. `data-state="open"`
+    fn from_str(input: &str) -> Option<Self> {
+        let input = input.trim().trim_left_matches('[').trim_right_matches(']');
+
+        let (name, value) = match input.find('=') {
+            Some(pos) => (&input[..pos], Some(input[(pos + 1)..].trim())),
+            None => (input, None),
+        };
+
+        let name = name.trim();
+        let is_valid_name = !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+        if !is_valid_name {
+            // i.e. `[lang|="en"]` or `[class~="a"]`
+            return None;
+        }
+
+        let value = value.map(|v| {
+            let is_quoted = v.len() >= 2 &&
+                ((v.starts_with('"') && v.ends_with('"')) || (v.starts_with('\'') && v.ends_with('\'')));
+            if is_quoted { &v[1..(v.len() - 1)] } else { v }
+        });
+
+        Some(Self {
+            name: name.to_string(),
+            value: value.map(|v| v.to_string()),
+        })
+    }
+
+    /// Returns whether the attributes of a node match this selector
+    pub(crate) fn matches(&self, attributes: &BTreeMap<String, String>) -> bool {
+        match (attributes.get(&self.name), &self.value) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(actual), Some(expected)) => actual == expected,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) enum CssDeclaration {
     Static(ParsedCssProperty),
     Dynamic(DynamicCssProperty),
+    /// A CSS-wide keyword (`inherit` / `initial`) for the property with the given key,
+    /// resolved when the properties of the node are populated
+    Keyword(String, CssWideKeyword),
 }
 
 /// A `CssProperty` is a type of CSS Rule,
@@ -153,7 +361,12 @@ impl Css {
         Self {
             rules: Vec::new(),
             needs_relayout: false,
+            relayout_causes: BTreeSet::new(),
+            thrashing_detector: LayoutThrashingDetector::default(),
             dynamic_css_overrides: FastHashMap::default(),
+            hovered_tags: BTreeSet::new(),
+            focused_tag: None,
+            safe_area_insets: SafeAreaInsets::default(),
         }
     }
 
@@ -176,6 +389,10 @@ impl Css {
         let mut current_type = "*";
         let mut current_id = None;
         let mut current_classes = HashSet::<&str>::new();
+        let mut current_pseudo_class = None;
+        let mut current_attributes = Vec::<CssAttributeSelector>::new();
+        // Rules with a pseudo-class that azul doesn't support (i.e. `:first-child`) are skipped
+        let mut is_unsupported_rule = false;
 
         'css_parse_loop: loop {
             let tokenize_result = tokenizer.parse_next();
@@ -195,6 +412,9 @@ impl Css {
                             current_type = "*";
                             current_id = None;
                             current_classes = HashSet::<&str>::new();
+                            current_pseudo_class = None;
+                            current_attributes = Vec::new();
+                            is_unsupported_rule = false;
                         },
                         Token::TypeSelector(div_type) => {
                             if parser_in_block {
@@ -214,11 +434,34 @@ impl Css {
                             }
                             current_classes.insert(class);
                         }
+                        Token::PseudoClass(pseudo_class) => {
+                            if parser_in_block {
+                                return Err(CssParseError::MalformedCss);
+                            }
+                            match CssPseudoClass::from_str(pseudo_class) {
+                                Some(p) => current_pseudo_class = Some(p),
+                                None => {
+                                    warn!("Skipping the CSS rule with the unsupported pseudo-class \":{}\"", pseudo_class);
+                                    is_unsupported_rule = true;
+                                },
+                            }
+                        }
+                        Token::AttributeSelector(attribute) => {
+                            if parser_in_block {
+                                return Err(CssParseError::MalformedCss);
+                            }
+                            current_attributes.push(CssAttributeSelector::from_str(attribute)
+                                .ok_or(CssParseError::UnsupportedAttributeSelector(attribute))?);
+                        }
                         Token::Declaration(key, val) => {
                             if !parser_in_block {
                                 return Err(CssParseError::MalformedCss);
                             }
 
+                            if is_unsupported_rule {
+                                continue;
+                            }
+
                             // see if the Declaration is static or dynamic
                             //
                             // css_val = "center" | "{{ my_dynamic_id | center }}"
@@ -227,7 +470,10 @@ impl Css {
                                 html_type: current_type.to_string(),
                                 id: current_id.clone(),
                                 classes: current_classes.iter().map(|e| e.to_string()).collect::<Vec<String>>(),
+                                pseudo_class: current_pseudo_class,
+                                attributes: current_attributes.clone(),
                                 declaration: (key.to_string(), css_decl),
+                                is_native: false,
                             };
                             // IMPORTANT!
                             css_rule.classes.sort();
@@ -251,26 +497,217 @@ impl Css {
             rules: css_rules,
             // force re-layout for the first frame
             needs_relayout: true,
+            relayout_causes: vec![RelayoutCause::Stylesheet].into_iter().collect(),
+            thrashing_detector: LayoutThrashingDetector::default(),
             dynamic_css_overrides: FastHashMap::default(),
+            hovered_tags: BTreeSet::new(),
+            focused_tag: None,
+            safe_area_insets: SafeAreaInsets::default(),
         })
     }
 
+    /// Combines the rules of two stylesheets, i.e. to layer a theme on top of a base stylesheet.
+    ///
+    /// Rules are still cascaded by their specificity first, i.e. a `#id` rule of the base
+    /// stylesheet wins over a `.class` rule of the theme. Only if two rules have the same
+    /// specificity, the `precedence` of `other` decides which one wins.
+    pub fn merge(&mut self, other: Css, precedence: Precedence) {
+        // Rules with the same specificity are cascaded in order, so the last rule wins
+        match precedence {
+            Precedence::Higher => {
+                self.rules.extend(other.rules);
+                self.dynamic_css_overrides.extend(other.dynamic_css_overrides);
+            },
+            Precedence::Lower => {
+                let mut rules = other.rules;
+                rules.extend(self.rules.drain(..));
+                self.rules = rules;
+                for (id, property) in other.dynamic_css_overrides {
+                    self.dynamic_css_overrides.entry(id).or_insert(property);
+                }
+            },
+        }
+
+        self.mark_relayout(RelayoutCause::Stylesheet);
+    }
+
+    /// Replaces the rules with the rules of the `theme`, i.e. to switch between a light and a
+    /// dark theme at runtime. The dynamic overrides and the hovered / focused node are kept.
+    ///
+    /// The `ui_description` (which was styled with the current rules) is styled again with
+    /// the rules of the `theme` and the resolved styles are compared node by node: the next
+    /// frame only has to be re-laid out if the layout of a node changed, not if the two
+    /// themes only differ in their colors.
+    pub fn switch_theme<T: Layout>(&mut self, theme: Css, ui_description: &UiDescription<T>) -> StyleChanges {
+        self.rules = theme.rules;
+
+        // Nothing was styled yet, so the changes aren't known
+        if ui_description.ui_descr_root.is_none() {
+            self.mark_relayout(RelayoutCause::Stylesheet);
+            return StyleChanges::default();
+        }
+
+        let changes = ui_description.diff_styles(&ui_description.restyle(self));
+        if changes.needs_relayout() {
+            self.mark_relayout(RelayoutCause::Stylesheet);
+        }
+        changes
+    }
+
+    /// Returns whether the next frame has to be laid out again (which is expensive) or if
+    /// it only has to be repainted. A re-layout is needed if the stylesheet changed or if a
+    /// dynamic override that affects the layout (i.e. a `width`) was changed, but not if
+    /// only paint properties (i.e. a `background-color`) were changed.
+    ///
+    /// Note that resizing the window always triggers a re-layout.
+    pub fn will_relayout(&self) -> bool {
+        self.needs_relayout
+    }
+
+    pub(crate) fn mark_relayout(&mut self, cause: RelayoutCause) {
+        self.needs_relayout = true;
+        self.relayout_causes.insert(cause);
+    }
+
+    /// Has to be called after a frame was laid out, resets `needs_relayout`.
+    ///
+    /// In debug builds, returns a warning if the layout had to be solved again in
+    /// too many frames in a row (layout thrashing), see `LayoutThrashingDetector`.
+    pub(crate) fn relayout_done(&mut self) -> Option<LayoutThrashingWarning> {
+        let mut causes = ::std::mem::replace(&mut self.relayout_causes, BTreeSet::new());
+        if !self.needs_relayout {
+            // `needs_relayout` was reset without a re-layout
+            causes.clear();
+        }
+        self.needs_relayout = false;
+        let warning = self.thrashing_detector.record_frame(&causes);
+        if cfg!(debug_assertions) { warning } else { None }
+    }
+
+    /// Replaces the dynamic overrides with the overrides that were set on the `FakeCss`.
+    /// If an override that affects the layout was added, removed or changed, the next
+    /// frame has to be re-laid out, see `will_relayout()`.
+    pub(crate) fn set_dynamic_css_overrides(&mut self, overrides: FastHashMap<String, ParsedCssProperty>) {
+
+        fn layout_changes<'a>(a: &'a FastHashMap<String, ParsedCssProperty>, b: &'a FastHashMap<String, ParsedCssProperty>)
+        -> impl Iterator<Item=&'a String>
+        {
+            a.iter().filter(move |(id, property)| property.affects_layout() && b.get(*id) != Some(*property)).map(|(id, _)| id)
+        }
+
+        let changed_ids = layout_changes(&overrides, &self.dynamic_css_overrides)
+            .chain(layout_changes(&self.dynamic_css_overrides, &overrides))
+            .cloned()
+            .collect::<BTreeSet<String>>();
+
+        for id in changed_ids {
+            self.mark_relayout(RelayoutCause::DynamicOverride(id));
+        }
+
+        self.dynamic_css_overrides = overrides;
+    }
+
+    /// Sets the tags of the nodes that the mouse is over, for the `:hover` rules.
+    ///
+    /// Returns whether the hovered nodes changed, i.e. whether the frame has to be redrawn.
+    /// The frame only has to be re-laid out if one of the `:hover` rules affects the layout.
+    pub(crate) fn set_hovered_tags(&mut self, hovered_tags: BTreeSet<u64>) -> bool {
+        if self.hovered_tags == hovered_tags {
+            return false;
+        }
+        if self.pseudo_class_affects_layout(CssPseudoClass::Hover) {
+            self.mark_relayout(RelayoutCause::Hover);
+        }
+        self.hovered_tags = hovered_tags;
+        true
+    }
+
+    /// Sets the tag of the focused node, for the `:focus` rules.
+    ///
+    /// Returns whether the focused node changed, i.e. whether the frame has to be redrawn.
+    /// The frame only has to be re-laid out if one of the `:focus` rules affects the layout.
+    pub(crate) fn set_focused_tag(&mut self, focused_tag: Option<u64>) -> bool {
+        if self.focused_tag == focused_tag {
+            return false;
+        }
+        if self.pseudo_class_affects_layout(CssPseudoClass::Focus) {
+            self.mark_relayout(RelayoutCause::Focus);
+        }
+        self.focused_tag = focused_tag;
+        true
+    }
+
+    /// Sets the insets that `env(safe-area-inset-*)` resolves to, i.e. when the
+    /// window was rotated. If the insets changed, the next frame has to be re-laid out.
+    pub(crate) fn set_safe_area_insets(&mut self, safe_area_insets: SafeAreaInsets) {
+        if self.safe_area_insets != safe_area_insets {
+            self.mark_relayout(RelayoutCause::SafeAreaInsets);
+            self.safe_area_insets = safe_area_insets;
+        }
+    }
+
+    /// Returns whether any of the rules with the given pseudo-class sets a property that
+    /// affects the layout. When a node enters or leaves the state, only these rules start
+    /// or stop to apply, so if they only set paint properties, the layout stays the same.
+    fn pseudo_class_affects_layout(&self, pseudo_class: CssPseudoClass) -> bool {
+        self.rules.iter()
+            .filter(|rule| rule.pseudo_class == Some(pseudo_class))
+            .any(|rule| match rule.declaration.1 {
+                // Under `box-sizing: border-box`, the border is drawn inside of the box
+                CssDeclaration::Static(ParsedCssProperty::Border(..)) |
+                CssDeclaration::Static(ParsedCssProperty::BorderSide(_)) => !self.is_border_box(rule),
+                CssDeclaration::Static(ref property) => property.affects_layout(),
+                // The override of a dynamic property can be any value, so look at the default
+                CssDeclaration::Dynamic(ref dynamic) => dynamic.default.affects_layout(),
+                // Don't know the value of the parent, so assume that the layout changes
+                CssDeclaration::Keyword(..) => true,
+            })
+    }
+
+    /// Returns whether the nodes that the `rule` applies to always have `box-sizing: border-box`:
+    /// either all nodes have it (`* { box-sizing: border-box; }`) or a rule without a
+    /// pseudo-class with the same selector as the `rule` sets it
+    fn is_border_box(&self, rule: &CssRule) -> bool {
+        self.rules.iter()
+            .filter(|other| other.pseudo_class.is_none())
+            .filter(|other| {
+                let is_universal = other.html_type == "*" && other.id.is_none() && other.classes.is_empty() && other.attributes.is_empty();
+                let is_same_selector = other.html_type == rule.html_type && other.id == rule.id &&
+                                       other.classes == rule.classes && other.attributes == rule.attributes;
+                is_universal || is_same_selector
+            })
+            .filter_map(|other| match other.declaration.1 {
+                CssDeclaration::Static(ParsedCssProperty::BoxSizing(box_sizing)) => Some(box_sizing),
+                _ => None,
+            })
+            .last() == Some(LayoutBoxSizing::BorderBox)
+    }
+
     /// Returns the native style for the OS
     #[cfg(target_os="windows")]
     pub fn native() -> Self {
-        Self::new_from_string(NATIVE_CSS_WINDOWS).unwrap()
+        Self::new_native(NATIVE_CSS_WINDOWS)
     }
 
     /// Returns the native style for the OS
     #[cfg(target_os="linux")]
     pub fn native() -> Self {
-        Self::new_from_string(NATIVE_CSS_LINUX).unwrap()
+        Self::new_native(NATIVE_CSS_LINUX)
     }
 
     /// Returns the native style for the OS
     #[cfg(target_os="macos")]
     pub fn native() -> Self {
-        Self::new_from_string(NATIVE_CSS_MACOS).unwrap()
+        Self::new_native(NATIVE_CSS_MACOS)
+    }
+
+    /// Parses a native stylesheet and marks its rules as native, see `CssRule::is_native`
+    pub(crate) fn new_native(css_string: &str) -> Self {
+        let mut css = Self::new_from_string(css_string).unwrap();
+        for rule in &mut css.rules {
+            rule.is_native = true;
+        }
+        css
     }
 }
 
@@ -365,7 +802,15 @@ fn determine_static_or_dynamic_css_property<'a>(key: &'a str, value: &'a str)
             }))
         },
         (false, false) => {
-            Ok(CssDeclaration::Static(ParsedCssProperty::from_kv(key, value)?))
+            match CssWideKeyword::from_str(value) {
+                Some(keyword) => {
+                    if !is_supported_css_key(key) {
+                        return Err(CssParsingError::UnsupportedCssKey(key, value).into());
+                    }
+                    Ok(CssDeclaration::Keyword(key.to_string(), keyword))
+                },
+                None => Ok(CssDeclaration::Static(ParsedCssProperty::from_kv(key, value)?)),
+            }
         }
     }
 }
@@ -392,6 +837,23 @@ fn test_detect_static_or_dynamic_property() {
         Err(DynamicCssParseError::InvalidId)
     );
 
+    assert_eq!(
+        determine_static_or_dynamic_css_property("color", " inherit"),
+        Ok(CssDeclaration::Keyword(String::from("color"), CssWideKeyword::Inherit))
+    );
+
+    assert_eq!(
+        determine_static_or_dynamic_css_property("width", "initial"),
+        Ok(CssDeclaration::Keyword(String::from("width"), CssWideKeyword::Initial))
+    );
+
+    assert_eq!(
+        determine_static_or_dynamic_css_property("aldfjasdflk", "inherit"),
+        Err(DynamicCssParseError::UnexpectedValue(
+            CssParsingError::UnsupportedCssKey("aldfjasdflk", "inherit")
+        ))
+    );
+
     assert_eq!(
         determine_static_or_dynamic_css_property("text-align", "[[  hello | center ]]"),
         Ok(CssDeclaration::Dynamic(DynamicCssProperty {
@@ -432,4 +894,255 @@ fn test_detect_static_or_dynamic_property() {
         determine_static_or_dynamic_css_property("text-align", "[[ |  ]]"),
         Err(DynamicCssParseError::EmptyBraces)
     );
+}
+
+#[test]
+fn test_parse_disabled_pseudo_class() {
+    let css = Css::new_from_string("button { width: 100px; } button:disabled { width: 50px; }").unwrap();
+    assert_eq!(css.rules.len(), 2);
+    assert_eq!(css.rules[0].pseudo_class, None);
+    assert_eq!(css.rules[1].pseudo_class, Some(CssPseudoClass::Disabled));
+    assert_eq!(css.rules[1].html_type, "button");
+
+    // Rules with an unsupported pseudo-class are skipped, the rest of the stylesheet still loads
+    let css = Css::new_from_string("div:first-child { width: 50px; } div:last-child { width: 60px; } div { height: 10px; }").unwrap();
+    assert_eq!(css.rules.len(), 1);
+    assert_eq!(css.rules[0].declaration.0, "height");
+}
+
+#[test]
+fn test_parse_attribute_selector() {
+    let css = Css::new_from_string("div[data-state=\"open\"] { width: 100px; } [data-active] { width: 50px; }").unwrap();
+    assert_eq!(css.rules.len(), 2);
+    assert_eq!(css.rules[0].html_type, "div");
+    assert_eq!(css.rules[0].attributes, vec![CssAttributeSelector { name: "data-state".into(), value: Some("open".into()) }]);
+    assert_eq!(css.rules[1].attributes, vec![CssAttributeSelector { name: "data-active".into(), value: None }]);
+
+    let mut attributes = BTreeMap::new();
+    attributes.insert(String::from("data-state"), String::from("open"));
+    assert!(css.rules[0].attributes[0].matches(&attributes));
+    attributes.insert(String::from("data-state"), String::from("closed"));
+    assert!(!css.rules[0].attributes[0].matches(&attributes));
+    assert!(!css.rules[1].attributes[0].matches(&attributes));
+
+    assert!(Css::new_from_string("div[lang|=\"en\"] { width: 50px; }").is_err());
+}
+
+#[test]
+fn test_merge_stylesheets() {
+    use dom::Dom;
+    use traits::Layout;
+    use css_parser::{TextColor, ColorU};
+    use test_utils::TestLayout;
+
+    let base = "
+        .label { color: black; }
+        .title { color: black; }
+    ";
+    let theme = ".label { color: white; }";
+
+    let get_text_color = |css: &Css, class: &str| {
+        let dom: Dom<TestLayout> = Dom::div().with_class(class);
+        let ui_description = TestLayout::style_dom(&dom, css);
+        ui_description.styled_nodes[&dom.root].css_constraints.list.iter().filter_map(|declaration| match declaration {
+            CssDeclaration::Static(ParsedCssProperty::TextColor(c)) => Some(*c),
+            _ => None,
+        }).last()
+    };
+
+    let black = TextColor(ColorU { r: 0, g: 0, b: 0, a: 255 });
+    let white = TextColor(ColorU { r: 255, g: 255, b: 255, a: 255 });
+
+    let mut css = Css::new_from_string(base).unwrap();
+    css.merge(Css::new_from_string(theme).unwrap(), Precedence::Higher);
+    assert_eq!(get_text_color(&css, "label"), Some(white));
+    assert_eq!(get_text_color(&css, "title"), Some(black));
+
+    let mut css = Css::new_from_string(base).unwrap();
+    css.merge(Css::new_from_string(theme).unwrap(), Precedence::Lower);
+    assert_eq!(get_text_color(&css, "label"), Some(black));
+}
+
+#[test]
+fn test_paint_only_override_does_not_relayout() {
+    use css_parser::{BackgroundColor, ColorU, LayoutWidth, PixelValue, CssMetric};
+
+    let mut css = Css::empty();
+    assert!(!css.will_relayout());
+
+    let mut overrides = FastHashMap::default();
+    overrides.insert(String::from("hover_color"), ParsedCssProperty::BackgroundColor(BackgroundColor(ColorU { r: 255, g: 0, b: 0, a: 255 })));
+    css.set_dynamic_css_overrides(overrides.clone());
+    assert!(!css.will_relayout());
+
+    overrides.insert(String::from("sidebar_width"), ParsedCssProperty::Width(LayoutWidth(PixelValue::from_metric(CssMetric::Px, 250.0))));
+    css.set_dynamic_css_overrides(overrides.clone());
+    assert!(css.will_relayout());
+
+    // After the frame was laid out, setting the same width again doesn't relayout
+    css.needs_relayout = false;
+    css.set_dynamic_css_overrides(overrides.clone());
+    assert!(!css.will_relayout());
+
+    // Removing the width does
+    overrides.remove("sidebar_width");
+    css.set_dynamic_css_overrides(overrides);
+    assert!(css.will_relayout());
+}
+
+#[test]
+fn test_paint_only_hover_does_not_relayout() {
+    use css_parser::{BackgroundColor, ColorU};
+    use dom::{Dom, NodeType, On, Callback, UpdateScreen};
+    use traits::Layout;
+    use app_state::AppState;
+    use window::WindowEvent;
+    use test_utils::TestLayout;
+
+    fn on_click(_: &mut AppState<TestLayout>, _: WindowEvent) -> UpdateScreen { UpdateScreen::DontRedraw }
+
+    let button = Dom::new(NodeType::Div).with_class("button").with_callback(On::MouseUp, Callback(on_click));
+    let button_tag = button.arena.borrow()[button.root].data.tag.unwrap();
+    let dom = Dom::div().with_child(button);
+
+    let mut css = Css::new_from_string(".button { background-color: #0000ff; } .button:hover { background-color: #ff0000; }").unwrap();
+    // The first frame was laid out
+    css.needs_relayout = false;
+
+    let background_of_button = |css: &Css| {
+        let ui_description = TestLayout::style_dom(&dom, css);
+        let arena = ui_description.ui_descr_arena.borrow();
+        let node_id = arena.linear_iter().find(|node_id| arena[*node_id].data.tag == Some(button_tag)).unwrap();
+        ui_description.computed_style(node_id).background_color
+    };
+
+    assert_eq!(background_of_button(&css), Some(BackgroundColor(ColorU { r: 0, g: 0, b: 255, a: 255 })));
+
+    // Hovering over the button only changes the background color, so the frame
+    // has to be redrawn, but the constraints don't have to be solved again
+    let mut hovered = BTreeSet::new();
+    hovered.insert(button_tag);
+    assert!(css.set_hovered_tags(hovered.clone()));
+    assert!(!css.will_relayout());
+    assert_eq!(background_of_button(&css), Some(BackgroundColor(ColorU { r: 255, g: 0, b: 0, a: 255 })));
+
+    // Moving the mouse within the button doesn't change anything
+    assert!(!css.set_hovered_tags(hovered));
+
+    assert!(css.set_hovered_tags(BTreeSet::new()));
+    assert!(!css.will_relayout());
+    assert_eq!(background_of_button(&css), Some(BackgroundColor(ColorU { r: 0, g: 0, b: 255, a: 255 })));
+
+    // A `:hover` rule that changes the width needs a relayout
+    let mut css = Css::new_from_string(".button:hover { width: 200px; }").unwrap();
+    css.needs_relayout = false;
+    let mut hovered = BTreeSet::new();
+    hovered.insert(button_tag);
+    assert!(css.set_hovered_tags(hovered));
+    assert!(css.will_relayout());
+}
+
+#[test]
+fn test_switching_color_themes_only_repaints_changed_nodes() {
+    use dom::{Dom, NodeType};
+    use traits::Layout;
+    use id_tree::NodeId;
+    use test_utils::TestLayout;
+
+    let light = "
+        div { width: 100px; }
+        .card { background-color: #ffffff; }
+        .footer { background-color: #cccccc; }
+    ";
+    let dark = "
+        div { width: 100px; }
+        .card { background-color: #222222; }
+        .footer { background-color: #cccccc; }
+    ";
+
+    let dom = Dom::<TestLayout>::div()
+        .with_child(Dom::new(NodeType::Div).with_class("card"))
+        .with_child(Dom::new(NodeType::Div).with_class("footer"));
+    let card = NodeId::new(1);
+
+    let mut css = Css::new_from_string(light).unwrap();
+    let ui_description = TestLayout::style_dom(&dom, &css);
+    // The first frame was laid out
+    css.needs_relayout = false;
+
+    // Only the background of the card changed: it is repainted, nothing is re-laid out
+    let changes = css.switch_theme(Css::new_from_string(dark).unwrap(), &ui_description);
+    assert_eq!(changes.repaint_nodes, vec![card].into_iter().collect());
+    assert!(changes.relayout_nodes.is_empty());
+    assert!(!css.will_relayout());
+    assert_eq!(css, {
+        let mut dark = Css::new_from_string(dark).unwrap();
+        dark.needs_relayout = false;
+        dark.relayout_causes.clear();
+        dark
+    });
+
+    // A theme that changes the width of the divs has to be re-laid out
+    let ui_description = TestLayout::style_dom(&dom, &css);
+    let changes = css.switch_theme(Css::new_from_string("div { width: 200px; }").unwrap(), &ui_description);
+    assert_eq!(changes.relayout_nodes.len(), 3);
+    assert!(css.will_relayout());
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn test_layout_thrashing_warning_names_the_override() {
+    use css_parser::{BackgroundColor, ColorU, LayoutWidth, PixelValue, CssMetric};
+
+    let width = |px: f32| ParsedCssProperty::Width(LayoutWidth(PixelValue::from_metric(CssMetric::Px, px)));
+    let color = |r: u8| ParsedCssProperty::BackgroundColor(BackgroundColor(ColorU { r: r, g: 0, b: 0, a: 255 }));
+
+    let mut css = Css::empty();
+
+    // A callback toggles the width (and a color, which doesn't affect the layout) on every frame
+    for frame in 1..=LAYOUT_THRASHING_FRAMES {
+        let mut overrides = FastHashMap::default();
+        overrides.insert(String::from("sidebar_width"), width(if frame % 2 == 0 { 100.0 } else { 200.0 }));
+        overrides.insert(String::from("sidebar_color"), color(frame as u8));
+        css.set_dynamic_css_overrides(overrides);
+        assert!(css.will_relayout());
+
+        let warning = css.relayout_done();
+        if frame < LAYOUT_THRASHING_FRAMES {
+            assert_eq!(warning, None);
+        } else {
+            assert_eq!(warning, Some(LayoutThrashingWarning {
+                frames: LAYOUT_THRASHING_FRAMES,
+                causes: vec![RelayoutCause::DynamicOverride(String::from("sidebar_width"))],
+            }));
+        }
+    }
+
+    // A frame without a re-layout resets the counter
+    assert_eq!(css.relayout_done(), None);
+    assert_eq!(css.thrashing_detector, LayoutThrashingDetector::default());
+}
+
+#[test]
+fn test_padding_resolves_safe_area_insets() {
+    use dom::Dom;
+    use id_tree::NodeId;
+    use css_parser::LayoutPadding;
+    use test_utils::TestLayout;
+
+    let mut css = Css::new_from_string("
+        .header { padding: env(safe-area-inset-top) 10px 0px; }
+    ").unwrap();
+    css.needs_relayout = false;
+    let dom = Dom::<TestLayout>::div().with_class("header");
+
+    // On desktop, the insets are zero
+    let ui_description = TestLayout::style_dom(&dom, &css);
+    assert_eq!(ui_description.computed_layout(NodeId::new(0)).padding, Some(LayoutPadding { top: 0.0, right: 10.0, bottom: 0.0, left: 10.0 }));
+
+    // The header moves below the notch of the window
+    css.set_safe_area_insets(SafeAreaInsets { top: 44.0, right: 0.0, bottom: 34.0, left: 0.0 });
+    assert!(css.will_relayout());
+    let ui_description = TestLayout::style_dom(&dom, &css);
+    assert_eq!(ui_description.computed_layout(NodeId::new(0)).padding, Some(LayoutPadding { top: 44.0, right: 10.0, bottom: 0.0, left: 10.0 }));
 }
\ No newline at end of file
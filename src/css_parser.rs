@@ -1,6 +1,9 @@
 //! Contains utilities to convert strings (CSS strings) to servo types
 
-use std::num::{ParseIntError, ParseFloatError};
+use std::{
+    num::{ParseIntError, ParseFloatError},
+    time::Duration,
+};
 pub use {
     euclid::{TypedSize2D, SideOffsets2D},
     webrender::api::{
@@ -72,6 +75,17 @@ macro_rules! typed_pixel_value_parser {
     )
 }
 
+/// Same as `typed_pixel_value_parser`, but also accepts percentages, see `parse_layout_size_value`
+macro_rules! typed_size_value_parser {
+    ($fn:ident, $return:ident) => (
+        fn $fn<'a>(input: &'a str)
+        -> Result<$return, PixelParseError<'a>>
+        {
+            parse_layout_size_value(input).and_then(|e| Ok($return(e)))
+        }
+    )
+}
+
 /// A successfully parsed CSS property
 #[derive(Debug, Clone, PartialEq)]
 pub enum ParsedCssProperty {
@@ -79,12 +93,19 @@ pub enum ParsedCssProperty {
     BackgroundColor(BackgroundColor),
     TextColor(TextColor),
     Border(BorderWidths, BorderDetails),
+    BorderSide(BorderSideOverride),
     Background(Background),
+    BackgroundSize(BackgroundSize),
+    BackgroundRepeat(BackgroundRepeat),
     FontSize(FontSize),
     FontFamily(FontFamily),
+    FontWeight(FontWeight),
+    FontStyle(FontStyle),
     TextAlign(TextAlignmentHorz),
     BoxShadow(Option<BoxShadowPreDisplayItem>),
+    TextShadow(Option<BoxShadowPreDisplayItem>),
     LineHeight(LineHeight),
+    TextIndent(TextIndent),
 
     Width(LayoutWidth),
     Height(LayoutHeight),
@@ -92,6 +113,12 @@ pub enum ParsedCssProperty {
     MinHeight(LayoutMinHeight),
     MaxWidth(LayoutMaxWidth),
     MaxHeight(LayoutMaxHeight),
+    AspectRatio(LayoutAspectRatio),
+    Padding(CssPadding),
+    PaddingSide(PaddingSideOverride),
+    Margin(CssMargin),
+    MarginSide(MarginSideOverride),
+    Gap(LayoutGap),
 
     FlexWrap(LayoutWrap),
     FlexDirection(LayoutDirection),
@@ -99,15 +126,65 @@ pub enum ParsedCssProperty {
     AlignItems(LayoutAlignItems),
     AlignContent(LayoutAlignContent),
     Overflow(LayoutOverflow),
+    TextOverflow(TextOverflowMode),
+    ContentVisibility(ContentVisibility),
+    BackdropFilter(BackdropFilter),
+    Filter(Filter),
+    Transition(Transition),
+    OverflowWrap(OverflowWrap),
+    WordBreak(WordBreak),
+    TextDecoration(TextDecorationLines),
+    Opacity(Opacity),
+    Visibility(Visibility),
+    Direction(TextDirection),
+    WritingMode(WritingMode),
+    UserSelect(UserSelect),
+    OverscrollBehavior(OverscrollBehavior),
+    Resize(LayoutResize),
+    TintColor(TintColor),
+    CaretColor(CaretColor),
+    Display(LayoutDisplay),
+    ConstraintStrength(LayoutConstraintStrength),
+    Contain(LayoutContain),
+    BoxSizing(LayoutBoxSizing),
+    Appearance(Appearance),
 }
 
 impl_from_no_lifetimes!(BorderRadius, ParsedCssProperty::BorderRadius);
+impl_from_no_lifetimes!(BorderSideOverride, ParsedCssProperty::BorderSide);
 impl_from_no_lifetimes!(Background, ParsedCssProperty::Background);
+impl_from_no_lifetimes!(BackgroundSize, ParsedCssProperty::BackgroundSize);
+impl_from_no_lifetimes!(BackgroundRepeat, ParsedCssProperty::BackgroundRepeat);
 impl_from_no_lifetimes!(FontSize, ParsedCssProperty::FontSize);
 impl_from_no_lifetimes!(FontFamily, ParsedCssProperty::FontFamily);
+impl_from_no_lifetimes!(FontWeight, ParsedCssProperty::FontWeight);
+impl_from_no_lifetimes!(FontStyle, ParsedCssProperty::FontStyle);
 impl_from_no_lifetimes!(LayoutOverflow, ParsedCssProperty::Overflow);
+impl_from_no_lifetimes!(TextOverflowMode, ParsedCssProperty::TextOverflow);
+impl_from_no_lifetimes!(ContentVisibility, ParsedCssProperty::ContentVisibility);
+impl_from_no_lifetimes!(BackdropFilter, ParsedCssProperty::BackdropFilter);
+impl_from_no_lifetimes!(Filter, ParsedCssProperty::Filter);
+impl_from_no_lifetimes!(Transition, ParsedCssProperty::Transition);
+impl_from_no_lifetimes!(OverflowWrap, ParsedCssProperty::OverflowWrap);
+impl_from_no_lifetimes!(WordBreak, ParsedCssProperty::WordBreak);
+impl_from_no_lifetimes!(TextDecorationLines, ParsedCssProperty::TextDecoration);
+impl_from_no_lifetimes!(Opacity, ParsedCssProperty::Opacity);
+impl_from_no_lifetimes!(Visibility, ParsedCssProperty::Visibility);
+impl_from_no_lifetimes!(TextDirection, ParsedCssProperty::Direction);
+impl_from_no_lifetimes!(WritingMode, ParsedCssProperty::WritingMode);
+impl_from_no_lifetimes!(UserSelect, ParsedCssProperty::UserSelect);
+impl_from_no_lifetimes!(OverscrollBehavior, ParsedCssProperty::OverscrollBehavior);
+impl_from_no_lifetimes!(LayoutResize, ParsedCssProperty::Resize);
+impl_from_no_lifetimes!(TintColor, ParsedCssProperty::TintColor);
+impl_from_no_lifetimes!(CaretColor, ParsedCssProperty::CaretColor);
+impl_from_no_lifetimes!(LayoutDisplay, ParsedCssProperty::Display);
+impl_from_no_lifetimes!(LayoutConstraintStrength, ParsedCssProperty::ConstraintStrength);
+impl_from_no_lifetimes!(LayoutContain, ParsedCssProperty::Contain);
+impl_from_no_lifetimes!(LayoutBoxSizing, ParsedCssProperty::BoxSizing);
+impl_from_no_lifetimes!(Appearance, ParsedCssProperty::Appearance);
 impl_from_no_lifetimes!(TextAlignmentHorz, ParsedCssProperty::TextAlign);
 impl_from_no_lifetimes!(LineHeight, ParsedCssProperty::LineHeight);
+impl_from_no_lifetimes!(TextIndent, ParsedCssProperty::TextIndent);
 
 impl_from_no_lifetimes!(LayoutWidth, ParsedCssProperty::Width);
 impl_from_no_lifetimes!(LayoutHeight, ParsedCssProperty::Height);
@@ -115,6 +192,10 @@ impl_from_no_lifetimes!(LayoutMinWidth, ParsedCssProperty::MinWidth);
 impl_from_no_lifetimes!(LayoutMinHeight, ParsedCssProperty::MinHeight);
 impl_from_no_lifetimes!(LayoutMaxWidth, ParsedCssProperty::MaxWidth);
 impl_from_no_lifetimes!(LayoutMaxHeight, ParsedCssProperty::MaxHeight);
+impl_from_no_lifetimes!(LayoutAspectRatio, ParsedCssProperty::AspectRatio);
+impl_from_no_lifetimes!(LayoutGap, ParsedCssProperty::Gap);
+impl_from_no_lifetimes!(CssPadding, ParsedCssProperty::Padding);
+impl_from_no_lifetimes!(PaddingSideOverride, ParsedCssProperty::PaddingSide);
 
 impl_from_no_lifetimes!(LayoutWrap, ParsedCssProperty::FlexWrap);
 impl_from_no_lifetimes!(LayoutDirection, ParsedCssProperty::FlexDirection);
@@ -148,11 +229,31 @@ impl ParsedCssProperty {
             "background-color"  => Ok(parse_css_background_color(value)?.into()),
             "color"             => Ok(parse_css_text_color(value)?.into()),
             "border"            => Ok(parse_css_border(value)?.into()),
+            "border-top"        => Ok(parse_css_border_side(value, BorderEdge::Top)?.into()),
+            "border-right"      => Ok(parse_css_border_side(value, BorderEdge::Right)?.into()),
+            "border-bottom"     => Ok(parse_css_border_side(value, BorderEdge::Bottom)?.into()),
+            "border-left"       => Ok(parse_css_border_side(value, BorderEdge::Left)?.into()),
+            "border-top-width"  => Ok(parse_css_border_side_width(value, BorderEdge::Top)?.into()),
+            "border-right-width"=> Ok(parse_css_border_side_width(value, BorderEdge::Right)?.into()),
+            "border-bottom-width"=> Ok(parse_css_border_side_width(value, BorderEdge::Bottom)?.into()),
+            "border-left-width" => Ok(parse_css_border_side_width(value, BorderEdge::Left)?.into()),
+            "border-top-style"  => Ok(parse_css_border_side_style(value, BorderEdge::Top)?.into()),
+            "border-right-style"=> Ok(parse_css_border_side_style(value, BorderEdge::Right)?.into()),
+            "border-bottom-style"=> Ok(parse_css_border_side_style(value, BorderEdge::Bottom)?.into()),
+            "border-left-style" => Ok(parse_css_border_side_style(value, BorderEdge::Left)?.into()),
+            "border-top-color"  => Ok(parse_css_border_side_color(value, BorderEdge::Top)?.into()),
+            "border-right-color"=> Ok(parse_css_border_side_color(value, BorderEdge::Right)?.into()),
+            "border-bottom-color"=> Ok(parse_css_border_side_color(value, BorderEdge::Bottom)?.into()),
+            "border-left-color" => Ok(parse_css_border_side_color(value, BorderEdge::Left)?.into()),
             "background"        => Ok(parse_css_background(value)?.into()),
+            "background-size"   => Ok(parse_css_background_size(value)?.into()),
+            "background-repeat" => Ok(parse_css_background_repeat(value)?.into()),
             "font-size"         => Ok(parse_css_font_size(value)?.into()),
             "font-family"       => Ok(parse_css_font_family(value)?.into()),
             "box-shadow"        => Ok(parse_css_box_shadow(value)?.into()),
+            "text-shadow"       => Ok(ParsedCssProperty::TextShadow(parse_css_text_shadow(value)?)),
             "line-height"       => Ok(parse_line_height(value)?.into()),
+            "text-indent"       => Ok(parse_text_indent(value)?.into()),
 
             "width"             => Ok(parse_layout_width(value)?.into()),
             "height"            => Ok(parse_layout_height(value)?.into()),
@@ -160,6 +261,19 @@ impl ParsedCssProperty {
             "min-height"        => Ok(parse_layout_min_height(value)?.into()),
             "max-width"         => Ok(parse_layout_max_width(value)?.into()),
             "max-height"        => Ok(parse_layout_max_height(value)?.into()),
+            "aspect-ratio"      => Ok(parse_layout_aspect_ratio(value)?.into()),
+            "padding"           => Ok(parse_layout_padding(value)?.into()),
+            "padding-top"       => Ok(parse_layout_padding_side(value, BorderEdge::Top)?.into()),
+            "padding-right"     => Ok(parse_layout_padding_side(value, BorderEdge::Right)?.into()),
+            "padding-bottom"    => Ok(parse_layout_padding_side(value, BorderEdge::Bottom)?.into()),
+            "padding-left"      => Ok(parse_layout_padding_side(value, BorderEdge::Left)?.into()),
+            // `margin` has the same syntax as `padding`
+            "margin"            => Ok(ParsedCssProperty::Margin(parse_layout_padding(value)?)),
+            "margin-top"        => Ok(ParsedCssProperty::MarginSide(parse_layout_padding_side(value, BorderEdge::Top)?)),
+            "margin-right"      => Ok(ParsedCssProperty::MarginSide(parse_layout_padding_side(value, BorderEdge::Right)?)),
+            "margin-bottom"     => Ok(ParsedCssProperty::MarginSide(parse_layout_padding_side(value, BorderEdge::Bottom)?)),
+            "margin-left"       => Ok(ParsedCssProperty::MarginSide(parse_layout_padding_side(value, BorderEdge::Left)?)),
+            "gap"               => Ok(parse_layout_gap(value)?.into()),
 
             "flex-wrap"         => Ok(parse_layout_wrap(value)?.into()),
             "flex-direction"    => Ok(parse_layout_direction(value)?.into()),
@@ -187,11 +301,50 @@ impl ParsedCssProperty {
                     vertical: TextOverflowBehaviour::Modified(overflow_y),
                 }.into())
             },
+            "font-weight"       => Ok(parse_css_font_weight(value)?.into()),
+            "font-style"        => Ok(parse_css_font_style(value)?.into()),
             "text-align"        => Ok(parse_layout_text_align(value)?.into()),
+            "text-overflow"     => Ok(parse_text_overflow_mode(value)?.into()),
+            "content-visibility"=> Ok(parse_content_visibility(value)?.into()),
+            "backdrop-filter"   => Ok(parse_css_backdrop_filter(value)?.into()),
+            "filter"            => Ok(parse_css_filter(value)?.into()),
+            "transition"        => Ok(parse_css_transition(value)?.into()),
+            // `word-wrap` is the legacy name of `overflow-wrap`
+            "overflow-wrap" |
+            "word-wrap"         => Ok(parse_overflow_wrap(value)?.into()),
+            "word-break"        => Ok(parse_word_break(value)?.into()),
+            "text-decoration"   => Ok(parse_text_decoration_lines(value)?.into()),
+            "opacity"           => Ok(parse_css_opacity(value)?.into()),
+            "visibility"        => Ok(parse_visibility(value)?.into()),
+            "direction"         => Ok(parse_text_direction(value)?.into()),
+            "writing-mode"      => Ok(parse_writing_mode(value)?.into()),
+            "user-select"       => Ok(parse_user_select(value)?.into()),
+            "overscroll-behavior" => Ok(parse_overscroll_behavior(value)?.into()),
+            "resize"            => Ok(parse_layout_resize(value)?.into()),
+            "display"           => Ok(parse_layout_display(value)?.into()),
+            "-azul-constraint-strength" => Ok(parse_layout_constraint_strength(value)?.into()),
+            "contain"           => Ok(parse_layout_contain(value)?.into()),
+            "box-sizing"        => Ok(parse_layout_box_sizing(value)?.into()),
+            "appearance"        => Ok(parse_appearance(value)?.into()),
+            "-azul-tint-color"  => Ok(parse_css_tint_color(value)?.into()),
+            "caret-color"       => Ok(parse_css_caret_color(value)?.into()),
 
             _ => Err((key, value).into())
         }
     }
+
+    /// Returns whether changing this property changes the layout, i.e. the sizes of the
+    /// rectangles have to be solved again. Otherwise the frame only has to be repainted.
+    pub fn affects_layout(&self) -> bool {
+        use self::ParsedCssProperty::*;
+        match *self {
+            Border(..) | BorderSide(_) |
+            Width(_) | Height(_) | MinWidth(_) | MinHeight(_) | MaxWidth(_) | MaxHeight(_) |
+            AspectRatio(_) | Padding(_) | PaddingSide(_) | Margin(_) | MarginSide(_) | Gap(_) | Display(_) | ConstraintStrength(_) | Contain(_) | BoxSizing(_) |
+            FlexWrap(_) | FlexDirection(_) | JustifyContent(_) | AlignItems(_) | AlignContent(_) => true,
+            _ => false,
+        }
+    }
 }
 
 /// Wrapper for the `overflow-{x,y}` + `overflow` property
@@ -227,6 +380,18 @@ impl LayoutOverflow {
             TextOverflowBehaviour::NotModified => false,
         }
     }
+
+    /// Returns if a horizontal scrollbar should be shown, given if the content
+    /// is currently overflowing the rectangle horizontally
+    pub fn needs_horizontal_scrollbar(&self, currently_overflowing: bool) -> bool {
+        self.horizontal.needs_scrollbar(currently_overflowing)
+    }
+
+    /// Returns if a vertical scrollbar should be shown, given if the content
+    /// is currently overflowing the rectangle vertically
+    pub fn needs_vertical_scrollbar(&self, currently_overflowing: bool) -> bool {
+        self.vertical.needs_scrollbar(currently_overflowing)
+    }
 }
 
 /// Error containing all sub-errors that could happen during CSS parsing
@@ -244,6 +409,10 @@ pub enum CssParsingError<'a> {
     CssBackgroundParseError(CssBackgroundParseError<'a>),
     CssColorParseError(CssColorParseError<'a>),
     CssBorderRadiusParseError(CssBorderRadiusParseError<'a>),
+    CssPaddingParseError(CssPaddingParseError<'a>),
+    CssBackdropFilterParseError(CssBackdropFilterParseError<'a>),
+    CssFilterParseError(CssFilterParseError<'a>),
+    CssTransitionParseError(CssTransitionParseError<'a>),
     /// Key is not supported, i.e. `#div { aldfjasdflk: 400px }` results in an
     /// `UnsupportedCssKey("aldfjasdflk", "400px")` error
     UnsupportedCssKey(&'a str, &'a str),
@@ -258,6 +427,10 @@ impl_from!(CssImageParseError, CssParsingError::CssImageParseError);
 impl_from!(CssFontFamilyParseError, CssParsingError::CssFontFamilyParseError);
 impl_from!(CssBackgroundParseError, CssParsingError::CssBackgroundParseError);
 impl_from!(CssBorderRadiusParseError, CssParsingError::CssBorderRadiusParseError);
+impl_from!(CssPaddingParseError, CssParsingError::CssPaddingParseError);
+impl_from!(CssBackdropFilterParseError, CssParsingError::CssBackdropFilterParseError);
+impl_from!(CssFilterParseError, CssParsingError::CssFilterParseError);
+impl_from!(CssTransitionParseError, CssParsingError::CssTransitionParseError);
 
 impl<'a> From<(&'a str, &'a str)> for CssParsingError<'a> {
     fn from((a, b): (&'a str, &'a str)) -> Self {
@@ -292,13 +465,26 @@ impl PixelValue {
         }
     }
 
-    pub fn to_pixels(&self) -> f32 {
+    /// Returns the value in pixels, or `None` for a percentage, which can only be
+    /// resolved against the size that it is relative to - see `to_pixels_relative_to`
+    pub fn to_pixels(&self) -> Option<f32> {
         match self.metric {
-            CssMetric::Px => { self.number as f32 / 1000.0 },
-            CssMetric::Pt => { (self.number as f32 / 1000.0) * PT_TO_PX },
-            CssMetric::Em => { (self.number as f32 / 1000.0) * EM_HEIGHT },
+            CssMetric::Px => Some(self.number as f32 / 1000.0),
+            CssMetric::Pt => Some((self.number as f32 / 1000.0) * PT_TO_PX),
+            CssMetric::Em => Some((self.number as f32 / 1000.0) * EM_HEIGHT),
+            CssMetric::Percent => None,
         }
     }
+
+    /// Same as `to_pixels`, but percentages are resolved against the `reference`
+    /// size, i.e. the width of the parent for a `width: 50%`
+    pub fn to_pixels_relative_to(&self, reference: f32) -> f32 {
+        self.to_pixels().unwrap_or((self.number as f32 / 1000.0) / 100.0 * reference)
+    }
+
+    pub fn is_percentage(&self) -> bool {
+        self.metric == CssMetric::Percent
+    }
 }
 
 /// "100%" or "1.0" value
@@ -313,6 +499,8 @@ pub enum CssMetric {
     Px,
     Pt,
     Em,
+    /// Only used for the `width` / `height` properties, relative to the size of the parent
+    Percent,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -323,6 +511,16 @@ pub enum CssBorderRadiusParseError<'a> {
 
 impl_from!(PixelParseError, CssBorderRadiusParseError::PixelParseError);
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CssPaddingParseError<'a> {
+    TooManyValues(&'a str),
+    PixelParseError(PixelParseError<'a>),
+    /// `env(...)` with a variable other than the `safe-area-inset-*` variables
+    UnknownEnvironmentVariable(&'a str),
+}
+
+impl_from!(PixelParseError, CssPaddingParseError::PixelParseError);
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum CssColorParseError<'a> {
     InvalidColor(&'a str),
@@ -356,6 +554,8 @@ pub enum CssBorderParseError<'a> {
 pub enum CssShadowParseError<'a> {
     InvalidSingleStatement(&'a str),
     TooManyComponents(&'a str),
+    /// `text-shadow` doesn't support `inset` shadows or a spread radius
+    InvalidTextShadow(&'a str),
     ValueParseErr(PixelParseError<'a>),
     ColorParseError(CssColorParseError<'a>),
 }
@@ -363,6 +563,190 @@ pub enum CssShadowParseError<'a> {
 impl_from!(PixelParseError, CssShadowParseError::ValueParseErr);
 impl_from!(CssColorParseError, CssShadowParseError::ColorParseError);
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum CssBackdropFilterParseError<'a> {
+    /// The filter function is not supported, i.e. `sepia(1)`
+    InvalidFilter(&'a str),
+    /// The filter has no or no closing brace, i.e. `blur(10px`
+    UnclosedBrace(&'a str),
+    PixelParseError(PixelParseError<'a>),
+}
+
+impl_from!(PixelParseError, CssBackdropFilterParseError::PixelParseError);
+
+/// parse the backdrop-filter, i.e. "blur(10px)"
+fn parse_css_backdrop_filter<'a>(input: &'a str)
+-> Result<BackdropFilter, CssBackdropFilterParseError<'a>>
+{
+    let mut input_iter = input.splitn(2, '(');
+    let filter_type = input_iter.next().unwrap_or(input).trim();
+
+    let brace_contents = match input_iter.next() {
+        Some(s) if s.ends_with(')') => s[..s.len() - 1].trim(),
+        _ => return Err(CssBackdropFilterParseError::UnclosedBrace(input)),
+    };
+
+    match filter_type {
+        "blur" => Ok(BackdropFilter::Blur(parse_pixel_value(brace_contents)?)),
+        _ => Err(CssBackdropFilterParseError::InvalidFilter(filter_type)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CssFilterParseError<'a> {
+    /// The filter function is not supported, i.e. `sepia(1)`
+    InvalidFilter(&'a str),
+    /// The filter has no or no closing brace, i.e. `drop-shadow(5px 5px`
+    UnclosedBrace(&'a str),
+    /// `drop-shadow` doesn't support `inset` shadows or a spread radius
+    InvalidDropShadow(&'a str),
+    CssShadowParseError(CssShadowParseError<'a>),
+}
+
+impl_from!(CssShadowParseError, CssFilterParseError::CssShadowParseError);
+
+/// parse the filter, i.e. "drop-shadow(5px 5px 10px #000000)"
+fn parse_css_filter<'a>(input: &'a str)
+-> Result<Filter, CssFilterParseError<'a>>
+{
+    let mut input_iter = input.splitn(2, '(');
+    let filter_type = input_iter.next().unwrap_or(input).trim();
+
+    let brace_contents = match input_iter.next() {
+        Some(s) if s.ends_with(')') => s[..s.len() - 1].trim(),
+        _ => return Err(CssFilterParseError::UnclosedBrace(input)),
+    };
+
+    match filter_type {
+        "drop-shadow" => match parse_css_box_shadow(brace_contents)? {
+            Some(shadow) if shadow.clip_mode == BoxShadowClipMode::Outset && shadow.spread_radius == 0.0 => Ok(Filter::DropShadow(shadow)),
+            _ => Err(CssFilterParseError::InvalidDropShadow(brace_contents)),
+        },
+        _ => Err(CssFilterParseError::InvalidFilter(filter_type)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CssTransitionParseError<'a> {
+    /// The transition has no property or no duration, i.e. `background-color`
+    MissingComponents(&'a str),
+    /// Too many components, i.e. `background-color 200ms ease-in 10ms`
+    TooManyComponents(&'a str),
+    /// The duration is not in `ms` or `s`, i.e. `200px`
+    InvalidDuration(&'a str),
+    InvalidTimingFunction(InvalidValueErr<'a>),
+}
+
+impl_from!(InvalidValueErr, CssTransitionParseError::InvalidTimingFunction);
+
+/// parse a transition, i.e. "background-color 200ms" or "width 0.5s ease-in"
+fn parse_css_transition<'a>(input: &'a str)
+-> Result<Transition, CssTransitionParseError<'a>>
+{
+    let mut components = split_whitespace_outside_of_braces(input).into_iter();
+
+    let property = components.next().ok_or(CssTransitionParseError::MissingComponents(input))?;
+    let duration = components.next().ok_or(CssTransitionParseError::MissingComponents(input))?;
+    let timing_function = match components.next() {
+        Some(timing_function) => parse_transition_timing_function(timing_function)?,
+        None => TransitionTimingFunction::default(),
+    };
+
+    if components.next().is_some() {
+        return Err(CssTransitionParseError::TooManyComponents(input));
+    }
+
+    Ok(Transition {
+        property: property.to_string(),
+        duration: parse_css_duration(duration).ok_or(CssTransitionParseError::InvalidDuration(duration))?,
+        timing_function: timing_function,
+    })
+}
+
+/// Splits the input at whitespace, but not inside of braces,
+/// i.e. `"width 1s cubic-bezier(0, 0, 1, 1)"` is split into three parts
+fn split_whitespace_outside_of_braces<'a>(input: &'a str) -> Vec<&'a str> {
+    let mut components = Vec::new();
+    let mut depth = 0_usize;
+    let mut start = None;
+
+    for (idx, c) in input.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            c if c.is_whitespace() && depth == 0 => {
+                if let Some(s) = start.take() {
+                    components.push(&input[s..idx]);
+                }
+                continue;
+            },
+            _ => { },
+        }
+        if start.is_none() {
+            start = Some(idx);
+        }
+    }
+
+    if let Some(s) = start {
+        components.push(&input[s..]);
+    }
+
+    components
+}
+
+/// parse a timing function, i.e. "ease-in" or "cubic-bezier(0.42, 0, 0.58, 1)"
+fn parse_transition_timing_function<'a>(input: &'a str)
+-> Result<TransitionTimingFunction, InvalidValueErr<'a>>
+{
+    let input = input.trim();
+
+    if !input.starts_with("cubic-bezier(") {
+        return parse_named_timing_function(input);
+    }
+
+    if !input.ends_with(')') {
+        return Err(InvalidValueErr(input));
+    }
+
+    let brace_contents = &input["cubic-bezier(".len()..(input.len() - 1)];
+    let mut points = [0.0_f32; 4];
+    let mut num_points = 0;
+
+    for value in brace_contents.split(',') {
+        if num_points >= points.len() {
+            return Err(InvalidValueErr(input));
+        }
+        points[num_points] = value.trim().parse::<f32>().map_err(|_| InvalidValueErr(input))?;
+        num_points += 1;
+    }
+
+    let (x1, y1, x2, y2) = (points[0], points[1], points[2], points[3]);
+
+    // The x coordinates have to be in the range 0.0 - 1.0, otherwise
+    // the curve isn't a function of the time anymore
+    if num_points != 4 || !(x1 >= 0.0 && x1 <= 1.0 && x2 >= 0.0 && x2 <= 1.0) || y1.is_nan() || y2.is_nan() {
+        return Err(InvalidValueErr(input));
+    }
+
+    Ok(TransitionTimingFunction::CubicBezier(x1, y1, x2, y2))
+}
+
+/// parse a duration, i.e. "200ms" or "0.2s"
+fn parse_css_duration(input: &str) -> Option<Duration> {
+    let (number, millis_per_unit) = if input.ends_with("ms") {
+        (&input[..input.len() - 2], 1.0)
+    } else if input.ends_with('s') {
+        (&input[..input.len() - 1], 1000.0)
+    } else {
+        return None;
+    };
+
+    match number.parse::<f32>() {
+        Ok(n) if n >= 0.0 => Some(Duration::from_millis((n * millis_per_unit).round() as u64)),
+        _ => None,
+    }
+}
+
 /// parse the border-radius like "5px 10px" or "5px 10px 6px 10px"
 fn parse_css_border_radius<'a>(input: &'a str)
 -> Result<BorderRadius, CssBorderRadiusParseError<'a>>
@@ -375,7 +759,7 @@ fn parse_css_border_radius<'a>(input: &'a str)
             // One value - border-radius: 15px;
             // (the value applies to all four corners, which are rounded equally:
 
-            let uniform_radius = parse_pixel_value(components.next().unwrap())?.to_pixels();
+            let uniform_radius = parse_pixels(components.next().unwrap())?;
             Ok(BorderRadius::uniform(uniform_radius))
         },
         2 => {
@@ -383,8 +767,8 @@ fn parse_css_border_radius<'a>(input: &'a str)
             // (first value applies to top-left and bottom-right corners,
             // and the second value applies to top-right and bottom-left corners):
 
-            let top_left_bottom_right = parse_pixel_value(components.next().unwrap())?.to_pixels();
-            let top_right_bottom_left = parse_pixel_value(components.next().unwrap())?.to_pixels();
+            let top_left_bottom_right = parse_pixels(components.next().unwrap())?;
+            let top_right_bottom_left = parse_pixels(components.next().unwrap())?;
 
             Ok(BorderRadius{
                 top_left: LayoutSize::new(top_left_bottom_right, top_left_bottom_right),
@@ -398,9 +782,9 @@ fn parse_css_border_radius<'a>(input: &'a str)
             // (first value applies to top-left corner,
             // second value applies to top-right and bottom-left corners,
             // and third value applies to bottom-right corner):
-            let top_left = parse_pixel_value(components.next().unwrap())?.to_pixels();
-            let top_right_bottom_left = parse_pixel_value(components.next().unwrap())?.to_pixels();
-            let bottom_right = parse_pixel_value(components.next().unwrap())?.to_pixels();
+            let top_left = parse_pixels(components.next().unwrap())?;
+            let top_right_bottom_left = parse_pixels(components.next().unwrap())?;
+            let bottom_right = parse_pixels(components.next().unwrap())?;
 
             Ok(BorderRadius{
                 top_left: LayoutSize::new(top_left, top_left),
@@ -415,10 +799,10 @@ fn parse_css_border_radius<'a>(input: &'a str)
             //  second value applies to top-right corner,
             //  third value applies to bottom-right corner,
             //  fourth value applies to bottom-left corner)
-            let top_left = parse_pixel_value(components.next().unwrap())?.to_pixels();
-            let top_right = parse_pixel_value(components.next().unwrap())?.to_pixels();
-            let bottom_right = parse_pixel_value(components.next().unwrap())?.to_pixels();
-            let bottom_left = parse_pixel_value(components.next().unwrap())?.to_pixels();
+            let top_left = parse_pixels(components.next().unwrap())?;
+            let top_right = parse_pixels(components.next().unwrap())?;
+            let bottom_right = parse_pixels(components.next().unwrap())?;
+            let bottom_left = parse_pixels(components.next().unwrap())?;
 
             Ok(BorderRadius{
                 top_left: LayoutSize::new(top_left, top_left),
@@ -433,6 +817,57 @@ fn parse_css_border_radius<'a>(input: &'a str)
     }
 }
 
+/// Parse a CSS padding, such as "10px 20px", in the order top, right, bottom, left
+/// (with the same rules for omitted values as in the `margin` / `padding` of CSS)
+fn parse_layout_padding<'a>(input: &'a str)
+-> Result<CssPadding, CssPaddingParseError<'a>>
+{
+    let mut values = Vec::<PaddingValue>::new();
+    for component in input.split_whitespace() {
+        values.push(parse_padding_value(component)?);
+    }
+
+    let (top, right, bottom, left) = match values.len() {
+        // padding: 10px;
+        1 => (values[0], values[0], values[0], values[0]),
+        // padding: 10px 20px; (top and bottom, left and right)
+        2 => (values[0], values[1], values[0], values[1]),
+        // padding: 10px 20px 30px; (top, left and right, bottom)
+        3 => (values[0], values[1], values[2], values[1]),
+        // padding: 10px 20px 30px 40px;
+        4 => (values[0], values[1], values[2], values[3]),
+        _ => return Err(CssPaddingParseError::TooManyValues(input)),
+    };
+
+    Ok(CssPadding { top, right, bottom, left })
+}
+
+/// Parse a single side of a padding or margin (`padding-top`, `margin-left`, etc.), i.e. "10px"
+fn parse_layout_padding_side<'a>(input: &'a str, edge: BorderEdge)
+-> Result<PaddingSideOverride, CssPaddingParseError<'a>>
+{
+    Ok(PaddingSideOverride { edge: edge, value: parse_padding_value(input)? })
+}
+
+/// Parses one side of a padding, i.e. `10px` or `env(safe-area-inset-top)`
+fn parse_padding_value<'a>(input: &'a str)
+-> Result<PaddingValue, CssPaddingParseError<'a>>
+{
+    if input.starts_with("env(") && input.ends_with(')') {
+        use self::EnvironmentVariable::*;
+        let variable = match input["env(".len()..input.len() - 1].trim() {
+            "safe-area-inset-top" => SafeAreaInsetTop,
+            "safe-area-inset-right" => SafeAreaInsetRight,
+            "safe-area-inset-bottom" => SafeAreaInsetBottom,
+            "safe-area-inset-left" => SafeAreaInsetLeft,
+            _ => return Err(CssPaddingParseError::UnknownEnvironmentVariable(input)),
+        };
+        Ok(PaddingValue::Environment(variable))
+    } else {
+        Ok(PaddingValue::Pixels(parse_pixels(input)?))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PixelParseError<'a> {
     InvalidComponent(&'a str),
@@ -468,6 +903,26 @@ fn parse_pixel_value<'a>(input: &'a str)
     })
 }
 
+/// Parses a single value such as "15px" into pixels
+fn parse_pixels<'a>(input: &'a str)
+-> Result<f32, PixelParseError<'a>>
+{
+    let value = parse_pixel_value(input)?;
+    value.to_pixels().ok_or(PixelParseError::InvalidComponent(input))
+}
+
+/// Parses a `width` / `height` value, i.e. "15px" or "50%" (of the size of the parent)
+fn parse_layout_size_value<'a>(input: &'a str)
+-> Result<PixelValue, PixelParseError<'a>>
+{
+    if input.ends_with('%') {
+        let number = input[..input.len() - 1].parse::<f32>().map_err(|e| PixelParseError::ValueParseErr(e))?;
+        Ok(PixelValue::from_metric(CssMetric::Percent, number))
+    } else {
+        parse_pixel_value(input)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PercentageParseError {
     ValueParseErr(ParseFloatError),
@@ -530,6 +985,28 @@ fn parse_css_text_color<'a>(input: &'a str)
     parse_css_color(input).and_then(|ok| Ok(TextColor(ok)))
 }
 
+/// Color that an `Image` or `GlTexture` is multiplied with (`-azul-tint-color` property),
+/// i.e. to draw a white icon in the accent color of a theme
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TintColor(pub ColorU);
+
+fn parse_css_tint_color<'a>(input: &'a str)
+-> Result<TintColor, CssColorParseError<'a>>
+{
+    parse_css_color(input).and_then(|ok| Ok(TintColor(ok)))
+}
+
+/// Color of the text caret of the focused node (`caret-color` property),
+/// defaults to the text color of the node
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CaretColor(pub ColorU);
+
+fn parse_css_caret_color<'a>(input: &'a str)
+-> Result<CaretColor, CssColorParseError<'a>>
+{
+    parse_css_color(input).and_then(|ok| Ok(CaretColor(ok)))
+}
+
 /// Parse a built-in background color
 ///
 /// "blue" -> "00FF00" -> ColorF { r: 0, g: 255, b: 0 })
@@ -774,29 +1251,7 @@ fn parse_color_no_hash<'a>(input: &'a str)
 fn parse_css_border<'a>(input: &'a str)
 -> Result<(BorderWidths, BorderDetails), CssBorderParseError<'a>>
 {
-    let mut input_iter = input.split_whitespace();
-
-    let (thickness, style, color);
-
-    match input_iter.clone().count() {
-        1 => {
-            style = parse_border_style(input_iter.next().unwrap())
-                            .map_err(|e| CssBorderParseError::InvalidBorderStyle(e))?;
-            thickness = 1.0;
-            color = ColorU { r: 0, g: 0, b: 0, a: 255 };
-        },
-        3 => {
-            thickness = parse_pixel_value(input_iter.next().unwrap())
-                           .map_err(|e| CssBorderParseError::ThicknessParseError(e))?.to_pixels();
-            style = parse_border_style(input_iter.next().unwrap())
-                           .map_err(|e| CssBorderParseError::InvalidBorderStyle(e))?;
-            color = parse_css_color(input_iter.next().unwrap())
-                           .map_err(|e| CssBorderParseError::ColorParseError(e))?;
-       },
-       _ => {
-            return Err(CssBorderParseError::InvalidBorderDeclaration(input));
-       }
-    }
+    let (thickness, style, color) = parse_border_components(input)?;
 
     let border_widths = BorderWidths {
         top: thickness,
@@ -821,6 +1276,77 @@ fn parse_css_border<'a>(input: &'a str)
     Ok((border_widths, border_details))
 }
 
+/// Parse a single side of a border (`border-top`, `border-left`, etc.), i.e. "2px solid red"
+fn parse_css_border_side<'a>(input: &'a str, edge: BorderEdge)
+-> Result<BorderSideOverride, CssBorderParseError<'a>>
+{
+    let (thickness, style, color) = parse_border_components(input)?;
+    Ok(BorderSideOverride {
+        edge: edge,
+        width: Some(thickness),
+        style: Some(style),
+        color: Some(color),
+    })
+}
+
+/// Parse the width of a single side of a border (`border-top-width`, etc.), i.e. "2px"
+fn parse_css_border_side_width<'a>(input: &'a str, edge: BorderEdge)
+-> Result<BorderSideOverride, CssBorderParseError<'a>>
+{
+    let thickness = parse_pixels(input)
+        .map_err(|e| CssBorderParseError::ThicknessParseError(e))?;
+    Ok(BorderSideOverride { edge: edge, width: Some(thickness), style: None, color: None })
+}
+
+/// Parse the style of a single side of a border (`border-top-style`, etc.), i.e. "dashed"
+fn parse_css_border_side_style<'a>(input: &'a str, edge: BorderEdge)
+-> Result<BorderSideOverride, CssBorderParseError<'a>>
+{
+    let style = parse_border_style(input)
+        .map_err(|e| CssBorderParseError::InvalidBorderStyle(e))?;
+    Ok(BorderSideOverride { edge: edge, width: None, style: Some(style), color: None })
+}
+
+/// Parse the color of a single side of a border (`border-top-color`, etc.), i.e. "red"
+fn parse_css_border_side_color<'a>(input: &'a str, edge: BorderEdge)
+-> Result<BorderSideOverride, CssBorderParseError<'a>>
+{
+    let color = parse_css_color(input)
+        .map_err(|e| CssBorderParseError::ColorParseError(e))?;
+    Ok(BorderSideOverride { edge: edge, width: None, style: None, color: Some(color) })
+}
+
+/// Parses the thickness, style and color of a border, i.e. "5px solid red" or "double"
+fn parse_border_components<'a>(input: &'a str)
+-> Result<(f32, BorderStyle, ColorU), CssBorderParseError<'a>>
+{
+    let mut input_iter = input.split_whitespace();
+
+    let (thickness, style, color);
+
+    match input_iter.clone().count() {
+        1 => {
+            style = parse_border_style(input_iter.next().unwrap())
+                            .map_err(|e| CssBorderParseError::InvalidBorderStyle(e))?;
+            thickness = 1.0;
+            color = ColorU { r: 0, g: 0, b: 0, a: 255 };
+        },
+        3 => {
+            thickness = parse_pixels(input_iter.next().unwrap())
+                           .map_err(|e| CssBorderParseError::ThicknessParseError(e))?;
+            style = parse_border_style(input_iter.next().unwrap())
+                           .map_err(|e| CssBorderParseError::InvalidBorderStyle(e))?;
+            color = parse_css_color(input_iter.next().unwrap())
+                           .map_err(|e| CssBorderParseError::ColorParseError(e))?;
+       },
+       _ => {
+            return Err(CssBorderParseError::InvalidBorderDeclaration(input));
+       }
+    }
+
+    Ok((thickness, style, color))
+}
+
 /// Parse a border style such as "none", "dotted", etc.
 ///
 /// "solid", "none", etc.
@@ -883,15 +1409,15 @@ fn parse_css_box_shadow<'a>(input: &'a str)
         },
         2 => {
             // box-shadow: 5px 10px; (h_offset, v_offset)
-            let h_offset = parse_pixel_value(input_iter.next().unwrap())?.to_pixels();
-            let v_offset = parse_pixel_value(input_iter.next().unwrap())?.to_pixels();
+            let h_offset = parse_pixels(input_iter.next().unwrap())?;
+            let v_offset = parse_pixels(input_iter.next().unwrap())?;
             box_shadow.offset.x = h_offset;
             box_shadow.offset.y = v_offset;
         },
         3 => {
             // box-shadow: 5px 10px inset; (h_offset, v_offset, inset)
-            let h_offset = parse_pixel_value(input_iter.next().unwrap())?.to_pixels();
-            let v_offset = parse_pixel_value(input_iter.next().unwrap())?.to_pixels();
+            let h_offset = parse_pixels(input_iter.next().unwrap())?;
+            let v_offset = parse_pixels(input_iter.next().unwrap())?;
             box_shadow.offset.x = h_offset;
             box_shadow.offset.y = v_offset;
 
@@ -902,13 +1428,13 @@ fn parse_css_box_shadow<'a>(input: &'a str)
             }
         },
         4 => {
-            let h_offset = parse_pixel_value(input_iter.next().unwrap())?.to_pixels();
-            let v_offset = parse_pixel_value(input_iter.next().unwrap())?.to_pixels();
+            let h_offset = parse_pixels(input_iter.next().unwrap())?;
+            let v_offset = parse_pixels(input_iter.next().unwrap())?;
             box_shadow.offset.x = h_offset;
             box_shadow.offset.y = v_offset;
 
             if !is_inset {
-                let blur = parse_pixel_value(input_iter.next().unwrap())?.to_pixels();
+                let blur = parse_pixels(input_iter.next().unwrap())?;
                 box_shadow.blur_radius = blur.into();
             }
 
@@ -918,16 +1444,16 @@ fn parse_css_box_shadow<'a>(input: &'a str)
         5 => {
             // box-shadow: 5px 10px 5px 10px #888888; (h_offset, v_offset, blur, spread, color)
             // box-shadow: 5px 10px 5px #888888 inset; (h_offset, v_offset, blur, color, inset)
-            let h_offset = parse_pixel_value(input_iter.next().unwrap())?.to_pixels();
-            let v_offset = parse_pixel_value(input_iter.next().unwrap())?.to_pixels();
+            let h_offset = parse_pixels(input_iter.next().unwrap())?;
+            let v_offset = parse_pixels(input_iter.next().unwrap())?;
             box_shadow.offset.x = h_offset;
             box_shadow.offset.y = v_offset;
 
-            let blur = parse_pixel_value(input_iter.next().unwrap())?.to_pixels();
+            let blur = parse_pixels(input_iter.next().unwrap())?;
             box_shadow.blur_radius = blur.into();
 
             if !is_inset {
-                let spread = parse_pixel_value(input_iter.next().unwrap())?.to_pixels();
+                let spread = parse_pixels(input_iter.next().unwrap())?;
                 box_shadow.spread_radius = spread.into();
             }
 
@@ -936,15 +1462,15 @@ fn parse_css_box_shadow<'a>(input: &'a str)
         },
         6 => {
             // box-shadow: 5px 10px 5px 10px #888888 inset; (h_offset, v_offset, blur, spread, color, inset)
-            let h_offset = parse_pixel_value(input_iter.next().unwrap())?.to_pixels();
-            let v_offset = parse_pixel_value(input_iter.next().unwrap())?.to_pixels();
+            let h_offset = parse_pixels(input_iter.next().unwrap())?;
+            let v_offset = parse_pixels(input_iter.next().unwrap())?;
             box_shadow.offset.x = h_offset;
             box_shadow.offset.y = v_offset;
 
-            let blur = parse_pixel_value(input_iter.next().unwrap())?.to_pixels();
+            let blur = parse_pixels(input_iter.next().unwrap())?;
             box_shadow.blur_radius = blur.into();
 
-            let spread = parse_pixel_value(input_iter.next().unwrap())?.to_pixels();
+            let spread = parse_pixels(input_iter.next().unwrap())?;
             box_shadow.spread_radius = spread.into();
 
             let color = parse_css_color(input_iter.next().unwrap())?;
@@ -958,6 +1484,18 @@ fn parse_css_box_shadow<'a>(input: &'a str)
     Ok(Some(box_shadow))
 }
 
+/// Parses a CSS text-shadow, which is a box-shadow without `inset` and spread radius
+fn parse_css_text_shadow<'a>(input: &'a str)
+-> Result<Option<BoxShadowPreDisplayItem>, CssShadowParseError<'a>>
+{
+    match parse_css_box_shadow(input)? {
+        Some(shadow) if shadow.clip_mode == BoxShadowClipMode::Inset || shadow.spread_radius != 0.0 => {
+            Err(CssShadowParseError::InvalidTextShadow(input))
+        },
+        shadow => Ok(shadow),
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum CssBackgroundParseError<'a> {
     Error(&'a str),
@@ -969,18 +1507,23 @@ pub enum CssBackgroundParseError<'a> {
     GradientParseError(CssGradientStopParseError<'a>),
     ShapeParseError(CssShapeParseError<'a>),
     ImageParseError(CssImageParseError<'a>),
+    ColorParseError(CssColorParseError<'a>),
 }
 
 impl_from!(CssDirectionParseError, CssBackgroundParseError::DirectionParseError);
 impl_from!(CssGradientStopParseError, CssBackgroundParseError::GradientParseError);
 impl_from!(CssShapeParseError, CssBackgroundParseError::ShapeParseError);
 impl_from!(CssImageParseError, CssBackgroundParseError::ImageParseError);
+impl_from!(CssColorParseError, CssBackgroundParseError::ColorParseError);
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Background {
     LinearGradient(LinearGradientPreInfo),
     RadialGradient(RadialGradientPreInfo),
     Image(CssImageId),
+    /// `background: image("id"), #ccc` - the color is painted instead of the
+    /// image if the image isn't available (i.e. it was never added or is deleted)
+    ImageWithFallback(CssImageId, ColorU),
     NoBackground,
 }
 
@@ -1000,10 +1543,114 @@ pub struct LinearGradientPreInfo {
 #[derive(Debug, Clone, PartialEq)]
 pub struct RadialGradientPreInfo {
     pub shape: Shape,
+    pub size: RadialGradientSize,
+    pub center: RadialGradientCenter,
     pub extend_mode: ExtendMode,
     pub stops: Vec<GradientStopPre>,
 }
 
+impl RadialGradientPreInfo {
+    /// Calculates the center and the radius of the gradient for the bounds,
+    /// relative to the top left corner of the bounds
+    pub fn to_center_and_radius(&self, rect: &LayoutRect)
+    -> (LayoutPoint, LayoutSize)
+    {
+        let center = self.center.to_point(rect);
+        let (w, h) = (rect.size.width, rect.size.height);
+
+        // Distances from the center to the closest / farthest side (horizontal, vertical)
+        let closest_side = (center.x.min(w - center.x).abs(), center.y.min(h - center.y).abs());
+        let farthest_side = (center.x.max(w - center.x).abs(), center.y.max(h - center.y).abs());
+
+        let radius = match self.size {
+            RadialGradientSize::Default => TypedSize2D::new(40.0, 40.0),
+            RadialGradientSize::Radius(x, y) => TypedSize2D::new(x.to_pixels_relative_to(w), y.to_pixels_relative_to(h)),
+            RadialGradientSize::ClosestSide | RadialGradientSize::FarthestSide => {
+                let closest = self.size == RadialGradientSize::ClosestSide;
+                let (x, y) = if closest { closest_side } else { farthest_side };
+                match self.shape {
+                    Shape::Circle => { let r = if closest { x.min(y) } else { x.max(y) }; TypedSize2D::new(r, r) },
+                    Shape::Ellipse => TypedSize2D::new(x, y),
+                }
+            },
+            RadialGradientSize::ClosestCorner | RadialGradientSize::FarthestCorner => {
+                let (x, y) = if self.size == RadialGradientSize::ClosestCorner { closest_side } else { farthest_side };
+                match self.shape {
+                    // The circle goes exactly through the corner
+                    Shape::Circle => { let r = x.hypot(y); TypedSize2D::new(r, r) },
+                    // The ellipse keeps the aspect ratio of the *-side ellipse,
+                    // scaled so that it goes through the corner
+                    Shape::Ellipse => TypedSize2D::new(x * ::std::f32::consts::SQRT_2, y * ::std::f32::consts::SQRT_2),
+                }
+            },
+        };
+
+        (center, radius)
+    }
+}
+
+/// Size of the ending shape of a radial gradient, i.e. `closest-side` or `40px 20px`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RadialGradientSize {
+    /// No size given: a fixed radius of 40px (note: CSS would use `farthest-corner`)
+    Default,
+    ClosestSide,
+    FarthestSide,
+    ClosestCorner,
+    FarthestCorner,
+    /// Horizontal and vertical radius - a circle has the same radius on both axes
+    Radius(PixelValue, PixelValue),
+}
+
+impl Default for RadialGradientSize {
+    fn default() -> Self {
+        RadialGradientSize::Default
+    }
+}
+
+/// A single component of a gradient position, i.e. `20%` or `10px`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GradientPosition {
+    Pixel(PixelValue),
+    /// Percentage of the width (horizontal) or height (vertical) of the bounds
+    Percentage(PercentageValue),
+}
+
+impl GradientPosition {
+    fn to_pixels(&self, length: f32) -> f32 {
+        match *self {
+            GradientPosition::Pixel(p) => p.to_pixels_relative_to(length),
+            GradientPosition::Percentage(p) => p.number * length,
+        }
+    }
+}
+
+/// Center of a radial gradient (`at <position>`), i.e. `at center` or `at 20% 80%`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RadialGradientCenter {
+    pub horizontal: GradientPosition,
+    pub vertical: GradientPosition,
+}
+
+impl Default for RadialGradientCenter {
+    /// The bottom left corner of the bounds
+    fn default() -> Self {
+        RadialGradientCenter {
+            horizontal: GradientPosition::Percentage(PercentageValue { number: 0.0 }),
+            vertical: GradientPosition::Percentage(PercentageValue { number: 1.0 }),
+        }
+    }
+}
+
+impl RadialGradientCenter {
+    /// Calculates the center for the bounds, relative to the top left corner of the bounds
+    pub fn to_point(&self, rect: &LayoutRect) -> LayoutPoint {
+        TypedPoint2D::new(
+            self.horizontal.to_pixels(rect.size.width),
+            self.vertical.to_pixels(rect.size.height))
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Direction {
     Angle(f32),
@@ -1099,12 +1746,37 @@ enum BackgroundType {
     Image,
 }
 
+/// Splits "image("id"), #ccc" into the background and the fallback color, at the
+/// first comma that isn't inside of braces (the commas of a gradient are inside of braces)
+fn split_background_fallback<'a>(input: &'a str) -> Option<(&'a str, &'a str)> {
+    let mut depth = 0_isize;
+    for (idx, c) in input.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => return Some((input[..idx].trim(), input[idx + 1..].trim())),
+            _ => { },
+        }
+    }
+    None
+}
+
 // parses a background, such as "linear-gradient(red, green)"
 fn parse_css_background<'a>(input: &'a str)
 -> Result<Background, CssBackgroundParseError<'a>>
 {
     use self::BackgroundType::*;
 
+    // "image("id"), #ccc" - the color after the image is the fallback of the image
+    if let Some((layer, fallback_color)) = split_background_fallback(input) {
+        let fallback_color = parse_css_color(fallback_color)?;
+        return match parse_css_background(layer)? {
+            Background::Image(id) => Ok(Background::ImageWithFallback(id, fallback_color)),
+            // gradients and `none` can't fail to load
+            _ => Err(CssBackgroundParseError::InvalidBackground(input)),
+        };
+    }
+
     let mut input_iter = input.splitn(2, "(");
     let first_item = input_iter.next();
 
@@ -1149,8 +1821,10 @@ fn parse_css_background<'a>(input: &'a str)
         None => return Err(CssBackgroundParseError::NoDirection(input)),
     };
 
-    // default shape: ellipse
+    // default shape: ellipse with a 40px radius, centered at the bottom left
     let mut shape = Shape::Ellipse;
+    let mut size = RadialGradientSize::default();
+    let mut center = RadialGradientCenter::default();
     // default gradient: from top to bottom
     let mut direction = Direction::FromTo(DirectionCorner::Top, DirectionCorner::Bottom);
 
@@ -1167,8 +1841,10 @@ fn parse_css_background<'a>(input: &'a str)
     }
 
     if is_radial_gradient {
-        if let Ok(sh) = parse_shape(first_brace_item) {
+        if let Ok((sh, sz, c)) = parse_radial_gradient_shape(first_brace_item) {
             shape = sh;
+            size = sz;
+            center = c;
             first_is_shape = true;
         }
     }
@@ -1265,6 +1941,8 @@ fn parse_css_background<'a>(input: &'a str)
         RadialGradient => {
             Ok(Background::RadialGradient(RadialGradientPreInfo {
                 shape: shape,
+                size: size,
+                center: center,
                 extend_mode: ExtendMode::Clamp,
                 stops: color_stops,
             }))
@@ -1272,6 +1950,8 @@ fn parse_css_background<'a>(input: &'a str)
         RepeatingRadialGradient => {
             Ok(Background::RadialGradient(RadialGradientPreInfo {
                 shape: shape,
+                size: size,
+                center: center,
                 extend_mode: ExtendMode::Repeat,
                 stops: color_stops,
             }))
@@ -1280,20 +1960,131 @@ fn parse_css_background<'a>(input: &'a str)
     }
 }
 
-/// Note: In theory, we could take a String here,
-/// but this leads to horrible lifetime issues. Also
-/// since we only parse the CSS once (at startup),
-/// the performance is absolutely negligible.
+/// Size of an image background (`background-size` property)
 ///
-/// However, this allows the `Css` struct to be independent
-/// of the original source text, i.e. the original CSS string
-/// can be deallocated after successfully parsing it.
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct CssImageId(pub(crate) String);
+/// NOTE: Without a `background-size`, the image is stretched to the size of the box
+/// (unlike in CSS, where the image would keep its intrinsic size).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BackgroundSize {
+    /// Scales the image to the smallest size that covers the whole box, keeping its aspect ratio
+    Cover,
+    /// Scales the image to the largest size that fits into the box, keeping its aspect ratio
+    Contain,
+    /// Width and height of the image, i.e. `50px auto` or `100% 50%`
+    Exact(BackgroundSizeComponent, BackgroundSizeComponent),
+}
 
-impl<'a> From<QuoteStripped<'a>> for CssImageId {
-    fn from(input: QuoteStripped<'a>) -> Self {
-        CssImageId(input.0.to_string())
+/// Width or height of a `background-size`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BackgroundSizeComponent {
+    /// Keeps the aspect ratio of the image (or its intrinsic size if both components are `auto`)
+    Auto,
+    Pixel(PixelValue),
+    /// Percentage of the width / height of the box
+    Percentage(PercentageValue),
+}
+
+impl BackgroundSize {
+    /// Calculates the size of a single image tile for the size of the box
+    /// and the intrinsic size of the image
+    pub fn to_size(&self, box_size: LayoutSize, image_size: LayoutSize) -> LayoutSize {
+        use self::BackgroundSizeComponent::*;
+
+        if image_size.width <= 0.0 || image_size.height <= 0.0 {
+            return box_size;
+        }
+
+        let scale_x = box_size.width / image_size.width;
+        let scale_y = box_size.height / image_size.height;
+        let aspect_ratio = image_size.width / image_size.height;
+
+        let resolve = |component: BackgroundSizeComponent, box_length: f32| match component {
+            Auto => None,
+            Pixel(p) => Some(p.to_pixels_relative_to(box_length)),
+            Percentage(p) => Some(p.number * box_length),
+        };
+
+        match *self {
+            BackgroundSize::Cover => image_size * scale_x.max(scale_y),
+            BackgroundSize::Contain => image_size * scale_x.min(scale_y),
+            BackgroundSize::Exact(width, height) => {
+                match (resolve(width, box_size.width), resolve(height, box_size.height)) {
+                    (Some(w), Some(h)) => LayoutSize::new(w, h),
+                    (Some(w), None) => LayoutSize::new(w, w / aspect_ratio),
+                    (None, Some(h)) => LayoutSize::new(h * aspect_ratio, h),
+                    (None, None) => image_size,
+                }
+            },
+        }
+    }
+}
+
+/// How an image background is repeated (`background-repeat` property)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BackgroundRepeat {
+    /// The default, the image is repeated horizontally and vertically
+    Repeat,
+    NoRepeat,
+    RepeatX,
+    RepeatY,
+}
+
+impl Default for BackgroundRepeat {
+    fn default() -> Self {
+        BackgroundRepeat::Repeat
+    }
+}
+
+// parses "cover", "contain", "50px", "50% auto", etc.
+fn parse_css_background_size<'a>(input: &'a str)
+-> Result<BackgroundSize, InvalidValueErr<'a>>
+{
+    let input = input.trim();
+    match input {
+        "cover" => return Ok(BackgroundSize::Cover),
+        "contain" => return Ok(BackgroundSize::Contain),
+        _ => { },
+    }
+
+    let parse_component = |component: &'a str| -> Result<BackgroundSizeComponent, InvalidValueErr<'a>> {
+        if component == "auto" {
+            Ok(BackgroundSizeComponent::Auto)
+        } else if let Some(percent) = parse_percentage(component) {
+            Ok(BackgroundSizeComponent::Percentage(PercentageValue { number: percent / 100.0 }))
+        } else {
+            parse_pixel_value(component).map(BackgroundSizeComponent::Pixel).map_err(|_| InvalidValueErr(input))
+        }
+    };
+
+    let mut components = input.split_whitespace();
+    let width = parse_component(components.next().ok_or(InvalidValueErr(input))?)?;
+    // "50px" is the same as "50px auto"
+    let height = match components.next() {
+        Some(height) => parse_component(height)?,
+        None => BackgroundSizeComponent::Auto,
+    };
+
+    if components.next().is_some() {
+        return Err(InvalidValueErr(input));
+    }
+
+    Ok(BackgroundSize::Exact(width, height))
+}
+
+/// Note: In theory, we could take a String here,
+/// but this leads to horrible lifetime issues. Also
+/// since we only parse the CSS once (at startup),
+/// the performance is absolutely negligible.
+///
+/// However, this allows the `Css` struct to be independent
+/// of the original source text, i.e. the original CSS string
+/// can be deallocated after successfully parsing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CssImageId(pub(crate) String);
+
+impl<'a> From<QuoteStripped<'a>> for CssImageId {
+    fn from(input: QuoteStripped<'a>) -> Self {
+        CssImageId(input.0.to_string())
     }
 }
 
@@ -1366,6 +2157,94 @@ fn parse_gradient_stop<'a>(input: &'a str)
     Ok(GradientStopPre { offset: percentage, color: color })
 }
 
+// parses the first argument of a radial gradient, i.e. "circle",
+// "ellipse closest-side at 20% 80%", "40px at center" or "at top right"
+fn parse_radial_gradient_shape<'a>(input: &'a str)
+-> Result<(Shape, RadialGradientSize, RadialGradientCenter), CssShapeParseError<'a>>
+{
+    let input = input.trim();
+    let mut tokens = input.split_whitespace().collect::<Vec<&str>>();
+    let mut center = RadialGradientCenter::default();
+
+    if let Some(at_pos) = tokens.iter().position(|t| *t == "at") {
+        center = parse_radial_gradient_center(&tokens[(at_pos + 1)..])
+            .ok_or(CssShapeParseError::ShapeErr(InvalidValueErr(input)))?;
+        tokens.truncate(at_pos);
+    } else if tokens.is_empty() {
+        return Err(CssShapeParseError::ShapeErr(InvalidValueErr(input)));
+    }
+
+    let mut shape = None;
+    let mut size = RadialGradientSize::Default;
+    let mut radii = Vec::new();
+
+    for token in tokens {
+        if let Ok(sh) = parse_shape(token) {
+            if shape.is_some() { return Err(CssShapeParseError::ShapeErr(InvalidValueErr(input))); }
+            shape = Some(sh);
+            continue;
+        }
+        size = match token {
+            "closest-side" => RadialGradientSize::ClosestSide,
+            "farthest-side" => RadialGradientSize::FarthestSide,
+            "closest-corner" => RadialGradientSize::ClosestCorner,
+            "farthest-corner" => RadialGradientSize::FarthestCorner,
+            _ => {
+                radii.push(parse_pixel_value(token).map_err(|_| CssShapeParseError::ShapeErr(InvalidValueErr(input)))?);
+                continue;
+            }
+        };
+    }
+
+    if size != RadialGradientSize::Default && !radii.is_empty() {
+        return Err(CssShapeParseError::ShapeErr(InvalidValueErr(input)));
+    }
+
+    // "40px" is a circle, "40px 20px" is an ellipse
+    let shape = match (shape, radii.len()) {
+        (Some(sh), 0) => sh,
+        (None, 0) => Shape::Ellipse,
+        (Some(Shape::Circle), 1) | (None, 1) => { size = RadialGradientSize::Radius(radii[0], radii[0]); Shape::Circle },
+        (Some(Shape::Ellipse), 2) | (None, 2) => { size = RadialGradientSize::Radius(radii[0], radii[1]); Shape::Ellipse },
+        _ => return Err(CssShapeParseError::ShapeErr(InvalidValueErr(input))),
+    };
+
+    Ok((shape, size, center))
+}
+
+// parses the position after the "at", i.e. ["center"], ["top", "right"] or ["20%", "10px"]
+fn parse_radial_gradient_center(tokens: &[&str])
+-> Option<RadialGradientCenter>
+{
+    let percent = |p: f32| GradientPosition::Percentage(PercentageValue { number: p });
+    let center = percent(0.5);
+
+    let parse_component = |token: &str| match token {
+        "left" | "top" => Some(percent(0.0)),
+        "center" => Some(center),
+        "right" | "bottom" => Some(percent(1.0)),
+        _ => if token.ends_with('%') {
+            parse_percentage(token).map(|p| percent(p / 100.0))
+        } else {
+            parse_pixel_value(token).ok().map(GradientPosition::Pixel)
+        },
+    };
+
+    let is_vertical_keyword = |token: &str| token == "top" || token == "bottom";
+    let is_horizontal_keyword = |token: &str| token == "left" || token == "right";
+
+    let (horizontal, vertical) = match *tokens {
+        [x] if is_vertical_keyword(x) => (center, parse_component(x)?),
+        [x] => (parse_component(x)?, center),
+        // "top right" is the same as "right top"
+        [a, b] if is_vertical_keyword(a) || is_horizontal_keyword(b) => (parse_component(b)?, parse_component(a)?),
+        [a, b] => (parse_component(a)?, parse_component(b)?),
+        _ => return None,
+    };
+
+    Some(RadialGradientCenter { horizontal, vertical })
+}
+
 // parses "5%" -> 5
 fn parse_percentage(input: &str)
 -> Option<f32>
@@ -1495,10 +2374,252 @@ pub struct LayoutHeight(pub PixelValue);
 pub struct LayoutMinHeight(pub PixelValue);
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub struct LayoutMaxHeight(pub PixelValue);
+/// Ratio of the width to the height of a box, i.e. `16/9` or `1.5`
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct LayoutAspectRatio(pub f32);
+/// Space between the items of a flex container (`gap` property)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LayoutGap {
+    /// `gap: normal`, no space between the items
+    Normal,
+    Pixel(PixelValue),
+    /// Percentage of the main-axis size of the container, i.e. `gap: 10%`
+    Percentage(PercentageValue),
+}
+
+impl Default for LayoutGap {
+    fn default() -> Self {
+        LayoutGap::Normal
+    }
+}
+
+impl LayoutGap {
+    /// Returns the gap in pixels. Percentages are resolved against the main-axis size of
+    /// the container - if the size of the container isn't known (`None`), as in CSS,
+    /// percentage gaps are treated as `0px`.
+    pub fn to_pixels(&self, container_main_axis_size: Option<f32>) -> f32 {
+        use self::LayoutGap::*;
+        match *self {
+            Normal => 0.0,
+            Pixel(px) => px.to_pixels().unwrap_or(0.0),
+            Percentage(p) => container_main_axis_size.map(|size| size * p.number).unwrap_or(0.0),
+        }
+    }
+}
+
+/// Whether a box is displayed at all (`display` property)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LayoutDisplay {
+    /// The box is laid out as a flex container (the default)
+    Flex,
+    /// The box and all of its children are neither laid out nor painted
+    None,
+}
+
+impl Default for LayoutDisplay {
+    fn default() -> Self {
+        LayoutDisplay::Flex
+    }
+}
+
+/// How strongly the `width` / `height` of a box is enforced by the layout solver
+/// (`-azul-constraint-strength` property). If the sizes of boxes conflict, i.e. because
+/// they don't fit into their parent, the sizes with the weaker strength yield first.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LayoutConstraintStrength {
+    Weak,
+    Medium,
+    /// The default strength
+    Strong,
+}
+
+impl Default for LayoutConstraintStrength {
+    fn default() -> Self {
+        LayoutConstraintStrength::Strong
+    }
+}
+
+impl LayoutConstraintStrength {
+    /// Returns the strength of the constraint for the cassowary solver
+    pub fn to_cassowary_strength(&self) -> f64 {
+        use cassowary::strength::{WEAK, MEDIUM, STRONG};
+        match *self {
+            LayoutConstraintStrength::Weak => WEAK,
+            LayoutConstraintStrength::Medium => MEDIUM,
+            LayoutConstraintStrength::Strong => STRONG,
+        }
+    }
+}
+
+/// Whether a box is a layout boundary (`contain` property): changes to the layout of the
+/// children of a contained box don't affect the layout outside of the box, so only the
+/// subtree of the box has to be laid out again
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LayoutContain {
+    /// The default, the box is laid out together with its parent
+    None,
+    /// `contain: layout`
+    Layout,
+    /// `contain: size` - the size of the box doesn't depend on its children
+    Size,
+}
+
+impl Default for LayoutContain {
+    fn default() -> Self {
+        LayoutContain::None
+    }
+}
+
+impl LayoutContain {
+    /// Returns whether the box establishes a layout boundary
+    pub fn is_layout_boundary(&self) -> bool {
+        *self != LayoutContain::None
+    }
+}
+
+/// Whether the border of a box is part of its `width` / `height` (`box-sizing` property)
+///
+/// NOTE: The border is currently always drawn inside of the bounds of the box. The
+/// `box-sizing` only decides whether adding or removing a border (i.e. in a `:hover`
+/// rule) can skip the re-layout: under `border-box`, the border can't change the size.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LayoutBoxSizing {
+    /// The default, the border is added to the size of the box
+    ContentBox,
+    /// The border is drawn inside of the size of the box
+    BorderBox,
+}
+
+impl Default for LayoutBoxSizing {
+    fn default() -> Self {
+        LayoutBoxSizing::ContentBox
+    }
+}
+
+/// Space between the edges of a box and its content (in pixels)
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct LayoutPadding {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+/// The parsed `padding` property. The sides can refer to environment variables, which
+/// are only known once the window is drawn, so the padding is resolved to a
+/// `LayoutPadding` when the node is styled.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct CssPadding {
+    pub top: PaddingValue,
+    pub right: PaddingValue,
+    pub bottom: PaddingValue,
+    pub left: PaddingValue,
+}
+
+impl CssPadding {
+    pub fn resolve(&self, safe_area_insets: &SafeAreaInsets) -> LayoutPadding {
+        LayoutPadding {
+            top: self.top.resolve(safe_area_insets),
+            right: self.right.resolve(safe_area_insets),
+            bottom: self.bottom.resolve(safe_area_insets),
+            left: self.left.resolve(safe_area_insets),
+        }
+    }
+}
+
+/// Space around the edges of a box, between the box and its parent (in pixels)
+pub type LayoutMargin = LayoutPadding;
+
+/// The parsed `margin` property, which has the same syntax as the `padding`
+pub type CssMargin = CssPadding;
+
+/// Sets a single side of the padding of a rectangle, i.e. `padding-top: 10px`.
+/// The other sides keep their current value.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct PaddingSideOverride {
+    pub edge: BorderEdge,
+    pub value: PaddingValue,
+}
+
+/// Sets a single side of the margin of a rectangle, i.e. `margin-left: 5px`
+pub type MarginSideOverride = PaddingSideOverride;
+
+impl PaddingSideOverride {
+    /// Applies the side to the current padding (or margin) of a rectangle,
+    /// the other sides are 0 if the rectangle has no padding yet
+    pub fn apply_to(&self, padding: Option<LayoutPadding>, safe_area_insets: &SafeAreaInsets) -> LayoutPadding {
+        let mut padding = padding.unwrap_or_default();
+        let value = self.value.resolve(safe_area_insets);
+        match self.edge {
+            BorderEdge::Top => padding.top = value,
+            BorderEdge::Right => padding.right = value,
+            BorderEdge::Bottom => padding.bottom = value,
+            BorderEdge::Left => padding.left = value,
+        }
+        padding
+    }
+}
+
+/// One side of the `padding` property
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum PaddingValue {
+    /// `10px`
+    Pixels(f32),
+    /// `env(safe-area-inset-top)`
+    Environment(EnvironmentVariable),
+}
+
+impl PaddingValue {
+    pub fn resolve(&self, safe_area_insets: &SafeAreaInsets) -> f32 {
+        match *self {
+            PaddingValue::Pixels(px) => px,
+            PaddingValue::Environment(variable) => variable.resolve(safe_area_insets),
+        }
+    }
+}
+
+/// Variables of the window that can be used in CSS with `env()`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum EnvironmentVariable {
+    SafeAreaInsetTop,
+    SafeAreaInsetRight,
+    SafeAreaInsetBottom,
+    SafeAreaInsetLeft,
+}
+
+impl EnvironmentVariable {
+    pub fn resolve(&self, safe_area_insets: &SafeAreaInsets) -> f32 {
+        use self::EnvironmentVariable::*;
+        match *self {
+            SafeAreaInsetTop => safe_area_insets.top,
+            SafeAreaInsetRight => safe_area_insets.right,
+            SafeAreaInsetBottom => safe_area_insets.bottom,
+            SafeAreaInsetLeft => safe_area_insets.left,
+        }
+    }
+}
+
+/// Insets from the edges of the window to the area that isn't covered by system UI
+/// (notches, rounded display corners, status bars), in pixels. Content can avoid the
+/// system UI with `padding: env(safe-area-inset-top)`, etc.
+///
+/// On desktop platforms, all insets are zero.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct SafeAreaInsets {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub struct LineHeight(pub PercentageValue);
 
+/// Offset of the first line of a text (`text-indent` property),
+/// negative values create a hanging indent
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct TextIndent(pub PixelValue);
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum LayoutDirection {
     Horizontal,
@@ -1538,101 +2659,665 @@ pub enum LayoutAlignItems {
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub enum LayoutAlignContent {
-    /// Default value. Lines stretch to take up the remaining space
-    Stretch,
-    /// Lines are packed toward the center of the flex container
-    Center,
-    /// Lines are packed toward the start of the flex container
-    Start,
-    /// Lines are packed toward the end of the flex container
-    End,
-    /// Lines are evenly distributed in the flex container
-    SpaceBetween,
-    /// Lines are evenly distributed in the flex container, with half-size spaces on either end
-    SpaceAround,
+pub enum LayoutAlignContent {
+    /// Default value. Lines stretch to take up the remaining space
+    Stretch,
+    /// Lines are packed toward the center of the flex container
+    Center,
+    /// Lines are packed toward the start of the flex container
+    Start,
+    /// Lines are packed toward the end of the flex container
+    End,
+    /// Lines are evenly distributed in the flex container
+    SpaceBetween,
+    /// Lines are evenly distributed in the flex container, with half-size spaces on either end
+    SpaceAround,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextOverflowBehaviour {
+    NotModified,
+    Modified(TextOverflowBehaviourInner),
+}
+
+impl Default for TextOverflowBehaviour {
+    fn default() -> Self {
+        TextOverflowBehaviour::NotModified
+    }
+}
+
+impl TextOverflowBehaviour {
+    /// `overflow: scroll` always shows a scrollbar, `overflow: auto` (the default)
+    /// only when the content overflows, `hidden` and `visible` never show a scrollbar.
+    pub fn needs_scrollbar(&self, currently_overflowing: bool) -> bool {
+        use self::TextOverflowBehaviourInner::*;
+        match *self {
+            TextOverflowBehaviour::Modified(m) => match m {
+                Scroll => true,
+                Auto => currently_overflowing,
+                Hidden | Visible => false,
+            },
+            TextOverflowBehaviour::NotModified => currently_overflowing,
+        }
+    }
+
+    /// Returns if content that overflows the box is clipped (`overflow: hidden`, `scroll` or `auto`)
+    pub fn clips_content(&self) -> bool {
+        use self::TextOverflowBehaviourInner::*;
+        match *self {
+            TextOverflowBehaviour::Modified(Hidden) |
+            TextOverflowBehaviour::Modified(Scroll) |
+            TextOverflowBehaviour::Modified(Auto) => true,
+            TextOverflowBehaviour::Modified(Visible) |
+            TextOverflowBehaviour::NotModified => false,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextOverflowBehaviourInner {
+    /// Always shows a scroll bar, overflows on scroll
+    Scroll,
+    /// Does not show a scroll bar by default, only when text is overflowing
+    Auto,
+    /// Never shows a scroll bar, simply clips text
+    Hidden,
+    /// Doesn't show a scroll bar, simply overflows the text
+    Visible,
+}
+
+impl Default for TextOverflowBehaviourInner {
+    fn default() -> Self {
+        TextOverflowBehaviourInner::Auto
+    }
+}
+
+/// How text that overflows its rectangle is cut off (`text-overflow` property)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextOverflowMode {
+    /// The text is simply clipped at the edge of the rectangle
+    Clip,
+    /// The text fades out to transparent at the trailing edge of the rectangle
+    Fade,
+}
+
+impl Default for TextOverflowMode {
+    fn default() -> Self {
+        TextOverflowMode::Clip
+    }
+}
+
+/// Whether the content of a node is rendered if the node is outside of
+/// the visible area (`content-visibility` property)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ContentVisibility {
+    /// The content is always rendered
+    Visible,
+    /// The content is only rendered if the node is (partially) visible, nodes that are
+    /// entirely outside of the visible area are skipped, but still keep their size
+    Auto,
+}
+
+impl Default for ContentVisibility {
+    fn default() -> Self {
+        ContentVisibility::Visible
+    }
+}
+
+/// One of the four sides of a border
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BorderEdge {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+/// Sets a single side of the border of a rectangle, i.e. `border-bottom: 2px solid red`
+/// or `border-left-color: blue`. Components that are `None` keep their current value.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BorderSideOverride {
+    pub edge: BorderEdge,
+    pub width: Option<f32>,
+    pub style: Option<BorderStyle>,
+    pub color: Option<ColorU>,
+}
+
+impl BorderSideOverride {
+    /// Applies the side to the current `border` of a rectangle. If the rectangle has no
+    /// border yet, the other sides have a width of 0 and the style `none`, so only
+    /// this side is drawn.
+    pub fn apply_to(&self, border: Option<(BorderWidths, BorderDetails)>) -> (BorderWidths, BorderDetails) {
+        let (mut widths, mut normal_border) = match border {
+            Some((widths, BorderDetails::Normal(normal_border))) => (widths, normal_border),
+            _ => {
+                let no_side = BorderSide {
+                    color: ColorU { r: 0, g: 0, b: 0, a: 255 }.into(),
+                    style: BorderStyle::None,
+                };
+                let no_widths = BorderWidths { top: 0.0, right: 0.0, bottom: 0.0, left: 0.0 };
+                (no_widths, NormalBorder {
+                    top: no_side,
+                    right: no_side,
+                    bottom: no_side,
+                    left: no_side,
+                    radius: BorderRadius::zero(),
+                })
+            },
+        };
+
+        let (width, side) = match self.edge {
+            BorderEdge::Top => (&mut widths.top, &mut normal_border.top),
+            BorderEdge::Right => (&mut widths.right, &mut normal_border.right),
+            BorderEdge::Bottom => (&mut widths.bottom, &mut normal_border.bottom),
+            BorderEdge::Left => (&mut widths.left, &mut normal_border.left),
+        };
+
+        if let Some(w) = self.width {
+            *width = w;
+        }
+        if let Some(s) = self.style {
+            side.style = s;
+        }
+        if let Some(c) = self.color {
+            side.color = c.into();
+        }
+
+        (widths, BorderDetails::Normal(normal_border))
+    }
+}
+
+/// Filter that is applied to the content of a rectangle (`filter` property)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Filter {
+    /// `drop-shadow(5px 5px 10px #000000)` - a shadow that follows the opaque pixels of
+    /// the content (i.e. of an image), not the bounds of the rectangle like a `box-shadow`
+    DropShadow(BoxShadowPreDisplayItem),
+}
+
+/// Filter that is applied to the content behind a rectangle (`backdrop-filter` property)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BackdropFilter {
+    /// `blur(10px)` - gaussian blur with the given radius
+    Blur(PixelValue),
+}
+
+/// Animates the change of a property over time (`transition` property),
+/// i.e. `transition: background-color 200ms ease-in`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition {
+    /// The CSS key of the property that should be animated, i.e. `"background-color"`
+    pub property: String,
+    /// How long it takes to go from the old to the new value
+    pub duration: Duration,
+    /// How the progress of the transition is mapped to the interpolated value
+    pub timing_function: TransitionTimingFunction,
+}
+
+/// Timing function of a `transition`, maps the elapsed time (0.0 - 1.0)
+/// to the progress of the transition (0.0 - 1.0)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TransitionTimingFunction {
+    Linear,
+    Ease,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    /// `cubic-bezier(x1, y1, x2, y2)` - the curve from `(0, 0)` to `(1, 1)` with
+    /// the control points `(x1, y1)` and `(x2, y2)`
+    CubicBezier(f32, f32, f32, f32),
+}
+
+impl Default for TransitionTimingFunction {
+    fn default() -> Self {
+        TransitionTimingFunction::Ease
+    }
+}
+
+impl TransitionTimingFunction {
+    /// Returns the progress of the transition for the elapsed time `t` (clamped to 0.0 - 1.0)
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.max(0.0).min(1.0);
+        match self.control_points() {
+            None => t,
+            Some((x1, y1, x2, y2)) => solve_cubic_bezier(x1, y1, x2, y2, t),
+        }
+    }
+
+    /// Returns the control points of the cubic bézier curve of the timing function
+    /// (the named timing functions are defined as curves by the CSS spec),
+    /// `None` for `linear`
+    fn control_points(&self) -> Option<(f32, f32, f32, f32)> {
+        use self::TransitionTimingFunction::*;
+        match *self {
+            Linear => None,
+            Ease => Some((0.25, 0.1, 0.25, 1.0)),
+            EaseIn => Some((0.42, 0.0, 1.0, 1.0)),
+            EaseOut => Some((0.0, 0.0, 0.58, 1.0)),
+            EaseInOut => Some((0.42, 0.0, 0.58, 1.0)),
+            CubicBezier(x1, y1, x2, y2) => Some((x1, y1, x2, y2)),
+        }
+    }
+}
+
+/// Evaluates one coordinate of the cubic bézier curve from 0.0 to 1.0
+/// with the control points `p1` and `p2` at the curve parameter `t`
+fn cubic_bezier(p1: f32, p2: f32, t: f32) -> f32 {
+    let u = 1.0 - t;
+    3.0 * u * u * t * p1 + 3.0 * u * t * t * p2 + t * t * t
+}
+
+/// Derivative of `cubic_bezier` with respect to `t`
+fn cubic_bezier_slope(p1: f32, p2: f32, t: f32) -> f32 {
+    let u = 1.0 - t;
+    3.0 * u * u * p1 + 6.0 * u * t * (p2 - p1) + 3.0 * t * t * (1.0 - p2)
+}
+
+/// Returns the y coordinate of the cubic bézier curve at the x coordinate `x`.
+///
+/// Finds the curve parameter `t` for `x` via Newton's method on the x-curve,
+/// if that doesn't converge (i.e. because the curve is too flat), falls back to bisection.
+fn solve_cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, x: f32) -> f32 {
+    const EPSILON: f32 = 1e-6;
+    const NEWTON_ITERATIONS: usize = 8;
+
+    let mut t = x;
+    for _ in 0..NEWTON_ITERATIONS {
+        let error = cubic_bezier(x1, x2, t) - x;
+        if error.abs() < EPSILON {
+            return cubic_bezier(y1, y2, t);
+        }
+        let slope = cubic_bezier_slope(x1, x2, t);
+        if slope.abs() < EPSILON {
+            break;
+        }
+        t = (t - error / slope).max(0.0).min(1.0);
+    }
+
+    // x(t) is monotonic, since x1 and x2 are in the range 0.0 - 1.0
+    let (mut lower, mut upper) = (0.0_f32, 1.0_f32);
+    t = x;
+    while upper - lower > EPSILON {
+        let current_x = cubic_bezier(x1, x2, t);
+        if (current_x - x).abs() < EPSILON {
+            break;
+        }
+        if current_x < x {
+            lower = t;
+        } else {
+            upper = t;
+        }
+        t = (lower + upper) / 2.0;
+    }
+
+    cubic_bezier(y1, y2, t)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextAlignmentHorz {
+    Left,
+    Center,
+    Right,
+}
+
+impl Default for TextAlignmentHorz {
+    fn default() -> Self {
+        TextAlignmentHorz::Left
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextAlignmentVert {
+    Top,
+    Center,
+    Bottom,
+}
+
+impl Default for TextAlignmentVert {
+    fn default() -> Self {
+        TextAlignmentVert::Top
+    }
+}
+
+/// Whether a word that is too long to fit on a line by itself may be broken
+/// in the middle of the word (`overflow-wrap` property)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OverflowWrap {
+    /// Words are only broken at spaces, long words overflow the rectangle
+    Normal,
+    /// A word that doesn't fit on a line by itself is moved to a new line
+    /// and broken at the character where it exceeds the width of the rectangle
+    BreakWord,
+}
+
+impl Default for OverflowWrap {
+    fn default() -> Self {
+        OverflowWrap::Normal
+    }
+}
+
+/// Where lines may be broken inside of words (`word-break` property)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WordBreak {
+    /// Words are only broken at spaces
+    Normal,
+    /// Lines may be broken between any two characters: words are not moved to a
+    /// new line, instead they fill up the remaining space of the current line
+    BreakAll,
+}
+
+impl Default for WordBreak {
+    fn default() -> Self {
+        WordBreak::Normal
+    }
+}
+
+/// Line that is drawn along the text (`text-decoration` property). The position
+/// and thickness of the line are taken from the metrics of the font.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextDecoration {
+    /// No line is drawn
+    None,
+    /// Line below the baseline of the text
+    Underline,
+    /// Line above the text, at the ascender of the font
+    Overline,
+    /// Line through the middle of the text, at the strikeout position of the font
+    LineThrough,
+}
+
+impl Default for TextDecoration {
+    fn default() -> Self {
+        TextDecoration::None
+    }
+}
+
+/// The lines of the `text-decoration` property, multiple lines can be drawn
+/// at the same time, i.e. `text-decoration: underline overline line-through`
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct TextDecorationLines {
+    pub underline: bool,
+    pub overline: bool,
+    pub line_through: bool,
+}
+
+impl From<TextDecoration> for TextDecorationLines {
+    fn from(line: TextDecoration) -> Self {
+        let mut lines = TextDecorationLines::default();
+        lines.insert(line);
+        lines
+    }
+}
+
+impl TextDecorationLines {
+    /// Adds the `line`, returns `false` if the line was already set
+    fn insert(&mut self, line: TextDecoration) -> bool {
+        let flag = match line {
+            TextDecoration::None => return true,
+            TextDecoration::Underline => &mut self.underline,
+            TextDecoration::Overline => &mut self.overline,
+            TextDecoration::LineThrough => &mut self.line_through,
+        };
+        let was_set = *flag;
+        *flag = true;
+        !was_set
+    }
+
+    /// Returns the lines that have to be drawn, from top to bottom
+    pub fn lines(&self) -> Vec<TextDecoration> {
+        let mut lines = Vec::new();
+        if self.overline { lines.push(TextDecoration::Overline); }
+        if self.line_through { lines.push(TextDecoration::LineThrough); }
+        if self.underline { lines.push(TextDecoration::Underline); }
+        lines
+    }
+}
+
+/// Parses the lines of a `text-decoration`, i.e. "underline overline". "none" can't be
+/// combined with other lines and every line may only appear once.
+fn parse_text_decoration_lines<'a>(input: &'a str)
+-> Result<TextDecorationLines, InvalidValueErr<'a>>
+{
+    if input == "none" {
+        return Ok(TextDecorationLines::default());
+    }
+
+    let mut lines = TextDecorationLines::default();
+    for line in input.split_whitespace() {
+        match parse_text_decoration(line)? {
+            TextDecoration::None => return Err(InvalidValueErr(input)),
+            line => if !lines.insert(line) { return Err(InvalidValueErr(input)); },
+        }
+    }
+
+    if lines == TextDecorationLines::default() {
+        Err(InvalidValueErr(input))
+    } else {
+        Ok(lines)
+    }
+}
+
+/// Whether a built-in widget is drawn with its default (native) styling (`appearance` property)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Appearance {
+    /// The default, the widget is styled by the rules of `Css::native`
+    Auto,
+    /// The rules of `Css::native` don't apply to the node, only the user CSS is applied
+    None,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Appearance::Auto
+    }
+}
+
+/// Opacity of a rectangle and all of its children, from `0.0` (transparent) to `1.0` (opaque)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Opacity(pub f32);
+
+/// Parses an opacity, i.e. "0.5" or "50%". The value is clamped to the range `0.0 - 1.0`
+fn parse_css_opacity<'a>(input: &'a str)
+-> Result<Opacity, InvalidValueErr<'a>>
+{
+    let opacity = if input.ends_with('%') {
+        input[..(input.len() - 1)].trim().parse::<f32>().map(|percent| percent / 100.0)
+    } else {
+        input.parse::<f32>()
+    };
+
+    match opacity {
+        Ok(o) if !o.is_nan() => Ok(Opacity(o.max(0.0).min(1.0))),
+        _ => Err(InvalidValueErr(input)),
+    }
+}
+
+/// Whether a rectangle is painted (`visibility` property). The visibility is inherited
+/// by the children, but a child of a hidden rectangle can set `visibility: visible` again.
+/// Hidden rectangles still take up space in the layout.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Visibility {
+    Visible,
+    Hidden,
+}
+
+impl Default for Visibility {
+    fn default() -> Self {
+        Visibility::Visible
+    }
+}
+
+/// Writing direction of a rectangle (`direction` property), inherited by the children.
+/// In right-to-left layouts, the vertical scrollbar is drawn on the left side.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextDirection {
+    Ltr,
+    Rtl,
 }
 
+impl Default for TextDirection {
+    fn default() -> Self {
+        TextDirection::Ltr
+    }
+}
+
+/// Direction of the lines of a text (`writing-mode` property), inherited by the children.
+///
+/// In the vertical modes, the glyphs are set upright and advance downwards by their
+/// horizontal advance (CJK glyphs are square, so this is the same as their vertical advance).
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub enum TextOverflowBehaviour {
-    NotModified,
-    Modified(TextOverflowBehaviourInner),
+pub enum WritingMode {
+    /// The default, horizontal lines that progress from the top to the bottom
+    HorizontalTb,
+    /// Vertical columns that progress from the right to the left
+    VerticalRl,
+    /// Vertical columns that progress from the left to the right
+    VerticalLr,
 }
 
-impl Default for TextOverflowBehaviour {
+impl Default for WritingMode {
     fn default() -> Self {
-        TextOverflowBehaviour::NotModified
+        WritingMode::HorizontalTb
+    }
+}
+
+impl WritingMode {
+    pub fn is_vertical(&self) -> bool {
+        *self != WritingMode::HorizontalTb
     }
 }
 
+/// Whether the text of a `Label` can be selected (`user-select` property)
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub enum TextOverflowBehaviourInner {
-    /// Always shows a scroll bar, overflows on scroll
-    Scroll,
-    /// Does not show a scroll bar by default, only when text is overflowing
-    Auto,
-    /// Never shows a scroll bar, simply clips text
-    Hidden,
-    /// Doesn't show a scroll bar, simply overflows the text
-    Visible,
+pub enum UserSelect {
+    None,
+    Text,
 }
 
-impl Default for TextOverflowBehaviourInner {
+impl Default for UserSelect {
     fn default() -> Self {
-        TextOverflowBehaviourInner::Auto
+        UserSelect::Text
     }
 }
 
+/// Whether a scroll container passes the rest of the scroll delta on to the outer
+/// scroll containers once it reaches its boundary (`overscroll-behavior` property)
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub enum TextAlignmentHorz {
-    Left,
-    Center,
-    Right,
+pub enum OverscrollBehavior {
+    /// Scroll chaining: the outer containers are scrolled
+    Auto,
+    /// The outer containers aren't scrolled
+    Contain,
+    /// Same as `Contain`, azul doesn't draw any overscroll effects
+    None,
 }
 
-impl Default for TextAlignmentHorz {
+impl Default for OverscrollBehavior {
     fn default() -> Self {
-        TextAlignmentHorz::Left
+        OverscrollBehavior::Auto
     }
 }
 
+/// Whether the user can resize a box by dragging the grip in its bottom-right corner
+/// (`resize` property), and along which axes
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub enum TextAlignmentVert {
-    Top,
-    Center,
-    Bottom,
+pub enum LayoutResize {
+    None,
+    Both,
+    Horizontal,
+    Vertical,
 }
 
-impl Default for TextAlignmentVert {
+impl Default for LayoutResize {
     fn default() -> Self {
-        TextAlignmentVert::Top
+        LayoutResize::None
+    }
+}
+
+impl LayoutResize {
+    /// Returns whether the width of the box can be resized
+    pub fn is_horizontal(&self) -> bool {
+        *self == LayoutResize::Both || *self == LayoutResize::Horizontal
+    }
+
+    /// Returns whether the height of the box can be resized
+    pub fn is_vertical(&self) -> bool {
+        *self == LayoutResize::Both || *self == LayoutResize::Vertical
     }
 }
 
 #[derive(Default, Debug, Clone, PartialEq)]
-pub(crate) struct RectStyle {
+pub struct RectStyle {
     /// Background color of this rectangle
-    pub(crate) background_color: Option<BackgroundColor>,
+    pub background_color: Option<BackgroundColor>,
     /// Shadow color
-    pub(crate) box_shadow: Option<BoxShadowPreDisplayItem>,
+    pub box_shadow: Option<BoxShadowPreDisplayItem>,
+    /// `text-shadow` property, the shadow of the glyphs and the text decorations
+    pub text_shadow: Option<BoxShadowPreDisplayItem>,
     /// Gradient (location) + stops
-    pub(crate) background: Option<Background>,
+    pub background: Option<Background>,
+    /// `background-size` property, only used for image backgrounds
+    pub background_size: Option<BackgroundSize>,
+    /// `background-repeat` property, only used for image backgrounds
+    pub background_repeat: Option<BackgroundRepeat>,
     /// Border
-    pub(crate) border: Option<(BorderWidths, BorderDetails)>,
+    pub border: Option<(BorderWidths, BorderDetails)>,
     /// Border radius
-    pub(crate) border_radius: Option<BorderRadius>,
+    pub border_radius: Option<BorderRadius>,
     /// Font size
-    pub(crate) font_size: Option<FontSize>,
+    pub font_size: Option<FontSize>,
     /// Font name / family
-    pub(crate) font_family: Option<FontFamily>,
+    pub font_family: Option<FontFamily>,
+    /// `font-weight`, selects the face of the font family
+    pub font_weight: Option<FontWeight>,
+    /// `font-style`, selects the face of the font family
+    pub font_style: Option<FontStyle>,
     /// Text color
-    pub(crate) font_color: Option<TextColor>,
+    pub font_color: Option<TextColor>,
     /// Text alignment
-    pub(crate) text_align: Option<TextAlignmentHorz>,
+    pub text_align: Option<TextAlignmentHorz>,
     /// Text overflow behaviour
-    pub(crate) overflow: Option<LayoutOverflow>,
+    pub overflow: Option<LayoutOverflow>,
     /// `line-height` property
-    pub(crate) line_height: Option<LineHeight>,
+    pub line_height: Option<LineHeight>,
+    /// `text-indent` property
+    pub text_indent: Option<TextIndent>,
+    /// `text-overflow` property
+    pub text_overflow: Option<TextOverflowMode>,
+    /// `content-visibility` property
+    pub content_visibility: Option<ContentVisibility>,
+    /// `backdrop-filter` property
+    pub backdrop_filter: Option<BackdropFilter>,
+    /// `filter` property, only used for images
+    pub filter: Option<Filter>,
+    /// `transition` property
+    pub transition: Option<Transition>,
+    /// `overflow-wrap` property
+    pub overflow_wrap: Option<OverflowWrap>,
+    /// `word-break` property
+    pub word_break: Option<WordBreak>,
+    /// `text-decoration` property
+    pub text_decoration: Option<TextDecorationLines>,
+    /// `opacity` property, applies to the rectangle and all of its children
+    pub opacity: Option<Opacity>,
+    /// `visibility` property, inherited by the children
+    pub visibility: Option<Visibility>,
+    /// `direction` property, inherited by the children
+    pub direction: Option<TextDirection>,
+    /// `writing-mode` property, inherited by the children
+    pub writing_mode: Option<WritingMode>,
+    /// `user-select` property
+    pub user_select: Option<UserSelect>,
+    /// `overscroll-behavior` property, only used for scroll containers
+    pub overscroll_behavior: Option<OverscrollBehavior>,
+    /// `resize` property, draws a resize grip in the bottom-right corner
+    pub resize: Option<LayoutResize>,
+    /// `-azul-tint-color` property, only used for images and OpenGL textures
+    pub tint_color: Option<TintColor>,
+    /// `caret-color` property, the color of the blinking text caret
+    pub caret_color: Option<CaretColor>,
+    /// `appearance` property, already applied when the CSS rules are matched
+    pub appearance: Option<Appearance>,
 }
 
 // Layout constraints for a given rectangle, such as ""
@@ -1644,6 +3329,23 @@ pub struct RectLayout {
     pub min_height: Option<LayoutMinHeight>,
     pub max_width: Option<LayoutMaxWidth>,
     pub max_height: Option<LayoutMaxHeight>,
+    /// `aspect-ratio` property - if only the width or only the height of the
+    /// box is set, the other dimension is derived from this ratio
+    pub aspect_ratio: Option<LayoutAspectRatio>,
+    /// `padding` property - the content (i.e. the text) and the children are laid out inside of the padding
+    pub padding: Option<LayoutPadding>,
+    /// `margin` property - the space between the box and the content box of its parent
+    pub margin: Option<LayoutMargin>,
+    /// `gap` property, the space between the children along the main axis
+    pub gap: Option<LayoutGap>,
+    /// `display` property, `display: none` removes the box and its children from the layout
+    pub display: Option<LayoutDisplay>,
+    /// `-azul-constraint-strength` property, the strength of the `width` / `height` constraints
+    pub constraint_strength: Option<LayoutConstraintStrength>,
+    /// `contain` property, a contained box is laid out again without its parent
+    pub contain: Option<LayoutContain>,
+    /// `box-sizing` property, whether the border is part of the size of the box
+    pub box_sizing: Option<LayoutBoxSizing>,
     pub direction: Option<LayoutDirection>,
     pub wrap: Option<LayoutWrap>,
     pub justify_content: Option<LayoutJustifyContent>,
@@ -1651,12 +3353,182 @@ pub struct RectLayout {
     pub align_content: Option<LayoutAlignContent>,
 }
 
-typed_pixel_value_parser!(parse_layout_width, LayoutWidth);
-typed_pixel_value_parser!(parse_layout_height, LayoutHeight);
-typed_pixel_value_parser!(parse_layout_min_height, LayoutMinHeight);
-typed_pixel_value_parser!(parse_layout_min_width, LayoutMinWidth);
-typed_pixel_value_parser!(parse_layout_max_width, LayoutMaxWidth);
-typed_pixel_value_parser!(parse_layout_max_height, LayoutMaxHeight);
+/// CSS-wide keywords, which are valid values for every property
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CssWideKeyword {
+    /// `inherit`: the property takes the resolved value of the parent node,
+    /// also for properties that aren't inherited by default
+    Inherit,
+    /// `initial`: the property is reset to its initial (default) value
+    Initial,
+}
+
+impl CssWideKeyword {
+    pub(crate) fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "inherit" => Some(CssWideKeyword::Inherit),
+            "initial" => Some(CssWideKeyword::Initial),
+            _ => None,
+        }
+    }
+}
+
+/// Generates the mapping from the CSS key to the field of the `RectStyle` / `RectLayout`
+/// that the property sets, for `apply_css_wide_keyword`
+macro_rules! copy_css_property_field {(
+    $key:expr, $source:expr, $style:expr, $layout:expr,
+    style: [$($($style_key:tt)|+ => $style_field:ident,)*]
+    layout: [$($($layout_key:tt)|+ => $layout_field:ident,)*]
+) => (
+    match $key {
+        $($($style_key)|+ => { $style.$style_field = $source.and_then(|(style, _)| style.$style_field.clone()); },)*
+        $($($layout_key)|+ => { $layout.$layout_field = $source.and_then(|(_, layout)| layout.$layout_field.clone()); },)*
+        _ => return false,
+    }
+)}
+
+/// Applies the CSS-wide `keyword` to the property `key` of a node: `inherit` copies the
+/// value from the resolved `parent` style / layout (the initial value for the root node),
+/// `initial` resets the property. Shorthands (i.e. `border`) reset the whole property.
+///
+/// Returns `false` if `key` isn't a supported CSS property.
+pub(crate) fn apply_css_wide_keyword(
+    key: &str,
+    keyword: CssWideKeyword,
+    style: &mut RectStyle,
+    layout: &mut RectLayout,
+    parent: Option<(&RectStyle, &RectLayout)>)
+-> bool
+{
+    let source = match keyword {
+        CssWideKeyword::Inherit => parent,
+        CssWideKeyword::Initial => None,
+    };
+
+    copy_css_property_field!(key, source, style, layout,
+        style: [
+            "border-radius" => border_radius,
+            "background-color" => background_color,
+            "color" => font_color,
+            "border" | "border-top" | "border-right" | "border-bottom" | "border-left" |
+            "border-top-width" | "border-right-width" | "border-bottom-width" | "border-left-width" |
+            "border-top-style" | "border-right-style" | "border-bottom-style" | "border-left-style" |
+            "border-top-color" | "border-right-color" | "border-bottom-color" | "border-left-color" => border,
+            "background" => background,
+            "background-size" => background_size,
+            "background-repeat" => background_repeat,
+            "font-size" => font_size,
+            "font-family" => font_family,
+            "font-weight" => font_weight,
+            "font-style" => font_style,
+            "box-shadow" => box_shadow,
+            "text-shadow" => text_shadow,
+            "line-height" => line_height,
+            "text-indent" => text_indent,
+            "overflow" | "overflow-x" | "overflow-y" => overflow,
+            "text-align" => text_align,
+            "text-overflow" => text_overflow,
+            "content-visibility" => content_visibility,
+            "backdrop-filter" => backdrop_filter,
+            "filter" => filter,
+            "transition" => transition,
+            "overflow-wrap" | "word-wrap" => overflow_wrap,
+            "word-break" => word_break,
+            "text-decoration" => text_decoration,
+            "opacity" => opacity,
+            "visibility" => visibility,
+            "direction" => direction,
+            "writing-mode" => writing_mode,
+            "user-select" => user_select,
+            "overscroll-behavior" => overscroll_behavior,
+            "resize" => resize,
+            "-azul-tint-color" => tint_color,
+            "caret-color" => caret_color,
+            "appearance" => appearance,
+        ]
+        layout: [
+            "width" => width,
+            "height" => height,
+            "min-width" => min_width,
+            "min-height" => min_height,
+            "max-width" => max_width,
+            "max-height" => max_height,
+            "aspect-ratio" => aspect_ratio,
+            "padding" | "padding-top" | "padding-right" | "padding-bottom" | "padding-left" => padding,
+            "margin" | "margin-top" | "margin-right" | "margin-bottom" | "margin-left" => margin,
+            "gap" => gap,
+            "display" => display,
+            "-azul-constraint-strength" => constraint_strength,
+            "contain" => contain,
+            "box-sizing" => box_sizing,
+            "flex-direction" => direction,
+            "flex-wrap" => wrap,
+            "justify-content" => justify_content,
+            "align-items" => align_items,
+            "align-content" => align_content,
+        ]
+    );
+
+    true
+}
+
+/// Returns whether `key` is a supported CSS property
+pub(crate) fn is_supported_css_key(key: &str) -> bool {
+    apply_css_wide_keyword(key, CssWideKeyword::Initial, &mut RectStyle::default(), &mut RectLayout::default(), None)
+}
+
+typed_size_value_parser!(parse_layout_width, LayoutWidth);
+typed_size_value_parser!(parse_layout_height, LayoutHeight);
+typed_size_value_parser!(parse_layout_min_height, LayoutMinHeight);
+typed_size_value_parser!(parse_layout_min_width, LayoutMinWidth);
+typed_size_value_parser!(parse_layout_max_width, LayoutMaxWidth);
+typed_size_value_parser!(parse_layout_max_height, LayoutMaxHeight);
+
+/// Parses a gap, i.e. "10px", "10%" or "normal". Negative gaps are invalid
+fn parse_layout_gap<'a>(input: &'a str)
+-> Result<LayoutGap, InvalidValueErr<'a>>
+{
+    if input == "normal" {
+        return Ok(LayoutGap::Normal);
+    }
+
+    let gap = if input.ends_with('%') {
+        match input[..(input.len() - 1)].trim().parse::<f32>() {
+            Ok(percent) => LayoutGap::Percentage(PercentageValue { number: percent / 100.0 }),
+            Err(_) => return Err(InvalidValueErr(input)),
+        }
+    } else {
+        match parse_pixel_value(input) {
+            Ok(px) => LayoutGap::Pixel(px),
+            Err(_) => return Err(InvalidValueErr(input)),
+        }
+    };
+
+    if gap.to_pixels(Some(1.0)) < 0.0 {
+        return Err(InvalidValueErr(input));
+    }
+
+    Ok(gap)
+}
+
+/// Parses an aspect ratio, i.e. "16/9", "16 / 9" or "1.5"
+fn parse_layout_aspect_ratio<'a>(input: &'a str)
+-> Result<LayoutAspectRatio, InvalidValueErr<'a>>
+{
+    let mut components = input.split('/').map(|c| c.trim().parse::<f32>());
+
+    let ratio = match (components.next(), components.next(), components.next()) {
+        (Some(Ok(ratio)), None, None) => ratio,
+        (Some(Ok(width)), Some(Ok(height)), None) if height != 0.0 => width / height,
+        _ => return Err(InvalidValueErr(input)),
+    };
+
+    if ratio > 0.0 {
+        Ok(LayoutAspectRatio(ratio))
+    } else {
+        Err(InvalidValueErr(input))
+    }
+}
 
 fn parse_line_height(input: &str)
 -> Result<LineHeight, PercentageParseError>
@@ -1668,6 +3540,7 @@ fn parse_line_height(input: &str)
 pub struct FontSize(pub(crate) PixelValue);
 
 typed_pixel_value_parser!(parse_css_font_size, FontSize);
+typed_pixel_value_parser!(parse_text_indent, TextIndent);
 
 impl FontSize {
     /// Creates the font size in pixel
@@ -1685,8 +3558,9 @@ impl FontSize {
         FontSize(PixelValue::from_metric(CssMetric::Pt, value))
     }
 
+    /// Returns the font size in pixels, a percentage is relative to the default font size
     pub fn to_pixels(&self) -> f32 {
-        self.0.to_pixels()
+        self.0.to_pixels_relative_to(EM_HEIGHT)
     }
 }
 
@@ -1752,6 +3626,61 @@ pub(crate) fn parse_css_font_family<'a>(input: &'a str) -> Result<FontFamily, Cs
     })
 }
 
+/// Weight of a font (`font-weight`), from `1` (thinnest) to `1000` (heaviest).
+/// `normal` is `400`, `bold` is `700`
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Hash)]
+pub struct FontWeight(pub u16);
+
+impl FontWeight {
+    pub const NORMAL: FontWeight = FontWeight(400);
+    pub const BOLD: FontWeight = FontWeight(700);
+
+    /// Returns whether the weight is bold enough that a regular face has to be
+    /// rendered with a synthetic ("faux") bold if no bolder face is available
+    pub fn is_bold(&self) -> bool {
+        self.0 >= 600
+    }
+}
+
+impl Default for FontWeight {
+    fn default() -> Self {
+        FontWeight::NORMAL
+    }
+}
+
+/// Parses a `font-weight`, i.e. `"bold"` or `"600"`
+fn parse_css_font_weight<'a>(input: &'a str)
+-> Result<FontWeight, InvalidValueErr<'a>>
+{
+    match input {
+        "normal" => Ok(FontWeight::NORMAL),
+        "bold" => Ok(FontWeight::BOLD),
+        _ => match input.parse::<u16>() {
+            Ok(weight) if weight >= 1 && weight <= 1000 => Ok(FontWeight(weight)),
+            _ => Err(InvalidValueErr(input)),
+        }
+    }
+}
+
+/// Style of a font (`font-style`)
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum FontStyle {
+    Normal,
+    Italic,
+    Oblique,
+}
+
+impl Default for FontStyle {
+    fn default() -> Self {
+        FontStyle::Normal
+    }
+}
+
+multi_type_parser!(parse_css_font_style, FontStyle,
+                    ["normal", Normal],
+                    ["italic", Italic],
+                    ["oblique", Oblique]);
+
 multi_type_parser!(parse_layout_direction, LayoutDirection,
                     ["row", Horizontal],
                     ["column", Vertical]);
@@ -1791,6 +3720,91 @@ multi_type_parser!(parse_layout_text_overflow, TextOverflowBehaviourInner,
                     ["visible", Visible],
                     ["hidden", Hidden]);
 
+multi_type_parser!(parse_text_overflow_mode, TextOverflowMode,
+                    ["clip", Clip],
+                    ["fade", Fade]);
+
+multi_type_parser!(parse_named_timing_function, TransitionTimingFunction,
+                    ["linear", Linear],
+                    ["ease", Ease],
+                    ["ease-in", EaseIn],
+                    ["ease-out", EaseOut],
+                    ["ease-in-out", EaseInOut]);
+
+multi_type_parser!(parse_content_visibility, ContentVisibility,
+                    ["visible", Visible],
+                    ["auto", Auto]);
+
+multi_type_parser!(parse_overflow_wrap, OverflowWrap,
+                    ["normal", Normal],
+                    ["break-word", BreakWord]);
+
+multi_type_parser!(parse_visibility, Visibility,
+                    ["visible", Visible],
+                    ["hidden", Hidden]);
+
+multi_type_parser!(parse_text_direction, TextDirection,
+                    ["ltr", Ltr],
+                    ["rtl", Rtl]);
+
+multi_type_parser!(parse_writing_mode, WritingMode,
+                    ["horizontal-tb", HorizontalTb],
+                    ["vertical-rl", VerticalRl],
+                    ["vertical-lr", VerticalLr]);
+
+multi_type_parser!(parse_user_select, UserSelect,
+                    ["none", None],
+                    ["text", Text]);
+
+multi_type_parser!(parse_overscroll_behavior, OverscrollBehavior,
+                    ["auto", Auto],
+                    ["contain", Contain],
+                    ["none", None]);
+
+multi_type_parser!(parse_layout_resize, LayoutResize,
+                    ["none", None],
+                    ["both", Both],
+                    ["horizontal", Horizontal],
+                    ["vertical", Vertical]);
+
+multi_type_parser!(parse_layout_display, LayoutDisplay,
+                    ["flex", Flex],
+                    ["none", None]);
+
+multi_type_parser!(parse_layout_constraint_strength, LayoutConstraintStrength,
+                    ["weak", Weak],
+                    ["medium", Medium],
+                    ["strong", Strong]);
+
+multi_type_parser!(parse_appearance, Appearance,
+                    ["auto", Auto],
+                    ["none", None]);
+
+multi_type_parser!(parse_css_background_repeat, BackgroundRepeat,
+                    ["repeat", Repeat],
+                    ["no-repeat", NoRepeat],
+                    ["repeat-x", RepeatX],
+                    ["repeat-y", RepeatY]);
+
+multi_type_parser!(parse_layout_box_sizing, LayoutBoxSizing,
+                    ["content-box", ContentBox],
+                    ["border-box", BorderBox]);
+
+multi_type_parser!(parse_layout_contain, LayoutContain,
+                    ["none", None],
+                    ["layout", Layout],
+                    ["size", Size]);
+
+multi_type_parser!(parse_text_decoration, TextDecoration,
+                    ["none", None],
+                    ["underline", Underline],
+                    ["overline", Overline],
+                    ["line-through", LineThrough]);
+
+multi_type_parser!(parse_word_break, WordBreak,
+                    ["normal", Normal],
+                    ["break-all", BreakAll]);
+
 multi_type_parser!(parse_layout_text_align, TextAlignmentHorz,
                     ["center", Center],
                     ["left", Left],
@@ -1931,6 +3945,46 @@ mod css_tests {
         }))));
     }
 
+    #[test]
+    fn test_parse_css_border_side() {
+        let red = ColorU { r: 255, g: 0, b: 0, a: 255 };
+        assert_eq!(ParsedCssProperty::from_kv("border-bottom", "2px solid red"), Ok(ParsedCssProperty::BorderSide(BorderSideOverride {
+            edge: BorderEdge::Bottom,
+            width: Some(2.0),
+            style: Some(BorderStyle::Solid),
+            color: Some(red),
+        })));
+        assert_eq!(ParsedCssProperty::from_kv("border-top-color", "red"), Ok(ParsedCssProperty::BorderSide(BorderSideOverride {
+            edge: BorderEdge::Top,
+            width: None,
+            style: None,
+            color: Some(red),
+        })));
+        assert_eq!(ParsedCssProperty::from_kv("border-left-width", "3px"), Ok(ParsedCssProperty::BorderSide(BorderSideOverride {
+            edge: BorderEdge::Left,
+            width: Some(3.0),
+            style: None,
+            color: None,
+        })));
+        assert!(ParsedCssProperty::from_kv("border-right-style", "wavy").is_err());
+
+        // Only the bottom side is set, the other sides are not drawn
+        let (widths, details) = parse_css_border_side("2px solid red", BorderEdge::Bottom).unwrap().apply_to(None);
+        assert_eq!(widths, BorderWidths { top: 0.0, right: 0.0, bottom: 2.0, left: 0.0 });
+        match details {
+            BorderDetails::Normal(n) => {
+                assert_eq!(n.bottom, BorderSide { color: red.into(), style: BorderStyle::Solid });
+                assert_eq!(n.top.style, BorderStyle::None);
+            },
+            _ => panic!("expected a normal border"),
+        }
+
+        // Longhands only override their own component
+        let border = parse_css_border("5px solid red").unwrap();
+        let (widths, _) = parse_css_border_side_width("1px", BorderEdge::Left).unwrap().apply_to(Some(border));
+        assert_eq!(widths, BorderWidths { top: 5.0, right: 5.0, bottom: 5.0, left: 1.0 });
+    }
+
     #[test]
     fn test_parse_css_border_2() {
         assert_eq!(parse_css_border("double"), Ok((BorderWidths {
@@ -2045,6 +4099,8 @@ mod css_tests {
         assert_eq!(parse_css_background("radial-gradient(circle, lime, blue, yellow)"),
             Ok(Background::RadialGradient(RadialGradientPreInfo {
                 shape: Shape::Circle,
+                size: RadialGradientSize::Default,
+                center: RadialGradientCenter::default(),
                 extend_mode: ExtendMode::Clamp,
                 stops: vec![
                 GradientStopPre {
@@ -2069,6 +4125,8 @@ mod css_tests {
         assert_eq!(parse_css_background("repeating-radial-gradient(circle, red 10%, blue 50%, lime, yellow)"),
             Ok(ParsedGradient::RadialGradient(RadialGradientPreInfo {
                 shape: Shape::Circle,
+                size: RadialGradientSize::Default,
+                center: RadialGradientCenter::default(),
                 extend_mode: ExtendMode::Repeat,
                 stops: vec![
                 GradientStopPre {
@@ -2091,6 +4149,72 @@ mod css_tests {
     }
     */
 
+    #[test]
+    fn test_parse_radial_gradient_center_and_size() {
+        let percent = |p: f32| GradientPosition::Percentage(PercentageValue { number: p });
+        let parse = |input| match parse_css_background(input) {
+            Ok(Background::RadialGradient(g)) => (g.shape, g.size, g.center),
+            other => panic!("{:?}", other),
+        };
+
+        assert_eq!(parse("radial-gradient(circle at center, lime, blue)"),
+            (Shape::Circle, RadialGradientSize::Default, RadialGradientCenter { horizontal: percent(0.5), vertical: percent(0.5) }));
+        assert_eq!(parse("radial-gradient(ellipse closest-side at 20% 80%, lime, blue)"),
+            (Shape::Ellipse, RadialGradientSize::ClosestSide, RadialGradientCenter { horizontal: percent(0.2), vertical: percent(0.8) }));
+        assert_eq!(parse("radial-gradient(at top right, lime, blue)"),
+            (Shape::Ellipse, RadialGradientSize::Default, RadialGradientCenter { horizontal: percent(1.0), vertical: percent(0.0) }));
+        assert_eq!(parse("radial-gradient(30px at 10px bottom, lime, blue)"),
+            (Shape::Circle, RadialGradientSize::Radius(PixelValue::from_metric(CssMetric::Px, 30.0), PixelValue::from_metric(CssMetric::Px, 30.0)),
+             RadialGradientCenter { horizontal: GradientPosition::Pixel(PixelValue::from_metric(CssMetric::Px, 10.0)), vertical: percent(1.0) }));
+        // Without a shape, the first item is a color stop, as before
+        assert_eq!(parse("radial-gradient(lime, blue)"),
+            (Shape::Ellipse, RadialGradientSize::Default, RadialGradientCenter::default()));
+        // A circle has only one radius
+        assert!(parse_css_background("radial-gradient(circle 30px 20px, lime, blue)").is_err());
+    }
+
+    #[test]
+    fn test_parse_background_size() {
+        use self::BackgroundSizeComponent::*;
+        let px = |p: f32| Pixel(PixelValue::from_metric(CssMetric::Px, p));
+
+        assert_eq!(parse_css_background_size("cover"), Ok(BackgroundSize::Cover));
+        assert_eq!(parse_css_background_size("contain"), Ok(BackgroundSize::Contain));
+        assert_eq!(parse_css_background_size("50px"), Ok(BackgroundSize::Exact(px(50.0), Auto)));
+        assert_eq!(parse_css_background_size("50% 20px"), Ok(BackgroundSize::Exact(Percentage(PercentageValue { number: 0.5 }), px(20.0))));
+        assert_eq!(parse_css_background_size("auto 10px"), Ok(BackgroundSize::Exact(Auto, px(10.0))));
+        assert!(parse_css_background_size("50px 50px 50px").is_err());
+        assert!(parse_css_background_size("big").is_err());
+    }
+
+    #[test]
+    fn test_background_size_keeps_the_aspect_ratio() {
+        let box_size = LayoutSize::new(200.0, 100.0);
+        let image_size = LayoutSize::new(50.0, 50.0);
+
+        assert_eq!(BackgroundSize::Cover.to_size(box_size, image_size), LayoutSize::new(200.0, 200.0));
+        assert_eq!(BackgroundSize::Contain.to_size(box_size, image_size), LayoutSize::new(100.0, 100.0));
+        assert_eq!(parse_css_background_size("25% auto").unwrap().to_size(box_size, image_size), LayoutSize::new(50.0, 50.0));
+        assert_eq!(parse_css_background_size("auto").unwrap().to_size(box_size, image_size), image_size);
+        assert_eq!(parse_css_background_size("10px 100%").unwrap().to_size(box_size, image_size), LayoutSize::new(10.0, 100.0));
+    }
+
+    #[test]
+    fn test_radial_gradient_center_resolves_against_the_bounds() {
+        let rect = LayoutRect::new(LayoutPoint::new(50.0, 50.0), LayoutSize::new(200.0, 100.0));
+        let gradient = |input| match parse_css_background(input) {
+            Ok(Background::RadialGradient(g)) => g.to_center_and_radius(&rect),
+            other => panic!("{:?}", other),
+        };
+
+        // Default: bottom left corner, 40px radius
+        assert_eq!(gradient("radial-gradient(circle, lime, blue)"), (LayoutPoint::new(0.0, 100.0), LayoutSize::new(40.0, 40.0)));
+        assert_eq!(gradient("radial-gradient(circle at center, lime, blue)"), (LayoutPoint::new(100.0, 50.0), LayoutSize::new(40.0, 40.0)));
+        assert_eq!(gradient("radial-gradient(circle closest-side at 25% 50%, lime, blue)"), (LayoutPoint::new(50.0, 50.0), LayoutSize::new(50.0, 50.0)));
+        assert_eq!(gradient("radial-gradient(ellipse farthest-side at 25% 50%, lime, blue)"), (LayoutPoint::new(50.0, 50.0), LayoutSize::new(150.0, 50.0)));
+        assert_eq!(gradient("radial-gradient(circle farthest-corner at left top, lime, blue)"), (LayoutPoint::new(0.0, 0.0), LayoutSize::new(200.0_f32.hypot(100.0), 200.0_f32.hypot(100.0))));
+    }
+
     #[test]
     fn test_parse_css_color_1() {
         assert_eq!(parse_css_color("#F0F8FF"), Ok(ColorU { r: 240, g: 248, b: 255, a: 255 }));
@@ -2175,10 +4299,332 @@ mod css_tests {
         }));
     }
 
+    #[test]
+    fn test_parse_text_overflow_mode() {
+        assert_eq!(ParsedCssProperty::from_kv("text-overflow", "fade"), Ok(ParsedCssProperty::TextOverflow(TextOverflowMode::Fade)));
+        assert_eq!(ParsedCssProperty::from_kv("text-overflow", "clip"), Ok(ParsedCssProperty::TextOverflow(TextOverflowMode::Clip)));
+        assert!(ParsedCssProperty::from_kv("text-overflow", "fade-out").is_err());
+    }
+
+    #[test]
+    fn test_parse_content_visibility() {
+        assert_eq!(ParsedCssProperty::from_kv("content-visibility", "auto"), Ok(ParsedCssProperty::ContentVisibility(ContentVisibility::Auto)));
+        assert_eq!(ParsedCssProperty::from_kv("content-visibility", "visible"), Ok(ParsedCssProperty::ContentVisibility(ContentVisibility::Visible)));
+        assert!(ParsedCssProperty::from_kv("content-visibility", "hidden").is_err());
+    }
+
+    #[test]
+    fn test_parse_overflow_wrap_and_word_break() {
+        assert_eq!(ParsedCssProperty::from_kv("overflow-wrap", "break-word"), Ok(ParsedCssProperty::OverflowWrap(OverflowWrap::BreakWord)));
+        assert_eq!(ParsedCssProperty::from_kv("word-wrap", "normal"), Ok(ParsedCssProperty::OverflowWrap(OverflowWrap::Normal)));
+        assert_eq!(ParsedCssProperty::from_kv("word-break", "break-all"), Ok(ParsedCssProperty::WordBreak(WordBreak::BreakAll)));
+        assert!(ParsedCssProperty::from_kv("word-break", "keep-all").is_err());
+    }
+
+    #[test]
+    fn test_parse_opacity_visibility_and_display() {
+        assert_eq!(ParsedCssProperty::from_kv("opacity", "0.5"), Ok(ParsedCssProperty::Opacity(Opacity(0.5))));
+        assert_eq!(ParsedCssProperty::from_kv("opacity", "25%"), Ok(ParsedCssProperty::Opacity(Opacity(0.25))));
+        assert_eq!(ParsedCssProperty::from_kv("opacity", "1.5"), Ok(ParsedCssProperty::Opacity(Opacity(1.0))));
+        assert!(ParsedCssProperty::from_kv("opacity", "half").is_err());
+        assert_eq!(ParsedCssProperty::from_kv("visibility", "hidden"), Ok(ParsedCssProperty::Visibility(Visibility::Hidden)));
+        assert_eq!(ParsedCssProperty::from_kv("display", "none"), Ok(ParsedCssProperty::Display(LayoutDisplay::None)));
+        assert!(ParsedCssProperty::from_kv("display", "grid").is_err());
+        assert_eq!(ParsedCssProperty::from_kv("contain", "layout"), Ok(ParsedCssProperty::Contain(LayoutContain::Layout)));
+        assert_eq!(ParsedCssProperty::from_kv("contain", "size"), Ok(ParsedCssProperty::Contain(LayoutContain::Size)));
+        assert!(ParsedCssProperty::from_kv("contain", "everything").is_err());
+        assert_eq!(ParsedCssProperty::from_kv("box-sizing", "border-box"), Ok(ParsedCssProperty::BoxSizing(LayoutBoxSizing::BorderBox)));
+        assert!(ParsedCssProperty::from_kv("box-sizing", "padding-box").is_err());
+        assert_eq!(ParsedCssProperty::from_kv("background-repeat", "repeat-x"), Ok(ParsedCssProperty::BackgroundRepeat(BackgroundRepeat::RepeatX)));
+        assert!(ParsedCssProperty::from_kv("background-repeat", "round").is_err());
+        assert_eq!(ParsedCssProperty::from_kv("appearance", "none"), Ok(ParsedCssProperty::Appearance(Appearance::None)));
+        assert!(ParsedCssProperty::from_kv("appearance", "button").is_err());
+    }
+
+    #[test]
+    fn test_parse_text_direction() {
+        assert_eq!(ParsedCssProperty::from_kv("direction", "rtl"), Ok(ParsedCssProperty::Direction(TextDirection::Rtl)));
+        assert_eq!(ParsedCssProperty::from_kv("direction", "ltr"), Ok(ParsedCssProperty::Direction(TextDirection::Ltr)));
+        assert!(ParsedCssProperty::from_kv("direction", "up").is_err());
+        assert_eq!(ParsedCssProperty::from_kv("writing-mode", "vertical-rl"), Ok(ParsedCssProperty::WritingMode(WritingMode::VerticalRl)));
+        assert!(ParsedCssProperty::from_kv("writing-mode", "sideways-rl").is_err());
+    }
+
+    #[test]
+    fn test_parse_tint_color() {
+        assert_eq!(ParsedCssProperty::from_kv("-azul-tint-color", "#ff0000"),
+                   Ok(ParsedCssProperty::TintColor(TintColor(ColorU { r: 255, g: 0, b: 0, a: 255 }))));
+        assert!(ParsedCssProperty::from_kv("-azul-tint-color", "not-a-color").is_err());
+    }
+
+    #[test]
+    fn test_parse_caret_color() {
+        assert_eq!(ParsedCssProperty::from_kv("caret-color", "red"),
+                   Ok(ParsedCssProperty::CaretColor(CaretColor(ColorU { r: 255, g: 0, b: 0, a: 255 }))));
+        assert!(ParsedCssProperty::from_kv("caret-color", "not-a-color").is_err());
+    }
+
+    #[test]
+    fn test_parse_text_indent() {
+        assert_eq!(ParsedCssProperty::from_kv("text-indent", "20px"),
+                   Ok(ParsedCssProperty::TextIndent(TextIndent(PixelValue::from_metric(CssMetric::Px, 20.0)))));
+        assert_eq!(ParsedCssProperty::from_kv("text-indent", "-1em"),
+                   Ok(ParsedCssProperty::TextIndent(TextIndent(PixelValue::from_metric(CssMetric::Em, -1.0)))));
+    }
+
+    #[test]
+    fn test_parse_user_select() {
+        assert_eq!(ParsedCssProperty::from_kv("user-select", "none"), Ok(ParsedCssProperty::UserSelect(UserSelect::None)));
+        assert_eq!(ParsedCssProperty::from_kv("user-select", "text"), Ok(ParsedCssProperty::UserSelect(UserSelect::Text)));
+        assert!(ParsedCssProperty::from_kv("user-select", "all").is_err());
+    }
+
+    #[test]
+    fn test_parse_overscroll_behavior() {
+        assert_eq!(ParsedCssProperty::from_kv("overscroll-behavior", "contain"), Ok(ParsedCssProperty::OverscrollBehavior(OverscrollBehavior::Contain)));
+        assert_eq!(ParsedCssProperty::from_kv("overscroll-behavior", "none"), Ok(ParsedCssProperty::OverscrollBehavior(OverscrollBehavior::None)));
+        assert_eq!(ParsedCssProperty::from_kv("overscroll-behavior", "auto"), Ok(ParsedCssProperty::OverscrollBehavior(OverscrollBehavior::Auto)));
+        assert!(ParsedCssProperty::from_kv("overscroll-behavior", "scroll").is_err());
+    }
+
+    #[test]
+    fn test_parse_font_weight_and_style() {
+        assert_eq!(ParsedCssProperty::from_kv("font-weight", "bold"), Ok(ParsedCssProperty::FontWeight(FontWeight::BOLD)));
+        assert_eq!(ParsedCssProperty::from_kv("font-weight", "normal"), Ok(ParsedCssProperty::FontWeight(FontWeight(400))));
+        assert_eq!(ParsedCssProperty::from_kv("font-weight", "300"), Ok(ParsedCssProperty::FontWeight(FontWeight(300))));
+        assert!(ParsedCssProperty::from_kv("font-weight", "0").is_err());
+        assert!(ParsedCssProperty::from_kv("font-weight", "heavy").is_err());
+        assert_eq!(ParsedCssProperty::from_kv("font-style", "italic"), Ok(ParsedCssProperty::FontStyle(FontStyle::Italic)));
+        assert_eq!(ParsedCssProperty::from_kv("font-style", "oblique"), Ok(ParsedCssProperty::FontStyle(FontStyle::Oblique)));
+        assert!(ParsedCssProperty::from_kv("font-style", "slanted").is_err());
+    }
+
+    #[test]
+    fn test_parse_resize() {
+        assert_eq!(ParsedCssProperty::from_kv("resize", "both"), Ok(ParsedCssProperty::Resize(LayoutResize::Both)));
+        assert_eq!(ParsedCssProperty::from_kv("resize", "horizontal"), Ok(ParsedCssProperty::Resize(LayoutResize::Horizontal)));
+        assert_eq!(ParsedCssProperty::from_kv("resize", "vertical"), Ok(ParsedCssProperty::Resize(LayoutResize::Vertical)));
+        assert_eq!(ParsedCssProperty::from_kv("resize", "none"), Ok(ParsedCssProperty::Resize(LayoutResize::None)));
+        assert!(ParsedCssProperty::from_kv("resize", "diagonal").is_err());
+    }
+
+    #[test]
+    fn test_parse_constraint_strength() {
+        assert_eq!(ParsedCssProperty::from_kv("-azul-constraint-strength", "weak"),
+            Ok(ParsedCssProperty::ConstraintStrength(LayoutConstraintStrength::Weak)));
+        assert!(ParsedCssProperty::from_kv("-azul-constraint-strength", "required").is_err());
+    }
+
+    #[test]
+    fn test_parse_text_decoration() {
+        assert_eq!(ParsedCssProperty::from_kv("text-decoration", "underline"), Ok(ParsedCssProperty::TextDecoration(TextDecoration::Underline.into())));
+        assert_eq!(ParsedCssProperty::from_kv("text-decoration", "overline"), Ok(ParsedCssProperty::TextDecoration(TextDecoration::Overline.into())));
+        assert_eq!(ParsedCssProperty::from_kv("text-decoration", "none"), Ok(ParsedCssProperty::TextDecoration(TextDecoration::None.into())));
+        assert!(ParsedCssProperty::from_kv("text-decoration", "blink").is_err());
+    }
+
+    #[test]
+    fn test_parse_multiple_text_decoration_lines() {
+        let all_lines = TextDecorationLines { underline: true, overline: true, line_through: true };
+        assert_eq!(ParsedCssProperty::from_kv("text-decoration", "underline overline line-through"), Ok(ParsedCssProperty::TextDecoration(all_lines)));
+        assert_eq!(all_lines.lines(), vec![TextDecoration::Overline, TextDecoration::LineThrough, TextDecoration::Underline]);
+        assert_eq!(ParsedCssProperty::from_kv("text-decoration", "line-through  underline"),
+                   Ok(ParsedCssProperty::TextDecoration(TextDecorationLines { underline: true, overline: false, line_through: true })));
+        assert!(ParsedCssProperty::from_kv("text-decoration", "underline underline").is_err());
+        assert!(ParsedCssProperty::from_kv("text-decoration", "underline none").is_err());
+        assert!(ParsedCssProperty::from_kv("text-decoration", "underline blink").is_err());
+    }
+
+    #[test]
+    fn test_parse_backdrop_filter() {
+        assert_eq!(ParsedCssProperty::from_kv("backdrop-filter", "blur(10px)"),
+            Ok(ParsedCssProperty::BackdropFilter(BackdropFilter::Blur(PixelValue::from_metric(CssMetric::Px, 10.0)))));
+        assert_eq!(parse_css_backdrop_filter("sepia(1)"), Err(CssBackdropFilterParseError::InvalidFilter("sepia")));
+        assert_eq!(parse_css_backdrop_filter("blur(10px"), Err(CssBackdropFilterParseError::UnclosedBrace("blur(10px")));
+    }
+
+    #[test]
+    fn test_parse_filter_and_text_shadow() {
+        let shadow = BoxShadowPreDisplayItem {
+            offset: LayoutVector2D::new(5.0, 5.0),
+            color: ColorF { r: 0.0, g: 0.0, b: 0.0, a: 1.0 },
+            blur_radius: 10.0,
+            spread_radius: 0.0,
+            clip_mode: BoxShadowClipMode::Outset,
+        };
+        assert_eq!(ParsedCssProperty::from_kv("filter", "drop-shadow(5px 5px 10px #000000)"),
+            Ok(ParsedCssProperty::Filter(Filter::DropShadow(shadow))));
+        assert_eq!(parse_css_filter("drop-shadow(5px 5px 10px #000000 inset)"),
+            Err(CssFilterParseError::InvalidDropShadow("5px 5px 10px #000000 inset")));
+        assert_eq!(parse_css_filter("sepia(1)"), Err(CssFilterParseError::InvalidFilter("sepia")));
+
+        assert_eq!(ParsedCssProperty::from_kv("text-shadow", "5px 5px 10px #000000"),
+            Ok(ParsedCssProperty::TextShadow(Some(shadow))));
+        assert_eq!(ParsedCssProperty::from_kv("text-shadow", "none"), Ok(ParsedCssProperty::TextShadow(None)));
+        assert_eq!(parse_css_text_shadow("5px 5px 10px 2px #000000"),
+            Err(CssShadowParseError::InvalidTextShadow("5px 5px 10px 2px #000000")));
+    }
+
+    #[test]
+    fn test_parse_transition() {
+        assert_eq!(parse_css_transition("background-color 200ms"), Ok(Transition {
+            property: String::from("background-color"),
+            duration: Duration::from_millis(200),
+            timing_function: TransitionTimingFunction::Ease,
+        }));
+        assert_eq!(parse_css_transition("width 0.5s ease-in-out"), Ok(Transition {
+            property: String::from("width"),
+            duration: Duration::from_millis(500),
+            timing_function: TransitionTimingFunction::EaseInOut,
+        }));
+        assert_eq!(parse_css_transition("width 1s cubic-bezier(0.42, 0, 0.58, 1)"), Ok(Transition {
+            property: String::from("width"),
+            duration: Duration::from_secs(1),
+            timing_function: TransitionTimingFunction::CubicBezier(0.42, 0.0, 0.58, 1.0),
+        }));
+        assert_eq!(parse_css_transition("width 200px"), Err(CssTransitionParseError::InvalidDuration("200px")));
+        assert_eq!(parse_css_transition("width"), Err(CssTransitionParseError::MissingComponents("width")));
+    }
+
+    #[test]
+    fn test_cubic_bezier_timing_function() {
+        assert_eq!(parse_transition_timing_function("cubic-bezier(0.42,0,0.58,1)"), Ok(TransitionTimingFunction::CubicBezier(0.42, 0.0, 0.58, 1.0)));
+        assert_eq!(parse_transition_timing_function("cubic-bezier(1.5, 0, 0.58, 1)"), Err(InvalidValueErr("cubic-bezier(1.5, 0, 0.58, 1)")));
+        assert_eq!(parse_transition_timing_function("cubic-bezier(0.42, 0, 0.58)"), Err(InvalidValueErr("cubic-bezier(0.42, 0, 0.58)")));
+
+        let ease_in_out = TransitionTimingFunction::CubicBezier(0.42, 0.0, 0.58, 1.0);
+        assert!((ease_in_out.apply(0.5) - 0.5).abs() < 0.001);
+        assert_eq!(ease_in_out.apply(0.0), 0.0);
+        assert!((ease_in_out.apply(1.0) - 1.0).abs() < 0.001);
+
+        // ease-in starts slower than linear
+        let ease_in = TransitionTimingFunction::EaseIn;
+        let linear = TransitionTimingFunction::Linear;
+        assert!(ease_in.apply(0.2) < linear.apply(0.2));
+        assert!(ease_in.apply(0.1) < ease_in.apply(0.2));
+    }
+
+    #[test]
+    fn test_parse_aspect_ratio() {
+        assert_eq!(parse_layout_aspect_ratio("16/9"), Ok(LayoutAspectRatio(16.0 / 9.0)));
+        assert_eq!(parse_layout_aspect_ratio("4 / 3"), Ok(LayoutAspectRatio(4.0 / 3.0)));
+        assert_eq!(parse_layout_aspect_ratio("1.5"), Ok(LayoutAspectRatio(1.5)));
+        assert_eq!(parse_layout_aspect_ratio("16/0"), Err(InvalidValueErr("16/0")));
+        assert_eq!(parse_layout_aspect_ratio("16/9/2"), Err(InvalidValueErr("16/9/2")));
+    }
+
+    #[test]
+    fn test_parse_gap() {
+        assert_eq!(ParsedCssProperty::from_kv("gap", "10%"), Ok(ParsedCssProperty::Gap(LayoutGap::Percentage(PercentageValue { number: 0.1 }))));
+        assert_eq!(ParsedCssProperty::from_kv("gap", "normal"), Ok(ParsedCssProperty::Gap(LayoutGap::Normal)));
+        assert_eq!(ParsedCssProperty::from_kv("gap", "8px"), Ok(ParsedCssProperty::Gap(LayoutGap::Pixel(PixelValue::from_metric(CssMetric::Px, 8.0)))));
+        assert!(ParsedCssProperty::from_kv("gap", "wide").is_err());
+        assert!(ParsedCssProperty::from_kv("gap", "-5%").is_err());
+
+        // Percentages are resolved against the main-axis size of the container
+        assert_eq!(LayoutGap::Percentage(PercentageValue { number: 0.1 }).to_pixels(Some(200.0)), 20.0);
+        assert_eq!(LayoutGap::Percentage(PercentageValue { number: 0.1 }).to_pixels(None), 0.0);
+        assert_eq!(LayoutGap::Normal.to_pixels(Some(200.0)), 0.0);
+    }
+
+    #[test]
+    fn test_parse_padding() {
+        let parse_resolved = |input| parse_layout_padding(input).map(|padding| padding.resolve(&SafeAreaInsets::default()));
+        assert_eq!(parse_resolved("10px"), Ok(LayoutPadding { top: 10.0, right: 10.0, bottom: 10.0, left: 10.0 }));
+        assert_eq!(parse_resolved("10px 20px"), Ok(LayoutPadding { top: 10.0, right: 20.0, bottom: 10.0, left: 20.0 }));
+        assert_eq!(parse_resolved("10px 20px 30px"), Ok(LayoutPadding { top: 10.0, right: 20.0, bottom: 30.0, left: 20.0 }));
+        assert_eq!(parse_resolved("1px 2px 3px 4px"), Ok(LayoutPadding { top: 1.0, right: 2.0, bottom: 3.0, left: 4.0 }));
+        assert_eq!(parse_layout_padding("1px 2px 3px 4px 5px"), Err(CssPaddingParseError::TooManyValues("1px 2px 3px 4px 5px")));
+        assert!(parse_layout_padding("").is_err());
+    }
+
+    #[test]
+    fn test_parse_padding_and_margin_sides() {
+        let insets = SafeAreaInsets::default();
+
+        // `margin` has the same shorthand as `padding`
+        assert_eq!(ParsedCssProperty::from_kv("margin", "5px 10px"), Ok(ParsedCssProperty::Margin(CssPadding {
+            top: PaddingValue::Pixels(5.0),
+            right: PaddingValue::Pixels(10.0),
+            bottom: PaddingValue::Pixels(5.0),
+            left: PaddingValue::Pixels(10.0),
+        })));
+
+        // The sides can be set independently, the other sides keep their value
+        let top = PaddingSideOverride { edge: BorderEdge::Top, value: PaddingValue::Pixels(7.0) };
+        assert_eq!(ParsedCssProperty::from_kv("padding-top", "7px"), Ok(ParsedCssProperty::PaddingSide(top)));
+        assert_eq!(ParsedCssProperty::from_kv("margin-top", "7px"), Ok(ParsedCssProperty::MarginSide(top)));
+        let padding = Some(LayoutPadding { top: 1.0, right: 2.0, bottom: 3.0, left: 4.0 });
+        assert_eq!(top.apply_to(padding, &insets), LayoutPadding { top: 7.0, right: 2.0, bottom: 3.0, left: 4.0 });
+        assert_eq!(top.apply_to(None, &insets), LayoutPadding { top: 7.0, right: 0.0, bottom: 0.0, left: 0.0 });
+
+        assert!(ParsedCssProperty::from_kv("margin-left", "5px 10px").is_err());
+        assert!(ParsedCssProperty::from_kv("margin-left", "5px").unwrap().affects_layout());
+    }
+
+    #[test]
+    fn test_parse_percentage_width() {
+        let width = parse_layout_width("50%").unwrap();
+        assert!(width.0.is_percentage());
+        assert_eq!(width.0.to_pixels_relative_to(300.0), 150.0);
+        // Without the size of the parent, a percentage can't be converted to pixels
+        assert_eq!(width.0.to_pixels(), None);
+        assert_eq!(parse_layout_max_height("25%").unwrap().0.to_pixels_relative_to(200.0), 50.0);
+
+        // Pixel values don't depend on the size of the parent
+        let width = parse_layout_width("120px").unwrap();
+        assert!(!width.0.is_percentage());
+        assert_eq!(width.0.to_pixels_relative_to(300.0), 120.0);
+        assert_eq!(width.0.to_pixels(), Some(120.0));
+
+        assert!(parse_layout_width("abc%").is_err());
+        // Percentages are only supported for sizes
+        assert!(parse_pixel_value("50%").is_err());
+    }
+
+    #[test]
+    fn test_parse_padding_with_safe_area_insets() {
+        use self::EnvironmentVariable::*;
+        assert_eq!(parse_layout_padding("env(safe-area-inset-top) 10px"), Ok(CssPadding {
+            top: PaddingValue::Environment(SafeAreaInsetTop),
+            right: PaddingValue::Pixels(10.0),
+            bottom: PaddingValue::Environment(SafeAreaInsetTop),
+            left: PaddingValue::Pixels(10.0),
+        }));
+        assert_eq!(parse_layout_padding("env(safe-area-inset-bottom)"), Ok(CssPadding {
+            top: PaddingValue::Environment(SafeAreaInsetBottom),
+            right: PaddingValue::Environment(SafeAreaInsetBottom),
+            bottom: PaddingValue::Environment(SafeAreaInsetBottom),
+            left: PaddingValue::Environment(SafeAreaInsetBottom),
+        }));
+        assert_eq!(parse_layout_padding("env(notch-height)"), Err(CssPaddingParseError::UnknownEnvironmentVariable("env(notch-height)")));
+
+        // The insets are only known when the padding is resolved
+        let insets = SafeAreaInsets { top: 44.0, right: 0.0, bottom: 34.0, left: 0.0 };
+        let padding = parse_layout_padding("env(safe-area-inset-top) 5px env(safe-area-inset-bottom)").unwrap();
+        assert_eq!(padding.resolve(&insets), LayoutPadding { top: 44.0, right: 5.0, bottom: 34.0, left: 5.0 });
+        assert_eq!(padding.resolve(&SafeAreaInsets::default()), LayoutPadding { top: 0.0, right: 5.0, bottom: 0.0, left: 5.0 });
+    }
+
     #[test]
     fn test_parse_background_image() {
         assert_eq!(parse_css_background("image(\"Cat 01\")"), Ok(Background::Image(
             CssImageId(String::from("Cat 01"))
         )));
     }
+
+    #[test]
+    fn test_parse_background_image_with_fallback_color() {
+        assert_eq!(parse_css_background("image(\"Cat 01\"), #cccccc"), Ok(Background::ImageWithFallback(
+            CssImageId(String::from("Cat 01")),
+            ColorU { r: 204, g: 204, b: 204, a: 255 },
+        )));
+        assert_eq!(parse_css_background("image(\"Cat 01\") , rgb(255, 0, 0)"), Ok(Background::ImageWithFallback(
+            CssImageId(String::from("Cat 01")),
+            ColorU { r: 255, g: 0, b: 0, a: 255 },
+        )));
+        // A gradient is always painted, it doesn't need a fallback
+        assert!(parse_css_background("linear-gradient(red, yellow), #cccccc").is_err());
+        assert!(parse_css_background("image(\"Cat 01\"), notacolor").is_err());
+    }
 }
\ No newline at end of file
@@ -2,9 +2,10 @@
 #![allow(unused_macros)]
 
 use webrender::api::*;
-use app_units::{AU_PER_PX, MIN_AU, MAX_AU, Au};
+use app_units::{MIN_AU, MAX_AU, Au};
 use euclid::{TypedRect, TypedSize2D};
 use cassowary::Constraint;
+use std::{time::Instant, collections::{BTreeMap, BTreeSet}};
 
 use {
     FastHashMap,
@@ -13,23 +14,48 @@ use {
     constraints::{DisplayRect, CssConstraint},
     ui_description::{UiDescription, StyledNode},
     window::UiSolver,
+    cache::DomHash,
     window_state::WindowSize,
-    id_tree::{Arena, NodeId},
+    id_tree::{Arena, NodeId, NodeEdge},
     css_parser::*,
     dom::{NodeData, NodeType::{self, *}},
     css::Css,
     text_layout::{TextOverflowPass2, ScrollbarInfo},
-    images::ImageId,
+    images::{ImageId, ImageRegion},
     text_cache::TextId,
     compositor::new_opengl_texture_id,
+    font::TextRenderingOptions,
+    transition::TransitionCache,
+    render_api::FontImageApi,
+    tooltip::{TooltipOverlay, TOOLTIP_FONT_SIZE},
+    spinner::{SpinnerValue, SpinnerClock, SpinnerArc, get_spinner_segments},
+    checkbox::{CheckBoxState, get_checkbox_mark},
+    resize::{ResizeStates, ResizeGrip, get_resize_grip_rect},
+    scroll::{ScrollKey, ScrollStates},
+    render_cache::{RenderCache, SubtreeCapture, SubtreeContents, RENDER_CACHE_ATTRIBUTE},
 };
 
 const DEFAULT_FONT_COLOR: TextColor = TextColor(ColorU { r: 0, b: 0, g: 0, a: 255 });
 const DEFAULT_BUILTIN_FONT_SANS_SERIF: FontId = FontId::BuiltinFont("sans-serif");
+/// Drawn in place of an image while the image is still being decoded
+const IMAGE_PLACEHOLDER_COLOR: BackgroundColor = BackgroundColor(ColorU { r: 220, g: 220, b: 220, a: 255 });
+const TOOLTIP_BACKGROUND_COLOR: BackgroundColor = BackgroundColor(ColorU { r: 255, g: 255, b: 225, a: 255 });
+/// Alpha of the placeholder text of an empty `Label`, relative to the text color of the node
+const PLACEHOLDER_TEXT_ALPHA: f32 = 0.5;
+/// Width of the text caret of the focused node, in pixels
+const CARET_WIDTH: f32 = 1.0;
 
 pub(crate) struct DisplayList<'a, T: Layout + 'a> {
     pub(crate) ui_descr: &'a UiDescription<T>,
-    pub(crate) rectangles: Arena<DisplayRectangle<'a>>
+    pub(crate) rectangles: Arena<DisplayRectangle<'a>>,
+    /// Rotation of the indeterminate spinners, as a fraction of a full turn
+    pub(crate) spinner_rotation: f32,
+    /// Whether the text caret of the focused node is drawn in this frame (the caret blinks)
+    pub(crate) is_caret_visible: bool,
+    /// The images of the subtrees marked with `Dom::with_cached_rendering`
+    pub(crate) render_cache: Option<&'a RenderCache>,
+    /// The scroll offsets of the scroll containers of the window (see `Dom::with_scroll_bounds`)
+    pub(crate) scroll_states: Option<&'a ScrollStates>,
 }
 
 /// DisplayRectangle is the main type which the layout parsing step gets operated on.
@@ -55,8 +81,35 @@ pub(crate) struct DisplayRectangle<'a> {
 pub(crate) struct SolvedLayout<T: Layout> {
     // List of previously solved constraints
     pub(crate) solved_constraints: FastHashMap<NodeId, NodeData<T>>,
+    /// The `flex-direction` of the nodes that have one (for `UiSolver::query_flex_items`)
+    pub(crate) flex_directions: FastHashMap<NodeId, LayoutDirection>,
+    /// The resize grips of the nodes with a `resize` property, in painting order
+    pub(crate) resize_grips: Vec<ResizeGrip>,
+    /// The layout inputs of each node and the constraints that were created from them,
+    /// so that only the constraints of the nodes whose layout inputs changed are re-created
+    pub(crate) node_constraints: FastHashMap<NodeId, (LayoutInputs, Vec<Constraint>)>,
+    /// The nodes whose constraints were re-created in the last re-layout
+    pub(crate) relayout_nodes: BTreeSet<NodeId>,
+    /// The size of the window in the last re-layout - the nodes without a `width` / `height`
+    /// and the percentages of the root node depend on it
+    pub(crate) window_dimensions: Option<LayoutSize>,
+    /// `content-visibility: auto` nodes that were outside of the window in the last re-layout.
+    /// The constraints of their descendants are only created once they are inside of the window,
+    /// until then, the size of such a node only comes from its own constraints.
+    pub(crate) deferred_containers: BTreeSet<NodeId>,
+    /// The cached subtrees (`Dom::with_cached_rendering`) that were painted in the last frame
+    pub(crate) cached_subtrees: BTreeSet<ScrollKey>,
+    /// The cached subtrees that had no up-to-date image and were painted node by node,
+    /// they are captured once the frame has been rendered
+    pub(crate) render_cache_captures: Vec<SubtreeCapture>,
+    /// The nodes that were painted in the last frame, for finding the region
+    /// of the window that changed in the next frame (see `get_dirty_rect`)
+    pub(crate) painted_nodes: PaintedNodes,
 }
 
+/// The hash, bounds and style of each painted node of a frame
+pub(crate) type PaintedNodes = BTreeMap<NodeId, (DomHash, LayoutRect, RectStyle)>;
+
 /// This is used for caching large strings (in the `push_text` function)
 /// In the cached version, you can lookup the text as well as the dimensions of
 /// the words in the `AppResources`. For the `Uncached` version, you'll have to re-
@@ -95,6 +148,15 @@ impl<T: Layout> SolvedLayout<T> {
     pub fn empty() -> Self {
         Self {
             solved_constraints: FastHashMap::default(),
+            flex_directions: FastHashMap::default(),
+            resize_grips: Vec::new(),
+            node_constraints: FastHashMap::default(),
+            relayout_nodes: BTreeSet::new(),
+            window_dimensions: None,
+            deferred_containers: BTreeSet::new(),
+            cached_subtrees: BTreeSet::new(),
+            render_cache_captures: Vec::new(),
+            painted_nodes: BTreeMap::new(),
         }
     }
 }
@@ -120,22 +182,184 @@ impl<'a, T: Layout + 'a> DisplayList<'a, T> {
     pub fn new_from_ui_description(ui_description: &'a UiDescription<T>) -> Self {
 
         let arena = ui_description.ui_descr_arena.borrow();
-        let display_rect_arena = arena.transform(|node, node_id| {
+        let mut display_rect_arena = arena.transform(|node, node_id| {
             let style = ui_description.styled_nodes.get(&node_id).unwrap_or(&ui_description.default_style_of_node);
-            let mut rect = DisplayRectangle::new(node.tag, style);
-            populate_css_properties(&mut rect, &ui_description.dynamic_css_overrides);
-            rect
+            DisplayRectangle::new(node.tag, style)
         });
 
+        // Populate the properties top-down, so that the parent is resolved
+        // before its children, which might `inherit` values from it
+        if let Some(root) = ui_description.ui_descr_root {
+            let node_ids = root.descendants(&display_rect_arena).collect::<Vec<NodeId>>();
+            for node_id in node_ids {
+                let parent = display_rect_arena[node_id].parent().map(|parent_id| {
+                    let parent = &display_rect_arena[parent_id].data;
+                    (parent.style.clone(), parent.layout.clone())
+                });
+                let rect = &mut display_rect_arena[node_id].data;
+                populate_css_properties(rect, node_id, &*arena, &ui_description.dynamic_css_overrides, &ui_description.safe_area_insets,
+                                        parent.as_ref().map(|(style, layout)| (style, layout)));
+            }
+        }
+
         Self {
             ui_descr: ui_description,
             rectangles: display_rect_arena,
+            spinner_rotation: 0.0,
+            is_caret_visible: false,
+            render_cache: None,
+            scroll_states: None,
+        }
+    }
+
+    /// Replaces the values of all properties that have a `transition` with their
+    /// interpolated values at the time `now`.
+    ///
+    /// Returns `true` if any transition is still running, i.e. the window has to be redrawn.
+    pub fn apply_transitions(&mut self, transitions: &mut TransitionCache, now: Instant) -> bool {
+        let mut is_running = false;
+
+        for rect_idx in self.rectangles.linear_iter() {
+            let rect = &mut self.rectangles[rect_idx].data;
+            if let Some(transition) = rect.style.transition.clone() {
+                if transitions.apply(rect_idx, &transition, &mut rect.style, &mut rect.layout, now) {
+                    is_running = true;
+                }
+            }
+        }
+
+        transitions.remove_unused_transitions();
+        is_running
+    }
+
+    /// Returns the `background-color` of the root node, which is used as the clear color of the
+    /// window, so that the window doesn't flash in the default `WindowCreateOptions::background`
+    /// color while it is resized (before the new frame is rendered).
+    ///
+    /// Returns `None` if the root node has no background color
+    pub fn get_clear_color(&self) -> Option<ColorF> {
+        let root = self.ui_descr.ui_descr_root?;
+        self.rectangles[root].data.style.background_color.map(|color| color.0.into())
+    }
+
+    /// Overrides the `width` / `height` of the nodes that the user resized by dragging
+    /// their resize grip, along the axes that their `resize` property allows
+    pub fn apply_resized_sizes(&mut self, resize_states: &ResizeStates) {
+        for rect_idx in self.rectangles.linear_iter() {
+            let rect = &mut self.rectangles[rect_idx].data;
+            let resize = rect.style.resize.unwrap_or_default();
+            let size = match resize_states.get_size(rect_idx) {
+                Some(size) => size,
+                None => continue,
+            };
+            if resize.is_horizontal() {
+                rect.layout.width = Some(LayoutWidth(PixelValue::from_metric(CssMetric::Px, size.width)));
+            }
+            if resize.is_vertical() {
+                rect.layout.height = Some(LayoutHeight(PixelValue::from_metric(CssMetric::Px, size.height)));
+            }
+        }
+    }
+
+    /// Sets the rotation of the indeterminate spinners to the rotation of the `clock` at the time `now`.
+    ///
+    /// Returns `true` if the DOM contains an indeterminate spinner, i.e. the window has to be redrawn.
+    pub fn apply_spinner_animation(&mut self, clock: &SpinnerClock, now: Instant) -> bool {
+        self.spinner_rotation = clock.rotation(now);
+        let arena = self.ui_descr.ui_descr_arena.borrow();
+        arena.linear_iter().any(|node_id| {
+            arena[node_id].data.node_type == Spinner(None) &&
+            !is_display_none(node_id, &self.rectangles)
+        })
+    }
+
+    /// Sets whether the text caret of the focused node (see `Css::focused_tag`) is drawn in this frame
+    pub fn set_caret_visibility(&mut self, is_caret_visible: bool) {
+        self.is_caret_visible = is_caret_visible;
+    }
+
+    /// Sets the images that the subtrees marked with `Dom::with_cached_rendering` are painted with
+    pub fn set_render_cache(&mut self, render_cache: &'a RenderCache) {
+        self.render_cache = Some(render_cache);
+    }
+
+    /// Sets the scroll offsets of the scroll containers, for finding the nodes that are in view
+    pub fn set_scroll_states(&mut self, scroll_states: &'a ScrollStates) {
+        self.scroll_states = Some(scroll_states);
+    }
+
+    /// Returns how far the content of the node was scrolled, if the node is a scroll container
+    fn get_content_scroll_offset(&self, node_id: NodeId) -> LayoutVector2D {
+        let arena = self.ui_descr.ui_descr_arena.borrow();
+        match self.scroll_states {
+            Some(scroll_states) if arena[node_id].data.scroll_bounds.is_some() => {
+                let (x, y) = scroll_states.get_offset(&ScrollKey::new(&*arena, node_id));
+                LayoutVector2D::new(x, y)
+            },
+            _ => LayoutVector2D::zero(),
         }
     }
 
+    /// Returns how far the node was scrolled, i.e. the sum of the scroll offsets of
+    /// the scroll containers that the node is in (not including the node itself)
+    fn get_scroll_offset(&self, node_id: NodeId) -> LayoutVector2D {
+        let ancestors = node_id.ancestors(&*self.ui_descr.ui_descr_arena.borrow()).skip(1).collect::<Vec<NodeId>>();
+        ancestors.into_iter()
+            .map(|ancestor| self.get_content_scroll_offset(ancestor))
+            .fold(LayoutVector2D::zero(), |offset, ancestor_offset| offset + ancestor_offset)
+    }
+
+    /// Returns the part of the content that is visible in the `viewport` from
+    /// the node, i.e. the viewport moved by the scroll offset of the node
+    fn get_scrolled_viewport(&self, node_id: NodeId, viewport: &LayoutRect) -> LayoutRect {
+        viewport.translate(&self.get_scroll_offset(node_id))
+    }
+
+    /// Estimates how many display items the nodes will push, from the style properties and
+    /// the node types - a node can push many items (background, border, shadows, text, ...),
+    /// so the number of nodes alone is a bad capacity hint for the `DisplayListBuilder`.
+    ///
+    /// This is only an estimate: nodes that are skipped (`display: none`, hidden nodes, ...)
+    /// are still counted, scrollbars, carets and tooltips are not counted.
+    pub(crate) fn estimate_display_item_count(&self) -> usize {
+        let arena = self.ui_descr.ui_descr_arena.borrow();
+        self.rectangles.linear_iter().map(|rect_idx| {
+            let style = &self.rectangles[rect_idx].data.style;
+            let mut count = 0;
+
+            if style.background_color.is_some() { count += 1; }
+            count += match style.background {
+                // gradients push their stops as a separate item
+                Some(Background::LinearGradient(_)) | Some(Background::RadialGradient(_)) => 2,
+                Some(Background::Image(_)) | Some(Background::ImageWithFallback(..)) => 1,
+                Some(Background::NoBackground) | None => 0,
+            };
+            if style.border.is_some() { count += 1; }
+            if style.box_shadow.is_some() { count += 1; }
+            // push + pop of the stacking context
+            if style.opacity.is_some() { count += 2; }
+            if style.filter.is_some() { count += 2; }
+
+            count += match arena[rect_idx].data.node_type {
+                Label(_) | Text(_) | TextInput(_) => {
+                    let decorations = style.text_decoration.map(|lines| {
+                        lines.underline as usize + lines.overline as usize + lines.line_through as usize
+                    }).unwrap_or(0);
+                    // push + pop of the text shadow
+                    let shadow = if style.text_shadow.is_some() { 2 } else { 0 };
+                    1 + decorations + shadow
+                },
+                Image(_) | GlTexture(_) | Spinner(_) | CheckBox(_) => 1,
+                Div => 0,
+            };
+
+            count
+        }).sum()
+    }
+
     /// Looks if any new images need to be uploaded and stores the in the image resources
     fn update_resources(
-        api: &RenderApi,
+        api: &dyn FontImageApi,
         app_resources: &mut AppResources,
         resource_updates: &mut Vec<ResourceUpdate>)
     {
@@ -144,7 +368,7 @@ impl<'a, T: Layout + 'a> DisplayList<'a, T> {
     }
 
     fn update_image_resources(
-        api: &RenderApi,
+        api: &dyn FontImageApi,
         app_resources: &mut AppResources,
         resource_updates: &mut Vec<ResourceUpdate>)
     {
@@ -162,7 +386,11 @@ impl<'a, T: Layout + 'a> DisplayList<'a, T> {
                 ImageState::Uploaded(_) => { },
                 ImageState::AboutToBeDeleted(ref k) => {
                     to_delete_images.push((key.clone(), k.clone()));
-                }
+                },
+                // Evicted images only get re-uploaded once they are displayed again
+                ImageState::Evicted(_) => { },
+                // Decoded images are moved to ReadyForUpload in `update_decoding_images`
+                ImageState::Decoding(_) => { },
             }
         }
 
@@ -172,21 +400,23 @@ impl<'a, T: Layout + 'a> DisplayList<'a, T> {
                 resource_updates.push(ResourceUpdate::DeleteImage(image_key));
             }
             app_resources.images.remove(&resource_key);
+            app_resources.texture_cache.last_used.remove(&resource_key);
         }
 
         // Upload all remaining images to the GPU only if the haven't been
         // uploaded yet
         for (resource_key, (data, descriptor)) in updated_images.into_iter() {
 
-            let key = api.generate_image_key();
+            let key = api.new_image_key();
             resource_updates.push(ResourceUpdate::AddImage(
-                AddImage { key, descriptor, data, tiling: None }
+                AddImage { key, descriptor, data: data.clone(), tiling: None }
             ));
 
             *app_resources.images.get_mut(&resource_key).unwrap() =
                 ImageState::Uploaded(ImageInfo {
                     key: key,
-                    descriptor: descriptor
+                    descriptor: descriptor,
+                    data: data,
             });
         }
     }
@@ -194,7 +424,7 @@ impl<'a, T: Layout + 'a> DisplayList<'a, T> {
     // almost the same as update_image_resources, but fonts
     // have two HashMaps that need to be updated
     fn update_font_resources(
-        api: &RenderApi,
+        api: &dyn FontImageApi,
         app_resources: &mut AppResources,
         resource_updates: &mut Vec<ResourceUpdate>)
     {
@@ -235,26 +465,32 @@ impl<'a, T: Layout + 'a> DisplayList<'a, T> {
 
         // Upload all remaining fonts to the GPU only if the haven't been uploaded yet
         for (resource_key, data) in updated_fonts.into_iter() {
-            let key = api.generate_font_key();
+            let key = api.new_font_key();
             resource_updates.push(ResourceUpdate::AddFont(AddFont::Raw(key, data, 0))); // TODO: use the index better?
             app_resources.font_data.get_mut(&resource_key).unwrap().2 = FontState::Uploaded(key);
         }
     }
 
-    pub fn into_display_list_builder(
+    /// Updates the constraints of the nodes whose layout changed (or of all nodes, if the DOM
+    /// or the size of the window changed) and solves the layout, the solved bounds of the
+    /// nodes can then be queried with `UiSolver::query_bounds_of_rect`.
+    ///
+    /// Returns whether the size of the window changed since the last layout
+    pub(crate) fn solve_layout(
         &self,
-        pipeline_id: PipelineId,
-        current_epoch: Epoch,
         ui_solver: &mut UiSolver<T>,
-        css: &mut Css,
+        css: &Css,
         app_resources: &mut AppResources,
-        render_api: &RenderApi,
         mut has_window_size_changed: bool,
         window_size: &WindowSize)
-    -> Option<DisplayListBuilder>
+    -> bool
     {
         let mut changeset = None;
 
+        // The changeset only contains the nodes of the new DOM, not the removed ones
+        let previous_nodes_len = ui_solver.dom_tree_cache.previous_layout.arena.nodes_len();
+        let nodes_were_removed = previous_nodes_len > self.rectangles.nodes_len();
+
         if let Some(root) = self.ui_descr.ui_descr_root {
             let local_changeset = ui_solver.dom_tree_cache.update(root, &*(self.ui_descr.ui_descr_arena.borrow()));
             ui_solver.edit_variable_cache.initialize_new_rectangles(&mut ui_solver.solver, &local_changeset);
@@ -262,27 +498,153 @@ impl<'a, T: Layout + 'a> DisplayList<'a, T> {
             changeset = Some(local_changeset);
         }
 
-        if css.needs_relayout {
+        let changeset_is_useless = match changeset {
+            None => true,
+            Some(ref c) => c.is_empty()
+        };
+
+        let window_dimensions = LayoutSize::new(window_size.dimensions.width as f32, window_size.dimensions.height as f32);
+        let has_window_been_resized = ui_solver.solved_layout.window_dimensions != Some(window_dimensions);
+
+        let viewport = LayoutRect::new(LayoutPoint::zero(), window_dimensions);
+        let has_dom_changed = !changeset_is_useless || nodes_were_removed;
+
+        // The descendants of deferred containers that are now inside of the window
+        // don't have any constraints yet, so they are laid out in this frame
+        let containers_in_view = if has_dom_changed {
+            Vec::new()
+        } else {
+            ui_solver.solved_layout.deferred_containers.iter().cloned()
+                .filter(|container| ui_solver.query_bounds_of_rect(*container)
+                    .map(|bounds| bounds.intersects(&self.get_scrolled_viewport(*container, &viewport)))
+                    .unwrap_or(true))
+                .collect::<Vec<NodeId>>()
+        };
+        for container in &containers_in_view {
+            ui_solver.solved_layout.deferred_containers.remove(container);
+            for descendant in container.descendants(&self.rectangles).skip(1) {
+                ui_solver.solved_layout.node_constraints.remove(&descendant);
+            }
+        }
+
+        // Changed nodes get new variables in the solver, which don't have any constraints yet
+        if css.needs_relayout || has_dom_changed || has_window_been_resized || !containers_in_view.is_empty() {
+
+            // If the DOM or the size of the window changed, the constraints of all nodes are re-created
+            if has_dom_changed || has_window_been_resized {
+                for (_, (_, old_constraints)) in ui_solver.solved_layout.node_constraints.drain() {
+                    for constraint in &old_constraints {
+                        // The variables of removed nodes were already removed from the solver
+                        let _ = ui_solver.solver.remove_constraint(constraint);
+                    }
+                }
+                ui_solver.solved_layout.deferred_containers.clear();
+            }
+
+            let relayout_nodes = self.ui_descr.ui_descr_root
+                .map(|root| get_relayout_nodes(root, &self.rectangles, &ui_solver.solved_layout.node_constraints))
+                .unwrap_or_default();
 
             // constraints were added or removed during the last frame
-            for rect_idx in self.rectangles.linear_iter() {
+            for rect_idx in relayout_nodes.iter().cloned() {
+                if let Some((_, old_constraints)) = ui_solver.solved_layout.node_constraints.remove(&rect_idx) {
+                    for constraint in &old_constraints {
+                        let _ = ui_solver.solver.remove_constraint(constraint);
+                    }
+                }
+
                 let rect = &self.rectangles[rect_idx].data;
-                let arena = &*self.ui_descr.ui_descr_arena.borrow();
-                let dom_hash = &ui_solver.dom_tree_cache.previous_layout.arena[rect_idx];
-                let display_rect = ui_solver.edit_variable_cache.map[&dom_hash.data];
-                let layout_contraints = create_layout_constraints(rect, rect_idx, &self.rectangles, window_size);
-                let cassowary_constraints = css_constraints_to_cassowary_constraints(&display_rect.1, &layout_contraints);
-                ui_solver.solver.add_constraints(&cassowary_constraints).unwrap();
+                let mut cassowary_constraints = Vec::new();
+                ui_solver.solved_layout.deferred_containers.remove(&rect_idx);
+
+                // The ancestors are laid out before their descendants
+                let is_deferred = rect_idx.ancestors(&self.rectangles).skip(1)
+                    .any(|ancestor| ui_solver.solved_layout.deferred_containers.contains(&ancestor));
+
+                // `display: none` nodes don't take up any space, deferred nodes aren't laid out yet
+                if !is_display_none(rect_idx, &self.rectangles) && !is_deferred {
+                    let dom_hash = &ui_solver.dom_tree_cache.previous_layout.arena[rect_idx];
+                    let display_rect = ui_solver.edit_variable_cache.map[&dom_hash.data];
+                    // The parent was laid out before its children, the children
+                    // are laid out inside of the content box of the parent
+                    let parent = self.rectangles[rect_idx].parent();
+                    let parent_padding = parent.and_then(|parent| self.rectangles[parent].data.layout.padding);
+                    let parent_size = parent
+                        .and_then(|parent| ui_solver.query_bounds_of_rect(parent))
+                        .map(|parent_bounds| get_content_rect(&parent_bounds, parent_padding).size);
+                    let layout_contraints = create_layout_constraints(rect, parent_size, window_size);
+                    cassowary_constraints = css_constraints_to_cassowary_constraints(&display_rect.1, &layout_contraints);
+
+                    // `margin` / `padding` - inset the rectangle into the content box of the parent
+                    let parent_rect = parent.map(|parent| ui_solver.edit_variable_cache.map[&ui_solver.dom_tree_cache.previous_layout.arena[parent].data].1);
+                    let position_constraints = create_position_constraints(rect, parent_rect, parent_padding.unwrap_or_default());
+                    cassowary_constraints.extend(css_constraints_to_cassowary_constraints(&display_rect.1, &position_constraints));
+
+                    // `justify-content: center` - position the children of the container
+                    if rect.layout.justify_content == Some(LayoutJustifyContent::Center) {
+                        let children = rect_idx.children(&self.rectangles)
+                            .filter(|child| !is_display_none(*child, &self.rectangles))
+                            .map(|child| ui_solver.edit_variable_cache.map[&ui_solver.dom_tree_cache.previous_layout.arena[child].data].1)
+                            .collect::<Vec<DisplayRect>>();
+                        cassowary_constraints.extend(create_centering_constraints(
+                            &display_rect.1,
+                            &children,
+                            rect.layout.direction.unwrap_or(LayoutDirection::Horizontal),
+                            rect.layout.gap.unwrap_or_default()));
+                    }
+
+                    ui_solver.solver.add_constraints(&cassowary_constraints).unwrap();
+
+                    // `min-height` / `max-height` - a text box without a `height` is as high as its
+                    // text (laid out in the solved width), clamped to the minimum / maximum height
+                    let has_content_height = rect.layout.height.is_none() && rect.layout.aspect_ratio.is_none() &&
+                        (rect.layout.min_height.is_some() || rect.layout.max_height.is_some());
+                    let text_height = if has_content_height {
+                        ui_solver.query_bounds_of_rect(rect_idx)
+                            .and_then(|bounds| self.get_text_height(rect_idx, get_content_rect(&bounds, rect.layout.padding).size.width, app_resources))
+                    } else {
+                        None
+                    };
+                    if let Some(text_height) = text_height {
+                        use cassowary::strength::STRONG;
+                        use constraints::{SizeConstraint, Strength};
+                        let padding = rect.layout.padding.unwrap_or_default();
+                        let content_height = CssConstraint::Size((SizeConstraint::Height(text_height + padding.top + padding.bottom), Strength(STRONG)));
+                        let content_constraints = css_constraints_to_cassowary_constraints(&display_rect.1, &vec![content_height]);
+                        ui_solver.solver.add_constraints(&content_constraints).unwrap();
+                        cassowary_constraints.extend(content_constraints);
+                    }
+
+                    // `content-visibility: auto` - the descendants of a node outside of the window
+                    // are laid out once the node is inside of the window
+                    let is_in_view = ui_solver.query_bounds_of_rect(rect_idx)
+                        .map(|bounds| bounds.intersects(&self.get_scrolled_viewport(rect_idx, &viewport)))
+                        .unwrap_or(true);
+                    if rect.style.content_visibility == Some(ContentVisibility::Auto) && !is_in_view {
+                        ui_solver.solved_layout.deferred_containers.insert(rect_idx);
+                    }
+                }
+
+                ui_solver.solved_layout.node_constraints.insert(rect_idx, (get_layout_inputs(rect_idx, &self.rectangles), cassowary_constraints));
             }
 
+            ui_solver.solved_layout.relayout_nodes = relayout_nodes;
+            ui_solver.solved_layout.window_dimensions = Some(window_dimensions);
+
             // if we push or pop constraints that means we also need to re-layout the window
             has_window_size_changed = true;
         }
 
-        let changeset_is_useless = match changeset {
-            None => true,
-            Some(c) => c.is_empty()
-        };
+        // Remember which nodes were laid out (for `UiSolver::dump_layout`)
+        if !changeset_is_useless || nodes_were_removed {
+            let arena = self.ui_descr.ui_descr_arena.borrow();
+            ui_solver.solved_layout.solved_constraints = arena.linear_iter().map(|id| (id, arena[id].data.clone())).collect();
+        }
+
+        // The flex direction can change without the DOM changing (i.e. in a `:hover` rule)
+        ui_solver.solved_layout.flex_directions = self.rectangles.linear_iter()
+            .filter_map(|id| Some((id, self.rectangles[id].data.layout.direction?)))
+            .collect();
 
         // recalculate the actual layout
         if css.needs_relayout || has_window_size_changed {
@@ -293,56 +655,338 @@ impl<'a, T: Layout + 'a> DisplayList<'a, T> {
             */
         }
 
-        css.needs_relayout = false;
+        has_window_been_resized
+    }
+
+    /// Returns how high the text of a `Label` / `Text` node is when it is laid out in the
+    /// `content_width` of the node, or `None` if the node has no (horizontal) text or if
+    /// its font isn't loaded
+    fn get_text_height(&self, rect_idx: NodeId, content_width: f32, app_resources: &mut AppResources) -> Option<f32> {
+        use text_layout::{self, LineBreakMode};
+
+        let text = match self.ui_descr.ui_descr_arena.borrow()[rect_idx].data.node_type {
+            Label(ref text) => TextInfo::Uncached(text.clone()),
+            Text(ref text_id) => TextInfo::Cached(*text_id),
+            _ => return None,
+        };
+
+        if determine_writing_mode(rect_idx, &self.rectangles).is_vertical() {
+            return None;
+        }
+
+        let style = &self.rectangles[rect_idx].data.style;
+        let font_family = style.font_family.as_ref()?;
+        let font_family_id = font_family.fonts.get(0).unwrap_or(&DEFAULT_BUILTIN_FONT_SANS_SERIF);
+        let font_id = app_resources.match_font_face(font_family_id, style.font_weight.unwrap_or_default(), style.font_style.unwrap_or_default()).font_id;
+        if !app_resources.font_data.contains_key(&font_id) {
+            return None;
+        }
+
+        Some(text_layout::get_text_height(
+            app_resources,
+            content_width,
+            &font_id,
+            &style.font_size.unwrap_or(DEFAULT_FONT_SIZE),
+            style.line_height,
+            style.text_indent,
+            &text,
+            &style.overflow.unwrap_or_default(),
+            LineBreakMode::from_style(style.overflow_wrap, style.word_break)))
+    }
+
+    pub fn into_display_list_builder(
+        &self,
+        pipeline_id: PipelineId,
+        current_epoch: Epoch,
+        ui_solver: &mut UiSolver<T>,
+        css: &mut Css,
+        app_resources: &mut AppResources,
+        render_api: &dyn FontImageApi,
+        has_window_size_changed: bool,
+        window_size: &WindowSize)
+    -> Option<DisplayListBuilder>
+    {
+        let has_window_been_resized = self.solve_layout(ui_solver, css, app_resources, has_window_size_changed, window_size);
+
+        if let Some(warning) = css.relayout_done() {
+            warn!("{}", warning);
+        }
 
         use glium::glutin::dpi::LogicalSize;
 
         let LogicalSize { width, height } = window_size.dimensions;
-        let mut builder = DisplayListBuilder::with_capacity(pipeline_id, TypedSize2D::new(width as f32, height as f32), self.rectangles.nodes_len());
+        // The capacity is the number of bytes that are reserved for the serialized display items
+        let capacity = self.estimate_display_item_count() * ::std::mem::size_of::<DisplayItem>();
+        let mut builder = DisplayListBuilder::with_capacity(pipeline_id, TypedSize2D::new(width as f32, height as f32), capacity);
         let mut resource_updates = Vec::<ResourceUpdate>::new();
         let full_screen_rect = LayoutRect::new(LayoutPoint::zero(), builder.content_size());;
 
         // Upload image and font resources
         Self::update_resources(render_api, app_resources, &mut resource_updates);
 
-        for rect_idx in self.rectangles.linear_iter() {
+        // OpenGL textures that were already added in this frame, by their OpenGL ID
+        let mut gl_texture_keys = FastHashMap::<u32, ImageKey>::default();
+
+        // Hash, bounds and style of all nodes in this frame, for calculating the dirty rect
+        let mut painted_nodes = PaintedNodes::new();
+
+        // For each node from the root to the current node: whether the node is
+        // hidden and whether it pushed an opacity stacking context
+        let mut parent_stack = Vec::<(bool, bool)>::new();
+        // The `display: none` node whose children are currently skipped
+        let mut skipped_subtree = None;
+        let mut resize_grips = Vec::<ResizeGrip>::new();
+        let mut cached_subtrees = BTreeSet::<ScrollKey>::new();
+        let mut render_cache_captures = Vec::<SubtreeCapture>::new();
+        // The nodes that were painted so far with their bounds, in painting order -
+        // the content behind a node with a `backdrop-filter`
+        let mut painted_behind = Vec::<(NodeId, LayoutRect)>::new();
+        let caret_tag = if self.is_caret_visible { css.focused_tag } else { None };
+
+        let traversal = self.ui_descr.ui_descr_root.into_iter().flat_map(|root| root.traverse(&self.rectangles));
+
+        for edge in traversal {
+
+            let rect_idx = match edge {
+                NodeEdge::Start(rect_idx) => rect_idx,
+                NodeEdge::End(rect_idx) => {
+                    if skipped_subtree == Some(rect_idx) {
+                        skipped_subtree = None;
+                    } else if skipped_subtree.is_none() {
+                        if let Some((_, true)) = parent_stack.pop() {
+                            builder.pop_stacking_context();
+                        }
+                    }
+                    continue;
+                },
+            };
+
+            if skipped_subtree.is_some() {
+                continue;
+            }
 
             let arena = self.ui_descr.ui_descr_arena.borrow();
             let node_type = &arena[rect_idx].data.node_type;
+            let placeholder = &arena[rect_idx].data.placeholder;
+            let rect = &self.rectangles[rect_idx].data;
+
+            // ask the solver what the bounds of the current rectangle is - nodes that
+            // weren't laid out (i.e. if there is no root) fill the whole window
+            let bounds = ui_solver.query_bounds_of_rect(rect_idx).unwrap_or(full_screen_rect);
+            let dom_hash = ui_solver.dom_tree_cache.previous_layout.arena[rect_idx].data;
+            painted_nodes.insert(rect_idx, (dom_hash, bounds, rect.style.clone()));
+
+            // `display: none` - neither the node nor its children are painted,
+            // the same goes for deferred nodes, whose children weren't laid out yet
+            if rect.layout.display == Some(LayoutDisplay::None) ||
+               ui_solver.solved_layout.deferred_containers.contains(&rect_idx) {
+                skipped_subtree = Some(rect_idx);
+                continue;
+            }
+
+            let is_parent_hidden = parent_stack.last().map(|&(is_hidden, _)| is_hidden).unwrap_or(false);
+            let is_hidden = match rect.style.visibility {
+                Some(Visibility::Hidden) => true,
+                Some(Visibility::Visible) => false,
+                None => is_parent_hidden,
+            };
+
+            // Cached subtrees with an up-to-date image are painted as that image
+            if !is_hidden && is_render_cached(rect_idx, &*arena) {
+                let key = ScrollKey::new(&*arena, rect_idx);
+                let contents = get_subtree_contents(rect_idx, &self.rectangles, ui_solver, full_screen_rect);
+                cached_subtrees.insert(key.clone());
+
+                // Animated nodes would freeze in the image
+                let is_animated = rect_idx.descendants(&*arena).any(|node_id| {
+                    arena[node_id].data.node_type == Spinner(None) ||
+                    (self.rectangles[node_id].data.tag.is_some() && self.rectangles[node_id].data.tag == css.focused_tag)
+                });
+
+                if !is_animated {
+                    if let Some(image_key) = self.render_cache.and_then(|render_cache| render_cache.get(&key, &contents)) {
+                        let info = LayoutPrimitiveInfo {
+                            tag: rect.tag.and_then(|tag| Some((tag, 0))),
+                            .. LayoutPrimitiveInfo::new(bounds)
+                        };
+                        builder.push_image(&info, bounds.size, LayoutSize::zero(), ImageRendering::Auto, AlphaType::PremultipliedAlpha, image_key);
+                        skipped_subtree = Some(rect_idx);
+                        continue;
+                    }
+                    render_cache_captures.push(SubtreeCapture { key, epoch: current_epoch, bounds, contents });
+                }
+            }
+
+            // The opacity stacking context is popped after the children have been pushed
+            let info = LayoutPrimitiveInfo::new(bounds);
+            let has_opacity = push_opacity(&info, &mut builder, &rect.style);
+            parent_stack.push((is_hidden, has_opacity));
 
-            // ask the solver what the bounds of the current rectangle is
-            // let bounds = ui_solver.query_bounds_of_rect(*rect_idx);
+            // `content-visibility: auto` - skip nodes that are outside of the visible area
+            if is_hidden || is_content_skipped(&rect.style, &bounds, &self.get_scrolled_viewport(rect_idx, &full_screen_rect)) {
+                continue;
+            }
+
+            // `backdrop-filter` - the nodes behind the node are painted again with the filter
+            // applied, below the node itself, so that only the content behind it is filtered.
+            // The copies can't be hit, the hit test has to find the original nodes.
+            if push_backdrop_filter(&info, &mut builder, &rect.style) {
+                for &(behind_idx, behind_bounds) in painted_behind.iter().filter(|(_, behind_bounds)| behind_bounds.intersects(&bounds)) {
+                    displaylist_handle_rect(
+                        &mut builder,
+                        current_epoch,
+                        behind_idx,
+                        &self.rectangles,
+                        &arena[behind_idx].data.node_type,
+                        &arena[behind_idx].data.placeholder,
+                        behind_bounds,
+                        full_screen_rect,
+                        self.spinner_rotation,
+                        false,
+                        false,
+                        self.get_content_scroll_offset(behind_idx),
+                        app_resources,
+                        render_api,
+                        &mut gl_texture_keys,
+                        &mut resource_updates);
+                }
+                builder.pop_stacking_context();
+                builder.pop_clip_id();
+            }
 
-            // temporary: fill the whole window with each rectangle
             displaylist_handle_rect(
                 &mut builder,
                 current_epoch,
                 rect_idx,
                 &self.rectangles,
                 node_type,
-                full_screen_rect, /* replace this with the real bounds */
+                placeholder,
+                bounds,
                 full_screen_rect,
+                self.spinner_rotation,
+                rect.tag.is_some() && rect.tag == caret_tag,
+                true,
+                self.get_content_scroll_offset(rect_idx),
                 app_resources,
                 render_api,
+                &mut gl_texture_keys,
                 &mut resource_updates);
+            painted_behind.push((rect_idx, bounds));
+
+            // The grip is painted on top of the content, at the bottom right corner of the node
+            if let Some(resize) = rect.style.resize.filter(|r| *r != LayoutResize::None) {
+                let grip_rect = get_resize_grip_rect(&bounds);
+                push_resize_grip(&grip_rect, &mut builder, resize);
+                resize_grips.push(ResizeGrip { node_id: rect_idx, resize: resize, rect: grip_rect, node_size: bounds.size });
+            }
+        }
+
+        ui_solver.solved_layout.resize_grips = resize_grips;
+        ui_solver.solved_layout.cached_subtrees = cached_subtrees;
+        ui_solver.solved_layout.render_cache_captures = render_cache_captures;
+
+        // Delete the least-recently-used images if the texture cache is over its budget
+        let evicted_image_keys = app_resources.texture_cache.evict_least_recently_used(&mut app_resources.images, current_epoch);
+        for image_key in evicted_image_keys {
+            resource_updates.push(ResourceUpdate::DeleteImage(image_key));
         }
 
         render_api.update_resources(resource_updates);
 
+        ui_solver.dirty_rect = if has_window_been_resized {
+            Some(full_screen_rect)
+        } else {
+            get_dirty_rect(&ui_solver.solved_layout.painted_nodes, &painted_nodes)
+        };
+        ui_solver.solved_layout.painted_nodes = painted_nodes;
+
         Some(builder)
     }
 }
 
+/// Returns the region of the window that has to be redrawn between two frames, i.e. the
+/// union of the old and new bounds of all nodes that were added, removed, changed, moved or
+/// restyled. Returns `None` if both frames look the same.
+///
+/// The dirty rect is not passed to webrender, which has no partial present yet, so the
+/// whole window is still rendered on every frame - it is only reported to the application
+/// (see `FakeWindow::get_dirty_rect`).
+pub(crate) fn get_dirty_rect(previous: &PaintedNodes, current: &PaintedNodes)
+-> Option<LayoutRect>
+{
+    let removed_or_changed = previous.iter().filter(|&(node_id, node)| current.get(node_id) != Some(node));
+    let added_or_changed = current.iter().filter(|&(node_id, node)| previous.get(node_id) != Some(node));
+
+    removed_or_changed.chain(added_or_changed)
+        .map(|(_, (_, bounds, _))| bounds)
+        .fold(None, |dirty_rect: Option<LayoutRect>, bounds| Some(match dirty_rect {
+            Some(dirty_rect) => dirty_rect.union(bounds),
+            None => *bounds,
+        }))
+}
+
+/// Pushes the tooltip of the window on top of all other display items,
+/// has to be called after all nodes have been pushed
+pub(crate) fn push_tooltip(
+    builder: &mut DisplayListBuilder,
+    tooltip: &TooltipOverlay,
+    app_resources: &mut AppResources,
+    render_api: &dyn FontImageApi)
+{
+    let info = LayoutPrimitiveInfo {
+        rect: tooltip.bounds,
+        clip_rect: tooltip.bounds,
+        is_backface_visible: false,
+        tag: None,
+    };
+
+    push_rect(&info, builder, &TOOLTIP_BACKGROUND_COLOR);
+
+    let style = RectStyle {
+        font_size: Some(FontSize::px(TOOLTIP_FONT_SIZE)),
+        font_family: Some(FontFamily { fonts: vec![DEFAULT_BUILTIN_FONT_SANS_SERIF] }),
+        font_color: Some(DEFAULT_FONT_COLOR),
+        .. Default::default()
+    };
+
+    let mut resource_updates = Vec::<ResourceUpdate>::new();
+
+    push_text(
+        &info,
+        &TextInfo::Uncached(tooltip.text.clone()),
+        builder,
+        &style,
+        &RectLayout::default(),
+        app_resources,
+        render_api,
+        &tooltip.bounds,
+        &mut resource_updates,
+        TextAlignmentHorz::Center,
+        TextAlignmentVert::Center,
+        TextDirection::Ltr,
+        WritingMode::HorizontalTb,
+        None,
+        LayoutVector2D::zero());
+
+    render_api.update_resources(resource_updates);
+}
+
 fn displaylist_handle_rect<'a>(
     builder: &mut DisplayListBuilder,
     current_epoch: Epoch,
     rect_idx: NodeId,
     arena: &Arena<DisplayRectangle<'a>>,
     html_node: &NodeType,
+    placeholder: &Option<String>,
     bounds: TypedRect<f32, LayoutPixel>,
     full_screen_rect: TypedRect<f32, LayoutPixel>,
+    spinner_rotation: f32,
+    is_caret_visible: bool,
+    is_hit_testable: bool,
+    scroll_offset: LayoutVector2D,
     app_resources: &mut AppResources,
-    render_api: &RenderApi,
+    render_api: &dyn FontImageApi,
+    gl_texture_keys: &mut FastHashMap<u32, ImageKey>,
     resource_updates: &mut Vec<ResourceUpdate>)
 {
     let rect = &arena[rect_idx].data;
@@ -351,17 +995,33 @@ fn displaylist_handle_rect<'a>(
         rect: bounds,
         clip_rect: bounds,
         is_backface_visible: false,
-        tag: rect.tag.and_then(|tag| Some((tag, 0))),
+        tag: if is_hit_testable { rect.tag.and_then(|tag| Some((tag, 0))) } else { None },
     };
 
-    let clip_region_id = rect.style.border_radius.and_then(|border_radius| {
-        let region = ComplexClipRegion {
-            rect: bounds,
-            radii: border_radius,
-            mode: ClipMode::Clip,
-        };
-        Some(builder.define_clip(bounds, vec![region], None))
-    });
+    let has_inset_shadow = rect.style.box_shadow.as_ref().map(|s| s.clip_mode == BoxShadowClipMode::Inset).unwrap_or(false);
+
+    // If only the background color has to be rounded (there is no content that could
+    // overflow the rounded corners), only the background is drawn inside of a clip
+    let is_rounded_background_only = match html_node {
+        Div => rect.style.background.is_none() && !has_inset_shadow,
+        _ => false,
+    };
+
+    let clip_region_id = match rect.style.border_radius {
+        Some(_) if is_rounded_background_only => None,
+        Some(border_radius) => {
+            let region = ComplexClipRegion {
+                rect: bounds,
+                radii: border_radius,
+                mode: ClipMode::Clip,
+            };
+            Some(builder.define_clip(bounds, vec![region], None))
+        },
+        // Inset shadows only show up if they are clipped, so non-rounded rectangles
+        // with an inset shadow get a rectangular clip
+        None if has_inset_shadow => Some(builder.define_clip(bounds, Vec::<ComplexClipRegion>::new(), None)),
+        None => None,
+    };
 
     // Push the "outset" box shadow, before the clip is active
     push_box_shadow(
@@ -376,7 +1036,10 @@ fn displaylist_handle_rect<'a>(
     }
 
     if let Some(ref bg_col) = rect.style.background_color {
-        push_rect(&info, builder, bg_col);
+        match rect.style.border_radius {
+            Some(ref border_radius) if is_rounded_background_only => push_rounded_rect(&info, builder, bg_col, border_radius),
+            _ => push_rect(&info, builder, bg_col),
+        }
     }
 
     if let Some(ref bg) = rect.style.background {
@@ -385,7 +1048,11 @@ fn displaylist_handle_rect<'a>(
             &bounds,
             builder,
             bg,
-            &app_resources);
+            &rect.style,
+            app_resources,
+            current_epoch,
+            render_api,
+            resource_updates);
     };
 
     // Push the inset shadow (if any)
@@ -401,22 +1068,49 @@ fn displaylist_handle_rect<'a>(
         &rect.style);
 
     let (horz_alignment, vert_alignment) = determine_text_alignment(rect_idx, arena);
+    let text_direction = determine_text_direction(rect_idx, arena);
+    let writing_mode = determine_writing_mode(rect_idx, arena);
 
     // handle the special content of the node
     match html_node {
         Div => { /* nothing special to do */ },
-        Label(text) => {
+        Label(_) | TextInput(_) => {
+            // The caret of a label is drawn after its last glyph, the caret of an input at its caret index
+            let (text, caret_glyph) = match html_node {
+                TextInput(input) => (&input.text, input.glyphs_before_caret()),
+                Label(text) => (text, text.chars().filter(|c| !c.is_whitespace()).count()),
+                _ => unreachable!(),
+            };
+            let is_empty = text.is_empty();
+            // An empty label shows its placeholder (if any) instead, with a dimmed color
+            let placeholder_style;
+            let (text, style) = match *placeholder {
+                Some(ref placeholder) if is_empty => {
+                    placeholder_style = get_placeholder_style(&rect.style);
+                    (placeholder, &placeholder_style)
+                },
+                _ => (text, &rect.style),
+            };
             push_text(
                 &info,
                 &TextInfo::Uncached(text.clone()),
                 builder,
-                &rect.style,
+                style,
+                &rect.layout,
                 app_resources,
-                &render_api,
+                render_api,
                 &bounds,
                 resource_updates,
                 horz_alignment,
-                vert_alignment);
+                vert_alignment,
+                text_direction,
+                writing_mode,
+                if is_caret_visible && !is_empty { Some(caret_glyph) } else { None },
+                scroll_offset);
+            // There is no glyph that the caret could follow, so it is drawn at the start of the content box
+            if is_caret_visible && is_empty {
+                push_empty_caret(builder, &bounds, &rect.style, &rect.layout);
+            }
         },
         Text(text_id) => {
             push_text(
@@ -424,47 +1118,70 @@ fn displaylist_handle_rect<'a>(
                 &TextInfo::Cached(*text_id),
                 builder,
                 &rect.style,
+                &rect.layout,
                 app_resources,
-                &render_api,
+                render_api,
                 &bounds,
                 resource_updates,
                 horz_alignment,
-                vert_alignment);
+                vert_alignment,
+                text_direction,
+                writing_mode,
+                if is_caret_visible { Some(usize::max_value()) } else { None },
+                scroll_offset);
         },
         Image(image_id) => {
-            push_image(&info, builder, &bounds, app_resources, image_id);
+            // The drop shadow is cast by the tinted image
+            let has_drop_shadow = match rect.style.filter {
+                Some(Filter::DropShadow(ref shadow)) => push_shadow_for(builder, shadow, ShadowCaster::Image { info: &info }),
+                None => false,
+            };
+            let has_tint = push_tint(&info, builder, &rect.style);
+            push_image(&info, builder, &bounds, app_resources, image_id, None, BackgroundRepeat::NoRepeat, current_epoch, render_api, resource_updates);
+            if has_tint {
+                builder.pop_stacking_context();
+            }
+            if has_drop_shadow {
+                builder.pop_stacking_context();
+            }
         },
         GlTexture(texture) => {
 
+            use glium::GlObject;
             use compositor::{ActiveTexture, ACTIVE_GL_TEXTURES};
 
             let opaque = true;
             let allow_mipmaps = true;
             let descriptor = ImageDescriptor::new(texture.inner.width(), texture.inner.height(), ImageFormat::BGRA8, opaque, allow_mipmaps);
-            let key = render_api.generate_image_key();
-            let external_image_id = ExternalImageId(new_opengl_texture_id() as u64);
 
-            let data = ImageData::External(ExternalImageData {
-                id: external_image_id,
-                channel_index: 0,
-                image_type: ExternalImageType::TextureHandle(TextureTarget::Default),
-            });
+            let has_tint = push_tint(&info, builder, &rect.style);
 
-            ACTIVE_GL_TEXTURES.lock().unwrap()
-                .entry(current_epoch).or_insert_with(|| FastHashMap::default())
-                .insert(external_image_id, ActiveTexture { texture: texture.clone() });
+            push_opengl_texture(&info, builder, &bounds, texture.inner.get_id(), descriptor, gl_texture_keys, resource_updates, || {
+                let key = render_api.new_image_key();
+                let external_image_id = ExternalImageId(new_opengl_texture_id() as u64);
 
-            resource_updates.push(ResourceUpdate::AddImage(
-                AddImage { key, descriptor, data, tiling: None }
-            ));
+                ACTIVE_GL_TEXTURES.lock().unwrap()
+                    .entry(current_epoch).or_insert_with(|| FastHashMap::default())
+                    .insert(external_image_id, ActiveTexture { texture: texture.clone() });
 
-            builder.push_image(
-                &info,
-                bounds.size,
-                LayoutSize::zero(),
-                ImageRendering::Auto,
-                AlphaType::Alpha,
-                key);
+                let data = ImageData::External(ExternalImageData {
+                    id: external_image_id,
+                    channel_index: 0,
+                    image_type: ExternalImageType::TextureHandle(TextureTarget::Default),
+                });
+
+                (key, data)
+            });
+
+            if has_tint {
+                builder.pop_stacking_context();
+            }
+        },
+        Spinner(value) => {
+            push_spinner(builder, &bounds, &rect.style, *value, spinner_rotation);
+        },
+        CheckBox(state) => {
+            push_checkbox_mark(builder, &bounds, &rect.style, *state);
         },
     }
 
@@ -473,6 +1190,69 @@ fn displaylist_handle_rect<'a>(
     }
 }
 
+/// Returns whether the node has `content-visibility: auto` and its `bounds` are entirely
+/// outside of the `viewport`. In that case, no text has to be shaped and no display
+/// items have to be pushed for the node. The space of the node is still reserved: only the
+/// constraints of its descendants are deferred (see `SolvedLayout::deferred_containers`).
+fn is_content_skipped(style: &RectStyle, bounds: &TypedRect<f32, LayoutPixel>, viewport: &TypedRect<f32, LayoutPixel>) -> bool {
+    style.content_visibility == Some(ContentVisibility::Auto) && !bounds.intersects(viewport)
+}
+
+/// Returns whether the node is the root of a subtree that is painted as a single image
+/// (see `Dom::with_cached_rendering`)
+fn is_render_cached<T: Layout>(node_id: NodeId, arena: &Arena<NodeData<T>>) -> bool {
+    arena[node_id].data.attributes.get(RENDER_CACHE_ATTRIBUTE).map(|v| v == "true").unwrap_or(false)
+}
+
+/// Returns the hash, bounds and style of all nodes of the subtree `root`,
+/// for checking whether the cached image of the subtree is still up to date
+fn get_subtree_contents<'a, T: Layout>(
+    root: NodeId,
+    rectangles: &Arena<DisplayRectangle<'a>>,
+    ui_solver: &UiSolver<T>,
+    full_screen_rect: LayoutRect)
+-> SubtreeContents
+{
+    root.descendants(rectangles).map(|node_id| {
+        let dom_hash = ui_solver.dom_tree_cache.previous_layout.arena[node_id].data;
+        let bounds = ui_solver.query_bounds_of_rect(node_id).unwrap_or(full_screen_rect);
+        (dom_hash, bounds, rectangles[node_id].data.style.clone())
+    }).collect()
+}
+
+/// Returns whether the node or one of its ancestors has `display: none`,
+/// i.e. whether the node is removed from the layout
+fn is_display_none<'a>(rect_idx: NodeId, arena: &Arena<DisplayRectangle<'a>>) -> bool {
+    rect_idx.ancestors(arena).any(|id| arena[id].data.layout.display == Some(LayoutDisplay::None))
+}
+
+/// Returns the style of the placeholder of an empty `Label`, which is the style of the label
+/// with a dimmed text color
+fn get_placeholder_style(style: &RectStyle) -> RectStyle {
+    let mut font_color = style.font_color.unwrap_or(DEFAULT_FONT_COLOR);
+    font_color.0.a = (font_color.0.a as f32 * PLACEHOLDER_TEXT_ALPHA) as u8;
+    RectStyle {
+        font_color: Some(font_color),
+        .. style.clone()
+    }
+}
+
+/// Returns the `direction` of the node, inherited from the closest ancestor that sets it
+fn determine_text_direction<'a>(rect_idx: NodeId, arena: &Arena<DisplayRectangle<'a>>) -> TextDirection {
+    rect_idx.ancestors(arena)
+        .filter_map(|id| arena[id].data.style.direction)
+        .next()
+        .unwrap_or_default()
+}
+
+/// Returns the `writing-mode` of the node, inherited from the closest ancestor that sets it
+fn determine_writing_mode<'a>(rect_idx: NodeId, arena: &Arena<DisplayRectangle<'a>>) -> WritingMode {
+    rect_idx.ancestors(arena)
+        .filter_map(|id| arena[id].data.style.writing_mode)
+        .next()
+        .unwrap_or_default()
+}
+
 /// For a given rectangle, determines what text alignment should be used
 fn determine_text_alignment<'a>(rect_idx: NodeId, arena: &Arena<DisplayRectangle<'a>>)
 -> (TextAlignmentHorz, TextAlignmentVert)
@@ -522,18 +1302,189 @@ fn push_rect(
     builder.push_rect(&info, color.0.into());
 }
 
+/// Pushes a rectangle with rounded corners: the clip with the rounded corners is
+/// only active for the rectangle itself, not for the rest of the node
+fn push_rounded_rect(
+    info: &PrimitiveInfo<LayoutPixel>,
+    builder: &mut DisplayListBuilder,
+    color: &BackgroundColor,
+    border_radius: &BorderRadius)
+{
+    let region = ComplexClipRegion {
+        rect: info.rect,
+        radii: *border_radius,
+        mode: ClipMode::Clip,
+    };
+    let clip_id = builder.define_clip(info.rect, vec![region], None);
+    builder.push_clip_id(clip_id);
+    push_rect(info, builder, color);
+    builder.pop_clip_id();
+}
+
+/// Pushes the arc of a spinner, see the `spinner` module
+fn push_spinner(
+    builder: &mut DisplayListBuilder,
+    bounds: &TypedRect<f32, LayoutPixel>,
+    style: &RectStyle,
+    value: Option<SpinnerValue>,
+    rotation: f32)
+{
+    let color = style.font_color.unwrap_or(DEFAULT_FONT_COLOR).0;
+    let arc = SpinnerArc::new(value, rotation);
+
+    for segment in get_spinner_segments(bounds, &arc) {
+        builder.push_rect(&LayoutPrimitiveInfo::new(segment), color.into());
+    }
+}
+
+/// Pushes the checkmark (or the dash) of a checkbox, see the `checkbox` module
+fn push_checkbox_mark(
+    builder: &mut DisplayListBuilder,
+    bounds: &TypedRect<f32, LayoutPixel>,
+    style: &RectStyle,
+    state: CheckBoxState)
+{
+    let color = style.font_color.unwrap_or(DEFAULT_FONT_COLOR).0;
+    for segment in get_checkbox_mark(bounds, state) {
+        builder.push_rect(&LayoutPrimitiveInfo::new(segment), color.into());
+    }
+}
+
+/// Returns the webrender filters for the `backdrop-filter` of the rectangle
+fn get_backdrop_filters(style: &RectStyle) -> Vec<FilterOp> {
+    match style.backdrop_filter {
+        Some(BackdropFilter::Blur(radius)) => radius.to_pixels().into_iter().map(FilterOp::Blur).collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Pushes a clip to the (rounded) bounds of the rectangle and a stacking context with the
+/// `backdrop-filter` applied, into which the content behind the rectangle is painted again.
+///
+/// NOTE: webrender can't sample the content behind a stacking context yet, so the
+/// content behind the rectangle has to be pushed a second time, inside of the filter.
+///
+/// Returns `true` if the clip and the stacking context were pushed (and have to be popped again)
+fn push_backdrop_filter(
+    info: &PrimitiveInfo<LayoutPixel>,
+    builder: &mut DisplayListBuilder,
+    style: &RectStyle)
+-> bool
+{
+    let filters = get_backdrop_filters(style);
+    if filters.is_empty() {
+        return false;
+    }
+
+    let rounded_corners = style.border_radius.map(|border_radius| ComplexClipRegion {
+        rect: info.rect,
+        radii: border_radius,
+        mode: ClipMode::Clip,
+    });
+    let clip_id = builder.define_clip(info.rect, rounded_corners, None);
+    builder.push_clip_id(clip_id);
+
+    builder.push_stacking_context(
+        info,
+        None,
+        None,
+        TransformStyle::Flat,
+        None,
+        MixBlendMode::Normal,
+        filters,
+        GlyphRasterSpace::Screen);
+
+    true
+}
+
+/// Returns the webrender filter for the `-azul-tint-color` of the rectangle: a color matrix
+/// that multiplies every channel of the image with the corresponding channel of the tint color
+fn get_tint_filter(style: &RectStyle) -> Option<FilterOp> {
+    let tint: ColorF = style.tint_color?.0.into();
+    Some(FilterOp::ColorMatrix([
+        tint.r, 0.0,    0.0,    0.0,
+        0.0,    tint.g, 0.0,    0.0,
+        0.0,    0.0,    tint.b, 0.0,
+        0.0,    0.0,    0.0,    tint.a,
+        // offsets
+        0.0,    0.0,    0.0,    0.0,
+    ]))
+}
+
+/// Pushes a stacking context with the `-azul-tint-color` of the rectangle, into which the
+/// image / OpenGL texture of the rectangle is drawn.
+///
+/// Returns `true` if a stacking context was pushed (and has to be popped again)
+fn push_tint(
+    info: &PrimitiveInfo<LayoutPixel>,
+    builder: &mut DisplayListBuilder,
+    style: &RectStyle)
+-> bool
+{
+    let filter = match get_tint_filter(style) {
+        Some(f) => f,
+        None => return false,
+    };
+
+    builder.push_stacking_context(
+        info,
+        None,
+        None,
+        TransformStyle::Flat,
+        None,
+        MixBlendMode::Normal,
+        vec![filter],
+        GlyphRasterSpace::Screen);
+
+    true
+}
+
+/// Pushes a stacking context with the `opacity` of the rectangle, so that the
+/// rectangle and all of its children are drawn with that opacity.
+///
+/// Returns `true` if a stacking context was pushed (and has to be popped again
+/// once the children of the rectangle have been pushed)
+fn push_opacity(
+    info: &PrimitiveInfo<LayoutPixel>,
+    builder: &mut DisplayListBuilder,
+    style: &RectStyle)
+-> bool
+{
+    let opacity = match style.opacity {
+        Some(Opacity(opacity)) if opacity < 1.0 => opacity,
+        _ => return false,
+    };
+
+    builder.push_stacking_context(
+        info,
+        None,
+        None,
+        TransformStyle::Flat,
+        None,
+        MixBlendMode::Normal,
+        vec![FilterOp::Opacity(PropertyBinding::Value(opacity), opacity)],
+        GlyphRasterSpace::Screen);
+
+    true
+}
+
 #[inline]
 fn push_text(
     info: &PrimitiveInfo<LayoutPixel>,
     text: &TextInfo,
     builder: &mut DisplayListBuilder,
     style: &RectStyle,
+    layout: &RectLayout,
     app_resources: &mut AppResources,
-    render_api: &RenderApi,
+    render_api: &dyn FontImageApi,
     bounds: &TypedRect<f32, LayoutPixel>,
     resource_updates: &mut Vec<ResourceUpdate>,
     horz_alignment: TextAlignmentHorz,
-    vert_alignment: TextAlignmentVert)
+    vert_alignment: TextAlignmentVert,
+    text_direction: TextDirection,
+    writing_mode: WritingMode,
+    caret_glyph: Option<usize>,
+    scroll_offset: LayoutVector2D)
 {
     use text_layout;
 
@@ -547,8 +1498,10 @@ fn push_text(
     };
 
     let font_size = style.font_size.unwrap_or(DEFAULT_FONT_SIZE);
-    let font_size_app_units = Au((font_size.0.to_pixels() as i32) * AU_PER_PX as i32);
-    let font_id = font_family.fonts.get(0).unwrap_or(&DEFAULT_BUILTIN_FONT_SANS_SERIF);
+    let font_size_app_units = font_size_to_app_units(font_size.to_pixels(), app_resources.font_size_quantization);
+    let font_family_id = font_family.fonts.get(0).unwrap_or(&DEFAULT_BUILTIN_FONT_SANS_SERIF);
+    let font_face = app_resources.match_font_face(font_family_id, style.font_weight.unwrap_or_default(), style.font_style.unwrap_or_default());
+    let font_id = &font_face.font_id;
     let font_result = push_font(font_id, font_size_app_units, resource_updates, app_resources, render_api);
 
     let font_instance_key = match font_result {
@@ -563,129 +1516,418 @@ fn push_text(
     let scrollbar_style = ScrollbarInfo {
         width: 17,
         padding: 2,
+        min_thumb_length: 20,
         background_color: BackgroundColor(ColorU { r: 241, g: 241, b: 241, a: 255 }),
         triangle_color: BackgroundColor(ColorU { r: 163, g: 163, b: 163, a: 255 }),
         bar_color: BackgroundColor(ColorU { r: 193, g: 193, b: 193, a: 255 }),
     };
 
-    let (positioned_glyphs, scrollbar_info) = text_layout::get_glyphs(
+    // `padding` - the text is laid out inside of the content box
+    let content_bounds = get_content_rect(bounds, layout.padding);
+
+    let (mut positioned_glyphs, scrollbar_info) = text_layout::get_glyphs(
         app_resources,
-        bounds,
+        &content_bounds,
         horz_alignment,
         vert_alignment,
         &font_id,
         &font_size,
         line_height,
+        style.text_indent,
         text,
         &overflow_behaviour,
-        &scrollbar_style
+        &scrollbar_style,
+        text_layout::LineBreakMode::from_style(style.overflow_wrap, style.word_break),
+        writing_mode,
     );
 
-    let font_color = style.font_color.unwrap_or(DEFAULT_FONT_COLOR).0.into();
-    let mut flags = FontInstanceFlags::empty();
+    // The text of a scroll container is moved up / left by how far it was scrolled,
+    // the caret and the text decorations are positioned at the moved glyphs
+    for glyph in &mut positioned_glyphs {
+        glyph.point = glyph.point - scroll_offset;
+    }
+
+    // The scrollbars and the clip of the text are placed in the content box
+    let bounds = &content_bounds;
+    let scrollbars = get_scrollbar_rects(&overflow_behaviour, &scrollbar_info, &scrollbar_style, bounds, &style.border, text_direction);
+    let clip_rect = get_clip_without_scrollbars(&get_text_clip_rect(bounds, layout.padding, &overflow_behaviour), &scrollbars);
+    let info = PrimitiveInfo { rect: *bounds, clip_rect: clip_rect, .. *info };
+
+    let font_color = style.font_color.unwrap_or(DEFAULT_FONT_COLOR).0.into();
+    let mut flags = FontInstanceFlags::empty();
     flags.set(FontInstanceFlags::SUBPIXEL_BGR, true);
     flags.set(FontInstanceFlags::FONT_SMOOTHING, true);
     flags.set(FontInstanceFlags::FORCE_AUTOHINT, true);
     flags.set(FontInstanceFlags::LCD_VERTICAL, true);
+    flags.set(FontInstanceFlags::SYNTHETIC_BOLD, font_face.synthetic_bold);
+    flags.set(FontInstanceFlags::SYNTHETIC_ITALICS, font_face.synthetic_italic);
 
     let options = GlyphOptions {
         render_mode: FontRenderMode::Subpixel,
         flags: flags,
     };
 
+    // `text-shadow` - the shadow is cast by the glyphs and the text decorations, not by the caret
+    let has_text_shadow = match style.text_shadow {
+        Some(ref shadow) => push_shadow_for(builder, shadow, ShadowCaster::Text { info: &info }),
+        None => false,
+    };
+
     builder.push_text(&info, &positioned_glyphs, font_instance_key, font_color, Some(options));
 
-    use text_layout::TextOverflow;
+    // `text-decoration` - the lines are clipped to the text box, just like the text
+    if let Some(text_decoration) = style.text_decoration {
+        let decoration_rects = text_layout::get_text_decoration_rects(app_resources, &positioned_glyphs, &font_id, &font_size, text_decoration);
+        for decoration_rect in decoration_rects {
+            builder.push_rect(&PrimitiveInfo { rect: decoration_rect, .. info }, font_color);
+        }
+    }
 
-    // If the rectangle should have a scrollbar, push a scrollbar onto the display list
-    // TODO !!!
-    if let TextOverflow::IsOverflowing(amount_vert) = scrollbar_info.vertical {
-        push_scrollbar(builder, &overflow_behaviour, &scrollbar_info, &scrollbar_style, bounds, &style.border)
+    if has_text_shadow {
+        builder.pop_all_shadows();
     }
-    if let TextOverflow::IsOverflowing(amount_horz) = scrollbar_info.horizontal {
-        push_scrollbar(builder, &overflow_behaviour, &scrollbar_info, &scrollbar_style, bounds, &style.border)
+
+    // The caret of the focused node is drawn after the `caret_glyph` glyphs, clipped like the text
+    if let Some(caret_glyph) = caret_glyph {
+        if let Some(caret_rect) = text_layout::get_caret_rect(app_resources, &positioned_glyphs, caret_glyph, &font_id, &font_size, CARET_WIDTH) {
+            builder.push_rect(&PrimitiveInfo { rect: caret_rect, .. info }, get_caret_color(style));
+        }
     }
+
+    // If the rectangle should have scrollbars, push them onto the display list
+    // (`overflow: auto` only shows a scrollbar if the text is overflowing, `overflow: scroll` always)
+    push_scrollbar(builder, &overflow_behaviour, &scrollbar_info, &scrollbar_style, bounds, &style.border, text_direction, scroll_offset);
+
+    // `text-overflow: fade` - fade the overflowing text out at the trailing edges
+    push_text_fade(builder, style, bounds, &scrollbar_info);
 }
 
-/// Adds a scrollbar to the left or bottom side of a rectangle.
-/// TODO: make styling configurable (like the width / style of the scrollbar)
-fn push_scrollbar(
+/// Returns the color of the text caret: the `caret-color` of the node, or its text color
+fn get_caret_color(style: &RectStyle) -> ColorF {
+    match style.caret_color {
+        Some(caret_color) => caret_color.0.into(),
+        None => style.font_color.unwrap_or(DEFAULT_FONT_COLOR).0.into(),
+    }
+}
+
+/// Pushes the caret of a node without text, at the start of its content box (and clipped to it)
+fn push_empty_caret(builder: &mut DisplayListBuilder, bounds: &LayoutRect, style: &RectStyle, layout: &RectLayout) {
+    let content_bounds = get_content_rect(bounds, layout.padding);
+    let font_size = style.font_size.unwrap_or(DEFAULT_FONT_SIZE).to_pixels();
+    let caret_rect = LayoutRect::new(content_bounds.origin, LayoutSize::new(CARET_WIDTH, font_size.min(content_bounds.size.height)));
+    builder.push_rect(&LayoutPrimitiveInfo::with_clip_rect(caret_rect, content_bounds), get_caret_color(style));
+}
+
+/// Returns the content box of a node, i.e. the `bounds` without the `padding`
+fn get_content_rect(bounds: &LayoutRect, padding: Option<LayoutPadding>) -> LayoutRect {
+    let padding = padding.unwrap_or_default();
+    LayoutRect::new(
+        LayoutPoint::new(bounds.origin.x + padding.left, bounds.origin.y + padding.top),
+        LayoutSize::new(
+            (bounds.size.width - padding.left - padding.right).max(0.0),
+            (bounds.size.height - padding.top - padding.bottom).max(0.0)))
+}
+
+/// Returns the clip rect of the text in the `content_box` of a node: in the directions where
+/// the overflow is clipped (`overflow: hidden`, `scroll` or `auto`), the text is clipped to the
+/// content box, otherwise (also without an `overflow`) it may overflow into the padding, but
+/// not out of the node.
+fn get_text_clip_rect(content_box: &LayoutRect, padding: Option<LayoutPadding>, overflow: &LayoutOverflow) -> LayoutRect {
+    let padding = padding.unwrap_or_default();
+
+    let (x, width) = if overflow.horizontal.clips_content() {
+        (content_box.origin.x, content_box.size.width)
+    } else {
+        (content_box.origin.x - padding.left, content_box.size.width + padding.left + padding.right)
+    };
+
+    let (y, height) = if overflow.vertical.clips_content() {
+        (content_box.origin.y, content_box.size.height)
+    } else {
+        (content_box.origin.y - padding.top, content_box.size.height + padding.top + padding.bottom)
+    };
+
+    LayoutRect::new(LayoutPoint::new(x, y), LayoutSize::new(width, height))
+}
+
+/// Length (in pixels) of the gradient that fades out overflowing text
+const TEXT_FADE_LENGTH: f32 = 20.0;
+
+/// For `text-overflow: fade`, returns the rectangles at the trailing edges of the text
+/// that have to be covered with a fade-out gradient, together with the start and end
+/// point of the gradient (relative to the rectangle).
+///
+/// Returns an empty Vec if the text doesn't overflow or `text-overflow` isn't set to `fade`.
+fn get_text_fade_rects(
+    style: &RectStyle,
+    bounds: &TypedRect<f32, LayoutPixel>,
+    overflow: &TextOverflowPass2)
+-> Vec<(TypedRect<f32, LayoutPixel>, LayoutPoint, LayoutPoint)>
+{
+    use euclid::TypedPoint2D;
+
+    let mut fade_rects = Vec::new();
+
+    if style.text_overflow != Some(TextOverflowMode::Fade) {
+        return fade_rects;
+    }
+
+    if overflow.horizontal.is_overflowing() {
+        let fade_width = TEXT_FADE_LENGTH.min(bounds.size.width);
+        fade_rects.push((
+            TypedRect::new(
+                TypedPoint2D::new(bounds.origin.x + bounds.size.width - fade_width, bounds.origin.y),
+                TypedSize2D::new(fade_width, bounds.size.height)),
+            LayoutPoint::zero(),
+            LayoutPoint::new(fade_width, 0.0),
+        ));
+    }
+
+    if overflow.vertical.is_overflowing() {
+        let fade_height = TEXT_FADE_LENGTH.min(bounds.size.height);
+        fade_rects.push((
+            TypedRect::new(
+                TypedPoint2D::new(bounds.origin.x, bounds.origin.y + bounds.size.height - fade_height),
+                TypedSize2D::new(bounds.size.width, fade_height)),
+            LayoutPoint::zero(),
+            LayoutPoint::new(0.0, fade_height),
+        ));
+    }
+
+    fade_rects
+}
+
+/// Pushes a gradient from transparent to the background color of the rectangle
+/// over the trailing edges of the text, so that the text appears to fade out
+fn push_text_fade(
     builder: &mut DisplayListBuilder,
+    style: &RectStyle,
+    bounds: &TypedRect<f32, LayoutPixel>,
+    overflow: &TextOverflowPass2)
+{
+    let fade_color: ColorF = style.background_color
+        .unwrap_or(BackgroundColor(ColorU { r: 255, g: 255, b: 255, a: 255 })).0.into();
+    let transparent = ColorF { a: 0.0, .. fade_color };
+
+    for (fade_rect, start, end) in get_text_fade_rects(style, bounds, overflow) {
+        let info = PrimitiveInfo {
+            rect: fade_rect,
+            clip_rect: fade_rect,
+            is_backface_visible: false,
+            tag: None,
+        };
+        let stops = vec![
+            GradientStop { offset: 0.0, color: transparent },
+            GradientStop { offset: 1.0, color: fade_color },
+        ];
+        let gradient = builder.create_gradient(start, end, stops, ExtendMode::Clamp);
+        builder.push_gradient(&info, gradient, fade_rect.size, LayoutSize::zero());
+    }
+}
+
+/// Bounds of the scrollbars of a rectangle
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct ScrollbarRects {
+    /// Track of the vertical scrollbar, `None` if there is no vertical scrollbar
+    vertical: Option<TypedRect<f32, LayoutPixel>>,
+    /// Track of the horizontal scrollbar, `None` if there is no horizontal scrollbar
+    horizontal: Option<TypedRect<f32, LayoutPixel>>,
+    /// The square in the corner between both scrollbars, only if both are visible
+    corner: Option<TypedRect<f32, LayoutPixel>>,
+}
+
+/// Returns where the scrollbars of a rectangle are drawn. If both scrollbars are visible,
+/// the tracks stop short of the corner that they share, so that they don't overlap.
+fn get_scrollbar_rects(
     display_behaviour: &LayoutOverflow,
     scrollbar_info: &TextOverflowPass2,
     scrollbar_style: &ScrollbarInfo,
     bounds: &TypedRect<f32, LayoutPixel>,
-    border: &Option<(BorderWidths, BorderDetails)>)
+    border: &Option<(BorderWidths, BorderDetails)>,
+    text_direction: TextDirection)
+-> ScrollbarRects
 {
-    use euclid::TypedPoint2D;
+    let has_vertical = display_behaviour.needs_vertical_scrollbar(scrollbar_info.vertical.is_overflowing());
+    let has_horizontal = display_behaviour.needs_horizontal_scrollbar(scrollbar_info.horizontal.is_overflowing());
 
-    // The border is inside the rectangle - subtract the border width on the left and bottom side,
-    // so that the scrollbar is laid out correctly
+    // The border is inside the rectangle - the scrollbars are drawn inside of the border
     let mut bounds = *bounds;
     if let Some((border_widths, _)) = border {
-        bounds.size.width -= border_widths.left;
-        bounds.size.height -= border_widths.bottom;
+        bounds.origin.x += border_widths.left;
+        bounds.origin.y += border_widths.top;
+        bounds.size.width -= border_widths.left + border_widths.right;
+        bounds.size.height -= border_widths.top + border_widths.bottom;
     }
 
-    {
-        // Background of scrollbar (vertical)
-        let scrollbar_vertical_background = TypedRect::<f32, LayoutPixel> {
-            origin: TypedPoint2D::new(bounds.origin.x + bounds.size.width - scrollbar_style.width as f32, bounds.origin.y),
-            size: TypedSize2D::new(scrollbar_style.width as f32, bounds.size.height),
-        };
+    let width = scrollbar_style.width as f32;
 
-        let scrollbar_vertical_background_info = PrimitiveInfo {
-            rect: scrollbar_vertical_background,
-            clip_rect: bounds,
-            is_backface_visible: false,
-            tag: None, // TODO: for hit testing
-        };
+    // In right-to-left layouts, the vertical scrollbar is on the left side of the rectangle
+    let vertical_x = match text_direction {
+        TextDirection::Ltr => bounds.origin.x + bounds.size.width - width,
+        TextDirection::Rtl => bounds.origin.x,
+    };
+    let horizontal_y = bounds.origin.y + bounds.size.height - width;
+
+    let vertical_height = if has_horizontal { bounds.size.height - width } else { bounds.size.height };
+    let (horizontal_x, horizontal_width) = match (has_vertical, text_direction) {
+        (false, _) => (bounds.origin.x, bounds.size.width),
+        (true, TextDirection::Ltr) => (bounds.origin.x, bounds.size.width - width),
+        (true, TextDirection::Rtl) => (bounds.origin.x + width, bounds.size.width - width),
+    };
+
+    ScrollbarRects {
+        vertical: if has_vertical {
+            Some(LayoutRect::new(LayoutPoint::new(vertical_x, bounds.origin.y), LayoutSize::new(width, vertical_height)))
+        } else {
+            None
+        },
+        horizontal: if has_horizontal {
+            Some(LayoutRect::new(LayoutPoint::new(horizontal_x, horizontal_y), LayoutSize::new(horizontal_width, width)))
+        } else {
+            None
+        },
+        corner: if has_vertical && has_horizontal {
+            Some(LayoutRect::new(LayoutPoint::new(vertical_x, horizontal_y), LayoutSize::new(width, width)))
+        } else {
+            None
+        },
+    }
+}
 
-        push_rect(&scrollbar_vertical_background_info, builder, &scrollbar_style.background_color);
+/// Shrinks the clip of the content, so that the content isn't drawn below the scrollbars
+fn get_clip_without_scrollbars(clip_rect: &LayoutRect, scrollbars: &ScrollbarRects) -> LayoutRect {
+    let mut min_x = clip_rect.min_x();
+    let mut max_x = clip_rect.max_x();
+    let mut max_y = clip_rect.max_y();
+
+    if let Some(vertical) = scrollbars.vertical {
+        // The vertical scrollbar is either on the left or on the right side
+        if vertical.min_x() <= min_x {
+            min_x = min_x.max(vertical.max_x());
+        } else {
+            max_x = max_x.min(vertical.min_x());
+        }
     }
 
-    {
-        // Actual scroll bar
-        let scrollbar_vertical_bar = TypedRect::<f32, LayoutPixel> {
-            origin: TypedPoint2D::new(
-                bounds.origin.x + bounds.size.width - scrollbar_style.width as f32 + scrollbar_style.padding as f32,
-                bounds.origin.y + scrollbar_style.width as f32),
-            size: TypedSize2D::new(
-                (scrollbar_style.width - (scrollbar_style.padding * 2)) as f32,
-                 bounds.size.height - (scrollbar_style.width * 2) as f32),
-        };
+    if let Some(horizontal) = scrollbars.horizontal {
+        max_y = max_y.min(horizontal.min_y());
+    }
+
+    LayoutRect::new(
+        LayoutPoint::new(min_x, clip_rect.min_y()),
+        LayoutSize::new((max_x - min_x).max(0.0), (max_y - clip_rect.min_y()).max(0.0)))
+}
+
+/// Returns the start (relative to the start of the space between the arrows) and the
+/// length of the thumb of a scrollbar.
+///
+/// The thumb is as long in relation to the track as the rectangle is in relation to the
+/// content, but at least `min_thumb_length` long. The thumb moves over the rest of the
+/// track, so that even a clamped thumb reaches the end of the track at the maximum
+/// scroll offset (which is the length of the `overflow`).
+fn get_scrollbar_thumb(track_length: f32, visible_length: f32, overflow: f32, scroll_offset: f32, min_thumb_length: f32)
+-> (f32, f32)
+{
+    let track_length = track_length.max(0.0);
+    let overflow = overflow.max(0.0);
+    let content_length = visible_length + overflow;
+
+    let thumb_length = if content_length > 0.0 {
+        track_length * visible_length / content_length
+    } else {
+        track_length
+    };
+    let thumb_length = thumb_length.max(min_thumb_length).min(track_length);
+
+    let scroll_ratio = if overflow > 0.0 {
+        (scroll_offset / overflow).max(0.0).min(1.0)
+    } else {
+        0.0
+    };
+
+    ((track_length - thumb_length) * scroll_ratio, thumb_length)
+}
+
+/// Adds the vertical and / or the horizontal scrollbar of a rectangle (depending on
+/// the `overflow` and on whether the content overflows), see `get_scrollbar_rects`.
+/// The thumbs are positioned according to the `scroll_offset` of the content.
+/// TODO: make styling configurable (like the width / style of the scrollbar)
+fn push_scrollbar(
+    builder: &mut DisplayListBuilder,
+    display_behaviour: &LayoutOverflow,
+    scrollbar_info: &TextOverflowPass2,
+    scrollbar_style: &ScrollbarInfo,
+    bounds: &TypedRect<f32, LayoutPixel>,
+    border: &Option<(BorderWidths, BorderDetails)>,
+    text_direction: TextDirection,
+    scroll_offset: LayoutVector2D)
+{
+    use text_layout::TextOverflow;
+
+    let scrollbars = get_scrollbar_rects(display_behaviour, scrollbar_info, scrollbar_style, bounds, border, text_direction);
 
-        let scrollbar_vertical_bar_info = PrimitiveInfo {
-            rect: scrollbar_vertical_bar,
-            clip_rect: bounds,
+    let width = scrollbar_style.width as f32;
+    let padding = scrollbar_style.padding as f32;
+    let min_thumb_length = scrollbar_style.min_thumb_length as f32;
+
+    let get_overflow = |overflow: &TextOverflow| match *overflow {
+        TextOverflow::IsOverflowing(px) => px,
+        TextOverflow::InBounds(_) => 0.0,
+    };
+
+    let push_scrollbar_rect = |builder: &mut DisplayListBuilder, rect: TypedRect<f32, LayoutPixel>, color: &BackgroundColor| {
+        let info = PrimitiveInfo {
+            rect: rect,
+            clip_rect: rect,
             is_backface_visible: false,
             tag: None, // TODO: for hit testing
         };
+        push_rect(&info, builder, color);
+    };
+
+    // The arrows are half as large as the inner part of the scrollbar
+    let get_triangle_rect = |origin: LayoutPoint| {
+        let size = width - padding * 2.0;
+        LayoutRect::new(
+            LayoutPoint::new(origin.x + padding + size / 4.0, origin.y + padding + size / 4.0),
+            LayoutSize::new(size / 2.0, size / 2.0))
+    };
+
+    if let Some(track) = scrollbars.vertical {
+        push_scrollbar_rect(builder, track, &scrollbar_style.background_color);
+
+        let (thumb_start, thumb_length) = get_scrollbar_thumb(
+            track.size.height - width * 2.0, bounds.size.height, get_overflow(&scrollbar_info.vertical),
+            scroll_offset.y, min_thumb_length);
+        let bar = LayoutRect::new(
+            LayoutPoint::new(track.origin.x + padding, track.origin.y + width + thumb_start),
+            LayoutSize::new(width - padding * 2.0, thumb_length));
+        push_scrollbar_rect(builder, bar, &scrollbar_style.bar_color);
 
-        push_rect(&scrollbar_vertical_bar_info, builder, &scrollbar_style.bar_color);
+        push_triangle(&get_triangle_rect(track.origin), builder, &scrollbar_style.triangle_color, TriangleDirection::PointUp);
+        let bottom = LayoutPoint::new(track.origin.x, track.max_y() - width);
+        push_triangle(&get_triangle_rect(bottom), builder, &scrollbar_style.triangle_color, TriangleDirection::PointDown);
     }
 
-    {
-        // Triangle top
-        let mut scrollbar_triangle_rect = TypedRect::<f32, LayoutPixel> {
-            origin: TypedPoint2D::new(
-                bounds.origin.x + bounds.size.width - scrollbar_style.width as f32 + scrollbar_style.padding as f32,
-                bounds.origin.y + scrollbar_style.padding as f32),
-            size: TypedSize2D::new(
-                (scrollbar_style.width - (scrollbar_style.padding * 2)) as f32,
-                (scrollbar_style.width - (scrollbar_style.padding * 2)) as f32),
-        };
+    if let Some(track) = scrollbars.horizontal {
+        push_scrollbar_rect(builder, track, &scrollbar_style.background_color);
 
-        scrollbar_triangle_rect.origin.x += scrollbar_triangle_rect.size.width / 4.0;
-        scrollbar_triangle_rect.origin.y += scrollbar_triangle_rect.size.height / 4.0;
-        scrollbar_triangle_rect.size.width /= 2.0;
-        scrollbar_triangle_rect.size.height /= 2.0;
+        let (thumb_start, thumb_length) = get_scrollbar_thumb(
+            track.size.width - width * 2.0, bounds.size.width, get_overflow(&scrollbar_info.horizontal),
+            scroll_offset.x, min_thumb_length);
+        let bar = LayoutRect::new(
+            LayoutPoint::new(track.origin.x + width + thumb_start, track.origin.y + padding),
+            LayoutSize::new(thumb_length, width - padding * 2.0));
+        push_scrollbar_rect(builder, bar, &scrollbar_style.bar_color);
 
-        push_triangle(&scrollbar_triangle_rect, builder, &scrollbar_style.triangle_color, TriangleDirection::PointUp);
+        push_triangle(&get_triangle_rect(track.origin), builder, &scrollbar_style.triangle_color, TriangleDirection::PointLeft);
+        let right = LayoutPoint::new(track.max_x() - width, track.origin.y);
+        push_triangle(&get_triangle_rect(right), builder, &scrollbar_style.triangle_color, TriangleDirection::PointRight);
+    }
 
-        // Triangle bottom
-        scrollbar_triangle_rect.origin.y += bounds.size.height - scrollbar_style.width as f32 + scrollbar_style.padding as f32;
-        push_triangle(&scrollbar_triangle_rect, builder, &scrollbar_style.triangle_color, TriangleDirection::PointDown);
+    // Fill the corner between both scrollbars with the background of the tracks
+    if let Some(corner) = scrollbars.corner {
+        push_scrollbar_rect(builder, corner, &scrollbar_style.background_color);
     }
 }
 
@@ -694,6 +1936,8 @@ enum TriangleDirection {
     PointDown,
     PointRight,
     PointLeft,
+    /// The bottom-right half of the rectangle
+    BottomRightCorner,
 }
 
 fn push_triangle(
@@ -722,6 +1966,7 @@ fn push_triangle(
         PointDown       => [(TRANSPARENT, BorderStyle::Hidden), (TRANSPARENT, BorderStyle::Hidden), (background_color.0, BorderStyle::Solid),  (TRANSPARENT, BorderStyle::Hidden)],
         PointLeft       => [(TRANSPARENT, BorderStyle::Hidden), (background_color.0, BorderStyle::Solid),  (TRANSPARENT, BorderStyle::Hidden), (TRANSPARENT, BorderStyle::Hidden)],
         PointRight      => [(background_color.0, BorderStyle::Solid),  (TRANSPARENT, BorderStyle::Hidden), (TRANSPARENT, BorderStyle::Hidden), (TRANSPARENT, BorderStyle::Hidden)],
+        BottomRightCorner => [(TRANSPARENT, BorderStyle::Hidden), (background_color.0, BorderStyle::Solid),  (TRANSPARENT, BorderStyle::Hidden), (background_color.0, BorderStyle::Solid) ],
     };
 
     let border_details = BorderDetails::Normal(NormalBorder {
@@ -744,8 +1989,28 @@ fn push_triangle(
     builder.push_border(&triangle_rect_info, border_widths, border_details);
 }
 
+/// Pushes the grip of a resizable node: a triangle in the bottom-right corner for `resize: both`,
+/// an arrow pointing in the direction of the resize axis otherwise
+fn push_resize_grip(
+    grip_rect: &TypedRect<f32, LayoutPixel>,
+    builder: &mut DisplayListBuilder,
+    resize: LayoutResize)
+{
+    const RESIZE_GRIP_COLOR: BackgroundColor = BackgroundColor(ColorU { r: 128, g: 128, b: 128, a: 255 });
+
+    let direction = match resize {
+        LayoutResize::Both => TriangleDirection::BottomRightCorner,
+        LayoutResize::Horizontal => TriangleDirection::PointRight,
+        LayoutResize::Vertical => TriangleDirection::PointDown,
+        LayoutResize::None => return,
+    };
+
+    push_triangle(grip_rect, builder, &RESIZE_GRIP_COLOR, direction);
+}
+
 /// WARNING: For "inset" shadows, you must push a clip ID first, otherwise the
-/// shadow will not show up.
+/// shadow will not show up (`displaylist_handle_rect` pushes a clip for every
+/// rectangle with an inset shadow).
 ///
 /// To prevent a shadow from being pushed twice, you have to annotate the clip
 /// mode for this - outset or inset.
@@ -769,31 +2034,97 @@ fn push_box_shadow(
         return;
     }
 
-    let clip_rect = if pre_shadow.clip_mode == BoxShadowClipMode::Inset {
-        // inset shadows do not work like outset shadows
-        // for inset shadows, you have to push a clip ID first, so that they are
-        // clipped to the bounds -we trust that the calling function knows to do this
-        *bounds
-    } else {
-        // calculate the maximum extent of the outset shadow
-        let mut clip_rect = *bounds;
-
-        let origin_displace = pre_shadow.spread_radius - pre_shadow.blur_radius;
-        clip_rect.origin.x = clip_rect.origin.x + pre_shadow.offset.x - origin_displace;
-        clip_rect.origin.y = clip_rect.origin.y + pre_shadow.offset.y - origin_displace;
+    push_shadow_for(builder, pre_shadow, ShadowCaster::Box { bounds, border_radius, full_screen_rect });
+}
 
-        let spread = (pre_shadow.spread_radius * 2.0) + (pre_shadow.blur_radius * 2.0);
-        clip_rect.size.height = clip_rect.size.height + spread;
-        clip_rect.size.width = clip_rect.size.width + spread;
+/// What casts a shadow, see `push_shadow_for`
+enum ShadowCaster<'a> {
+    /// `box-shadow`: the shadow has the shape of the (rounded) bounds of the box
+    Box {
+        bounds: &'a TypedRect<f32, LayoutPixel>,
+        border_radius: BorderRadius,
+        full_screen_rect: &'a TypedRect<f32, LayoutPixel>,
+    },
+    /// `text-shadow`: the glyphs (and text decorations) that are pushed until
+    /// `pop_all_shadows` is called cast the shadow
+    Text { info: &'a PrimitiveInfo<LayoutPixel> },
+    /// `filter: drop-shadow`: the content that is pushed until the stacking context is
+    /// popped casts the shadow, so the transparent pixels of an image don't cast a shadow
+    Image { info: &'a PrimitiveInfo<LayoutPixel> },
+}
 
-        // prevent shadows that are larger than the full screen
-        clip_rect.intersection(full_screen_rect).unwrap_or(clip_rect)
-    };
+/// Pushes a shadow with the offset, blur and color of the `shadow`, shaped like the `caster`.
+///
+/// Returns `true` if the shadow has to be popped again after the text / image has been
+/// pushed (with `pop_all_shadows` for text, `pop_stacking_context` for images)
+fn push_shadow_for(
+    builder: &mut DisplayListBuilder,
+    shadow: &BoxShadowPreDisplayItem,
+    caster: ShadowCaster)
+-> bool
+{
+    match caster {
+        ShadowCaster::Box { bounds, border_radius, full_screen_rect } => {
+            let clip_rect = if shadow.clip_mode == BoxShadowClipMode::Inset {
+                // inset shadows do not work like outset shadows
+                // for inset shadows, you have to push a clip ID first, so that they are
+                // clipped to the bounds -we trust that the calling function knows to do this
+                *bounds
+            } else {
+                let clip_rect = get_outset_shadow_rect(bounds, shadow);
+                // prevent shadows that are larger than the full screen
+                clip_rect.intersection(full_screen_rect).unwrap_or(clip_rect)
+            };
+
+            let info = LayoutPrimitiveInfo::with_clip_rect(LayoutRect::zero(), clip_rect);
+            builder.push_box_shadow(&info, *bounds, shadow.offset, shadow.color,
+                                     shadow.blur_radius, shadow.spread_radius,
+                                     border_radius, shadow.clip_mode);
+            false
+        },
+        ShadowCaster::Text { info } => {
+            builder.push_shadow(info, Shadow {
+                offset: shadow.offset,
+                color: shadow.color,
+                blur_radius: shadow.blur_radius,
+            });
+            true
+        },
+        ShadowCaster::Image { info } => {
+            builder.push_stacking_context(
+                info,
+                None,
+                None,
+                TransformStyle::Flat,
+                None,
+                MixBlendMode::Normal,
+                vec![FilterOp::DropShadow(shadow.offset, shadow.blur_radius, shadow.color)],
+                GlyphRasterSpace::Screen);
+            true
+        },
+    }
+}
 
-    let info = LayoutPrimitiveInfo::with_clip_rect(LayoutRect::zero(), clip_rect);
-    builder.push_box_shadow(&info, *bounds, pre_shadow.offset, pre_shadow.color,
-                             pre_shadow.blur_radius, pre_shadow.spread_radius,
-                             border_radius, pre_shadow.clip_mode);
+/// Calculates the maximum extent of an outset shadow: the bounds are moved by the offset,
+/// grown by the spread radius (or shrunk, if the spread is negative) and grown by the blur
+/// radius, since the blur fades out the shadow beyond its edges. The corner radius doesn't
+/// change the extent, the rounded corners are always inside of this rectangle.
+fn get_outset_shadow_rect(bounds: &TypedRect<f32, LayoutPixel>, pre_shadow: &BoxShadowPreDisplayItem)
+-> TypedRect<f32, LayoutPixel>
+{
+    let spread = pre_shadow.spread_radius;
+    let blur = pre_shadow.blur_radius.max(0.0);
+
+    // A negative spread can't shrink the shadow below a size of zero
+    let shadow_width = (bounds.size.width + 2.0 * spread).max(0.0);
+    let shadow_height = (bounds.size.height + 2.0 * spread).max(0.0);
+    let shadow_origin = LayoutPoint::new(
+        bounds.origin.x + pre_shadow.offset.x + (bounds.size.width - shadow_width) / 2.0,
+        bounds.origin.y + pre_shadow.offset.y + (bounds.size.height - shadow_height) / 2.0);
+
+    LayoutRect::new(
+        LayoutPoint::new(shadow_origin.x - blur, shadow_origin.y - blur),
+        LayoutSize::new(shadow_width + 2.0 * blur, shadow_height + 2.0 * blur))
 }
 
 #[inline]
@@ -802,7 +2133,11 @@ fn push_background(
     bounds: &TypedRect<f32, LayoutPixel>,
     builder: &mut DisplayListBuilder,
     background: &Background,
-    app_resources: &AppResources)
+    style: &RectStyle,
+    app_resources: &mut AppResources,
+    current_epoch: Epoch,
+    render_api: &dyn FontImageApi,
+    resource_updates: &mut Vec<ResourceUpdate>)
 {
     match background {
         Background::RadialGradient(gradient) => {
@@ -811,8 +2146,7 @@ fn push_background(
                     offset: gradient_pre.offset.unwrap(),
                     color: gradient_pre.color,
                 }).collect();
-            let center = bounds.bottom_left(); // TODO - expose in CSS
-            let radius = TypedSize2D::new(40.0, 40.0); // TODO - expose in CSS
+            let (center, radius) = gradient.to_center_and_radius(&bounds);
             let gradient = builder.create_radial_gradient(center, radius, stops, gradient.extend_mode);
             builder.push_radial_gradient(&info, gradient, bounds.size, LayoutSize::zero());
         },
@@ -826,39 +2160,175 @@ fn push_background(
             let gradient = builder.create_gradient(begin_pt, end_pt, stops, gradient.extend_mode);
             builder.push_gradient(&info, gradient, bounds.size, LayoutSize::zero());
         },
-        Background::Image(css_image_id) => {
-            if let Some(image_id) = app_resources.css_ids_to_image_ids.get(&css_image_id.0) {
-                push_image(info, builder, bounds, app_resources, image_id);
+        Background::Image(css_image_id) | Background::ImageWithFallback(css_image_id, _) => {
+            // The image was never added or was already deleted
+            let image_id = app_resources.css_ids_to_image_ids.get(&css_image_id.0).cloned()
+                .filter(|image_id| app_resources.images.contains_key(image_id) || app_resources.image_regions.contains_key(image_id));
+            match (image_id, background) {
+                (Some(image_id), _) => {
+                    let background_repeat = style.background_repeat.unwrap_or_default();
+                    push_image(info, builder, bounds, app_resources, &image_id, style.background_size, background_repeat, current_epoch, render_api, resource_updates);
+                },
+                (None, Background::ImageWithFallback(_, fallback_color)) => push_rect(info, builder, &BackgroundColor(*fallback_color)),
+                (None, _) => { },
             }
         },
         Background::NoBackground => { },
     }
 }
 
+/// Pushes an OpenGL texture as an external image.
+///
+/// If the same texture (same OpenGL ID) was already used by another node in this frame,
+/// its `ImageKey` is reused. Otherwise `add_texture` is called to generate a new key
+/// and to register the texture, and the texture is added to the `resource_updates`.
+fn push_opengl_texture<F>(
+    info: &PrimitiveInfo<LayoutPixel>,
+    builder: &mut DisplayListBuilder,
+    bounds: &TypedRect<f32, LayoutPixel>,
+    texture_id: u32,
+    descriptor: ImageDescriptor,
+    gl_texture_keys: &mut FastHashMap<u32, ImageKey>,
+    resource_updates: &mut Vec<ResourceUpdate>,
+    add_texture: F)
+where F: FnOnce() -> (ImageKey, ImageData)
+{
+    use std::collections::hash_map::Entry::*;
+
+    let key = match gl_texture_keys.entry(texture_id) {
+        Occupied(existing) => *existing.get(),
+        Vacant(vacant) => {
+            let (key, data) = add_texture();
+            resource_updates.push(ResourceUpdate::AddImage(
+                AddImage { key, descriptor, data, tiling: None }
+            ));
+            *vacant.insert(key)
+        },
+    };
+
+    builder.push_image(
+        &info,
+        bounds.size,
+        LayoutSize::zero(),
+        ImageRendering::Auto,
+        AlphaType::Alpha,
+        key);
+}
+
 fn push_image(
     info: &PrimitiveInfo<LayoutPixel>,
     builder: &mut DisplayListBuilder,
     bounds: &TypedRect<f32, LayoutPixel>,
-    app_resources: &AppResources,
-    image_id: &ImageId)
+    app_resources: &mut AppResources,
+    image_id: &ImageId,
+    background_size: Option<BackgroundSize>,
+    background_repeat: BackgroundRepeat,
+    current_epoch: Epoch,
+    render_api: &dyn FontImageApi,
+    resource_updates: &mut Vec<ResourceUpdate>)
 {
-    if let Some(image_info) = app_resources.images.get(image_id) {
-        use images::ImageState::*;
-        match image_info {
-            Uploaded(image_info) => {
-                builder.push_image(
-                        &info,
-                        bounds.size,
-                        LayoutSize::zero(),
-                        ImageRendering::Auto,
-                        AlphaType::Alpha,
-                        image_info.key);
+    use images::{ImageInfo, ImageState::*};
+
+    // Image regions (i.e. the icons of a sprite sheet) draw a part of their source image
+    let (image_id, region) = match app_resources.image_regions.get(image_id) {
+        Some(&(source_image_id, region)) => (source_image_id, Some(region)),
+        None => (*image_id, None),
+    };
+
+    let image = match app_resources.images.get_mut(&image_id) {
+        Some(image_state) => {
+            // Re-upload the image if it was evicted from the texture cache
+            let reuploaded = match *image_state {
+                Evicted((ref data, ref descriptor)) => {
+                    let descriptor = *descriptor;
+                    let key = render_api.new_image_key();
+                    resource_updates.push(ResourceUpdate::AddImage(
+                        AddImage { key, descriptor, data: data.clone(), tiling: None }
+                    ));
+                    Some(Uploaded(ImageInfo { key, descriptor, data: data.clone() }))
+                },
+                _ => None,
+            };
+
+            if let Some(reuploaded) = reuploaded {
+                *image_state = reuploaded;
+            }
+
+            match *image_state {
+                Uploaded(ref image_info) => Some((image_info.key, image_info.descriptor.size)),
+                Decoding(_) => {
+                    // The image is still being decoded on a background thread
+                    push_rect(info, builder, &IMAGE_PLACEHOLDER_COLOR);
+                    None
+                },
+                _ => None,
+            }
+        },
+        None => None,
+    };
+
+    if let Some((image_key, image_size)) = image {
+        app_resources.texture_cache.mark_used(image_id, current_epoch);
+
+        // webrender can't draw a part of an image, so the whole image is scaled and
+        // moved so that the region covers the bounds - everything else is clipped
+        let (image_rect, tile_size) = match region {
+            Some(region) => {
+                let image_rect = get_image_region_rect(bounds, (image_size.width, image_size.height), &region);
+                (image_rect, image_rect.size)
             },
-            _ => { },
-        }
+            None => {
+                // Without a `background-size`, the image is stretched to the bounds
+                let intrinsic_size = LayoutSize::new(image_size.width as f32, image_size.height as f32);
+                let tile_size = background_size.map(|s| s.to_size(bounds.size, intrinsic_size)).unwrap_or(bounds.size);
+                (get_background_image_rect(bounds, tile_size, background_repeat), tile_size)
+            },
+        };
+
+        let image_info = LayoutPrimitiveInfo {
+            rect: image_rect,
+            clip_rect: info.clip_rect.intersection(bounds).unwrap_or(*bounds),
+            .. *info
+        };
+
+        builder.push_image(
+                &image_info,
+                tile_size,
+                LayoutSize::zero(),
+                ImageRendering::Auto,
+                AlphaType::Alpha,
+                image_key);
     }
 }
 
+/// Returns the area that is filled with image tiles of the size `tile_size`, starting
+/// at the top left corner of the `bounds` - tiles outside of the bounds are clipped
+fn get_background_image_rect(bounds: &TypedRect<f32, LayoutPixel>, tile_size: LayoutSize, repeat: BackgroundRepeat)
+-> TypedRect<f32, LayoutPixel>
+{
+    use css_parser::BackgroundRepeat::*;
+    let size = match repeat {
+        Repeat => bounds.size,
+        NoRepeat => tile_size,
+        RepeatX => LayoutSize::new(bounds.size.width, tile_size.height),
+        RepeatY => LayoutSize::new(tile_size.width, bounds.size.height),
+    };
+    LayoutRect::new(bounds.origin, size)
+}
+
+/// Returns where the whole image (of the size `image_size`) has to be drawn so that
+/// the `region` of the image exactly covers the `bounds`
+fn get_image_region_rect(bounds: &TypedRect<f32, LayoutPixel>, image_size: (u32, u32), region: &ImageRegion)
+-> TypedRect<f32, LayoutPixel>
+{
+    let scale_x = bounds.size.width / (region.width.max(1) as f32);
+    let scale_y = bounds.size.height / (region.height.max(1) as f32);
+
+    LayoutRect::new(
+        LayoutPoint::new(bounds.origin.x - region.x as f32 * scale_x, bounds.origin.y - region.y as f32 * scale_y),
+        LayoutSize::new(image_size.0 as f32 * scale_x, image_size.1 as f32 * scale_y))
+}
+
 #[inline]
 fn push_border(
     info: &PrimitiveInfo<LayoutPixel>,
@@ -881,7 +2351,7 @@ fn push_font(
     font_size_app_units: Au,
     resource_updates: &mut Vec<ResourceUpdate>,
     app_resources: &mut AppResources,
-    render_api: &RenderApi)
+    render_api: &dyn FontImageApi)
 -> Option<FontInstanceKey>
 {
     use font::FontState;
@@ -891,6 +2361,8 @@ fn push_font(
         return None;
     }
 
+    let text_rendering = app_resources.text_rendering;
+
     let &(ref font, _, ref font_state) = match app_resources.font_data.get(font_id) {
         Some(f) => f,
         None => return None,
@@ -902,16 +2374,9 @@ fn push_font(
                                      .or_insert(FastHashMap::default());
             let font_instance_key = font_sizes_hashmap.entry(font_size_app_units)
                 .or_insert_with(|| {
-                    let f_instance_key = render_api.generate_font_instance_key();
+                    let f_instance_key = render_api.new_font_instance_key();
                     resource_updates.push(ResourceUpdate::AddFontInstance(
-                        AddFontInstance {
-                            key: f_instance_key,
-                            font_key: font_key,
-                            glyph_size: font_size_app_units,
-                            options: None,
-                            platform_options: None,
-                            variations: Vec::new(),
-                        }
+                        create_font_instance(f_instance_key, font_key, font_size_app_units, text_rendering)
                     ));
                     f_instance_key
                 }
@@ -926,20 +2391,68 @@ fn push_font(
     }
 }
 
+/// Converts the font size (in pixels) to the size of the font instance. If `quantization`
+/// is set, the size is rounded to the nearest multiple of it, so that slightly different
+/// sizes (i.e. during a `font-size` transition) share one font instance
+fn font_size_to_app_units(font_size_px: f32, quantization: Option<f32>) -> Au {
+    let font_size_px = match quantization {
+        Some(step) if step > 0.0 => (font_size_px / step).round() * step,
+        _ => font_size_px,
+    };
+    Au::from_f32_px(font_size_px)
+}
+
+/// Creates a new font instance, with the gamma / contrast of the
+/// `text_rendering` options (if any) applied to the instance
+fn create_font_instance(
+    key: FontInstanceKey,
+    font_key: FontKey,
+    glyph_size: Au,
+    text_rendering: Option<TextRenderingOptions>)
+-> AddFontInstance
+{
+    AddFontInstance {
+        key: key,
+        font_key: font_key,
+        glyph_size: glyph_size,
+        options: None,
+        platform_options: text_rendering.and_then(|options| options.into_platform_options()),
+        variations: Vec::new(),
+    }
+}
+
 /// Populate and parse the CSS style properties
-fn populate_css_properties(rect: &mut DisplayRectangle, css_overrides: &FastHashMap<String, ParsedCssProperty>)
+///
+/// Dynamic properties are resolved by walking up from `node_id`: the nearest node that
+/// provides an override scope for the dynamic ID wins, `css_overrides` (the global
+/// overrides of the `Css`) is only used if no node provides the ID.
+///
+/// `parent` is the already populated style and layout of the parent node, which
+/// properties with the value `inherit` are copied from (`None` for the root node).
+pub(crate) fn populate_css_properties<T: Layout>(
+    rect: &mut DisplayRectangle,
+    node_id: NodeId,
+    arena: &Arena<NodeData<T>>,
+    css_overrides: &FastHashMap<String, ParsedCssProperty>,
+    safe_area_insets: &SafeAreaInsets,
+    parent: Option<(&RectStyle, &RectLayout)>)
 {
     use css_parser::ParsedCssProperty::{self, *};
 
-    fn apply_parsed_css_property(rect: &mut DisplayRectangle, property: &ParsedCssProperty) {
+    fn apply_parsed_css_property(rect: &mut DisplayRectangle, property: &ParsedCssProperty, safe_area_insets: &SafeAreaInsets) {
         match property {
             BorderRadius(b)             => { rect.style.border_radius = Some(*b);                   },
             BackgroundColor(c)          => { rect.style.background_color = Some(*c);                },
             TextColor(t)                => { rect.style.font_color = Some(*t);                      },
             Border(widths, details)     => { rect.style.border = Some((*widths, *details));         },
+            BorderSide(side)            => { rect.style.border = Some(side.apply_to(rect.style.border)); },
             Background(b)               => { rect.style.background = Some(b.clone());               },
+            BackgroundSize(s)           => { rect.style.background_size = Some(*s);                 },
+            BackgroundRepeat(r)         => { rect.style.background_repeat = Some(*r);               },
             FontSize(f)                 => { rect.style.font_size = Some(*f);                       },
             FontFamily(f)               => { rect.style.font_family = Some(f.clone());              },
+            FontWeight(w)               => { rect.style.font_weight = Some(*w);                     },
+            FontStyle(s)                => { rect.style.font_style = Some(*s);                      },
             Overflow(o)                 => {
                 if let Some(ref mut existing_overflow) = rect.style.overflow {
                     existing_overflow.merge(o);
@@ -948,8 +2461,27 @@ fn populate_css_properties(rect: &mut DisplayRectangle, css_overrides: &FastHash
                 }
             },
             TextAlign(ta)               => { rect.style.text_align = Some(*ta);                     },
+            TextOverflow(t)             => { rect.style.text_overflow = Some(*t);                   },
+            ContentVisibility(c)        => { rect.style.content_visibility = Some(*c);              },
+            BackdropFilter(f)           => { rect.style.backdrop_filter = Some(*f);                 },
+            Filter(f)                   => { rect.style.filter = Some(*f);                          },
+            Transition(t)               => { rect.style.transition = Some(t.clone());               },
+            OverflowWrap(o)             => { rect.style.overflow_wrap = Some(*o);                   },
+            WordBreak(w)                => { rect.style.word_break = Some(*w);                      },
+            TextDecoration(t)           => { rect.style.text_decoration = Some(*t);                 },
+            Opacity(o)                  => { rect.style.opacity = Some(*o);                         },
+            Visibility(v)               => { rect.style.visibility = Some(*v);                      },
+            Direction(d)                => { rect.style.direction = Some(*d);                       },
+            WritingMode(w)              => { rect.style.writing_mode = Some(*w);                    },
+            UserSelect(u)               => { rect.style.user_select = Some(*u);                     },
+            OverscrollBehavior(o)       => { rect.style.overscroll_behavior = Some(*o);             },
+            Resize(r)                   => { rect.style.resize = Some(*r);                          },
+            TintColor(t)                => { rect.style.tint_color = Some(*t);                      },
+            CaretColor(c)               => { rect.style.caret_color = Some(*c);                     },
             BoxShadow(opt_box_shadow)   => { rect.style.box_shadow = *opt_box_shadow;               },
+            TextShadow(opt_text_shadow) => { rect.style.text_shadow = *opt_text_shadow;             },
             LineHeight(lh)              => { rect.style.line_height = Some(*lh);                     },
+            TextIndent(ti)              => { rect.style.text_indent = Some(*ti);                     },
 
             Width(w)                    => { rect.layout.width = Some(*w);                          },
             Height(h)                   => { rect.layout.height = Some(*h);                         },
@@ -957,6 +2489,17 @@ fn populate_css_properties(rect: &mut DisplayRectangle, css_overrides: &FastHash
             MinHeight(mh)               => { rect.layout.min_height = Some(*mh);                    },
             MaxWidth(mw)                => { rect.layout.max_width = Some(*mw);                     },
             MaxHeight(mh)               => { rect.layout.max_height = Some(*mh);                    },
+            AspectRatio(a)              => { rect.layout.aspect_ratio = Some(*a);                   },
+            Padding(p)                  => { rect.layout.padding = Some(p.resolve(safe_area_insets)); },
+            PaddingSide(p)              => { rect.layout.padding = Some(p.apply_to(rect.layout.padding, safe_area_insets)); },
+            Margin(m)                   => { rect.layout.margin = Some(m.resolve(safe_area_insets)); },
+            MarginSide(m)               => { rect.layout.margin = Some(m.apply_to(rect.layout.margin, safe_area_insets)); },
+            Gap(g)                      => { rect.layout.gap = Some(*g);                            },
+            Display(d)                  => { rect.layout.display = Some(*d);                        },
+            ConstraintStrength(s)       => { rect.layout.constraint_strength = Some(*s);            },
+            Contain(c)                  => { rect.layout.contain = Some(*c);                        },
+            BoxSizing(b)                => { rect.layout.box_sizing = Some(*b);                     },
+            Appearance(a)               => { rect.style.appearance = Some(*a);                      },
 
             FlexWrap(w)                 => { rect.layout.wrap = Some(*w);                           },
             FlexDirection(d)            => { rect.layout.direction = Some(*d);                      },
@@ -975,26 +2518,37 @@ fn populate_css_properties(rect: &mut DisplayRectangle, css_overrides: &FastHash
     for constraint in &rect.styled_node.css_constraints.list {
         use css::CssDeclaration::*;
         match constraint {
-            Static(static_property) => apply_parsed_css_property(rect, static_property),
+            Static(static_property) => apply_parsed_css_property(rect, static_property, safe_area_insets),
             Dynamic(dynamic_property) => {
-                let calculated_property = css_overrides.get(&dynamic_property.dynamic_id);
+                let calculated_property = node_id.ancestors(arena)
+                    .filter_map(|ancestor| arena[ancestor].data.dynamic_css_overrides.get(&dynamic_property.dynamic_id))
+                    .next()
+                    .or_else(|| css_overrides.get(&dynamic_property.dynamic_id));
                 if let Some(overridden_property) = calculated_property {
                     assert!(property_type_matches(overridden_property, &dynamic_property.default),
                             "css values don't have the same discriminant type");
-                    apply_parsed_css_property(rect, overridden_property);
+                    apply_parsed_css_property(rect, overridden_property, safe_area_insets);
                 } else {
-                    apply_parsed_css_property(rect, &dynamic_property.default);
+                    apply_parsed_css_property(rect, &dynamic_property.default, safe_area_insets);
                 }
-            }
+            },
+            Keyword(key, keyword) => {
+                apply_css_wide_keyword(key, *keyword, &mut rect.style, &mut rect.layout, parent);
+            },
         }
     }
 }
 
-// Returns the constraints for one rectangle
-fn create_layout_constraints<'a>(
+/// Returns the constraints for the size of one rectangle, created from its `width`, `height`,
+/// `min-*` and `max-*` properties. Percentages are relative to the solved size of the parent
+/// (`parent_size`), or to the size of the window for the root.
+///
+/// A rectangle without a `width` / `height` fills its parent (or the window), but yields to
+/// any other size constraint. The `min-*` and `max-*` constraints are stronger than any size,
+/// but they are not required, so that conflicting values can't make the layout unsolvable.
+fn create_layout_constraints(
     rect: &DisplayRectangle,
-    rect_id: NodeId,
-    arena: &Arena<DisplayRectangle<'a>>,
+    parent_size: Option<LayoutSize>,
     window_size: &WindowSize)
 -> Vec<CssConstraint>
 {
@@ -1002,16 +2556,173 @@ fn create_layout_constraints<'a>(
     use constraints::{SizeConstraint, Strength};
 
     let mut layout_constraints = Vec::<CssConstraint>::new();
-    /*
-    let max_width = arena.get_wh_for_rectangle(rect_id, WidthOrHeight::Width)
-                         .unwrap_or(window_size.width as f32);
-    */
-    layout_constraints.push(CssConstraint::Size((SizeConstraint::Width(200.0), Strength(STRONG))));
-    layout_constraints.push(CssConstraint::Size((SizeConstraint::Height(200.0), Strength(STRONG))));
+
+    let containing_size = parent_size.unwrap_or_else(||
+        LayoutSize::new(window_size.dimensions.width as f32, window_size.dimensions.height as f32));
+    // Without a width / height, the rectangle fills the containing box, except for its margin
+    let margin = rect.layout.margin.unwrap_or_default();
+    let fill_size = LayoutSize::new(
+        (containing_size.width - margin.left - margin.right).max(0.0),
+        (containing_size.height - margin.top - margin.bottom).max(0.0));
+    let width = rect.layout.width.map(|w| w.0.to_pixels_relative_to(containing_size.width));
+    let height = rect.layout.height.map(|h| h.0.to_pixels_relative_to(containing_size.height));
+    let size_strength = Strength(rect.layout.constraint_strength.unwrap_or_default().to_cassowary_strength());
+    let fill_strength = Strength(MEDIUM);
+
+    match (width, height, rect.layout.aspect_ratio) {
+        // aspect-ratio: if only one dimension is set, the other one is derived from the ratio
+        (Some(width), None, Some(aspect_ratio)) => {
+            layout_constraints.push(CssConstraint::Size((SizeConstraint::Width(width), size_strength)));
+            layout_constraints.push(CssConstraint::Size((SizeConstraint::AspectRatio(1.0 / aspect_ratio.0), Strength(REQUIRED))));
+        },
+        (None, Some(height), Some(aspect_ratio)) => {
+            layout_constraints.push(CssConstraint::Size((SizeConstraint::Height(height), size_strength)));
+            layout_constraints.push(CssConstraint::Size((SizeConstraint::AspectRatio(1.0 / aspect_ratio.0), Strength(REQUIRED))));
+        },
+        (None, None, Some(aspect_ratio)) => {
+            layout_constraints.push(CssConstraint::Size((SizeConstraint::Width(fill_size.width), fill_strength)));
+            layout_constraints.push(CssConstraint::Size((SizeConstraint::AspectRatio(1.0 / aspect_ratio.0), Strength(REQUIRED))));
+        },
+        (width, height, _) => {
+            layout_constraints.push(CssConstraint::Size(match width {
+                Some(width) => (SizeConstraint::Width(width), size_strength),
+                None => (SizeConstraint::Width(fill_size.width), fill_strength),
+            }));
+            layout_constraints.push(CssConstraint::Size(match height {
+                Some(height) => (SizeConstraint::Height(height), size_strength),
+                None => (SizeConstraint::Height(fill_size.height), fill_strength),
+            }));
+        },
+    }
+
+    // If the minimum and the maximum conflict, the minimum wins
+    let min_strength = Strength(create(999.0, 0.0, 0.0, 1.0));
+    let max_strength = Strength(create(998.0, 0.0, 0.0, 1.0));
+
+    if let Some(min_width) = rect.layout.min_width {
+        layout_constraints.push(CssConstraint::Size((SizeConstraint::MinWidth(min_width.0.to_pixels_relative_to(containing_size.width)), min_strength)));
+    }
+    if let Some(max_width) = rect.layout.max_width {
+        layout_constraints.push(CssConstraint::Size((SizeConstraint::MaxWidth(max_width.0.to_pixels_relative_to(containing_size.width)), max_strength)));
+    }
+    if let Some(min_height) = rect.layout.min_height {
+        layout_constraints.push(CssConstraint::Size((SizeConstraint::MinHeight(min_height.0.to_pixels_relative_to(containing_size.height)), min_strength)));
+    }
+    if let Some(max_height) = rect.layout.max_height {
+        layout_constraints.push(CssConstraint::Size((SizeConstraint::MaxHeight(max_height.0.to_pixels_relative_to(containing_size.height)), max_strength)));
+    }
 
     layout_constraints
 }
 
+/// Returns the constraints for the position of a rectangle: the top left corner of the
+/// rectangle is inset into the content box of the `parent` (the `parent_padding` plus the
+/// `margin` of the rectangle). The root rectangle is inset into the window by its margin.
+///
+/// The constraints are only `STRONG`, so that `justify-content: center` can override them.
+fn create_position_constraints(rect: &DisplayRectangle, parent: Option<DisplayRect>, parent_padding: LayoutPadding)
+-> Vec<CssConstraint>
+{
+    use cassowary::strength::STRONG;
+    use constraints::{SizeConstraint, PaddingConstraint, Strength, Padding, Point};
+
+    let margin = rect.layout.margin.unwrap_or_default();
+
+    match parent {
+        Some(parent) => vec![
+            CssConstraint::Padding((PaddingConstraint::AlignLeft(parent.left), Strength(STRONG), Padding(parent_padding.left + margin.left))),
+            CssConstraint::Padding((PaddingConstraint::AlignTop(parent.top), Strength(STRONG), Padding(parent_padding.top + margin.top))),
+        ],
+        None => vec![
+            CssConstraint::Size((SizeConstraint::TopLeft(Point::new(margin.left, margin.top)), Strength(STRONG))),
+        ],
+    }
+}
+
+/// The layout properties that the constraints of a node are created from: the layout of the node
+/// and the layout of its parent, since the padding and the size of the parent are part of the
+/// constraints of the node
+pub(crate) type LayoutInputs = (RectLayout, Option<RectLayout>);
+
+fn get_layout_inputs<'a>(node_id: NodeId, arena: &Arena<DisplayRectangle<'a>>) -> LayoutInputs {
+    (arena[node_id].data.layout, arena[node_id].parent().map(|parent| arena[parent].data.layout))
+}
+
+/// Returns the nodes whose constraints have to be re-created, because their layout inputs
+/// changed since the last re-layout (or because they are new). A changed node invalidates the
+/// whole subtree of its closest ancestor with a `contain` property (or the whole tree if there is
+/// no such ancestor), since the sizes and positions of its parent and siblings depend on it.
+/// The layout outside of a contained subtree doesn't depend on its children, so the rest of
+/// the tree keeps its constraints.
+fn get_relayout_nodes<'a>(
+    root: NodeId,
+    arena: &Arena<DisplayRectangle<'a>>,
+    node_constraints: &FastHashMap<NodeId, (LayoutInputs, Vec<Constraint>)>)
+-> BTreeSet<NodeId>
+{
+    let mut relayout_roots = BTreeSet::new();
+
+    for node_id in root.descendants(arena) {
+        let layout_inputs = get_layout_inputs(node_id, arena);
+        if node_constraints.get(&node_id).map(|(previous_inputs, _)| previous_inputs) == Some(&layout_inputs) {
+            continue;
+        }
+        // Skip the node itself: the size of a contained node still affects its parent
+        let layout_boundary = node_id.ancestors(arena).skip(1)
+            .find(|ancestor| arena[*ancestor].data.layout.contain.unwrap_or_default().is_layout_boundary())
+            .unwrap_or(root);
+        relayout_roots.insert(layout_boundary);
+    }
+
+    relayout_roots.into_iter().flat_map(|relayout_root| relayout_root.descendants(arena)).collect()
+}
+
+/// Returns the constraints for `justify-content: center`: the `children` of the `container`
+/// are packed along the main axis (separated by the `gap`), and the leftover space of the
+/// container is split equally into the space before the first and after the last child.
+fn create_centering_constraints(container: &DisplayRect, children: &[DisplayRect], direction: LayoutDirection, gap: LayoutGap)
+-> Vec<Constraint>
+{
+    use cassowary::{Variable, Expression, WeightedRelation::EQ, strength::REQUIRED};
+
+    // Start, end and size of a rectangle along the main axis
+    let main_axis = |rect: &DisplayRect| -> (Variable, Variable, Variable) {
+        match direction {
+            LayoutDirection::Horizontal => (rect.left, rect.right, rect.width),
+            LayoutDirection::Vertical => (rect.top, rect.bottom, rect.height),
+        }
+    };
+
+    let (container_start, container_end, container_size) = main_axis(container);
+
+    // Percentage gaps are relative to the (not yet solved) size of the container
+    let gap = match gap {
+        LayoutGap::Percentage(p) => Expression::from(container_size * p.number as f64),
+        other => Expression::from_constant(other.to_pixels(None) as f64),
+    };
+
+    let mut constraints = vec![container_end | EQ(REQUIRED) | container_start + container_size];
+
+    for child in children {
+        let (start, end, size) = main_axis(child);
+        constraints.push(end | EQ(REQUIRED) | start + size);
+    }
+
+    for siblings in children.windows(2) {
+        let (_, previous_end, _) = main_axis(&siblings[0]);
+        let (next_start, _, _) = main_axis(&siblings[1]);
+        constraints.push(next_start - previous_end | EQ(REQUIRED) | gap.clone());
+    }
+
+    if let (Some(first), Some(last)) = (children.first(), children.last()) {
+        let (first_start, _, _) = main_axis(first);
+        let (_, last_end, _) = main_axis(last);
+        constraints.push(first_start - container_start | EQ(REQUIRED) | container_end - last_end);
+    }
+
+    constraints
+}
+
 fn css_constraints_to_cassowary_constraints(rect: &DisplayRect, css: &Vec<CssConstraint>)
 -> Vec<Constraint>
 {
@@ -1029,97 +2740,1985 @@ fn css_constraints_to_cassowary_constraints(rect: &DisplayRect, css: &Vec<CssCon
     ).collect()
 }
 
-// Layout / tracing-related functions
+/// Returns the size of the root rectangle of the solved layout, including its margin.
+///
+/// If the root rectangle has no `width` / `height`, it is as large as its content in that
+/// direction (instead of filling the window): the union of the margin boxes of its children,
+/// plus its padding.
+pub(crate) fn measure_content_size<'a, T: Layout>(display_list: &DisplayList<'a, T>, ui_solver: &UiSolver<T>)
+-> LayoutSize
+{
+    let root = match display_list.ui_descr.ui_descr_root {
+        Some(root) => root,
+        None => return LayoutSize::zero(),
+    };
+    let root_bounds = match ui_solver.query_bounds_of_rect(root) {
+        Some(bounds) => bounds,
+        None => return LayoutSize::zero(),
+    };
+
+    let arena = &display_list.rectangles;
+    let get_margin_box = |node_id: NodeId, bounds: LayoutRect| {
+        let margin = arena[node_id].data.layout.margin.unwrap_or_default();
+        LayoutRect::new(
+            LayoutPoint::new(bounds.origin.x - margin.left, bounds.origin.y - margin.top),
+            LayoutSize::new(bounds.size.width + margin.left + margin.right, bounds.size.height + margin.top + margin.bottom))
+    };
 
-// What constraint (width or height) to search for when looking for a fitting width / height constraint
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-enum WidthOrHeight {
-    Width,
-    Height,
+    let content_rect = root.children(arena)
+        .filter(|child| !is_display_none(*child, arena))
+        .filter_map(|child| ui_solver.query_bounds_of_rect(child).map(|bounds| get_margin_box(child, bounds)))
+        .fold(None, |content_rect: Option<LayoutRect>, margin_box| Some(match content_rect {
+            Some(content_rect) => content_rect.union(&margin_box),
+            None => margin_box,
+        }));
+
+    let root_layout = &arena[root].data.layout;
+    let padding = root_layout.padding.unwrap_or_default();
+    let content_size = content_rect.map(|content_rect| content_rect.size).unwrap_or(LayoutSize::zero());
+    let size = LayoutSize::new(
+        if root_layout.width.is_some() { root_bounds.size.width } else { content_size.width + padding.left + padding.right },
+        if root_layout.height.is_some() { root_bounds.size.height } else { content_size.height + padding.top + padding.bottom });
+
+    get_margin_box(root, LayoutRect::new(root_bounds.origin, size)).size
 }
 
-impl<'a> Arena<DisplayRectangle<'a>> {
+// Empty test, for some reason codecov doesn't detect any files (and therefore
+// doesn't report codecov % correctly) except if they have at least one test in
+// the file. This is an empty test, which should be updated later on
+#[test]
+fn __codecov_test_display_list_file() {
 
-    /// Recursive algorithm for getting the dimensions of a rectangle
-    ///
-    /// This function can be used on any rectangle to get the maximum allowed width
-    /// (for inserting the width / height constraint into the layout solver).
-    /// It simply traverses upwards through the nodes, until it finds a matching min-width / width
-    /// constraint, returns None, if the root node is reached (with no constraints)
-    ///
-    /// Usually, you'd use it like:
-    ///
-    /// ```no_run,ignore
-    /// let max_width = arena.get_wh_for_rectangle(id, WidthOrHeight::Width)
-    ///                      .unwrap_or(window_dimensions.width);
-    /// ```
-    fn get_wh_for_rectangle(&self, id: NodeId, field: WidthOrHeight) -> Option<f32> {
-
-        use self::WidthOrHeight::*;
-
-        let node = &self[id];
-
-        macro_rules! get_wh {
-            ($field_name:ident, $min_field:ident) => ({
-                let mut $field_name: Option<f32> = None;
-
-                match node.data.layout.$min_field {
-                    Some(m_w) => {
-                        let m_w_px = m_w.0.to_pixels();
-                        match node.data.layout.$field_name {
-                            Some(w) => {
-                                // width + min_width
-                                let w_px = w.0.to_pixels();
-                                $field_name = Some(m_w_px.max(w_px));
-                            },
-                            None => {
-                                // min_width
-                                $field_name = Some(m_w_px);
-                            }
-                        }
-                    },
-                    None => {
-                        match node.data.layout.$field_name {
-                            Some(w) => {
-                                // width
-                                let w_px = w.0.to_pixels();
-                                $field_name = Some(w_px);
-                            },
-                            None => {
-                                // neither width nor min_width
-                            }
-                        }
-                    }
-                };
+}
 
-                if $field_name.is_none() {
-                    match node.parent() {
-                        Some(p) => $field_name = self.get_wh_for_rectangle(p, field),
-                        None => { },
-                    }
+#[test]
+fn test_text_fade_overlay() {
+    use text_layout::TextOverflow;
+    use euclid::TypedPoint2D;
+
+    let bounds = TypedRect::new(TypedPoint2D::new(0.0, 0.0), TypedSize2D::new(100.0, 100.0));
+    let overflowing_vertically = TextOverflowPass2 {
+        horizontal: TextOverflow::InBounds(10.0),
+        vertical: TextOverflow::IsOverflowing(50.0),
+    };
+
+    // text-overflow: clip (default) - no fade overlay
+    let mut style = RectStyle::default();
+    assert!(get_text_fade_rects(&style, &bounds, &overflowing_vertically).is_empty());
+
+    // text-overflow: fade - fade overlay at the bottom edge
+    style.text_overflow = Some(TextOverflowMode::Fade);
+    let fade_rects = get_text_fade_rects(&style, &bounds, &overflowing_vertically);
+    assert_eq!(fade_rects.len(), 1);
+    assert_eq!(fade_rects[0].0, TypedRect::new(TypedPoint2D::new(0.0, 80.0), TypedSize2D::new(100.0, 20.0)));
+
+    // text-overflow: fade, but the text fits - no fade overlay
+    let in_bounds = TextOverflowPass2 {
+        horizontal: TextOverflow::InBounds(10.0),
+        vertical: TextOverflow::InBounds(10.0),
+    };
+    assert!(get_text_fade_rects(&style, &bounds, &in_bounds).is_empty());
+}
+
+#[test]
+fn test_shared_opengl_texture_is_only_added_once() {
+    use euclid::TypedPoint2D;
+
+    let bounds = TypedRect::new(TypedPoint2D::new(0.0, 0.0), TypedSize2D::new(100.0, 100.0));
+    let info = LayoutPrimitiveInfo::new(bounds);
+    let mut builder = DisplayListBuilder::new(PipelineId(0, 0), bounds.size);
+    let descriptor = ImageDescriptor::new(100, 100, ImageFormat::BGRA8, true, true);
+    let mut gl_texture_keys = FastHashMap::default();
+    let mut resource_updates = Vec::new();
+
+    let texture_id = 5;
+    let mut next_key = 0;
+
+    // The same texture, used in two nodes
+    for _ in 0..2 {
+        push_opengl_texture(&info, &mut builder, &bounds, texture_id, descriptor, &mut gl_texture_keys, &mut resource_updates, || {
+            next_key += 1;
+            (ImageKey::new(IdNamespace(0), next_key), ImageData::new(vec![0; 100 * 100 * 4]))
+        });
+    }
+
+    assert_eq!(resource_updates.len(), 1);
+
+    let (_, _, display_list) = builder.finalize();
+    let mut pushed_image_keys = Vec::new();
+    let mut iter = display_list.iter();
+    while let Some(item) = iter.next() {
+        if let SpecificDisplayItem::Image(ref image) = *item.item() {
+            pushed_image_keys.push(image.image_key);
+        }
+    }
+
+    let shared_key = ImageKey::new(IdNamespace(0), 1);
+    assert_eq!(pushed_image_keys, vec![shared_key, shared_key]);
+}
+
+#[test]
+fn test_content_visibility_auto_skips_offscreen_nodes() {
+    use dom::Dom;
+    use display_list_capture::DisplayListOp;
+    use test_utils::{TestLayout, paint_dom, app_resources_with_test_font};
+
+    // A list of 100 items, each item is placed 40px below the previous one:
+    // only the first 15 items are inside of the window (800x600)
+    let mut list = Dom::<TestLayout>::div();
+    let mut css_source = String::from(".item { font-family: \"Webly Sleeky UI\"; font-size: 16px; width: 100px; height: 20px; }");
+    for i in 0..100 {
+        list.add_child(Dom::label(format!("Item {}", i)).with_class("item").with_id(format!("item-{}", i)));
+        css_source.push_str(&format!(" #item-{} {{ margin-top: {}px; }}", i, i * 40));
+    }
+
+    let count_texts = |css_source: &str| {
+        let mut css = Css::new_from_string(css_source).unwrap();
+        paint_dom(&list, &mut css, &mut app_resources_with_test_font()).ops.iter().filter(|op| match op {
+            DisplayListOp::Text { .. } => true,
+            _ => false,
+        }).count()
+    };
+
+    // Without content-visibility: auto, every item is rendered
+    assert_eq!(count_texts(&css_source), 100);
+
+    // The text of the items below the window isn't shaped or pushed
+    css_source.push_str(" .item { content-visibility: auto; }");
+    assert_eq!(count_texts(&css_source), 15);
+}
+
+#[test]
+fn test_backdrop_filter_blurs_the_content_behind_the_node() {
+    use dom::{Dom, On, Callback, UpdateScreen};
+    use app_state::AppState;
+    use window::WindowEvent;
+    use display_list_capture::DisplayListOp;
+    use test_utils::{TestLayout, paint_dom};
+
+    fn on_click(_: &mut AppState<TestLayout>, _: WindowEvent) -> UpdateScreen { UpdateScreen::Redraw }
+
+    const PHOTO: ColorU = ColorU { r: 255, g: 0, b: 0, a: 255 };
+    const GLASS: ColorU = ColorU { r: 255, g: 255, b: 255, a: 68 };
+    const CONTENT: ColorU = ColorU { r: 0, g: 0, b: 255, a: 255 };
+
+    let dom = Dom::<TestLayout>::div().with_id("photo").with_callback(On::MouseUp, Callback(on_click))
+        .with_child(Dom::div().with_id("glass")
+            .with_child(Dom::div().with_id("content")));
+
+    let mut css = Css::new_from_string("
+        #photo { background-color: #ff0000; }
+        #glass { backdrop-filter: blur(10px); background-color: #ffffff44; width: 200px; height: 100px; }
+        #content { background-color: #0000ff; width: 100px; height: 50px; }
+    ").unwrap();
+
+    let photo_tag = dom.arena.borrow()[dom.root].data.tag.unwrap();
+    let capture = paint_dom(&dom, &mut css, &mut AppResources::without_system_resources());
+    let ops = &capture.ops;
+    let is_blur = |op: &DisplayListOp| match op {
+        DisplayListOp::PushStackingContext { filters } => *filters == vec![FilterOp::Blur(10.0)],
+        _ => false,
+    };
+
+    // The colors of the painted rectangles and whether they were painted inside of the blur
+    let mut is_blurred = false;
+    let mut painted = Vec::new();
+    let mut photo_tags = Vec::new();
+    for (op, tag) in ops.iter().zip(capture.tags.iter()) {
+        match op {
+            DisplayListOp::PushStackingContext { .. } if is_blur(op) => is_blurred = true,
+            DisplayListOp::PopStackingContext => is_blurred = false,
+            DisplayListOp::Rect { color, .. } => {
+                painted.push((*color, is_blurred));
+                if *color == ColorF::from(PHOTO) {
+                    photo_tags.push(*tag);
                 }
+            },
+            _ => { },
+        }
+    }
+
+    // The photo behind the glass is painted a second time, blurred - the glass and its content stay sharp
+    assert_eq!(painted, vec![
+        (ColorF::from(PHOTO), false),
+        (ColorF::from(PHOTO), true),
+        (ColorF::from(GLASS), false),
+        (ColorF::from(CONTENT), false),
+    ]);
+
+    // Only the original photo can be hit, not the blurred copy
+    assert_eq!(photo_tags, vec![Some((photo_tag, 0)), None]);
+
+    // The blurred copy of the photo is clipped to the bounds of the glass
+    let glass_bounds = ops.iter().filter_map(|op| match op {
+        DisplayListOp::Rect { rect, color } if *color == ColorF::from(GLASS) => Some(*rect),
+        _ => None,
+    }).next().unwrap();
+    assert_eq!(glass_bounds.size, LayoutSize::new(200.0, 100.0));
+    let blur = ops.iter().position(|op| is_blur(op)).unwrap();
+    assert_eq!(ops[blur - 1], DisplayListOp::Clip { rect: glass_bounds });
+}
+
+#[test]
+fn test_font_instance_text_rendering_options() {
+
+    let key = FontInstanceKey(IdNamespace(0), 0);
+    let font_key = FontKey(IdNamespace(0), 0);
+    let glyph_size = Au::from_px(12);
+
+    // Default: webrender decides the gamma
+    let instance = create_font_instance(key, font_key, glyph_size, None);
+    assert_eq!(instance.platform_options, None);
+
+    let text_rendering = TextRenderingOptions { gamma: 220, contrast: 120 };
+    let instance = create_font_instance(key, font_key, glyph_size, Some(text_rendering));
+
+    // webrender only supports the gamma / contrast of font instances on Windows
+    #[cfg(target_os = "windows")] {
+        let platform_options = instance.platform_options.unwrap();
+        assert_eq!(platform_options.gamma, 220);
+        assert_eq!(platform_options.contrast, 120);
+    }
+
+    // On other platforms, the options are ignored
+    #[cfg(not(target_os = "windows"))] {
+        assert_eq!(instance.platform_options, None);
+    }
+}
+
+#[test]
+fn test_aspect_ratio_derives_height_from_width() {
+    use cassowary::Solver;
+    use ui_description::StyledNode;
+
+    let styled_node = StyledNode::default();
+    let mut rect = DisplayRectangle::new(None, &styled_node);
+    rect.layout.width = Some(LayoutWidth(PixelValue::from_metric(CssMetric::Px, 160.0)));
+    rect.layout.aspect_ratio = Some(LayoutAspectRatio(16.0 / 9.0));
+
+    let window_size = WindowSize::default();
+    let layout_constraints = create_layout_constraints(&rect, None, &window_size);
+
+    let display_rect = DisplayRect::default();
+    let mut solver = Solver::new();
+    solver.add_constraints(&css_constraints_to_cassowary_constraints(&display_rect, &layout_constraints)).unwrap();
+
+    assert!((solver.get_value(display_rect.width) - 160.0).abs() < 0.01);
+    assert!((solver.get_value(display_rect.height) - 90.0).abs() < 0.01);
+}
+
+#[test]
+fn test_build_display_list_with_fake_render_api() {
+    use std::collections::BTreeMap;
+    use dom::Dom;
+    use ui_state::UiState;
+    use images::{self, ImageState};
+    use render_api::FakeRenderApi;
+    use test_utils::TestLayout;
+
+    let mut app_resources = AppResources::without_system_resources();
+    let image_id = images::new_image_id();
+    let image_data = ImageData::new(vec![255; 16]);
+    let image_descriptor = ImageDescriptor::new(2, 2, ImageFormat::BGRA8, true, false);
+    app_resources.images.insert(image_id, ImageState::ReadyForUpload((image_data, image_descriptor)));
+
+    let ui_state = UiState {
+        dom: Dom::<TestLayout>::div().with_child(Dom::new(NodeType::Image(image_id))),
+        callback_list: BTreeMap::new(),
+        node_ids_to_callbacks_list: BTreeMap::new(),
+    };
+
+    let mut css = Css::empty();
+    let ui_description = UiDescription::from_ui_state(&ui_state, &css);
+    let display_list = DisplayList::new_from_ui_description(&ui_description);
+
+    let mut ui_solver = UiSolver::new();
+
+    let render_api = FakeRenderApi::default();
+    let builder = display_list.into_display_list_builder(
+        PipelineId(0, 0),
+        Epoch(0),
+        &mut ui_solver,
+        &mut css,
+        &mut app_resources,
+        &render_api,
+        true,
+        &WindowSize::default());
+
+    assert!(builder.is_some());
+
+    // The image is uploaded with the key generated by the fake API
+    let resource_updates = render_api.resource_updates.borrow();
+    assert_eq!(resource_updates.len(), 1);
+    match resource_updates[0] {
+        ResourceUpdate::AddImage(ref add_image) => assert_eq!(add_image.key, ImageKey::new(IdNamespace(0), 0)),
+        _ => panic!("expected ResourceUpdate::AddImage"),
+    }
+
+    match app_resources.images[&image_id] {
+        ImageState::Uploaded(ref image_info) => assert_eq!(image_info.key, ImageKey::new(IdNamespace(0), 0)),
+        _ => panic!("image was not uploaded"),
+    }
+}
+
+#[test]
+fn test_dirty_rect_of_single_node_color_change() {
+    use dom::Dom;
+    use window::UiSolver;
+    use test_utils::{TestLayout, paint_frame};
+
+    let mut css = Css::new_from_string("
+        .red { background-color: #ff0000; width: 200px; height: 100px; }
+        .blue { background-color: #0000ff; width: 200px; height: 100px; }
+        #second { margin-top: 150px; }
+    ").unwrap();
+    let mut ui_solver = UiSolver::new();
+    let mut app_resources = AppResources::without_system_resources();
+
+    let mut render_frame = |second_color: &str, css: &mut Css, ui_solver: &mut UiSolver<TestLayout>| {
+        let dom = Dom::<TestLayout>::div()
+            .with_child(Dom::div().with_class("red"))
+            .with_child(Dom::div().with_id("second").with_class(second_color));
+        let ui_description = TestLayout::style_dom(&dom, css);
+        let display_list = DisplayList::new_from_ui_description(&ui_description);
+        paint_frame(&display_list, ui_solver, css, &mut app_resources, false, &WindowSize::default());
+    };
+
+    // On the first frame, the whole window is drawn
+    render_frame("red", &mut css, &mut ui_solver);
+    let full_window = LayoutRect::new(LayoutPoint::zero(), LayoutSize::new(800.0, 600.0));
+    assert_eq!(ui_solver.dirty_rect, Some(full_window));
+
+    // Only the color of the second child changes
+    render_frame("blue", &mut css, &mut ui_solver);
+    let first_child_bounds = ui_solver.query_bounds_of_rect(NodeId::new(1)).unwrap();
+    let second_child_bounds = ui_solver.query_bounds_of_rect(NodeId::new(2)).unwrap();
+    assert_eq!(second_child_bounds.size, LayoutSize::new(200.0, 100.0));
+    assert!(!second_child_bounds.intersects(&first_child_bounds));
+    assert_eq!(ui_solver.dirty_rect, Some(second_child_bounds));
+
+    // Nothing changes
+    render_frame("blue", &mut css, &mut ui_solver);
+    assert_eq!(ui_solver.dirty_rect, None);
+}
+
+#[test]
+fn test_sized_siblings_are_drawn_at_their_solved_bounds() {
+    use dom::Dom;
+    use window::UiSolver;
+    use display_list_capture::DisplayListOp;
+    use test_utils::{TestLayout, paint_frame};
+
+    let mut css = Css::new_from_string("
+        #sidebar { background-color: #ff0000; width: 100px; height: 50px; }
+        #content { background-color: #0000ff; width: 300px; height: 80px; margin-left: 150px; margin-top: 20px; }
+    ").unwrap();
+
+    let dom = Dom::<TestLayout>::div()
+        .with_child(Dom::div().with_id("sidebar"))
+        .with_child(Dom::div().with_id("content"));
+    let (sidebar, content) = (NodeId::new(1), NodeId::new(2));
+
+    let mut ui_solver = UiSolver::new();
+    let ui_description = TestLayout::style_dom(&dom, &css);
+    let display_list = DisplayList::new_from_ui_description(&ui_description);
+    let ops = paint_frame(&display_list, &mut ui_solver, &mut css, &mut AppResources::without_system_resources(), true, &WindowSize::default()).ops;
+
+    let sidebar_bounds = ui_solver.query_bounds_of_rect(sidebar).unwrap();
+    let content_bounds = ui_solver.query_bounds_of_rect(content).unwrap();
+    assert_eq!(sidebar_bounds, LayoutRect::new(LayoutPoint::zero(), LayoutSize::new(100.0, 50.0)));
+    assert_eq!(content_bounds, LayoutRect::new(LayoutPoint::new(150.0, 20.0), LayoutSize::new(300.0, 80.0)));
+
+    // Each sibling is drawn at its own solved bounds, not over the whole window
+    let painted_rects = ops.into_iter().filter_map(|op| match op {
+        DisplayListOp::Rect { rect, .. } => Some(rect),
+        _ => None,
+    }).collect::<Vec<LayoutRect>>();
+    assert_eq!(painted_rects, vec![sidebar_bounds, content_bounds]);
+}
+
+#[test]
+fn test_text_is_clipped_to_padded_content_box() {
+    let bounds = LayoutRect::new(LayoutPoint::new(0.0, 0.0), LayoutSize::new(200.0, 100.0));
+    let padding = Some(LayoutPadding { top: 10.0, right: 20.0, bottom: 10.0, left: 20.0 });
+
+    let content_box = get_content_rect(&bounds, padding);
+    assert_eq!(content_box, LayoutRect::new(LayoutPoint::new(20.0, 10.0), LayoutSize::new(160.0, 80.0)));
+
+    // `overflow: hidden` - the overflowing text is clipped at the padding
+    let hidden_behaviour = TextOverflowBehaviour::Modified(TextOverflowBehaviourInner::Hidden);
+    let hidden = LayoutOverflow { horizontal: hidden_behaviour, vertical: hidden_behaviour };
+    assert_eq!(get_text_clip_rect(&content_box, padding, &hidden), content_box);
+
+    // `overflow: visible` - the text may overflow into the padding, but not out of the node
+    let visible_behaviour = TextOverflowBehaviour::Modified(TextOverflowBehaviourInner::Visible);
+    let visible = LayoutOverflow { horizontal: visible_behaviour, vertical: visible_behaviour };
+    assert_eq!(get_text_clip_rect(&content_box, padding, &visible), bounds);
+
+    // Without an `overflow`, the text is clipped to the bounds of the node, like before
+    assert_eq!(get_text_clip_rect(&content_box, padding, &LayoutOverflow::default()), bounds);
+
+    // Without padding, the text is clipped to the bounds, like before
+    assert_eq!(get_text_clip_rect(&get_content_rect(&bounds, None), None, &hidden), bounds);
+}
+
+#[test]
+fn test_push_text_clips_overflowing_text_to_the_padded_content_box() {
+    use dom::Dom;
+    use display_list_capture::DisplayListOp;
+    use test_utils::{TestLayout, paint_dom, app_resources_with_test_font};
+
+    let mut css = Css::new_from_string("
+        .label { font-family: \"Webly Sleeky UI\"; font-size: 16px; width: 200px; height: 60px; padding: 10px 20px; padding-left: 30px; }
+        .hidden { overflow: hidden; }
+    ").unwrap();
+
+    let text = (0..40).map(|i| format!("Line {}", i)).collect::<Vec<String>>().join(" ");
+
+    // Returns the clip and the glyphs of the text of the label
+    let mut paint_label = |classes: &[&str]| {
+        let label = classes.iter().fold(Dom::<TestLayout>::label(text.clone()), |label, class| label.with_class(*class));
+        let ops = paint_dom(&Dom::div().with_child(label), &mut css, &mut app_resources_with_test_font()).ops;
+        ops.into_iter().filter_map(|op| match op {
+            DisplayListOp::Text { clip_rect, glyphs, .. } => Some((clip_rect, glyphs)),
+            _ => None,
+        }).next().unwrap()
+    };
+
+    let bounds = LayoutRect::new(LayoutPoint::zero(), LayoutSize::new(200.0, 60.0));
+    let content_box = LayoutRect::new(LayoutPoint::new(30.0, 10.0), LayoutSize::new(150.0, 40.0));
+
+    // `overflow: hidden` - the text is clipped to the content box, inset by the
+    // padding (the `padding-left` overrides the left side of the shorthand)
+    let (hidden_clip, hidden_glyphs) = paint_label(&["label", "hidden"]);
+    assert_eq!(hidden_clip, content_box);
+    assert!(hidden_glyphs.iter().all(|(_, point)| point.x >= content_box.origin.x));
+
+    // Without an `overflow`, the text is only clipped to the bounds of the node
+    let (default_clip, _) = paint_label(&["label"]);
+    assert_eq!(default_clip, bounds);
+}
+
+#[test]
+fn test_tooltip_is_drawn_on_top_of_the_nodes() {
+    use dom::Dom;
+    use window::UiSolver;
+    use font::{self, FontState};
+    use render_api::FakeRenderApi;
+    use display_list_capture::{DisplayListCapture, DisplayListOp};
+    use test_utils::TestLayout;
+
+    let mut css = Css::new_from_string("div { background-color: #ff0000; }").unwrap();
+    let dom = Dom::<TestLayout>::div().with_child(Dom::div());
+    let ui_description = TestLayout::style_dom(&dom, &css);
+    let display_list = DisplayList::new_from_ui_description(&ui_description);
+
+    // The tooltip is drawn with the built-in sans-serif font
+    let font_bytes = include_bytes!("../assets/fonts/weblysleekuil.ttf").to_vec();
+    let (parsed_font, font_data) = font::rusttype_load_font(font_bytes, None).unwrap();
+    let mut app_resources = AppResources::without_system_resources();
+    app_resources.font_data.insert(DEFAULT_BUILTIN_FONT_SANS_SERIF, (parsed_font, font_data, FontState::Uploaded(FontKey(IdNamespace(0), 100))));
+
+    let render_api = FakeRenderApi::default();
+    let mut builder = display_list.into_display_list_builder(
+        PipelineId(0, 0),
+        Epoch(0),
+        &mut UiSolver::new(),
+        &mut css,
+        &mut app_resources,
+        &render_api,
+        true,
+        &WindowSize::default()).unwrap();
+
+    let tooltip = TooltipOverlay {
+        text: String::from("Save"),
+        bounds: LayoutRect::new(LayoutPoint::new(100.0, 120.0), LayoutSize::new(60.0, 24.0)),
+    };
+    push_tooltip(&mut builder, &tooltip, &mut app_resources, &render_api);
+
+    let (_, _, built_display_list) = builder.finalize();
+    let ops = DisplayListCapture::new(&built_display_list).ops;
+
+    // The background and the text of the tooltip are the last items, after the nodes
+    let (node_ops, tooltip_ops) = ops.split_at(ops.len() - 2);
+    let node_backgrounds = node_ops.iter().filter(|op| match op {
+        DisplayListOp::Rect { color, .. } => *color == ColorF::from(ColorU { r: 255, g: 0, b: 0, a: 255 }),
+        _ => false,
+    }).count();
+    assert_eq!(node_backgrounds, 2);
+    assert_eq!(tooltip_ops[0], DisplayListOp::Rect { rect: tooltip.bounds, color: TOOLTIP_BACKGROUND_COLOR.0.into() });
+    match tooltip_ops[1] {
+        DisplayListOp::Text { rect, ref glyphs, .. } => {
+            assert_eq!(rect, tooltip.bounds);
+            assert_eq!(glyphs.len(), 4);
+        },
+        ref other => panic!("expected the text of the tooltip, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_hidden_parent_hides_children() {
+    use dom::Dom;
+    use display_list_capture::DisplayListOp;
+    use test_utils::{TestLayout, paint_dom};
+
+    const HIDDEN: ColorU = ColorU { r: 255, g: 0, b: 0, a: 255 };
+    const CHILD: ColorU = ColorU { r: 0, g: 255, b: 0, a: 255 };
+    const VISIBLE_CHILD: ColorU = ColorU { r: 0, g: 0, b: 255, a: 255 };
+    const NOT_DISPLAYED: ColorU = ColorU { r: 255, g: 255, b: 0, a: 255 };
+
+    let dom = Dom::<TestLayout>::div()
+        .with_child(Dom::div().with_id("hidden")
+            .with_child(Dom::div().with_class("child"))
+            .with_child(Dom::div().with_id("visible-child")))
+        .with_child(Dom::div().with_id("not-displayed")
+            .with_child(Dom::div().with_class("child")));
+
+    let mut css = Css::new_from_string("
+        #hidden { visibility: hidden; opacity: 0.5; background-color: #ff0000; }
+        .child { background-color: #00ff00; }
+        #visible-child { visibility: visible; background-color: #0000ff; }
+        #not-displayed { display: none; background-color: #ffff00; }
+    ").unwrap();
+
+    let ops = paint_dom(&dom, &mut css, &mut AppResources::without_system_resources()).ops;
+    let painted_colors = ops.iter().filter_map(|op| match op {
+        DisplayListOp::Rect { color, .. } => Some(*color),
+        _ => None,
+    }).collect::<Vec<ColorF>>();
+    let pushed_stacking_contexts = ops.iter().filter(|op| match op { DisplayListOp::PushStackingContext { .. } => true, _ => false }).count();
+    let popped_stacking_contexts = ops.iter().filter(|op| **op == DisplayListOp::PopStackingContext).count();
+
+    // Only the child that overrides the visibility of its hidden parent is painted
+    assert_eq!(painted_colors, vec![ColorF::from(VISIBLE_CHILD)]);
+    assert!(!painted_colors.contains(&ColorF::from(HIDDEN)));
+    assert!(!painted_colors.contains(&ColorF::from(CHILD)));
+    assert!(!painted_colors.contains(&ColorF::from(NOT_DISPLAYED)));
+
+    // The opacity of the hidden parent still wraps the visible child
+    assert_eq!(pushed_stacking_contexts, 1);
+    assert_eq!(popped_stacking_contexts, 1);
+}
+
+#[test]
+fn test_single_side_border() {
+    use euclid::TypedPoint2D;
+
+    let bounds = TypedRect::new(TypedPoint2D::new(0.0, 0.0), TypedSize2D::new(100.0, 100.0));
+    let info = LayoutPrimitiveInfo::new(bounds);
+
+    let mut style = RectStyle::default();
+    match ParsedCssProperty::from_kv("border-bottom", "2px solid red") {
+        Ok(ParsedCssProperty::BorderSide(side)) => style.border = Some(side.apply_to(style.border)),
+        other => panic!("expected a border side, got {:?}", other),
+    }
+
+    let mut builder = DisplayListBuilder::new(PipelineId(0, 0), bounds.size);
+    push_border(&info, &mut builder, &style);
+
+    let (_, _, display_list) = builder.finalize();
+    let mut borders = Vec::new();
+    let mut iter = display_list.iter();
+    while let Some(item) = iter.next() {
+        if let SpecificDisplayItem::Border(ref border) = *item.item() {
+            borders.push(border.widths);
+        }
+    }
+
+    assert_eq!(borders, vec![BorderWidths { top: 0.0, right: 0.0, bottom: 2.0, left: 0.0 }]);
+}
+
+#[test]
+fn test_quantized_font_sizes_share_font_instance() {
+    use render_api::FakeRenderApi;
+    use test_utils::{TEST_FONT_FAMILY, app_resources_with_test_font};
+
+    let font_id = FontId::ExternalFont(String::from(TEST_FONT_FAMILY));
+    let mut app_resources = app_resources_with_test_font();
+
+    let render_api = FakeRenderApi::default();
+    let mut resource_updates = Vec::new();
+
+    let quantization = Some(0.25);
+    let first = push_font(&font_id, font_size_to_app_units(16.01, quantization), &mut resource_updates, &mut app_resources, &render_api);
+    let second = push_font(&font_id, font_size_to_app_units(16.02, quantization), &mut resource_updates, &mut app_resources, &render_api);
+    assert!(first.is_some());
+    assert_eq!(first, second);
+    assert_eq!(resource_updates.len(), 1);
+
+    // Without quantization, the sizes are kept as they are
+    assert!(font_size_to_app_units(16.01, None) != font_size_to_app_units(16.02, None));
+    assert_eq!(font_size_to_app_units(16.2, quantization), Au::from_f32_px(16.25));
+}
+
+#[test]
+fn test_image_region_of_sprite_sheet() {
+    use images::{self, ImageInfo, ImageState};
+    use render_api::FakeRenderApi;
+
+    // A sprite sheet with two 32x32 icons next to each other
+    let mut app_resources = AppResources::without_system_resources();
+    let sprite_sheet_id = images::new_image_id();
+    let sprite_sheet_key = ImageKey::new(IdNamespace(0), 7);
+    app_resources.css_ids_to_image_ids.insert(String::from("icons"), sprite_sheet_id);
+    app_resources.images.insert(sprite_sheet_id, ImageState::Uploaded(ImageInfo {
+        key: sprite_sheet_key,
+        descriptor: ImageDescriptor::new(64, 32, ImageFormat::BGRA8, true, false),
+        data: ImageData::new(vec![255; 64 * 32 * 4]),
+    }));
+
+    let second_icon = ImageRegion { x: 32, y: 0, width: 32, height: 32 };
+    assert_eq!(app_resources.add_image_region("save-icon", "icons", second_icon), Some(()));
+    assert_eq!(app_resources.add_image_region("other-icon", "missing", second_icon), None);
+    assert!(app_resources.has_image("save-icon"));
+    let save_icon_id = app_resources.css_ids_to_image_ids["save-icon"];
+
+    // Draw the icon at half its size
+    let bounds = LayoutRect::new(LayoutPoint::new(10.0, 10.0), LayoutSize::new(16.0, 16.0));
+    let info = LayoutPrimitiveInfo::new(bounds);
+    let mut builder = DisplayListBuilder::new(PipelineId(0, 0), LayoutSize::new(100.0, 100.0));
+    let render_api = FakeRenderApi::default();
+    let mut resource_updates = Vec::new();
+    push_image(&info, &mut builder, &bounds, &mut app_resources, &save_icon_id, None, BackgroundRepeat::NoRepeat, Epoch(0), &render_api, &mut resource_updates);
+
+    // The source image is used, nothing new is uploaded
+    assert!(resource_updates.is_empty());
+
+    let (_, _, display_list) = builder.finalize();
+    let mut pushed_images = Vec::new();
+    let mut iter = display_list.iter();
+    while let Some(item) = iter.next() {
+        if let SpecificDisplayItem::Image(ref image) = *item.item() {
+            pushed_images.push((image.image_key, item.rect(), *item.clip_rect()));
+        }
+    }
+
+    // The whole sheet is scaled and moved, so that only the second icon is visible within the bounds
+    let sheet_rect = LayoutRect::new(LayoutPoint::new(-6.0, 10.0), LayoutSize::new(32.0, 16.0));
+    assert_eq!(pushed_images, vec![(sprite_sheet_key, sheet_rect, bounds)]);
+}
+
+#[test]
+fn test_sprite_atlas_regions_draw_their_sub_rect() {
+    use std::collections::HashMap;
+    use images::{ImageInfo, ImageState, ImageType};
+    use render_api::FakeRenderApi;
+
+    // A 4x2 atlas (binary PPM), with a 1x2 "button" and a 3x2 "button_hover" sprite
+    let mut atlas_bytes = b"P6\n4 2\n255\n".to_vec();
+    atlas_bytes.extend(vec![255; 4 * 2 * 3]);
+
+    let mut regions = HashMap::new();
+    regions.insert(String::from("button"), ImageRegion { x: 0, y: 0, width: 1, height: 2 });
+    regions.insert(String::from("button_hover"), ImageRegion { x: 1, y: 0, width: 3, height: 2 });
+
+    let mut app_resources = AppResources::without_system_resources();
+    let atlas = app_resources.add_sprite_atlas(&mut &atlas_bytes[..], ImageType::Pnm, regions).unwrap();
+    assert_eq!(atlas.region("missing"), None);
+
+    // Upload the atlas, like in `into_display_list_builder`
+    let atlas_key = ImageKey::new(IdNamespace(0), 9);
+    let uploaded = match app_resources.images[&atlas.image_id] {
+        ImageState::ReadyForUpload((ref data, descriptor)) => ImageState::Uploaded(ImageInfo { key: atlas_key, descriptor: descriptor, data: data.clone() }),
+        ref other => panic!("atlas is not ready for upload: {:?}", other),
+    };
+    app_resources.images.insert(atlas.image_id, uploaded);
+
+    let render_api = FakeRenderApi::default();
+    let bounds = LayoutRect::new(LayoutPoint::zero(), LayoutSize::new(12.0, 4.0));
+    let info = LayoutPrimitiveInfo::new(bounds);
+
+    let mut draw_region = |name: &str| {
+        let mut builder = DisplayListBuilder::new(PipelineId(0, 0), LayoutSize::new(100.0, 100.0));
+        let mut resource_updates = Vec::new();
+        push_image(&info, &mut builder, &bounds, &mut app_resources, &atlas.region(name).unwrap(), None, BackgroundRepeat::NoRepeat, Epoch(0), &render_api, &mut resource_updates);
+        assert!(resource_updates.is_empty());
+
+        let (_, _, display_list) = builder.finalize();
+        let mut iter = display_list.iter();
+        let mut pushed_images = Vec::new();
+        while let Some(item) = iter.next() {
+            if let SpecificDisplayItem::Image(ref image) = *item.item() {
+                pushed_images.push((image.image_key, item.rect()));
+            }
+        }
+        pushed_images
+    };
+
+    // The whole atlas is scaled and moved, so that only the region covers the bounds
+    assert_eq!(draw_region("button"), vec![
+        (atlas_key, LayoutRect::new(LayoutPoint::zero(), LayoutSize::new(48.0, 4.0)))]);
+    assert_eq!(draw_region("button_hover"), vec![
+        (atlas_key, LayoutRect::new(LayoutPoint::new(-4.0, 0.0), LayoutSize::new(16.0, 4.0)))]);
+}
+
+#[test]
+fn test_weak_width_yields_to_strong_sibling() {
+    use cassowary::{Solver, WeightedRelation::EQ, strength::REQUIRED};
+    use ui_description::StyledNode;
+
+    let styled_node = StyledNode::default();
+    let window_size = WindowSize::default();
+
+    // Two siblings that both want to be 200px wide, but only 300px are available
+    let mut weak = DisplayRectangle::new(None, &styled_node);
+    weak.layout.width = Some(LayoutWidth(PixelValue::from_metric(CssMetric::Px, 200.0)));
+    weak.layout.constraint_strength = Some(LayoutConstraintStrength::Weak);
+    let mut strong = DisplayRectangle::new(None, &styled_node);
+    strong.layout.width = Some(LayoutWidth(PixelValue::from_metric(CssMetric::Px, 200.0)));
+
+    let weak_rect = DisplayRect::default();
+    let strong_rect = DisplayRect::default();
+
+    let mut solver = Solver::new();
+    solver.add_constraints(&css_constraints_to_cassowary_constraints(&weak_rect, &create_layout_constraints(&weak, None, &window_size))).unwrap();
+    solver.add_constraints(&css_constraints_to_cassowary_constraints(&strong_rect, &create_layout_constraints(&strong, None, &window_size))).unwrap();
+    solver.add_constraint(weak_rect.width + strong_rect.width | EQ(REQUIRED) | 300.0).unwrap();
+
+    assert!((solver.get_value(strong_rect.width) - 200.0).abs() < 0.01);
+    assert!((solver.get_value(weak_rect.width) - 100.0).abs() < 0.01);
+}
+
+#[test]
+fn test_outset_box_shadow_is_not_clipped() {
+    let full_screen_rect = LayoutRect::new(LayoutPoint::zero(), LayoutSize::new(800.0, 600.0));
+    let bounds = LayoutRect::new(LayoutPoint::new(50.0, 50.0), LayoutSize::new(100.0, 100.0));
+
+    let shadow_clip_rects = |style: &RectStyle| {
+        let mut builder = DisplayListBuilder::new(PipelineId(0, 0), full_screen_rect.size);
+        push_box_shadow(&mut builder, style, &bounds, &full_screen_rect, BoxShadowClipMode::Outset);
+        let (_, _, display_list) = builder.finalize();
+        let mut clip_rects = Vec::new();
+        let mut iter = display_list.iter();
+        while let Some(item) = iter.next() {
+            if let SpecificDisplayItem::BoxShadow(_) = *item.item() {
+                clip_rects.push(*item.clip_rect());
+            }
+        }
+        clip_rects
+    };
+
+    let mut style = RectStyle::default();
+    style.border_radius = Some(BorderRadius::uniform(20.0));
+    style.box_shadow = Some(BoxShadowPreDisplayItem {
+        offset: LayoutVector2D::new(0.0, 0.0),
+        color: ColorF::new(0.0, 0.0, 0.0, 1.0),
+        blur_radius: 5.0,
+        spread_radius: 10.0,
+        clip_mode: BoxShadowClipMode::Outset,
+    });
+
+    // The rounded shadow is grown by the spread and the blur on all sides
+    assert_eq!(shadow_clip_rects(&style), vec![LayoutRect::new(LayoutPoint::new(35.0, 35.0), LayoutSize::new(130.0, 130.0))]);
+
+    // A large negative spread - the shadow is smaller than the box and lies inside of it
+    style.box_shadow.as_mut().unwrap().spread_radius = -30.0;
+    let clip_rects = shadow_clip_rects(&style);
+    assert_eq!(clip_rects, vec![LayoutRect::new(LayoutPoint::new(75.0, 75.0), LayoutSize::new(50.0, 50.0))]);
+    assert!(bounds.contains_rect(&clip_rects[0]));
+
+    // The shadow can't shrink below zero
+    style.box_shadow.as_mut().unwrap().spread_radius = -80.0;
+    assert_eq!(shadow_clip_rects(&style), vec![LayoutRect::new(LayoutPoint::new(95.0, 95.0), LayoutSize::new(10.0, 10.0))]);
+}
+
+#[test]
+fn test_rtl_vertical_scrollbar_is_on_the_left() {
+    use text_layout::TextOverflow;
+    use test_utils::test_scrollbar_info;
+
+    let bounds = LayoutRect::new(LayoutPoint::new(50.0, 50.0), LayoutSize::new(200.0, 100.0));
+    let scrollbar_style = test_scrollbar_info();
+    // The text overflows the container vertically
+    let scrollbar_info = TextOverflowPass2 {
+        horizontal: TextOverflow::InBounds(0.0),
+        vertical: TextOverflow::IsOverflowing(80.0),
+    };
+
+    let scrollbar_track = |text_direction: TextDirection| {
+        let mut builder = DisplayListBuilder::new(PipelineId(0, 0), LayoutSize::new(800.0, 600.0));
+        push_scrollbar(&mut builder, &LayoutOverflow::default(), &scrollbar_info, &scrollbar_style, &bounds, &None, text_direction, LayoutVector2D::zero());
+        let (_, _, display_list) = builder.finalize();
+        let mut iter = display_list.iter();
+        while let Some(item) = iter.next() {
+            if let SpecificDisplayItem::Rectangle(_) = *item.item() {
+                return item.rect();
+            }
+        }
+        panic!("no scrollbar track was pushed");
+    };
+
+    let rtl_track = scrollbar_track(TextDirection::Rtl);
+    assert_eq!(rtl_track.origin, LayoutPoint::new(50.0, 50.0));
+    assert_eq!(rtl_track.size, LayoutSize::new(17.0, 100.0));
+
+    let ltr_track = scrollbar_track(TextDirection::Ltr);
+    assert_eq!(ltr_track.origin, LayoutPoint::new(250.0 - 17.0, 50.0));
+}
+
+#[test]
+fn test_scrollbars_on_both_axes_share_the_corner() {
+    use text_layout::TextOverflow;
+    use test_utils::test_scrollbar_info;
+
+    let bounds = LayoutRect::new(LayoutPoint::new(0.0, 0.0), LayoutSize::new(200.0, 100.0));
+    let scrollbar_style = test_scrollbar_info();
+    // The text overflows the container on both axes
+    let scrollbar_info = TextOverflowPass2 {
+        horizontal: TextOverflow::IsOverflowing(50.0),
+        vertical: TextOverflow::IsOverflowing(80.0),
+    };
+
+    let mut builder = DisplayListBuilder::new(PipelineId(0, 0), LayoutSize::new(800.0, 600.0));
+    push_scrollbar(&mut builder, &LayoutOverflow::default(), &scrollbar_info, &scrollbar_style, &bounds, &None, TextDirection::Ltr, LayoutVector2D::zero());
+    let (_, _, display_list) = builder.finalize();
+    let mut iter = display_list.iter();
+    let background_color: ColorF = scrollbar_style.background_color.0.into();
+    let mut background_rects = Vec::new();
+    while let Some(item) = iter.next() {
+        if let SpecificDisplayItem::Rectangle(ref r) = *item.item() {
+            if r.color == background_color {
+                background_rects.push(item.rect());
+            }
+        }
+    }
+
+    let vertical_track = LayoutRect::new(LayoutPoint::new(183.0, 0.0), LayoutSize::new(17.0, 83.0));
+    let horizontal_track = LayoutRect::new(LayoutPoint::new(0.0, 83.0), LayoutSize::new(183.0, 17.0));
+    let corner = LayoutRect::new(LayoutPoint::new(183.0, 83.0), LayoutSize::new(17.0, 17.0));
+    assert_eq!(background_rects, vec![vertical_track, horizontal_track, corner]);
+    assert!(!vertical_track.intersects(&horizontal_track));
+
+    // The content isn't drawn below the scrollbars
+    let scrollbars = get_scrollbar_rects(&LayoutOverflow::default(), &scrollbar_info, &scrollbar_style, &bounds, &None, TextDirection::Ltr);
+    assert_eq!(get_clip_without_scrollbars(&bounds, &scrollbars), LayoutRect::new(LayoutPoint::zero(), LayoutSize::new(183.0, 83.0)));
+}
+
+#[test]
+fn test_horizontal_scrollbar_is_drawn_along_the_bottom() {
+    use text_layout::TextOverflow;
+    use test_utils::test_scrollbar_info;
+
+    let bounds = LayoutRect::new(LayoutPoint::new(0.0, 0.0), LayoutSize::new(200.0, 100.0));
+    let border = match ParsedCssProperty::from_kv("border", "3px solid #000000") {
+        Ok(ParsedCssProperty::Border(widths, details)) => Some((widths, details)),
+        other => panic!("unexpected border: {:?}", other),
+    };
+    let scrollbar_style = test_scrollbar_info();
+    // A long single-line label only overflows horizontally
+    let scrollbar_info = TextOverflowPass2 {
+        horizontal: TextOverflow::IsOverflowing(300.0),
+        vertical: TextOverflow::InBounds(20.0),
+    };
+
+    let scrollbars = get_scrollbar_rects(&LayoutOverflow::default(), &scrollbar_info, &scrollbar_style, &bounds, &border, TextDirection::Ltr);
+    assert_eq!(scrollbars.vertical, None);
+    assert_eq!(scrollbars.corner, None);
+    // The track lies inside of the left, right and bottom border
+    assert_eq!(scrollbars.horizontal, Some(LayoutRect::new(LayoutPoint::new(3.0, 80.0), LayoutSize::new(194.0, 17.0))));
 
-                $field_name
-            })
+    // A vertical scrollbar lies inside of the top, right and bottom border
+    let vertical_info = TextOverflowPass2 {
+        horizontal: TextOverflow::InBounds(20.0),
+        vertical: TextOverflow::IsOverflowing(300.0),
+    };
+    let vertical = get_scrollbar_rects(&LayoutOverflow::default(), &vertical_info, &scrollbar_style, &bounds, &border, TextDirection::Ltr);
+    assert_eq!(vertical.vertical, Some(LayoutRect::new(LayoutPoint::new(180.0, 3.0), LayoutSize::new(17.0, 94.0))));
+
+    let mut builder = DisplayListBuilder::new(PipelineId(0, 0), LayoutSize::new(800.0, 600.0));
+    push_scrollbar(&mut builder, &LayoutOverflow::default(), &scrollbar_info, &scrollbar_style, &bounds, &border, TextDirection::Ltr, LayoutVector2D::zero());
+    let (_, _, display_list) = builder.finalize();
+
+    let bar_color: ColorF = scrollbar_style.bar_color.0.into();
+    let mut thumb = None;
+    let mut triangles = Vec::new();
+    let mut iter = display_list.iter();
+    while let Some(item) = iter.next() {
+        match *item.item() {
+            SpecificDisplayItem::Rectangle(ref r) if r.color == bar_color => thumb = Some(item.rect()),
+            SpecificDisplayItem::Border(_) => triangles.push(item.rect()),
+            _ => { },
+        }
+    }
+
+    // The thumb lies between the left and the right arrow, with a length of visible / total
+    let thumb = thumb.expect("no scrollbar thumb was pushed");
+    assert_eq!(thumb.origin, LayoutPoint::new(20.0, 82.0));
+    assert_eq!(thumb.size, LayoutSize::new((194.0 - 34.0) * 200.0 / 500.0, 13.0));
+
+    // The left and the right arrow are at the ends of the track
+    assert_eq!(triangles.len(), 2);
+    assert!(triangles[0].max_x() <= 3.0 + 17.0 && triangles[0].min_y() >= 80.0);
+    assert!(triangles[1].min_x() >= 197.0 - 17.0 && triangles[1].min_y() >= 80.0);
+}
+
+#[test]
+fn test_scrollbar_thumb_has_a_minimum_length() {
+    use text_layout::TextOverflow;
+    use test_utils::test_scrollbar_info;
+
+    let bounds = LayoutRect::new(LayoutPoint::new(0.0, 0.0), LayoutSize::new(200.0, 100.0));
+    let scrollbar_style = test_scrollbar_info();
+    // The content is 100 times as high as the rectangle
+    let overflow = 99.0 * bounds.size.height;
+    let scrollbar_info = TextOverflowPass2 {
+        horizontal: TextOverflow::InBounds(0.0),
+        vertical: TextOverflow::IsOverflowing(overflow),
+    };
+
+    let thumb_at = |scroll_offset: f32| {
+        let mut builder = DisplayListBuilder::new(PipelineId(0, 0), LayoutSize::new(800.0, 600.0));
+        push_scrollbar(&mut builder, &LayoutOverflow::default(), &scrollbar_info, &scrollbar_style, &bounds, &None, TextDirection::Ltr, LayoutVector2D::new(0.0, scroll_offset));
+        let (_, _, display_list) = builder.finalize();
+        let bar_color: ColorF = scrollbar_style.bar_color.0.into();
+        let mut iter = display_list.iter();
+        while let Some(item) = iter.next() {
+            if let SpecificDisplayItem::Rectangle(ref r) = *item.item() {
+                if r.color == bar_color {
+                    return item.rect();
+                }
+            }
         }
+        panic!("no scrollbar thumb was pushed");
+    };
+
+    // The space between the arrows ranges from 17px to 83px, a proportional thumb would be 0.66px long
+    let top_thumb = thumb_at(0.0);
+    assert_eq!(top_thumb.size.height, 20.0);
+    assert_eq!(top_thumb.origin.y, 17.0);
+
+    // The clamped thumb still reaches the bottom of the track at the maximum scroll offset
+    let bottom_thumb = thumb_at(overflow);
+    assert_eq!(bottom_thumb.size.height, 20.0);
+    assert_eq!(bottom_thumb.max_y(), 83.0);
+
+    // Halfway down the content, the thumb is halfway down the track
+    assert_eq!(thumb_at(overflow / 2.0).origin.y, 17.0 + (66.0 - 20.0) / 2.0);
+}
+
+#[test]
+fn test_scrolled_text_moves_the_glyphs_and_the_scrollbar_thumb() {
+    use dom::Dom;
+    use window::UiSolver;
+    use scroll::{ScrollBounds, find_scroll_chain};
+    use display_list_capture::DisplayListOp;
+    use test_utils::{TestLayout, paint_frame, app_resources_with_test_font};
+
+    let text = (0..40).map(|i| format!("Line {}", i)).collect::<Vec<String>>().join(" ");
+    let log = Dom::<TestLayout>::label(text).with_class("log").with_scroll_bounds(ScrollBounds::new(0.0, 1000.0));
+    let log_tag = log.arena.borrow()[log.root].data.tag.unwrap();
+    let dom = Dom::<TestLayout>::div().with_child(log);
+
+    let css_source = "
+        .log { font-family: \"Webly Sleeky UI\"; font-size: 16px; width: 200px; height: 50px; overflow: auto; }
+    ";
+
+    // Returns the glyph positions and the scrollbar thumb of the log
+    let paint_scrolled = |scroll_states: &ScrollStates| {
+        let mut css = Css::new_from_string(css_source).unwrap();
+        let ui_description = TestLayout::style_dom(&dom, &css);
+        let mut display_list = DisplayList::new_from_ui_description(&ui_description);
+        display_list.set_scroll_states(scroll_states);
+        let ops = paint_frame(&display_list, &mut UiSolver::new(), &mut css, &mut app_resources_with_test_font(), true, &WindowSize::default()).ops;
+        let glyph_positions = ops.iter().filter_map(|op| match op {
+            DisplayListOp::Text { glyphs, .. } => Some(glyphs.iter().map(|(_, point)| *point).collect::<Vec<LayoutPoint>>()),
+            _ => None,
+        }).next().unwrap();
+        let bar_color = ColorF::from(ColorU { r: 193, g: 193, b: 193, a: 255 });
+        let thumb = ops.iter().filter_map(|op| match op {
+            DisplayListOp::Rect { rect, color } if *color == bar_color => Some(*rect),
+            _ => None,
+        }).next().unwrap();
+        (glyph_positions, thumb)
+    };
+
+    let mut scroll_states = ScrollStates::default();
+    let (unscrolled_glyphs, unscrolled_thumb) = paint_scrolled(&scroll_states);
+
+    let scroll_chain = find_scroll_chain(&TestLayout::style_dom(&dom, &Css::empty()), log_tag);
+    scroll_states.scroll(&scroll_chain, (0.0, 30.0));
+    let (scrolled_glyphs, scrolled_thumb) = paint_scrolled(&scroll_states);
 
-        match field {
-            Width => {
-                let w = get_wh!(width, min_width);
-                w
+    // The text is moved up by the scroll offset
+    let scroll_offset = LayoutVector2D::new(0.0, 30.0);
+    assert_eq!(scrolled_glyphs, unscrolled_glyphs.iter().map(|point| *point - scroll_offset).collect::<Vec<LayoutPoint>>());
+
+    // The thumb starts at the top of the track and moves down, but keeps its length
+    assert_eq!(unscrolled_thumb.origin.y, 17.0);
+    assert!(scrolled_thumb.origin.y > unscrolled_thumb.origin.y);
+    assert_eq!(scrolled_thumb.size, unscrolled_thumb.size);
+}
+
+#[test]
+fn test_min_max_height_clamp_the_height_of_the_text_in_the_solver() {
+    use dom::Dom;
+    use window::UiSolver;
+    use display_list_capture::DisplayListOp;
+    use test_utils::{TestLayout, paint_frame, app_resources_with_test_font};
+
+    let css_source = "
+        .short { font-family: \"Webly Sleeky UI\"; font-size: 16px; width: 200px; min-height: 100px; }
+        .long { font-family: \"Webly Sleeky UI\"; font-size: 16px; width: 200px; max-height: 50px; overflow: auto; }
+    ";
+
+    // Returns the solved bounds of the label and the painted primitives
+    let paint_label = |text: String, class: &str| {
+        let mut css = Css::new_from_string(css_source).unwrap();
+        let dom = Dom::<TestLayout>::div().with_child(Dom::label(text).with_class(class));
+        let ui_description = TestLayout::style_dom(&dom, &css);
+        let display_list = DisplayList::new_from_ui_description(&ui_description);
+        let mut ui_solver = UiSolver::new();
+        let ops = paint_frame(&display_list, &mut ui_solver, &mut css, &mut app_resources_with_test_font(), true, &WindowSize::default()).ops;
+        (ui_solver.query_bounds_of_rect(NodeId::new(1)).unwrap(), ops)
+    };
+
+    let bar_color = ColorF::from(ColorU { r: 193, g: 193, b: 193, a: 255 });
+    let has_scrollbar = |ops: &[DisplayListOp]| ops.iter().any(|op| match op {
+        DisplayListOp::Rect { color, .. } => *color == bar_color,
+        _ => false,
+    });
+
+    // A single line is lower than the `min-height`, so the box grows to the minimum height
+    let (short_bounds, short_ops) = paint_label(String::from("Hello"), "short");
+    assert_eq!(short_bounds.size, LayoutSize::new(200.0, 100.0));
+    assert!(!has_scrollbar(&short_ops));
+
+    // The lines are higher than the `max-height`, so the box shrinks to the maximum height
+    // and the overflowing text gets a scrollbar
+    let long_text = (0..40).map(|i| format!("Line {}", i)).collect::<Vec<String>>().join(" ");
+    let (long_bounds, long_ops) = paint_label(long_text, "long");
+    assert_eq!(long_bounds.size, LayoutSize::new(200.0, 50.0));
+    assert!(has_scrollbar(&long_ops));
+}
+
+#[test]
+fn test_scrollbar_thumb_is_proportional_to_the_visible_content() {
+    // The content is twice as high as the rectangle: the thumb covers half of the track
+    assert_eq!(get_scrollbar_thumb(100.0, 200.0, 200.0, 0.0, 20.0), (0.0, 50.0));
+    assert_eq!(get_scrollbar_thumb(100.0, 200.0, 200.0, 100.0, 20.0), (25.0, 50.0));
+    assert_eq!(get_scrollbar_thumb(100.0, 200.0, 200.0, 200.0, 20.0), (50.0, 50.0));
+
+    // The content is only slightly larger than the rectangle: the thumb almost fills the track
+    let (start, length) = get_scrollbar_thumb(100.0, 200.0, 2.0, 2.0, 20.0);
+    assert!(length > 98.0 && length < 100.0);
+    assert_eq!(start + length, 100.0);
+
+    // A track that is shorter than the minimum length is completely filled by the thumb
+    assert_eq!(get_scrollbar_thumb(10.0, 200.0, 2000.0, 1000.0, 20.0), (0.0, 10.0));
+
+    // Scroll offsets beyond the content don't move the thumb out of the track
+    assert_eq!(get_scrollbar_thumb(100.0, 200.0, 200.0, 500.0, 20.0), (50.0, 50.0));
+    assert_eq!(get_scrollbar_thumb(100.0, 200.0, 200.0, -10.0, 20.0), (0.0, 50.0));
+}
+
+#[test]
+fn test_empty_text_input_shows_placeholder() {
+    use widgets::TextInput;
+    use display_list_capture::DisplayListOp;
+    use test_utils::{TestLayout, paint_dom, app_resources_with_test_font};
+
+    const TEXT_COLOR: ColorU = ColorU { r: 0, g: 0, b: 0, a: 255 };
+    const PLACEHOLDER_COLOR: ColorU = ColorU { r: 0, g: 0, b: 0, a: 127 };
+
+    // Returns the colors of all text items that were pushed for the text input
+    let pushed_text_colors = |text_input: TextInput| {
+        let mut css = Css::new_from_string("
+            .__azul-native-input { font-family: \"Webly Sleeky UI\"; font-size: 16px; color: #000000; }
+        ").unwrap();
+
+        let ops = paint_dom(&text_input.dom::<TestLayout>(), &mut css, &mut app_resources_with_test_font()).ops;
+        ops.into_iter().filter_map(|op| match op {
+            DisplayListOp::Text { color, .. } => Some(color),
+            _ => None,
+        }).collect::<Vec<ColorF>>()
+    };
+
+    // The empty input renders the placeholder glyphs, dimmed
+    assert_eq!(pushed_text_colors(TextInput::new("").with_placeholder("Search")), vec![ColorF::from(PLACEHOLDER_COLOR)]);
+
+    // As soon as there is text, only the text is rendered
+    assert_eq!(pushed_text_colors(TextInput::new("azul").with_placeholder("Search")), vec![ColorF::from(TEXT_COLOR)]);
+
+    // Without a placeholder, an empty input renders no text at all
+    assert_eq!(pushed_text_colors(TextInput::new("")), Vec::<ColorF>::new());
+}
+
+#[test]
+fn test_inset_box_shadow_without_border_radius_is_clipped() {
+    use std::collections::BTreeMap;
+    use dom::Dom;
+    use ui_state::UiState;
+    use render_api::FakeRenderApi;
+    use test_utils::TestLayout;
+
+    let ui_state = UiState {
+        dom: Dom::<TestLayout>::div().with_id("square"),
+        callback_list: BTreeMap::new(),
+        node_ids_to_callbacks_list: BTreeMap::new(),
+    };
+
+    let mut css = Css::new_from_string("
+        #square { box-shadow: 0px 0px 5px #000000 inset; }
+    ").unwrap();
+
+    let ui_description = UiDescription::from_ui_state(&ui_state, &css);
+    let display_list = DisplayList::new_from_ui_description(&ui_description);
+
+    let mut ui_solver = UiSolver::new();
+
+    let mut app_resources = AppResources::without_system_resources();
+    let render_api = FakeRenderApi::default();
+    let builder = display_list.into_display_list_builder(
+        PipelineId(0, 0),
+        Epoch(0),
+        &mut ui_solver,
+        &mut css,
+        &mut app_resources,
+        &render_api,
+        true,
+        &WindowSize::default()).unwrap();
+
+    let (_, _, built_display_list) = builder.finalize();
+    let mut defined_clips = Vec::new();
+    let mut inset_shadows = Vec::new();
+    let mut iter = built_display_list.iter();
+    while let Some(item) = iter.next() {
+        match *item.item() {
+            SpecificDisplayItem::Clip(_) => defined_clips.push(item.rect()),
+            SpecificDisplayItem::BoxShadow(ref shadow) if shadow.clip_mode == BoxShadowClipMode::Inset => {
+                // The clip has to be defined before the shadow is pushed
+                assert_eq!(defined_clips.len(), 1);
+                inset_shadows.push(shadow.box_bounds);
             },
-            Height => {
-                let h = get_wh!(height, min_height);
-                h
+            _ => { },
+        }
+    }
+
+    // The square is clipped to its bounds, so that the inset shadow shows up
+    assert_eq!(inset_shadows.len(), 1);
+    assert_eq!(defined_clips, inset_shadows);
+}
+
+#[test]
+fn test_indeterminate_spinner_requests_redraws() {
+    use std::time::Duration;
+    use dom::Dom;
+    use ui_state::UiState;
+    use test_utils::TestLayout;
+
+    let ui_state_with_child = |child: Dom<TestLayout>| UiState {
+        dom: Dom::div().with_child(child),
+        callback_list: BTreeMap::new(),
+        node_ids_to_callbacks_list: BTreeMap::new(),
+    };
+
+    let started = Instant::now();
+    let clock = SpinnerClock::new(started);
+    let css = Css::empty();
+
+    // The indeterminate spinner rotates, so every frame has to be redrawn
+    let ui_state = ui_state_with_child(Dom::spinner());
+    let ui_description = UiDescription::from_ui_state(&ui_state, &css);
+    let mut display_list = DisplayList::new_from_ui_description(&ui_description);
+    assert!(display_list.apply_spinner_animation(&clock, started + Duration::from_millis(100)));
+    let first_rotation = display_list.spinner_rotation;
+    assert!(display_list.apply_spinner_animation(&clock, started + Duration::from_millis(300)));
+    assert!(display_list.spinner_rotation > first_rotation);
+
+    // A determinate spinner only changes if its value changes
+    let ui_state = ui_state_with_child(Dom::determinate_spinner(0.25));
+    let ui_description = UiDescription::from_ui_state(&ui_state, &css);
+    let mut display_list = DisplayList::new_from_ui_description(&ui_description);
+    assert!(!display_list.apply_spinner_animation(&clock, started + Duration::from_millis(100)));
+}
+
+#[test]
+fn test_root_background_sets_clear_color() {
+    use dom::Dom;
+    use ui_state::UiState;
+    use test_utils::TestLayout;
+
+    let ui_state = UiState {
+        dom: Dom::<TestLayout>::div().with_class("root").with_child(Dom::div().with_class("child")),
+        callback_list: BTreeMap::new(),
+        node_ids_to_callbacks_list: BTreeMap::new(),
+    };
+
+    // Only the background of the root node is used as the clear color
+    let css = Css::new_from_string(".child { background-color: #0000ff; }").unwrap();
+    let ui_description = UiDescription::from_ui_state(&ui_state, &css);
+    assert_eq!(DisplayList::new_from_ui_description(&ui_description).get_clear_color(), None);
+
+    let css = Css::new_from_string(".root { background-color: #ff0000; } .child { background-color: #0000ff; }").unwrap();
+    let ui_description = UiDescription::from_ui_state(&ui_state, &css);
+    assert_eq!(DisplayList::new_from_ui_description(&ui_description).get_clear_color(), Some(ColorF::new(1.0, 0.0, 0.0, 1.0)));
+}
+
+#[test]
+fn test_tinted_white_image_is_drawn_red() {
+    use images::{self, ImageInfo, ImageState};
+    use render_api::FakeRenderApi;
+
+    let mut app_resources = AppResources::without_system_resources();
+    let icon_id = images::new_image_id();
+    let icon_key = ImageKey::new(IdNamespace(0), 3);
+    app_resources.images.insert(icon_id, ImageState::Uploaded(ImageInfo {
+        key: icon_key,
+        descriptor: ImageDescriptor::new(2, 2, ImageFormat::BGRA8, true, false),
+        data: ImageData::new(vec![255; 2 * 2 * 4]),
+    }));
+
+    let mut style = RectStyle::default();
+    style.tint_color = Some(TintColor(ColorU { r: 255, g: 0, b: 0, a: 255 }));
+    let red_tint = FilterOp::ColorMatrix([
+        1.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+        0.0, 0.0, 0.0, 0.0,
+    ]);
+    assert_eq!(get_tint_filter(&style), Some(red_tint));
+    assert_eq!(get_tint_filter(&RectStyle::default()), None);
+
+    let bounds = LayoutRect::new(LayoutPoint::zero(), LayoutSize::new(16.0, 16.0));
+    let info = LayoutPrimitiveInfo::new(bounds);
+    let mut builder = DisplayListBuilder::new(PipelineId(0, 0), bounds.size);
+    let render_api = FakeRenderApi::default();
+    let mut resource_updates = Vec::new();
+
+    assert!(push_tint(&info, &mut builder, &style));
+    push_image(&info, &mut builder, &bounds, &mut app_resources, &icon_id, None, BackgroundRepeat::NoRepeat, Epoch(0), &render_api, &mut resource_updates);
+    builder.pop_stacking_context();
+
+    // The image is drawn inside of a stacking context with the tint filter
+    let (_, _, display_list) = builder.finalize();
+    let mut filters_of_image = None;
+    let mut current_filters = Vec::new();
+    let mut iter = display_list.iter();
+    while let Some(item) = iter.next() {
+        match *item.item() {
+            SpecificDisplayItem::PushStackingContext(_) => current_filters = display_list.get(item.filters()).collect(),
+            SpecificDisplayItem::PopStackingContext => current_filters = Vec::new(),
+            SpecificDisplayItem::Image(ref image) if image.image_key == icon_key => filters_of_image = Some(current_filters.clone()),
+            _ => { },
+        }
+    }
+    assert_eq!(filters_of_image, Some(vec![red_tint]));
+}
+
+#[test]
+fn test_rounded_background_is_clipped_on_its_own() {
+
+    use dom::Dom;
+    use display_list_capture::{DisplayListCapture, DisplayListOp};
+    use test_utils::{TestLayout, paint_dom};
+
+    fn capture(dom: &Dom<TestLayout>, css: &mut Css) -> DisplayListCapture {
+        paint_dom(dom, css, &mut AppResources::without_system_resources())
+    }
+
+    let mut css = Css::new_from_string("
+        .rounded { background-color: #ff0000; border-radius: 5px; }
+    ").unwrap();
+
+    // A div with a rounded background only: the background is drawn inside of its own clip
+    let ops = capture(&Dom::div().with_class("rounded"), &mut css).ops;
+    let clips = ops.iter().filter(|op| match op { DisplayListOp::Clip { .. } => true, _ => false }).count();
+    assert_eq!(clips, 1);
+    assert!(!ops.iter().any(|op| match op { DisplayListOp::BoxShadow { .. } => true, _ => false }));
+    assert!(ops.windows(2).any(|ops| match (&ops[0], &ops[1]) {
+        (DisplayListOp::Clip { rect: clip }, DisplayListOp::Rect { rect, color }) =>
+            clip == rect && *color == ColorF::new(1.0, 0.0, 0.0, 1.0),
+        _ => false,
+    }));
+
+    // The text of a label could overflow the rounded corners, so the label still gets a clip
+    let ops = capture(&Dom::new(NodeType::Label("Hello".into())).with_class("rounded"), &mut css).ops;
+    assert!(ops.iter().any(|op| match op { DisplayListOp::Clip { .. } => true, _ => false }));
+}
+
+#[test]
+fn test_caret_is_drawn_in_the_caret_color() {
+
+    use dom::Dom;
+    use window::UiSolver;
+    use display_list_capture::DisplayListOp;
+    use test_utils::{TestLayout, paint_frame, app_resources_with_test_font};
+
+    // Returns the colors of the text items and of the rects that were pushed for the input
+    fn pushed_colors(text: &str, css: &mut Css, is_caret_visible: bool) -> (Vec<ColorF>, Vec<(LayoutRect, ColorF)>) {
+        let dom = Dom::<TestLayout>::new(NodeType::Label(text.into())).with_class("input").with_title("Input");
+        css.set_focused_tag(dom.arena.borrow()[dom.root].data.tag);
+
+        let ui_description = TestLayout::style_dom(&dom, css);
+        let mut display_list = DisplayList::new_from_ui_description(&ui_description);
+        display_list.set_caret_visibility(is_caret_visible);
+        let capture = paint_frame(&display_list, &mut UiSolver::new(), css, &mut app_resources_with_test_font(), true, &WindowSize::default());
+
+        let mut text_colors = Vec::new();
+        let mut rects = Vec::new();
+        for op in capture.ops {
+            match op {
+                DisplayListOp::Text { color, .. } => text_colors.push(color),
+                DisplayListOp::Rect { rect, color } => rects.push((rect, color)),
+                _ => { },
             }
         }
+        (text_colors, rects)
     }
+
+    let text_color = ColorF::new(0.0, 0.0, 0.0, 1.0);
+    let caret_color = ColorF::new(1.0, 0.0, 0.0, 1.0);
+
+    let mut css = Css::new_from_string("
+        .input { font-family: \"Webly Sleeky UI\"; font-size: 16px; color: #000000; caret-color: red; }
+    ").unwrap();
+
+    // The text keeps its color, the caret is drawn after the text in the `caret-color`
+    let (text_colors, rects) = pushed_colors("azul", &mut css, true);
+    assert_eq!(text_colors, vec![text_color]);
+    assert_eq!(rects.len(), 1);
+    assert_eq!(rects[0].1, caret_color);
+    assert_eq!(rects[0].0.size.width, CARET_WIDTH);
+    assert!(rects[0].0.origin.x > 0.0);
+
+    // While the caret is blinked out, no caret is drawn
+    let (_, rects) = pushed_colors("azul", &mut css, false);
+    assert!(rects.is_empty());
+
+    // An empty input draws the caret at the start of the content box
+    let (text_colors, rects) = pushed_colors("", &mut css, true);
+    assert!(text_colors.is_empty());
+    assert_eq!(rects, vec![(LayoutRect::new(LayoutPoint::zero(), LayoutSize::new(CARET_WIDTH, 16.0)), caret_color)]);
+
+    // Without a `caret-color`, the caret has the text color
+    let mut css = Css::new_from_string("
+        .input { font-family: \"Webly Sleeky UI\"; font-size: 16px; color: #000000; }
+    ").unwrap();
+    let (_, rects) = pushed_colors("azul", &mut css, true);
+    assert_eq!(rects.iter().map(|r| r.1).collect::<Vec<_>>(), vec![text_color]);
 }
 
-// Empty test, for some reason codecov doesn't detect any files (and therefore
-// doesn't report codecov % correctly) except if they have at least one test in
-// the file. This is an empty test, which should be updated later on
 #[test]
-fn __codecov_test_display_list_file() {
+fn test_contain_layout_only_relayouts_the_contained_subtree() {
+
+    use dom::Dom;
+    use window::UiSolver;
+    use test_utils::{TestLayout, paint_frame};
+
+    fn layout_frame(dom: &Dom<TestLayout>, css: &mut Css, ui_solver: &mut UiSolver<TestLayout>) {
+        let ui_description = TestLayout::style_dom(dom, css);
+        let display_list = DisplayList::new_from_ui_description(&ui_description);
+        paint_frame(&display_list, ui_solver, css, &mut AppResources::without_system_resources(), false, &WindowSize::default());
+    }
+
+    let dom = Dom::<TestLayout>::div()
+        .with_child(Dom::div().with_id("contained").with_child(Dom::div().with_id("inner")))
+        .with_child(Dom::div().with_id("sibling").with_child(Dom::div().with_id("sibling-child")));
+
+    let node_id = |id: &str| {
+        let arena = dom.arena.borrow();
+        arena.linear_iter().find(|node_id| arena[*node_id].data.id.as_ref().map(|s| s.as_str()) == Some(id)).unwrap()
+    };
+    let (contained, inner, sibling, sibling_child) = (node_id("contained"), node_id("inner"), node_id("sibling"), node_id("sibling-child"));
+
+    let css_with_inner_width = |contain: &str, inner_width: &str| Css::new_from_string(&format!("
+        #contained {{ contain: {}; width: 100px; }}
+        #inner {{ width: {}; }}
+        #sibling {{ width: 80px; }}
+        #sibling-child {{ width: 40px; }}
+    ", contain, inner_width)).unwrap();
+
+    let mut ui_solver = UiSolver::new();
+
+    // The first frame lays out every node
+    layout_frame(&dom, &mut css_with_inner_width("layout", "50px"), &mut ui_solver);
+    assert_eq!(ui_solver.solved_layout.relayout_nodes.len(), 5);
+    let sibling_bounds = ui_solver.query_bounds_of_rect(sibling).unwrap();
+    let sibling_child_bounds = ui_solver.query_bounds_of_rect(sibling_child).unwrap();
+
+    // Only the contained subtree is laid out again when the inner node changes
+    layout_frame(&dom, &mut css_with_inner_width("layout", "70px"), &mut ui_solver);
+    assert_eq!(ui_solver.solved_layout.relayout_nodes, vec![contained, inner].into_iter().collect());
+    assert_eq!(ui_solver.query_bounds_of_rect(inner).unwrap().size.width, 70.0);
+    assert_eq!(ui_solver.query_bounds_of_rect(contained).unwrap().size.width, 100.0);
+    assert_eq!(ui_solver.query_bounds_of_rect(sibling).unwrap(), sibling_bounds);
+    assert_eq!(ui_solver.query_bounds_of_rect(sibling_child).unwrap(), sibling_child_bounds);
+
+    // Without `contain`, the same change lays out the whole tree again
+    layout_frame(&dom, &mut css_with_inner_width("none", "70px"), &mut ui_solver);
+    layout_frame(&dom, &mut css_with_inner_width("none", "90px"), &mut ui_solver);
+    assert_eq!(ui_solver.solved_layout.relayout_nodes.len(), 5);
+    assert_eq!(ui_solver.query_bounds_of_rect(inner).unwrap().size.width, 90.0);
+
+    // The constraints of a node also depend on the layout of its parent: if they were created
+    // with a different padding of the parent, the contained subtree has to be laid out again
+    let mut css = css_with_inner_width("layout", "90px");
+    layout_frame(&dom, &mut css, &mut ui_solver);
+    let (inner_inputs, _) = ui_solver.solved_layout.node_constraints.get_mut(&inner).unwrap();
+    inner_inputs.1 = inner_inputs.1.map(|parent_layout| RectLayout { padding: Some(LayoutPadding { top: 10.0, .. Default::default() }), .. parent_layout });
+
+    let ui_description = TestLayout::style_dom(&dom, &css);
+    let display_list = DisplayList::new_from_ui_description(&ui_description);
+    let relayout_nodes = get_relayout_nodes(NodeId::new(0), &display_list.rectangles, &ui_solver.solved_layout.node_constraints);
+    assert_eq!(relayout_nodes, vec![contained, inner].into_iter().collect());
+}
+
+#[test]
+fn test_multiple_text_decoration_lines_are_drawn_together() {
+
+    use dom::Dom;
+    use display_list_capture::DisplayListOp;
+    use test_utils::{TestLayout, paint_dom, app_resources_with_test_font};
+
+    let mut css = Css::new_from_string("
+        .decorated { font-family: \"Webly Sleeky UI\"; font-size: 16px; text-decoration: underline overline line-through; }
+    ").unwrap();
+    let dom = Dom::<TestLayout>::new(NodeType::Label("Hello".into())).with_class("decorated");
+    let ops = paint_dom(&dom, &mut css, &mut app_resources_with_test_font()).ops;
+
+    let baseline = ops.iter().filter_map(|op| match op {
+        DisplayListOp::Text { glyphs, .. } => glyphs.first().map(|(_, point)| point.y),
+        _ => None,
+    }).next().unwrap();
+    let decoration_rects = ops.iter().filter_map(|op| match op {
+        DisplayListOp::Rect { rect, .. } => Some(*rect),
+        _ => None,
+    }).collect::<Vec<LayoutRect>>();
+
+    // weblysleekuil.ttf: 2048 units per em, ascender 2210, strikeout position 530 (size 102),
+    // underline position -178 (thickness 119). The lines are drawn from top to bottom.
+    let scale = 16.0 / 2048.0;
+    assert_eq!(decoration_rects.len(), 3);
+    assert_eq!(decoration_rects[0].origin.y, baseline - 2210.0 * scale);
+    assert_eq!(decoration_rects[1].origin.y, baseline - 530.0 * scale);
+    assert_eq!(decoration_rects[1].size.height, 102.0 * scale);
+    assert_eq!(decoration_rects[2].origin.y, baseline + 178.0 * scale);
+    assert_eq!(decoration_rects[2].size.height, 119.0 * scale);
+}
+
+#[test]
+fn test_appearance_none_removes_the_native_button_styling() {
+
+    use css::Precedence;
+    use widgets::button::Button;
+    use display_list_capture::DisplayListOp;
+    use test_utils::{TestLayout, paint_dom, app_resources_with_test_font};
+
+    fn render_button(user_css: &str) -> Vec<DisplayListOp> {
+        let mut css = Css::new_native("
+            .__azul-native-button {
+                border: 1px solid #b7b7b7;
+                box-shadow: 0px 0px 3px #c5c5c5;
+                background: linear-gradient(#fcfcfc, #efefef);
+            }
+        ");
+        css.merge(Css::new_from_string(user_css).unwrap(), Precedence::Higher);
+        let dom = Button::with_label("OK").dom::<TestLayout>();
+        paint_dom(&dom, &mut css, &mut app_resources_with_test_font()).ops
+    }
+
+    fn is_widget_styling(op: &DisplayListOp) -> bool {
+        match op {
+            DisplayListOp::Border { .. } |
+            DisplayListOp::BoxShadow { .. } |
+            DisplayListOp::Gradient { .. } |
+            DisplayListOp::Rect { .. } => true,
+            _ => false,
+        }
+    }
+
+    fn is_text(op: &DisplayListOp) -> bool {
+        match op {
+            DisplayListOp::Text { .. } => true,
+            _ => false,
+        }
+    }
+
+    let font = ".__azul-native-button { font-family: \"Webly Sleeky UI\"; font-size: 16px; }";
+
+    // By default, the button is drawn with the native styling
+    let native_ops = render_button(font);
+    assert!(native_ops.iter().any(|op| match op { DisplayListOp::Border { .. } => true, _ => false }));
+    assert!(native_ops.iter().any(|op| match op { DisplayListOp::Gradient { .. } => true, _ => false }));
+
+    // With `appearance: none`, only the label text is left
+    let ops = render_button(&format!("{} .__azul-native-button {{ appearance: none; }}", font));
+    assert!(!ops.iter().any(is_widget_styling));
+    assert_eq!(ops.iter().filter(|op| is_text(op)).count(), 1);
+
+    // User CSS still applies to a button with `appearance: none`
+    let ops = render_button(&format!("{} .__azul-native-button {{ appearance: none; border: 2px solid #000000; }}", font));
+    assert_eq!(ops.iter().filter(|op| match op { DisplayListOp::Border { .. } => true, _ => false }).count(), 1);
+    assert!(!ops.iter().any(|op| match op { DisplayListOp::Gradient { .. } => true, _ => false }));
+}
+
+#[test]
+fn test_drop_shadow_of_transparent_image_follows_its_alpha() {
+
+    use dom::Dom;
+    use images::{self, ImageInfo, ImageState};
+    use display_list_capture::DisplayListOp;
+    use test_utils::{TestLayout, paint_dom};
+
+    // A 2x2 image where only the top left pixel is opaque
+    let mut pixels = vec![0; 2 * 2 * 4];
+    pixels[..4].copy_from_slice(&[0, 0, 255, 255]);
+
+    let mut app_resources = AppResources::without_system_resources();
+    let image_id = images::new_image_id();
+    let image_key = ImageKey::new(IdNamespace(0), 3);
+    app_resources.images.insert(image_id, ImageState::Uploaded(ImageInfo {
+        key: image_key,
+        descriptor: ImageDescriptor::new(2, 2, ImageFormat::BGRA8, false, false),
+        data: ImageData::new(pixels),
+    }));
+
+    let mut css = Css::new_from_string("
+        image { filter: drop-shadow(5px 5px 10px #000000); }
+    ").unwrap();
+    let dom = Dom::<TestLayout>::new(NodeType::Image(image_id));
+    let ops = paint_dom(&dom, &mut css, &mut app_resources).ops;
+
+    // The image is drawn into a stacking context with a drop shadow filter, so the shadow is
+    // cast by the opaque pixels of the image - a box shadow would be cast by its bounds
+    let image_idx = ops.iter().position(|op| match op {
+        DisplayListOp::Image { image_key: key, .. } => *key == image_key,
+        _ => false,
+    }).expect("image was not pushed");
+    let drop_shadow = FilterOp::DropShadow(LayoutVector2D::new(5.0, 5.0), 10.0, ColorF::new(0.0, 0.0, 0.0, 1.0));
+    assert_eq!(ops[image_idx - 1], DisplayListOp::PushStackingContext { filters: vec![drop_shadow] });
+    assert_eq!(ops[image_idx + 1], DisplayListOp::PopStackingContext);
+    assert!(!ops.iter().any(|op| match op { DisplayListOp::BoxShadow { .. } => true, _ => false }));
+}
+
+#[test]
+fn test_hover_border_on_border_box_does_not_relayout() {
+
+    use std::collections::BTreeSet;
+    use dom::{Dom, On, Callback, UpdateScreen};
+    use app_state::AppState;
+    use window::{UiSolver, WindowEvent};
+    use display_list_capture::DisplayListOp;
+    use test_utils::{TestLayout, paint_frame};
+
+    fn on_click(_: &mut AppState<TestLayout>, _: WindowEvent) -> UpdateScreen { UpdateScreen::DontRedraw }
+
+    fn render_frame(dom: &Dom<TestLayout>, css: &mut Css, ui_solver: &mut UiSolver<TestLayout>) -> Vec<DisplayListOp> {
+        let ui_description = TestLayout::style_dom(dom, css);
+        let display_list = DisplayList::new_from_ui_description(&ui_description);
+        paint_frame(&display_list, ui_solver, css, &mut AppResources::without_system_resources(), false, &WindowSize::default()).ops
+    }
+
+    let card = Dom::new(NodeType::Div).with_class("card").with_callback(On::MouseUp, Callback(on_click));
+    let card_tag = card.arena.borrow()[card.root].data.tag.unwrap();
+    let dom = Dom::<TestLayout>::div().with_child(card);
+    let card_id = {
+        let arena = dom.arena.borrow();
+        arena.linear_iter().find(|node_id| arena[*node_id].data.tag == Some(card_tag)).unwrap()
+    };
+
+    let hover_css = |box_sizing: &str| Css::new_from_string(&format!("
+        .card {{ box-sizing: {}; width: 100px; height: 50px; }}
+        .card:hover {{ border: 5px solid #000000; }}
+    ", box_sizing)).unwrap();
+
+    let mut css = hover_css("border-box");
+    let mut ui_solver = UiSolver::new();
+
+    let ops = render_frame(&dom, &mut css, &mut ui_solver);
+    assert!(!ops.iter().any(|op| match op { DisplayListOp::Border { .. } => true, _ => false }));
+    let card_bounds = ui_solver.query_bounds_of_rect(card_id).unwrap();
+    assert!(!css.will_relayout());
+
+    // Hovering adds the border, which is drawn inside of the border-box without a relayout
+    let mut hovered = BTreeSet::new();
+    hovered.insert(card_tag);
+    assert!(css.set_hovered_tags(hovered.clone()));
+    assert!(!css.will_relayout());
+
+    let ops = render_frame(&dom, &mut css, &mut ui_solver);
+    assert!(ops.iter().any(|op| match op { DisplayListOp::Border { rect, .. } => *rect == card_bounds, _ => false }));
+    assert_eq!(ui_solver.query_bounds_of_rect(card_id).unwrap(), card_bounds);
+
+    // Under content-box, the border would change the size of the box
+    let mut css = hover_css("content-box");
+    css.needs_relayout = false;
+    assert!(css.set_hovered_tags(hovered));
+    assert!(css.will_relayout());
+}
+
+#[test]
+fn test_display_item_estimate_counts_every_primitive_of_a_node() {
+
+    use {dom::Dom, css::Css, test_utils::TestLayout};
+
+    let css = Css::new_from_string("
+        .card { background-color: #ffffff; border: 1px solid #000000; box-shadow: 0px 2px 4px #00000044; }
+    ").unwrap();
+
+    let dom = Dom::<TestLayout>::div().with_class("card")
+        .with_child(Dom::div().with_class("card"))
+        .with_child(Dom::new(NodeType::Label("Hello".into())));
+
+    let ui_description = TestLayout::style_dom(&dom, &css);
+    let display_list = DisplayList::new_from_ui_description(&ui_description);
+
+    // Two cards with a background, a border and a shadow, plus one text run
+    assert_eq!(display_list.rectangles.nodes_len(), 3);
+    assert_eq!(display_list.estimate_display_item_count(), 3 + 3 + 1);
+    assert!(display_list.estimate_display_item_count() > display_list.rectangles.nodes_len());
+}
+
+#[test]
+fn test_background_size_and_repeat_of_image_backgrounds() {
+
+    use render_api::FakeRenderApi;
+    use images::{self, ImageInfo, ImageState};
+
+    let mut app_resources = AppResources::without_system_resources();
+    let image_id = images::new_image_id();
+    let image_key = ImageKey::new(IdNamespace(0), 3);
+    app_resources.images.insert(image_id, ImageState::Uploaded(ImageInfo {
+        key: image_key,
+        descriptor: ImageDescriptor::new(10, 10, ImageFormat::BGRA8, true, false),
+        data: ImageData::new(vec![255; 10 * 10 * 4]),
+    }));
+
+    let bounds = LayoutRect::new(LayoutPoint::new(5.0, 5.0), LayoutSize::new(100.0, 50.0));
+    let render_api = FakeRenderApi::default();
+
+    // Returns the rect and the tile size of the pushed image
+    let mut push = |size: Option<&str>, repeat: BackgroundRepeat| {
+        let info = LayoutPrimitiveInfo::new(bounds);
+        let mut builder = DisplayListBuilder::new(PipelineId(0, 0), LayoutSize::new(200.0, 200.0));
+        let background_size = size.map(|s| match ParsedCssProperty::from_kv("background-size", s) {
+            Ok(ParsedCssProperty::BackgroundSize(size)) => size,
+            other => panic!("{:?}", other),
+        });
+        push_image(&info, &mut builder, &bounds, &mut app_resources, &image_id, background_size, repeat, Epoch(0), &render_api, &mut Vec::new());
+        let (_, _, display_list) = builder.finalize();
+        let mut iter = display_list.iter();
+        let mut pushed = None;
+        while let Some(item) = iter.next() {
+            if let SpecificDisplayItem::Image(ref image) = *item.item() {
+                pushed = Some((item.rect(), image.stretch_size));
+            }
+        }
+        pushed.expect("image was not pushed")
+    };
+
+    let rect = |x: f32, y: f32, w: f32, h: f32| LayoutRect::new(LayoutPoint::new(x, y), LayoutSize::new(w, h));
+
+    // Without a background-size, the image is stretched to the bounds (as before)
+    assert_eq!(push(None, BackgroundRepeat::Repeat), (bounds, bounds.size));
+    // contain: 50x50, drawn once in the top left corner
+    assert_eq!(push(Some("contain"), BackgroundRepeat::NoRepeat), (rect(5.0, 5.0, 50.0, 50.0), LayoutSize::new(50.0, 50.0)));
+    // cover: 100x100, the part outside of the bounds is clipped
+    assert_eq!(push(Some("cover"), BackgroundRepeat::NoRepeat), (rect(5.0, 5.0, 100.0, 100.0), LayoutSize::new(100.0, 100.0)));
+    // 20px tiles, repeated over the whole bounds or only along one axis
+    assert_eq!(push(Some("20px"), BackgroundRepeat::Repeat), (bounds, LayoutSize::new(20.0, 20.0)));
+    assert_eq!(push(Some("20px"), BackgroundRepeat::RepeatX), (rect(5.0, 5.0, 100.0, 20.0), LayoutSize::new(20.0, 20.0)));
+    assert_eq!(push(Some("20px"), BackgroundRepeat::RepeatY), (rect(5.0, 5.0, 20.0, 50.0), LayoutSize::new(20.0, 20.0)));
+    // Percentages are relative to the bounds
+    assert_eq!(push(Some("50% 100%"), BackgroundRepeat::Repeat), (bounds, LayoutSize::new(50.0, 50.0)));
+}
+
+#[test]
+fn test_sizes_are_created_from_css_and_relative_to_the_parent() {
+
+    use dom::Dom;
+    use window::UiSolver;
+    use glium::glutin::dpi::LogicalSize;
+    use test_utils::{TestLayout, paint_frame};
+
+    fn layout_frame(dom: &Dom<TestLayout>, css: &mut Css, ui_solver: &mut UiSolver<TestLayout>, window_size: &WindowSize) {
+        let ui_description = TestLayout::style_dom(dom, css);
+        let display_list = DisplayList::new_from_ui_description(&ui_description);
+        paint_frame(&display_list, ui_solver, css, &mut AppResources::without_system_resources(), true, window_size);
+    }
+
+    let mut css = Css::new_from_string("
+        #sidebar { width: 25%; height: 300px; }
+        #clamped { width: 50%; min-width: 120px; max-height: 100px; }
+    ").unwrap();
+
+    let dom = Dom::<TestLayout>::div()
+        .with_child(Dom::div().with_id("sidebar")
+            .with_child(Dom::div().with_id("fill")))
+        .with_child(Dom::div().with_id("clamped"));
+    let (root, sidebar, fill, clamped) = (NodeId::new(0), NodeId::new(1), NodeId::new(2), NodeId::new(3));
+
+    let mut ui_solver = UiSolver::new();
+    let size_of = |ui_solver: &UiSolver<TestLayout>, node_id| ui_solver.query_bounds_of_rect(node_id).unwrap().size;
+
+    // The root fills the window, the percentages are relative to the parent,
+    // nodes without a size fill their parent
+    let window_size = WindowSize::default();
+    layout_frame(&dom, &mut css, &mut ui_solver, &window_size);
+    assert_eq!(size_of(&ui_solver, root), LayoutSize::new(800.0, 600.0));
+    assert_eq!(size_of(&ui_solver, sidebar), LayoutSize::new(200.0, 300.0));
+    assert_eq!(size_of(&ui_solver, fill), LayoutSize::new(200.0, 300.0));
+    assert_eq!(size_of(&ui_solver, clamped), LayoutSize::new(400.0, 100.0));
+
+    // Resizing the window re-creates the constraints, the min-width overrides the 50%
+    let window_size = WindowSize { dimensions: LogicalSize::new(200.0, 150.0), .. WindowSize::default() };
+    layout_frame(&dom, &mut css, &mut ui_solver, &window_size);
+    assert_eq!(size_of(&ui_solver, root), LayoutSize::new(200.0, 150.0));
+    assert_eq!(size_of(&ui_solver, sidebar), LayoutSize::new(50.0, 300.0));
+    assert_eq!(size_of(&ui_solver, fill), LayoutSize::new(50.0, 300.0));
+    assert_eq!(size_of(&ui_solver, clamped), LayoutSize::new(120.0, 100.0));
+}
+
+#[test]
+fn test_padding_and_margin_inset_the_children() {
+
+    use dom::Dom;
+    use window::UiSolver;
+    use test_utils::{TestLayout, paint_frame};
+
+    let mut css = Css::new_from_string("
+        #outer { padding: 10px 20px; }
+        #inner { margin: 5px; padding-left: 8px; }
+        #leaf { margin-top: 2px; height: 50px; }
+    ").unwrap();
+
+    let dom = Dom::<TestLayout>::div().with_id("outer")
+        .with_child(Dom::div().with_id("inner")
+            .with_child(Dom::div().with_id("leaf")));
+    let (outer, inner, leaf) = (NodeId::new(0), NodeId::new(1), NodeId::new(2));
+
+    let ui_description = TestLayout::style_dom(&dom, &css);
+    let display_list = DisplayList::new_from_ui_description(&ui_description);
+    let mut ui_solver = UiSolver::new();
+    paint_frame(&display_list, &mut ui_solver, &mut css, &mut AppResources::without_system_resources(), true, &WindowSize::default());
+
+    let bounds_of = |node_id| ui_solver.query_bounds_of_rect(node_id).unwrap();
+    let rect = |x, y, w, h| LayoutRect::new(LayoutPoint::new(x, y), LayoutSize::new(w, h));
+
+    // The root fills the window (800x600), its padding only insets its children
+    assert_eq!(bounds_of(outer), rect(0.0, 0.0, 800.0, 600.0));
+    // Inset by the padding of the parent plus its own margin on all sides
+    assert_eq!(bounds_of(inner), rect(25.0, 15.0, 750.0, 570.0));
+    // Only the left padding of the parent and the top margin are set
+    assert_eq!(bounds_of(leaf), rect(33.0, 17.0, 742.0, 50.0));
+}
+
+#[test]
+fn test_indeterminate_checkbox_renders_a_dash() {
+
+    use dom::Dom;
+    use display_list_capture::DisplayListOp;
+    use test_utils::{TestLayout, paint_dom};
+
+    let render_checkbox = |state: CheckBoxState| -> Vec<DisplayListOp> {
+        let mut css = Css::new_from_string(".checkbox { width: 16px; height: 16px; }").unwrap();
+        let dom = Dom::<TestLayout>::checkbox(state).with_class("checkbox");
+        paint_dom(&dom, &mut css, &mut AppResources::without_system_resources()).ops
+    };
+
+    let black = ColorF::new(0.0, 0.0, 0.0, 1.0);
+
+    assert_eq!(render_checkbox(CheckBoxState::Unchecked), vec![]);
+
+    // A single horizontal dash, vertically centered in the 16x16 checkbox
+    assert_eq!(render_checkbox(CheckBoxState::Indeterminate), vec![
+        DisplayListOp::Rect { rect: LayoutRect::new(LayoutPoint::new(4.0, 7.0), LayoutSize::new(8.0, 2.0)), color: black },
+    ]);
+
+    // The checkmark is made of many small squares instead
+    let checkmark = render_checkbox(CheckBoxState::Checked);
+    assert!(checkmark.len() > 1);
+    for op in &checkmark {
+        match op {
+            DisplayListOp::Rect { rect, color } => {
+                assert_eq!(rect.size, LayoutSize::new(2.0, 2.0));
+                assert_eq!(*color, black);
+            },
+            other => panic!("expected a rect, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn test_offscreen_content_visibility_auto_defers_the_layout_of_the_children() {
+
+    use dom::Dom;
+    use window::UiSolver;
+    use scroll::{ScrollBounds, find_scroll_chain};
+    use test_utils::{TestLayout, paint_frame};
+
+    let mut list = Dom::<TestLayout>::div().with_id("list");
+    for _ in 0..50 {
+        list.add_child(Dom::div().with_class("item"));
+    }
+    let page = Dom::<TestLayout>::div().with_id("page").with_scroll_bounds(ScrollBounds::new(0.0, 5000.0)).with_child(list);
+    let page_tag = page.arena.borrow()[page.root].data.tag.unwrap();
+    let dom = Dom::<TestLayout>::div().with_child(page);
+    let (root, list) = (NodeId::new(0), NodeId::new(2));
+    let items = (3..53).map(NodeId::new).collect::<Vec<NodeId>>();
+
+    let mut ui_solver = UiSolver::new();
+
+    let layout_with_css = |css_source: &str, scroll_states: &ScrollStates, ui_solver: &mut UiSolver<TestLayout>| {
+        let mut css = Css::new_from_string(css_source).unwrap();
+        let ui_description = TestLayout::style_dom(&dom, &css);
+        let mut display_list = DisplayList::new_from_ui_description(&ui_description);
+        display_list.set_scroll_states(scroll_states);
+        paint_frame(&display_list, ui_solver, &mut css, &mut AppResources::without_system_resources(), true, &WindowSize::default());
+    };
+
+    let css_source = "
+        #list { content-visibility: auto; margin-top: 5000px; height: 100px; }
+        .item { height: 20px; }
+    ";
+
+    // The list is far below the window, so the items don't get any constraints
+    let mut scroll_states = ScrollStates::default();
+    layout_with_css(css_source, &scroll_states, &mut ui_solver);
+
+    let constraint_count = |ui_solver: &UiSolver<TestLayout>, node_id| ui_solver.solved_layout.node_constraints[&node_id].1.len();
+    assert!(ui_solver.solved_layout.deferred_containers.contains(&list));
+    assert!(constraint_count(&ui_solver, root) > 0);
+    assert!(constraint_count(&ui_solver, list) > 0);
+    assert!(items.iter().all(|item| constraint_count(&ui_solver, *item) == 0));
+    assert_eq!(ui_solver.query_bounds_of_rect(list).unwrap().origin.y, 5000.0);
+
+    // Scrolling the page, but not far enough to reach the list, doesn't lay out the items
+    let scroll_chain = find_scroll_chain(&TestLayout::style_dom(&dom, &Css::empty()), page_tag);
+    scroll_states.scroll(&scroll_chain, (0.0, 1000.0));
+    layout_with_css(css_source, &scroll_states, &mut ui_solver);
+    assert!(ui_solver.solved_layout.deferred_containers.contains(&list));
+    assert!(items.iter().all(|item| constraint_count(&ui_solver, *item) == 0));
+
+    // Once the page is scrolled down to the list, the items are laid out
+    scroll_states.scroll(&scroll_chain, (0.0, 3950.0));
+    layout_with_css(css_source, &scroll_states, &mut ui_solver);
+
+    assert!(ui_solver.solved_layout.deferred_containers.is_empty());
+    assert!(items.iter().all(|item| constraint_count(&ui_solver, *item) > 0));
+    assert_eq!(ui_solver.query_bounds_of_rect(items[0]).unwrap().size.height, 20.0);
+}
+
+#[test]
+fn test_text_input_draws_the_caret_at_the_caret_index() {
+
+    use dom::Dom;
+    use window::UiSolver;
+    use text_input_state::TextInputState;
+    use display_list_capture::DisplayListOp;
+    use test_utils::{TestLayout, paint_frame, app_resources_with_test_font};
+
+    // Returns the number of text items and the rects that were pushed for the focused node
+    fn render_focused(dom: Dom<TestLayout>) -> (usize, Vec<LayoutRect>) {
+        let mut css = Css::new_from_string("
+            .field { font-family: \"Webly Sleeky UI\"; font-size: 16px; color: #000000; }
+        ").unwrap();
+        css.set_focused_tag(dom.arena.borrow()[dom.root].data.tag);
+
+        let ui_description = TestLayout::style_dom(&dom, &css);
+        let mut display_list = DisplayList::new_from_ui_description(&ui_description);
+        display_list.set_caret_visibility(true);
+        let ops = paint_frame(&display_list, &mut UiSolver::new(), &mut css, &mut app_resources_with_test_font(), true, &WindowSize::default()).ops;
+        let text_count = ops.iter().filter(|op| match op { DisplayListOp::Text { .. } => true, _ => false }).count();
+        let rects = ops.into_iter().filter_map(|op| match op { DisplayListOp::Rect { rect, .. } => Some(rect), _ => None }).collect();
+        (text_count, rects)
+    }
+
+    let input_caret_x = |caret: usize| {
+        let dom = Dom::text_input(TextInputState { text: String::from("azul"), caret: caret })
+            .with_class("field").with_title("Input");
+        let (text_count, rects) = render_focused(dom);
+        assert_eq!(text_count, 1);
+        assert_eq!(rects.len(), 1);
+        assert_eq!(rects[0].size.width, CARET_WIDTH);
+        rects[0].origin.x
+    };
+
+    // The caret moves to the right with the caret index
+    let (start, middle, end) = (input_caret_x(0), input_caret_x(2), input_caret_x(4));
+    assert!(start < middle && middle < end);
+
+    // At the end of the text, the caret is drawn where the caret of a label with the same text is drawn
+    let label = Dom::new(NodeType::Label(String::from("azul"))).with_class("field").with_title("Input");
+    let (_, label_rects) = render_focused(label);
+    assert_eq!(label_rects[0].origin.x, end);
+}
+
+#[test]
+fn test_missing_background_image_paints_the_fallback_color() {
+
+    use dom::Dom;
+    use display_list_capture::DisplayListOp;
+    use test_utils::{TestLayout, paint_dom};
+
+    let ops_for_css = |css_source: &str| {
+        let mut css = Css::new_from_string(css_source).unwrap();
+        paint_dom(&Dom::<TestLayout>::div(), &mut css, &mut AppResources::without_system_resources()).ops
+    };
+
+    // "missing" was never added to the `AppResources`
+    let window_rect = LayoutRect::new(LayoutPoint::zero(), LayoutSize::new(800.0, 600.0));
+    assert_eq!(ops_for_css("div { background: image(\"missing\"), #cccccc; }"), vec![
+        DisplayListOp::Rect { rect: window_rect, color: ColorU { r: 204, g: 204, b: 204, a: 255 }.into() },
+    ]);
+
+    // Without a fallback color, nothing is painted
+    assert_eq!(ops_for_css("div { background: image(\"missing\"); }"), vec![]);
+}
+
+#[test]
+fn test_cached_subtree_is_painted_as_a_single_image() {
+
+    use dom::Dom;
+    use window::UiSolver;
+    use display_list_capture::DisplayListOp;
+    use test_utils::{TestLayout, paint_frame};
+
+    fn dom(dot_class: &str) -> Dom<TestLayout> {
+        Dom::div().with_child(
+            Dom::div().with_class("illustration").with_cached_rendering()
+                .with_child(Dom::div().with_class("dot"))
+                .with_child(Dom::div().with_class("dot"))
+                .with_child(Dom::div().with_class(dot_class)))
+    }
+
+    fn paint(dom: &Dom<TestLayout>, ui_solver: &mut UiSolver<TestLayout>, render_cache: &RenderCache) -> Vec<DisplayListOp> {
+        let mut css = Css::new_from_string("
+            .illustration { width: 200px; height: 100px; background: #ff0000; }
+            .dot, .big-dot { width: 10px; height: 10px; background: #0000ff; }
+            .big-dot { width: 20px; }
+        ").unwrap();
+        let ui_description = TestLayout::style_dom(dom, &css);
+        let mut display_list = DisplayList::new_from_ui_description(&ui_description);
+        display_list.set_render_cache(render_cache);
+        paint_frame(&display_list, ui_solver, &mut css, &mut AppResources::without_system_resources(), true, &WindowSize::default()).ops
+    }
 
+    let mut ui_solver = UiSolver::new();
+    let mut render_cache = RenderCache::default();
+
+    // No image yet - the illustration and its three dots are painted and the subtree is captured
+    let ops = paint(&dom("dot"), &mut ui_solver, &render_cache);
+    assert_eq!(ops.iter().filter(|op| match op { DisplayListOp::Rect { .. } => true, _ => false }).count(), 4);
+    assert_eq!(ui_solver.solved_layout.render_cache_captures.len(), 1);
+
+    // What `update_render_cache` does after the frame was rendered
+    let capture = ui_solver.solved_layout.render_cache_captures.remove(0);
+    let illustration_bounds = capture.bounds;
+    assert_eq!(illustration_bounds.size, LayoutSize::new(200.0, 100.0));
+    let image_key = ImageKey::new(IdNamespace(0), 100);
+    render_cache.insert(capture, image_key);
+
+    // The next frame pushes the image instead of the primitives of the subtree
+    assert_eq!(paint(&dom("dot"), &mut ui_solver, &render_cache), vec![
+        DisplayListOp::Image { rect: illustration_bounds, image_key: image_key },
+    ]);
+    assert!(ui_solver.solved_layout.render_cache_captures.is_empty());
+
+    // Changing a node of the subtree invalidates the image
+    let ops = paint(&dom("big-dot"), &mut ui_solver, &render_cache);
+    assert!(!ops.contains(&DisplayListOp::Image { rect: illustration_bounds, image_key: image_key }));
+    assert_eq!(ops.iter().filter(|op| match op { DisplayListOp::Rect { .. } => true, _ => false }).count(), 4);
+    assert_eq!(ui_solver.solved_layout.render_cache_captures.len(), 1);
 }
@@ -0,0 +1,175 @@
+//! Captures the primitives of a built display list as plain data, so that rendering
+//! regression ("golden") tests can assert on the exact display items that are emitted
+//! for a DOM + CSS, without a GPU.
+//!
+//! The capture can be compared directly (`==`) or printed (one item per line), i.e.
+//! to compare it against the contents of a golden file.
+
+use std::fmt;
+use webrender::api::{
+    BuiltDisplayList, SpecificDisplayItem, LayoutRect, LayoutPoint, ColorF,
+    BorderWidths, BorderRadius, FontInstanceKey, ImageKey, GlyphIndex, FilterOp,
+    ItemTag, LayoutVector2D,
+};
+
+/// A single primitive of a display list
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum DisplayListOp {
+    /// `push_rect`
+    Rect { rect: LayoutRect, color: ColorF },
+    /// `push_text` - the clip of the text, the glyph indices and the positions of the glyphs
+    Text { rect: LayoutRect, clip_rect: LayoutRect, font_instance_key: FontInstanceKey, color: ColorF, glyphs: Vec<(GlyphIndex, LayoutPoint)> },
+    /// `push_border`
+    Border { rect: LayoutRect, widths: BorderWidths },
+    /// `push_image`
+    Image { rect: LayoutRect, image_key: ImageKey },
+    /// `push_box_shadow`
+    BoxShadow { rect: LayoutRect, color: ColorF, blur_radius: f32, spread_radius: f32, border_radius: BorderRadius },
+    /// `push_gradient`
+    Gradient { rect: LayoutRect },
+    /// `push_radial_gradient`
+    RadialGradient { rect: LayoutRect },
+    /// `define_clip`
+    Clip { rect: LayoutRect },
+    /// `push_stacking_context`, with the filters of the stacking context
+    PushStackingContext { filters: Vec<FilterOp> },
+    /// `pop_stacking_context`
+    PopStackingContext,
+    /// Any other display item, that azul doesn't emit
+    Other,
+}
+
+/// The primitives of a display list, in the order that they were pushed
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DisplayListCapture {
+    pub(crate) ops: Vec<DisplayListOp>,
+    /// The hit-test tag of each primitive, in the same order as the `ops`
+    pub(crate) tags: Vec<Option<ItemTag>>,
+}
+
+impl DisplayListCapture {
+
+    /// Captures the display items of the finalized builder that was returned
+    /// by `DisplayList::into_display_list_builder`
+    pub(crate) fn new(display_list: &BuiltDisplayList) -> Self {
+
+        use self::DisplayListOp::*;
+
+        let mut ops = Vec::new();
+        let mut tags = Vec::new();
+        let mut iter = display_list.iter();
+
+        while let Some(item) = iter.next() {
+            let rect = item.rect();
+            let op = match *item.item() {
+                SpecificDisplayItem::Rectangle(ref r) => Rect { rect: rect, color: r.color },
+                SpecificDisplayItem::Text(ref t) => Text {
+                    rect: rect,
+                    clip_rect: *item.clip_rect(),
+                    font_instance_key: t.font_key,
+                    color: t.color,
+                    glyphs: display_list.get(item.glyphs()).map(|glyph| (glyph.index, glyph.point)).collect(),
+                },
+                SpecificDisplayItem::Border(ref b) => Border { rect: rect, widths: b.widths },
+                SpecificDisplayItem::Image(ref i) => Image { rect: rect, image_key: i.image_key },
+                SpecificDisplayItem::BoxShadow(ref s) => BoxShadow {
+                    rect: rect,
+                    color: s.color,
+                    blur_radius: s.blur_radius,
+                    spread_radius: s.spread_radius,
+                    border_radius: s.border_radius,
+                },
+                SpecificDisplayItem::Gradient(_) => Gradient { rect: rect },
+                SpecificDisplayItem::RadialGradient(_) => RadialGradient { rect: rect },
+                SpecificDisplayItem::Clip(_) => Clip { rect: rect },
+                SpecificDisplayItem::PushStackingContext(_) => PushStackingContext { filters: display_list.get(item.filters()).collect() },
+                SpecificDisplayItem::PopStackingContext => PopStackingContext,
+                // The gradient stops belong to the following gradient item
+                SpecificDisplayItem::SetGradientStops => continue,
+                _ => Other,
+            };
+            ops.push(op);
+            tags.push(item.get_layout_primitive_info(&LayoutVector2D::zero()).tag);
+        }
+
+        Self { ops, tags }
+    }
+}
+
+fn fmt_rect(rect: &LayoutRect) -> String {
+    format!("{}x{} at ({}, {})", rect.size.width, rect.size.height, rect.origin.x, rect.origin.y)
+}
+
+fn fmt_border_radius(radius: &BorderRadius) -> String {
+    format!("{}x{} {}x{} {}x{} {}x{}",
+        radius.top_left.width, radius.top_left.height, radius.top_right.width, radius.top_right.height,
+        radius.bottom_right.width, radius.bottom_right.height, radius.bottom_left.width, radius.bottom_left.height)
+}
+
+fn fmt_color(color: &ColorF) -> String {
+    format!("rgba({}, {}, {}, {})", color.r, color.g, color.b, color.a)
+}
+
+impl fmt::Display for DisplayListOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::DisplayListOp::*;
+        match self {
+            Rect { rect, color } => write!(f, "rect {} {}", fmt_rect(rect), fmt_color(color)),
+            Text { rect, clip_rect, font_instance_key, color, glyphs } => {
+                write!(f, "text {} clip {} font {:?} {}", fmt_rect(rect), fmt_rect(clip_rect), font_instance_key, fmt_color(color))?;
+                for (index, point) in glyphs {
+                    write!(f, " {}@({}, {})", index, point.x, point.y)?;
+                }
+                Ok(())
+            },
+            Border { rect, widths } => write!(f, "border {} widths {} {} {} {}",
+                fmt_rect(rect), widths.top, widths.right, widths.bottom, widths.left),
+            Image { rect, image_key } => write!(f, "image {} {:?}", fmt_rect(rect), image_key),
+            BoxShadow { rect, color, blur_radius, spread_radius, border_radius } => write!(f, "box-shadow {} {} blur {} spread {} radius {}",
+                fmt_rect(rect), fmt_color(color), blur_radius, spread_radius, fmt_border_radius(border_radius)),
+            Gradient { rect } => write!(f, "gradient {}", fmt_rect(rect)),
+            RadialGradient { rect } => write!(f, "radial-gradient {}", fmt_rect(rect)),
+            Clip { rect } => write!(f, "clip {}", fmt_rect(rect)),
+            PushStackingContext { filters } => write!(f, "push-stacking-context {:?}", filters),
+            PopStackingContext => write!(f, "pop-stacking-context"),
+            Other => write!(f, "other"),
+        }
+    }
+}
+
+impl fmt::Display for DisplayListCapture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for op in &self.ops {
+            writeln!(f, "{}", op)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_capture_div_with_background_and_border() {
+
+    use webrender::api::LayoutSize;
+    use {
+        dom::Dom,
+        css::Css,
+        resources::AppResources,
+        test_utils::{TestLayout, paint_dom},
+    };
+
+    let mut css = Css::new_from_string("
+        div { background-color: #ff0000; border: 2px solid #000000; }
+    ").unwrap();
+
+    let dom = Dom::<TestLayout>::div();
+    let capture = paint_dom(&dom, &mut css, &mut AppResources::without_system_resources());
+
+    // The div has no size, so it fills the window (800x600 by default)
+    let bounds = LayoutRect::new(LayoutPoint::zero(), LayoutSize::new(800.0, 600.0));
+
+    assert_eq!(capture.ops, vec![
+        DisplayListOp::Rect { rect: bounds, color: ColorF::new(1.0, 0.0, 0.0, 1.0) },
+        DisplayListOp::Border { rect: bounds, widths: BorderWidths { top: 2.0, right: 2.0, bottom: 2.0, left: 2.0 } },
+    ]);
+    assert_eq!(capture.to_string(), "rect 800x600 at (0, 0) rgba(1, 0, 0, 1)\nborder 800x600 at (0, 0) widths 2 2 2 2\n");
+}
@@ -1,9 +1,9 @@
 use std::{
     fmt,
     rc::Rc,
-    cell::RefCell,
+    cell::{Cell, RefCell},
     hash::{Hash, Hasher},
-    sync::atomic::{AtomicUsize, Ordering},
+    thread::LocalKey,
     collections::BTreeMap,
 };
 use glium::{Texture2d, framebuffer::SimpleFrameBuffer};
@@ -15,11 +15,40 @@ use {
     traits::Layout,
     app_state::AppState,
     id_tree::{NodeId, Node, Arena},
+    css_parser::ParsedCssProperty,
+    scroll::ScrollBounds,
+    spinner::SpinnerValue,
+    checkbox::CheckBoxState,
+    text_input_state::TextInputState,
+    focus::FOCUS_TRAP_ATTRIBUTE,
+    render_cache::RENDER_CACHE_ATTRIBUTE,
 };
 
-/// This is only accessed from the main thread, so it's safe to use
-pub(crate) static NODE_ID: AtomicUsize = AtomicUsize::new(0);
-pub(crate) static CALLBACK_ID: AtomicUsize = AtomicUsize::new(0);
+thread_local! {
+    /// The tag of the next node that gets a callback. The DOM is only built on the main
+    /// thread, so the counters are thread-local (which also keeps them independent in tests)
+    static NODE_ID: Cell<u64> = Cell::new(0);
+    /// The ID of the next callback in `collect_callbacks`
+    static CALLBACK_ID: Cell<u64> = Cell::new(0);
+}
+
+fn next_id(counter: &'static LocalKey<Cell<u64>>) -> u64 {
+    counter.with(|c| {
+        let id = c.get();
+        c.set(id + 1);
+        id
+    })
+}
+
+/// Resets the node tags and callback IDs, has to be called before the DOM of a frame is built.
+///
+/// Because the DOM is rebuilt from scratch every frame, the tags would otherwise grow forever.
+/// This way, the tags stay bounded by the number of nodes with callbacks and the same DOM
+/// always gets the same tags (which is necessary for i.e. keeping the focus across frames).
+pub(crate) fn reset_tag_counters() {
+    NODE_ID.with(|c| c.set(0));
+    CALLBACK_ID.with(|c| c.set(0));
+}
 
 /// A callback function has to return if the screen should
 /// be updated after the function has run.PartialEq
@@ -57,6 +86,10 @@ impl<T: Layout> Clone for Callback<T> {
 /// as a unique ID for the function. This way, we can hash and compare DOM nodes
 /// (to create diffs between two states). Comparing usizes is more efficient
 /// than re-creating the whole DOM and serves as a caching mechanism.
+///
+/// The address of a function can change between two compilations of the program,
+/// so the hash is only stable within one process. Use `Dom::with_callback_id` if the
+/// hash of the DOM has to be reproducible across runs.
 impl<T: Layout> Hash for Callback<T> {
   fn hash<H>(&self, state: &mut H) where H: Hasher {
     state.write_usize(self.0 as usize);
@@ -90,6 +123,15 @@ pub enum NodeType {
     /// Equality and Hash values are only checked by the OpenGl texture ID,
     /// azul does not check that the contents of two textures are the same
     GlTexture(Texture),
+    /// Loading spinner, painted as an arc in the text color of the node. An indeterminate
+    /// spinner (`None`) rotates continuously, a determinate spinner shows its progress
+    Spinner(Option<SpinnerValue>),
+    /// Checkbox, the checkmark (or the dash of an indeterminate checkbox)
+    /// is painted in the text color of the node
+    CheckBox(CheckBoxState),
+    /// Editable single-line text, the caret is drawn while the input is focused.
+    /// Hash and equality include the text, so that editing the text changes the DOM
+    TextInput(TextInputState),
 }
 
 impl NodeType {
@@ -100,6 +142,9 @@ impl NodeType {
             Label(_) | Text(_) => "p",
             Image(_) => "image",
             GlTexture(_) => "texture",
+            Spinner(_) => "spinner",
+            CheckBox(_) => "checkbox",
+            TextInput(_) => "input",
         }
     }
 }
@@ -186,8 +231,20 @@ pub enum On {
     MouseEnter,
     /// Mouse cursor has left the element
     MouseLeave,
-    /// Mousewheel / touchpad scrolling
+    /// Mousewheel / touchpad scrolling, the scrolled lines / pixels are in `WindowEvent::scroll_delta`
     Scroll,
+    /// The element has received the keyboard focus
+    FocusReceived,
+    /// The element has lost the keyboard focus
+    FocusLost,
+    /// A key has been pressed while the element had the keyboard focus,
+    /// the key is passed to the callback in the `WindowEvent::keyboard_input`
+    KeyDown,
+    /// A key has been released while the element had the keyboard focus
+    KeyUp,
+    /// The left mouse button has been clicked twice on the element within the
+    /// `double_click_interval` of the window, fired after the `MouseUp` of the second click
+    DoubleClick,
 }
 
 pub struct NodeData<T: Layout> {
@@ -201,6 +258,27 @@ pub struct NodeData<T: Layout> {
     pub events: CallbackList<T>,
     /// Tag for hit-testing
     pub tag: Option<u64>,
+    /// Dynamic CSS overrides that are scoped to this node and its children
+    /// (for example the overrides of a single component instance).
+    ///
+    /// A `[[ dynamic_id | default ]]` property is resolved by walking up
+    /// from the node, the nearest scope that provides the ID wins. If no scope
+    /// provides the ID, the global overrides of the `Css` are used.
+    pub dynamic_css_overrides: BTreeMap<String, ParsedCssProperty>,
+    /// Disabled nodes don't invoke their callbacks and can be
+    /// styled via the `:disabled` pseudo-class
+    pub disabled: bool,
+    /// Tooltip that is shown when the mouse hovers over the node for a while
+    pub title: Option<String>,
+    /// Text that is shown (dimmed) instead of the text of a `Label` while the label is empty,
+    /// i.e. the placeholder of a `TextInput`
+    pub placeholder: Option<String>,
+    /// Makes the node a scroll container that can be scrolled by the given amount,
+    /// see `Dom::with_scroll_bounds`
+    pub scroll_bounds: Option<ScrollBounds>,
+    /// Custom attributes of the node (`data-state` -> `open`), which can be
+    /// matched by attribute selectors in the CSS (`[data-state="open"]`)
+    pub attributes: BTreeMap<String, String>,
 }
 
 impl<T: Layout> PartialEq for NodeData<T> {
@@ -209,7 +287,13 @@ impl<T: Layout> PartialEq for NodeData<T> {
         self.id == other.id &&
         self.classes == other.classes &&
         self.events == other.events &&
-        self.tag == other.tag
+        self.tag == other.tag &&
+        self.dynamic_css_overrides == other.dynamic_css_overrides &&
+        self.disabled == other.disabled &&
+        self.title == other.title &&
+        self.placeholder == other.placeholder &&
+        self.scroll_bounds == other.scroll_bounds &&
+        self.attributes == other.attributes
     }
 }
 
@@ -223,6 +307,12 @@ impl<T: Layout> Default for NodeData<T> {
             classes: Vec::new(),
             events: CallbackList::default(),
             tag: None,
+            dynamic_css_overrides: BTreeMap::new(),
+            disabled: false,
+            title: None,
+            placeholder: None,
+            scroll_bounds: None,
+            attributes: BTreeMap::new(),
         }
     }
 }
@@ -235,6 +325,16 @@ impl<T: Layout> Hash for NodeData<T> {
             class.hash(state);
         }
         self.events.hash(state);
+        // ParsedCssProperty can't be hashed (it contains floats), so only the IDs are hashed
+        for override_id in self.dynamic_css_overrides.keys() {
+            override_id.hash(state);
+        }
+        self.disabled.hash(state);
+        self.title.hash(state);
+        self.placeholder.hash(state);
+        // ScrollBounds can't be hashed (it contains floats), so the bits of the floats are hashed
+        self.scroll_bounds.map(|b| (b.max_x.to_bits(), b.max_y.to_bits())).hash(state);
+        self.attributes.hash(state);
     }
 }
 
@@ -256,6 +356,12 @@ impl<T: Layout> Clone for NodeData<T> {
             classes: self.classes.clone(),
             events: self.events.special_clone(),
             tag: self.tag.clone(),
+            dynamic_css_overrides: self.dynamic_css_overrides.clone(),
+            disabled: self.disabled,
+            title: self.title.clone(),
+            placeholder: self.placeholder.clone(),
+            scroll_bounds: self.scroll_bounds,
+            attributes: self.attributes.clone(),
         }
     }
 }
@@ -268,13 +374,25 @@ impl<T: Layout> fmt::Debug for NodeData<T> {
                 \tid: {:?}, \
                 \tclasses: {:?}, \
                 \tevents: {:?}, \
-                \ttag: {:?} \
+                \ttag: {:?}, \
+                \tdynamic_css_overrides: {:?}, \
+                \tdisabled: {:?}, \
+                \ttitle: {:?}, \
+                \tplaceholder: {:?}, \
+                \tscroll_bounds: {:?}, \
+                \tattributes: {:?} \
             }}",
         self.node_type,
         self.id,
         self.classes,
         self.events,
-        self.tag)
+        self.tag,
+        self.dynamic_css_overrides,
+        self.disabled,
+        self.title,
+        self.placeholder,
+        self.scroll_bounds,
+        self.attributes)
     }
 }
 
@@ -284,7 +402,11 @@ impl<T: Layout> PartialEq for CallbackList<T> {
         return false;
     }
     self.callbacks.iter().all(|(key, val)| {
-        rhs.callbacks.get(key) == Some(val)
+        match (self.stable_ids.get(key), rhs.stable_ids.get(key)) {
+            (Some(a), Some(b)) => rhs.callbacks.contains_key(key) && a == b,
+            (None, None) => rhs.callbacks.get(key) == Some(val),
+            _ => false,
+        }
     })
   }
 }
@@ -293,6 +415,7 @@ impl<T: Layout> CallbackList<T> {
     fn special_clone(&self) -> Self {
         Self {
             callbacks: self.callbacks.clone(),
+            stable_ids: self.stable_ids.clone(),
         }
     }
 }
@@ -306,6 +429,12 @@ impl<T: Layout> NodeData<T> {
             classes: Vec::new(),
             events: CallbackList::<T>::new(),
             tag: None,
+            dynamic_css_overrides: BTreeMap::new(),
+            disabled: false,
+            title: None,
+            placeholder: None,
+            scroll_bounds: None,
+            attributes: BTreeMap::new(),
         }
     }
 
@@ -318,6 +447,12 @@ impl<T: Layout> NodeData<T> {
             classes: self.classes.clone(),
             events: self.events.special_clone(),
             tag: self.tag.clone(),
+            dynamic_css_overrides: self.dynamic_css_overrides.clone(),
+            disabled: self.disabled,
+            title: self.title.clone(),
+            placeholder: self.placeholder.clone(),
+            scroll_bounds: self.scroll_bounds,
+            attributes: self.attributes.clone(),
         }
     }
 }
@@ -346,21 +481,29 @@ impl<T: Layout> fmt::Debug for Dom<T> {
 
 #[derive(Clone, Eq)]
 pub struct CallbackList<T: Layout> {
-    pub callbacks: BTreeMap<On, Callback<T>>
+    pub callbacks: BTreeMap<On, Callback<T>>,
+    /// User-provided IDs of the callbacks (see `Dom::with_callback_id`). A callback
+    /// with a stable ID is hashed and compared by the ID instead of the function pointer
+    pub stable_ids: BTreeMap<On, u64>,
 }
 
 impl<T: Layout> Default for CallbackList<T> {
     fn default() -> Self {
         Self {
             callbacks: BTreeMap::default(),
+            stable_ids: BTreeMap::default(),
         }
     }
 }
 
 impl<T: Layout> Hash for CallbackList<T> {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        for callback in &self.callbacks {
-            callback.hash(state);
+        for (on, callback) in &self.callbacks {
+            on.hash(state);
+            match self.stable_ids.get(on) {
+                Some(stable_id) => (true, stable_id).hash(state),
+                None => (false, callback).hash(state),
+            }
         }
     }
 }
@@ -375,6 +518,7 @@ impl<T: Layout> CallbackList<T> {
     pub fn new() -> Self {
         Self {
             callbacks: BTreeMap::new(),
+            stable_ids: BTreeMap::new(),
         }
     }
 }
@@ -445,6 +589,57 @@ impl<T: Layout> Dom<T> {
         }
     }
 
+    /// Shorthand for `Dom::new(NodeType::Div)`
+    #[inline]
+    pub fn div() -> Self {
+        Self::new(NodeType::Div)
+    }
+
+    /// Shorthand for `Dom::new(NodeType::Label(text.into()))`
+    #[inline]
+    pub fn label<S: Into<String>>(text: S) -> Self {
+        Self::new(NodeType::Label(text.into()))
+    }
+
+    /// Shorthand for `Dom::new(NodeType::Spinner(None))`: an indeterminate spinner,
+    /// which rotates as long as it is in the DOM
+    #[inline]
+    pub fn spinner() -> Self {
+        Self::new(NodeType::Spinner(None))
+    }
+
+    /// A spinner that shows the progress `value` (from 0.0 to 1.0) as an arc,
+    /// i.e. a quarter circle for 0.25
+    #[inline]
+    pub fn determinate_spinner(value: f32) -> Self {
+        Self::new(NodeType::Spinner(Some(SpinnerValue::new(value))))
+    }
+
+    /// Shorthand for `Dom::new(NodeType::CheckBox(state))`
+    #[inline]
+    pub fn checkbox(state: CheckBoxState) -> Self {
+        Self::new(NodeType::CheckBox(state))
+    }
+
+    /// Shorthand for `Dom::new(NodeType::TextInput(state))`
+    #[inline]
+    pub fn text_input(state: TextInputState) -> Self {
+        Self::new(NodeType::TextInput(state))
+    }
+
+    /// Wraps the DOM in a modal, which traps the keyboard focus: while the modal is in the
+    /// DOM, Tab / Shift+Tab only cycle through the focusable nodes inside of the modal.
+    ///
+    /// To close the modal with Escape, add a shortcut for Escape (`AppState::add_shortcut`)
+    /// that removes the modal from the DOM.
+    #[inline]
+    pub fn modal(self) -> Self {
+        Self::div()
+            .with_class("__azul-native-modal")
+            .with_attribute(FOCUS_TRAP_ATTRIBUTE, "true")
+            .with_child(self)
+    }
+
     /// Adds a sibling to the current DOM
     pub fn add_sibling(&mut self, sibling: Self) {
 
@@ -606,12 +801,76 @@ impl<T: Layout> Dom<T> {
         self
     }
 
+    /// Same as `set_callback_id`, but easier to use for method chaining in a builder-style pattern
+    #[inline]
+    pub fn with_callback_id(mut self, on: On, id: u64, callback: Callback<T>) -> Self {
+        self.set_callback_id(on, id, callback);
+        self
+    }
+
+    /// Same as `set_disabled`, but easier to use for method chaining in a builder-style pattern
+    #[inline]
+    pub fn with_disabled(mut self, disabled: bool) -> Self {
+        self.set_disabled(disabled);
+        self
+    }
+
+    /// Same as `set_title`, but easier to use for method chaining in a builder-style pattern
+    #[inline]
+    pub fn with_title<S: Into<String>>(mut self, title: S) -> Self {
+        self.set_title(title);
+        self
+    }
+
+    /// Same as `set_placeholder`, but easier to use for method chaining in a builder-style pattern
+    #[inline]
+    pub fn with_placeholder<S: Into<String>>(mut self, placeholder: S) -> Self {
+        self.set_placeholder(placeholder);
+        self
+    }
+
+    /// Same as `set_scroll_bounds`, but easier to use for method chaining in a builder-style pattern
+    #[inline]
+    pub fn with_scroll_bounds(mut self, bounds: ScrollBounds) -> Self {
+        self.set_scroll_bounds(bounds);
+        self
+    }
+
+    /// Same as `set_attribute`, but easier to use for method chaining in a builder-style pattern
+    #[inline]
+    pub fn with_attribute<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.set_attribute(key, value);
+        self
+    }
+
+    /// Paints the DOM as a single image, which is only re-captured when a node of the DOM
+    /// changes, instead of painting all of its nodes on every frame. Only useful for large,
+    /// static DOMs (i.e. an illustration made of many nodes): the image contains everything
+    /// that is painted behind the DOM and only the root of the DOM receives callbacks.
+    #[inline]
+    pub fn with_cached_rendering(self) -> Self {
+        self.with_attribute(RENDER_CACHE_ATTRIBUTE, "true")
+    }
+
+    /// Same as `set_css_override`, but easier to use for method chaining in a builder-style pattern
+    #[inline]
+    pub fn with_css_override<S: Into<String>>(mut self, id: S, property: ParsedCssProperty) -> Self {
+        self.set_css_override(id, property);
+        self
+    }
+
     #[inline]
     pub fn with_child(mut self, child: Self) -> Self {
         self.add_child(child);
         self
     }
 
+    /// Appends a `Label` with the given text as a child, same as `.with_child(Dom::label(text))`
+    #[inline]
+    pub fn with_text<S: Into<String>>(self, text: S) -> Self {
+        self.with_child(Self::label(text))
+    }
+
     #[inline]
     pub fn with_sibling(mut self, sibling: Self) -> Self {
         self.add_sibling(sibling);
@@ -631,7 +890,111 @@ impl<T: Layout> Dom<T> {
     #[inline]
     pub fn set_callback(&mut self, on: On, callback: Callback<T>) {
         self.arena.borrow_mut()[self.head].data.events.callbacks.insert(on, callback);
-        self.arena.borrow_mut()[self.head].data.tag = Some(NODE_ID.fetch_add(1, Ordering::SeqCst) as u64);
+        self.arena.borrow_mut()[self.head].data.events.stable_ids.remove(&on);
+        self.arena.borrow_mut()[self.head].data.tag = Some(next_id(&NODE_ID));
+    }
+
+    /// Same as `set_callback`, but the callback is hashed and compared by the given ID
+    /// instead of the address of the function. The address can differ between two builds
+    /// of the program, the ID doesn't, so the hash of the DOM stays reproducible across runs
+    /// (i.e. for caches that are stored on disk).
+    ///
+    /// Two different functions must not use the same ID, otherwise the DOM diff
+    /// can't tell them apart.
+    #[inline]
+    pub fn set_callback_id(&mut self, on: On, id: u64, callback: Callback<T>) {
+        self.set_callback(on, callback);
+        self.arena.borrow_mut()[self.head].data.events.stable_ids.insert(on, id);
+    }
+
+    /// Disables or enables the current node. The callbacks of a disabled node are
+    /// not invoked and the node matches the `:disabled` pseudo-class in the CSS
+    #[inline]
+    pub fn set_disabled(&mut self, disabled: bool) {
+        self.arena.borrow_mut()[self.head].data.disabled = disabled;
+    }
+
+    /// Sets the tooltip of the current node, which is shown after the mouse hovered over the
+    /// node for the `tooltip_delay` of the window (see `WindowCreateOptions`)
+    #[inline]
+    pub fn set_title<S: Into<String>>(&mut self, title: S) {
+        let mut arena = self.arena.borrow_mut();
+        let data = &mut arena[self.head].data;
+        data.title = Some(title.into());
+        // The node has to be hit-tested in order to detect the hover
+        if data.tag.is_none() {
+            data.tag = Some(next_id(&NODE_ID));
+        }
+    }
+
+    /// Sets the placeholder of the current node, which is shown instead of
+    /// the text of a `Label` with a dimmed color while the text is empty
+    #[inline]
+    pub fn set_placeholder<S: Into<String>>(&mut self, placeholder: S) {
+        self.arena.borrow_mut()[self.head].data.placeholder = Some(placeholder.into());
+    }
+
+    /// Makes the current node a scroll container, which can be scrolled by the given bounds.
+    /// The `On::Scroll` callback of the node is only invoked while the node is scrolled, once it
+    /// reaches its boundary, the scroll events are passed on to the next outer scroll container
+    #[inline]
+    pub fn set_scroll_bounds(&mut self, bounds: ScrollBounds) {
+        let mut arena = self.arena.borrow_mut();
+        let data = &mut arena[self.head].data;
+        data.scroll_bounds = Some(bounds);
+        // The node has to be hit-tested in order to receive the scroll events
+        if data.tag.is_none() {
+            data.tag = Some(next_id(&NODE_ID));
+        }
+    }
+
+    /// Sets a custom attribute of the current node, i.e. `("data-state", "open")`.
+    /// The node then matches the `[data-state="open"]` attribute selector in the CSS
+    #[inline]
+    pub fn set_attribute<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) {
+        self.arena.borrow_mut()[self.head].data.attributes.insert(key.into(), value.into());
+    }
+
+    /// Calls `f` for every node in the DOM, in pre-order (every node is visited before its children)
+    pub fn visit<F: FnMut(NodeId, &NodeData<T>)>(&self, mut f: F) {
+        let arena = self.arena.borrow();
+        for node_id in self.pre_order_node_ids() {
+            f(node_id, &arena[node_id].data);
+        }
+    }
+
+    /// Same as `visit`, but allows the nodes to be modified
+    pub fn visit_mut<F: FnMut(NodeId, &mut NodeData<T>)>(&mut self, mut f: F) {
+        let node_ids = self.pre_order_node_ids();
+        let mut arena = self.arena.borrow_mut();
+        for node_id in node_ids {
+            f(node_id, &mut arena[node_id].data);
+        }
+    }
+
+    /// Returns the IDs of all nodes (including the siblings of the root node), in pre-order
+    fn pre_order_node_ids(&self) -> Vec<NodeId> {
+        use id_tree::NodeEdge;
+
+        let arena = &*self.arena.borrow();
+        let mut node_ids = Vec::with_capacity(arena.nodes_len());
+
+        for root in self.root.following_siblings(arena) {
+            for edge in root.traverse(arena) {
+                if let NodeEdge::Start(node_id) = edge {
+                    node_ids.push(node_id);
+                }
+            }
+        }
+
+        node_ids
+    }
+
+    /// Overrides the dynamic CSS property `id` for the current node and all of its children,
+    /// without affecting nodes outside of this subtree
+    #[inline]
+    pub fn set_css_override<S: Into<String>>(&mut self, id: S, property: ParsedCssProperty) {
+        self.arena.borrow_mut()[self.head].data.dynamic_css_overrides.insert(id.into(), property);
     }
 }
 
@@ -645,8 +1008,12 @@ impl<T: Layout> Dom<T> {
         for item in self.root.traverse(&*self.arena.borrow()) {
             let mut cb_id_list = BTreeMap::<On, u64>::new();
             let item = &self.arena.borrow()[item.inner_value()];
+            // The callbacks of disabled nodes are never invoked
+            if item.data.disabled {
+                continue;
+            }
             for (on, callback) in item.data.events.callbacks.iter() {
-                let callback_id = CALLBACK_ID.fetch_add(1, Ordering::SeqCst) as u64;
+                let callback_id = next_id(&CALLBACK_ID);
                 callback_list.insert(callback_id, *callback);
                 cb_id_list.insert(*on, callback_id);
             }
@@ -743,6 +1110,160 @@ fn test_dom_from_iter_1() {
             classes: Vec::new(),
             tag: None,
             events: CallbackList::default(),
+            dynamic_css_overrides: BTreeMap::new(),
+            disabled: false,
+            title: None,
+            placeholder: None,
+            scroll_bounds: None,
+            attributes: BTreeMap::new(),
         }
     }));
+}
+
+#[test]
+fn test_dom_label_and_div_shorthands() {
+
+    use test_utils::TestLayout;
+    let label = Dom::<TestLayout>::label("x");
+    assert_eq!(label.arena.borrow()[label.root].data.node_type, NodeType::Label(String::from("x")));
+
+    let div = Dom::<TestLayout>::div();
+    assert_eq!(div.arena.borrow()[div.root].data.node_type, NodeType::Div);
+}
+
+#[test]
+fn test_dom_with_text_appends_label_child() {
+
+    use test_utils::TestLayout;
+
+    let dom = Dom::<TestLayout>::div().with_text("y");
+    let arena = dom.arena.borrow();
+
+    assert_eq!(arena[dom.root].data.node_type, NodeType::Div);
+    let label = arena[dom.root].first_child().expect("div has no label child");
+    assert_eq!(arena[label].data.node_type, NodeType::Label(String::from("y")));
+    assert_eq!(arena[dom.root].last_child(), Some(label));
+}
+
+#[test]
+fn test_dom_visit() {
+
+    use test_utils::TestLayout;
+
+    let dom = Dom::<TestLayout>::new(NodeType::Div)
+        .with_child(
+            Dom::new(NodeType::Div)
+            .with_id("sibling-1")
+            .with_child(Dom::new(NodeType::Div)
+                .with_id("sibling-1-child-1")))
+        .with_child(Dom::new(NodeType::Div)
+            .with_id("sibling-2")
+            .with_child(Dom::new(NodeType::Div)
+                .with_id("sibling-2-child-1")));
+
+    let mut node_count = 0;
+    let mut ids = Vec::new();
+    dom.visit(|_, node| {
+        node_count += 1;
+        if let Some(ref id) = node.id {
+            ids.push(id.clone());
+        }
+    });
+
+    assert_eq!(node_count, 5);
+    // Pre-order: every node is visited before its children
+    assert_eq!(ids, vec!["sibling-1", "sibling-1-child-1", "sibling-2", "sibling-2-child-1"]);
+}
+
+#[test]
+fn test_dom_visit_mut() {
+
+    use test_utils::TestLayout;
+
+    let mut dom = Dom::<TestLayout>::div()
+        .with_child(Dom::div().with_class("a").with_text("hello"))
+        .with_child(Dom::label("world"));
+    dom.visit_mut(|_, node| node.classes.push(String::from("visited")));
+
+    let mut visited_nodes = 0;
+    dom.visit(|_, node| {
+        assert_eq!(node.classes.last(), Some(&String::from("visited")));
+        visited_nodes += 1;
+    });
+    assert_eq!(visited_nodes, 4);
+}
+
+#[test]
+fn test_tags_stay_bounded_when_rebuilding_dom() {
+
+    use test_utils::TestLayout;
+
+    fn on_click(_: &mut AppState<TestLayout>, _: WindowEvent) -> UpdateScreen { UpdateScreen::Redraw }
+
+    let layout = || Dom::<TestLayout>::new(NodeType::Div)
+        .with_child(Dom::new(NodeType::Div).with_callback(On::MouseUp, Callback(on_click)))
+        .with_child(Dom::new(NodeType::Div).with_callback(On::MouseUp, Callback(on_click)))
+        .with_child(Dom::new(NodeType::Div)
+            .with_callback(On::MouseUp, Callback(on_click))
+            .with_callback(On::MouseDown, Callback(on_click)));
+
+    let mut first_frame = None;
+
+    for _ in 0..1000 {
+        reset_tag_counters();
+        let dom = layout();
+        let mut callback_list = BTreeMap::new();
+        let mut node_ids_to_callbacks_list = BTreeMap::new();
+        dom.collect_callbacks(&mut callback_list, &mut node_ids_to_callbacks_list);
+
+        // Every `with_callback` allocates a tag, so 4 tags and 4 callbacks
+        assert!(node_ids_to_callbacks_list.keys().all(|tag| *tag < 4));
+        assert!(callback_list.keys().all(|id| *id < 4));
+
+        // Rebuilding the same DOM results in the same tags
+        if let Some(ref first) = first_frame {
+            assert_eq!(*first, node_ids_to_callbacks_list);
+            continue;
+        }
+        first_frame = Some(node_ids_to_callbacks_list);
+    }
+}
+
+#[test]
+fn test_stable_callback_ids_make_the_dom_hash_reproducible() {
+
+    use test_utils::TestLayout;
+    // Two different functions, i.e. the same handler in two builds of the program
+    fn on_click_a(_: &mut AppState<TestLayout>, _: WindowEvent) -> UpdateScreen { UpdateScreen::Redraw }
+    fn on_click_b(_: &mut AppState<TestLayout>, _: WindowEvent) -> UpdateScreen { UpdateScreen::DontRedraw }
+
+    fn node_hashes(dom: &Dom<TestLayout>) -> Vec<DomHash> {
+        dom.arena.borrow().nodes.iter().map(|node| node.data.calculate_node_data_hash()).collect()
+    }
+
+    // Built with the builder-style functions
+    let first = Dom::<TestLayout>::div()
+        .with_class("button")
+        .with_child(Dom::label("Ok").with_callback_id(On::MouseUp, 1, Callback(on_click_a)));
+
+    // Built with the setters, with a different function
+    let mut label = Dom::label("Ok");
+    label.set_callback_id(On::MouseUp, 1, Callback(on_click_b));
+    let mut second = Dom::<TestLayout>::div();
+    second.set_class("button");
+    second.add_child(label);
+
+    assert_eq!(node_hashes(&first), node_hashes(&second));
+
+    // Without the IDs, the function pointers are hashed
+    let third = Dom::<TestLayout>::div()
+        .with_class("button")
+        .with_child(Dom::label("Ok").with_callback(On::MouseUp, Callback(on_click_b)));
+    assert_ne!(node_hashes(&first)[1], node_hashes(&third)[1]);
+
+    // A different ID results in a different hash
+    let fourth = Dom::<TestLayout>::div()
+        .with_class("button")
+        .with_child(Dom::label("Ok").with_callback_id(On::MouseUp, 2, Callback(on_click_a)));
+    assert_ne!(node_hashes(&first)[1], node_hashes(&fourth)[1]);
 }
\ No newline at end of file
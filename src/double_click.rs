@@ -0,0 +1,111 @@
+//! Detection of double clicks
+//!
+//! Every time the left mouse button is released, the topmost node under the cursor is
+//! compared to the node of the previous click. If both clicks hit the same node within
+//! the double click interval of the window and the cursor didn't move more than a few
+//! pixels in between, `On::DoubleClick` is fired in addition to the `On::MouseUp` /
+//! `On::LeftMouseUp` events of the second click.
+
+use std::time::{Duration, Instant};
+
+/// Default maximum time between the two clicks of a double click
+pub(crate) const DEFAULT_DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
+/// How far the cursor may move between the two clicks of a double click, in pixels
+const DOUBLE_CLICK_RADIUS: f32 = 4.0;
+
+/// A click that could be the first click of a double click
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Click {
+    /// Tag of the topmost node under the cursor
+    tag: u64,
+    at: Instant,
+    cursor: (f32, f32),
+}
+
+/// Remembers the last click of a window
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DoubleClickState {
+    /// Maximum time between the two clicks
+    interval: Duration,
+    last_click: Option<Click>,
+}
+
+impl Default for DoubleClickState {
+    fn default() -> Self {
+        Self::new(DEFAULT_DOUBLE_CLICK_INTERVAL)
+    }
+}
+
+impl DoubleClickState {
+
+    pub(crate) fn new(interval: Duration) -> Self {
+        Self {
+            interval: interval,
+            last_click: None,
+        }
+    }
+
+    /// Called when the left mouse button was released. `tag` is the tag of the topmost node
+    /// under the cursor, `None` if the cursor isn't over a node with a tag.
+    ///
+    /// Returns whether the click is the second click of a double click. The click that
+    /// completes a double click can't start a new one, so a triple click only fires
+    /// a single double click.
+    pub(crate) fn on_click(&mut self, tag: Option<u64>, cursor: (f32, f32), now: Instant) -> bool {
+        let tag = match tag {
+            Some(t) => t,
+            None => {
+                self.last_click = None;
+                return false;
+            },
+        };
+
+        let is_double_click = match self.last_click {
+            Some(last) => {
+                let (dx, dy) = (cursor.0 - last.cursor.0, cursor.1 - last.cursor.1);
+                last.tag == tag &&
+                now.duration_since(last.at) <= self.interval &&
+                (dx * dx + dy * dy).sqrt() <= DOUBLE_CLICK_RADIUS
+            },
+            None => false,
+        };
+
+        self.last_click = if is_double_click { None } else { Some(Click { tag, at: now, cursor }) };
+        is_double_click
+    }
+}
+
+#[test]
+fn test_double_click_detection() {
+    let start = Instant::now();
+    let after = |millis| start + Duration::from_millis(millis);
+    let mut state = DoubleClickState::default();
+
+    // Two clicks on the same node within the interval
+    assert!(!state.on_click(Some(1), (10.0, 10.0), start));
+    assert!(state.on_click(Some(1), (12.0, 11.0), after(300)));
+
+    // The third click starts a new double click
+    assert!(!state.on_click(Some(1), (12.0, 11.0), after(400)));
+    assert!(state.on_click(Some(1), (12.0, 11.0), after(500)));
+
+    // Too slow
+    assert!(!state.on_click(Some(1), (10.0, 10.0), after(1000)));
+    assert!(!state.on_click(Some(1), (10.0, 10.0), after(1500)));
+
+    // Another node
+    assert!(!state.on_click(Some(2), (10.0, 10.0), after(1600)));
+
+    // The cursor moved too far
+    assert!(!state.on_click(Some(2), (30.0, 10.0), after(1700)));
+
+    // Clicking empty space in between
+    assert!(!state.on_click(None, (30.0, 10.0), after(1800)));
+    assert!(!state.on_click(Some(2), (30.0, 10.0), after(1900)));
+
+    // A custom interval
+    let mut state = DoubleClickState::new(Duration::from_millis(100));
+    assert!(!state.on_click(Some(1), (0.0, 0.0), start));
+    assert!(!state.on_click(Some(1), (0.0, 0.0), after(300)));
+}
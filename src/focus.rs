@@ -0,0 +1,729 @@
+//! Keeps track of which DOM node currently has the keyboard focus
+//!
+//! Nodes are identified by their hit-testing tag (the same tag that is used
+//! for looking up the callbacks of a node). Focus changes are not dispatched
+//! immediately - they are queued and dispatched to the callbacks
+//! (`On::FocusReceived` / `On::FocusLost`) at the beginning of the next frame.
+//!
+//! Pressing Enter or Space while a node is focused "activates" the node, i.e. its
+//! `On::MouseUp` callback is invoked, as if the node was clicked.
+//!
+//! All key presses / releases (except for global shortcuts) are sent to the
+//! `On::KeyDown` / `On::KeyUp` callbacks of the focused node.
+//!
+//! While a node is focused, the text caret blinks: the caret toggles its visibility
+//! every `CARET_BLINK_INTERVAL` and the screen is only redrawn when the caret toggles.
+//!
+//! The text of a focused `Label` can be selected: Ctrl+A selects the whole text
+//! and Ctrl+C copies the selected text to the clipboard. Labels with `user-select: none`
+//! can't be selected, neither via the keyboard nor by dragging the mouse.
+//!
+//! Clicking a node with a callback focuses it (even if it has no `On::FocusReceived`
+//! callback, i.e. a node that only handles `On::KeyDown`), clicking anywhere else
+//! blurs the focused node.
+//!
+//! Tab / Shift+Tab move the focus to the next / previous node with a callback. While a
+//! modal (see `Dom::modal`) is in the DOM, the focus is trapped inside of the modal.
+
+use std::time::{Duration, Instant};
+use glium::glutin::{KeyboardInput, ElementState, VirtualKeyCode};
+use clipboard2::ClipboardError;
+use {
+    dom::{Dom, NodeData, On, UpdateScreen, NodeType},
+    css_parser::UserSelect,
+    resources::AppResources,
+    traits::Layout,
+    ui_description::UiDescription,
+};
+
+/// How long the caret stays visible / invisible before toggling
+pub(crate) const CARET_BLINK_INTERVAL: Duration = Duration::from_millis(530);
+/// Attribute of the root node of a focus trap, see `Dom::modal`
+pub(crate) const FOCUS_TRAP_ATTRIBUTE: &str = "aria-modal";
+
+/// Direction in which Tab (`Next`) or Shift+Tab (`Previous`) moves the focus
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum FocusDirection {
+    Next,
+    Previous,
+}
+
+/// Which node (identified by its tag) currently has the focus
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct FocusState {
+    /// Tag of the currently focused node, `None` if no node is focused
+    pub(crate) focused_tag: Option<u64>,
+    /// Focus events that still have to be dispatched to the callbacks
+    /// of the nodes, in the order they happened
+    pub(crate) pending_events: Vec<(u64, On)>,
+    /// Blink timer of the caret of the focused node
+    pub(crate) caret: CaretBlink,
+    /// Selected text of the focused node, `None` if nothing is selected
+    pub(crate) selection: Option<TextSelection>,
+    /// Character where the current mouse drag selection started,
+    /// `None` if the mouse isn't selecting text
+    pub(crate) drag_anchor: Option<usize>,
+}
+
+/// Range of selected characters (not bytes) in the text of a node, `start..end`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct TextSelection {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+impl TextSelection {
+    /// Returns the selected part of the `text`
+    pub(crate) fn selected_text<'a>(&self, text: &'a str) -> &'a str {
+        let byte_index = |char_index: usize| text.char_indices().nth(char_index).map(|(idx, _)| idx).unwrap_or(text.len());
+        let start = byte_index(self.start.min(self.end));
+        let end = byte_index(self.start.max(self.end));
+        &text[start..end]
+    }
+}
+
+/// Keyboard shortcuts that act on the text selection of the focused node
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum SelectionShortcut {
+    /// Ctrl+A
+    SelectAll,
+    /// Ctrl+C
+    Copy,
+}
+
+/// Write access to a clipboard, so that copying can be tested without a system clipboard
+pub(crate) trait ClipboardWriter {
+    fn write_string(&mut self, contents: String) -> Result<(), ClipboardError>;
+}
+
+impl<'a> ClipboardWriter for AppResources<'a> {
+    fn write_string(&mut self, contents: String) -> Result<(), ClipboardError> {
+        self.set_clipboard_string(contents)
+    }
+}
+
+/// Toggles the visibility of the text caret in a fixed interval
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct CaretBlink {
+    /// How long the caret stays visible / invisible
+    interval: Duration,
+    /// When the caret was last toggled, `None` if the timer wasn't started yet
+    last_toggle: Option<Instant>,
+    /// Whether the caret is currently visible
+    is_visible: bool,
+}
+
+impl Default for CaretBlink {
+    fn default() -> Self {
+        Self::new(CARET_BLINK_INTERVAL)
+    }
+}
+
+impl CaretBlink {
+
+    pub(crate) fn new(interval: Duration) -> Self {
+        Self {
+            interval: interval,
+            last_toggle: None,
+            is_visible: true,
+        }
+    }
+
+    /// Makes the caret visible and restarts the timer on the next `tick`,
+    /// so that the caret doesn't disappear immediately after a focus change
+    pub(crate) fn reset(&mut self) {
+        self.last_toggle = None;
+        self.is_visible = true;
+    }
+
+    /// Advances the timer to `now`. Returns `UpdateScreen::Redraw` if the visibility
+    /// of the caret changed since the last call, i.e. at most once per interval.
+    ///
+    /// If more than one interval has passed since the last tick (i.e. because a frame
+    /// took very long), the skipped toggles are not drawn, only the resulting state is.
+    pub(crate) fn tick(&mut self, now: Instant) -> UpdateScreen {
+        let last_toggle = match self.last_toggle {
+            Some(t) => t,
+            None => {
+                self.last_toggle = Some(now);
+                return UpdateScreen::DontRedraw;
+            }
+        };
+
+        let interval_nanos = duration_to_nanos(self.interval);
+        if now <= last_toggle || interval_nanos == 0 {
+            return UpdateScreen::DontRedraw;
+        }
+
+        let elapsed_intervals = duration_to_nanos(now.duration_since(last_toggle)) / interval_nanos;
+        if elapsed_intervals == 0 {
+            return UpdateScreen::DontRedraw;
+        }
+
+        self.last_toggle = Some(last_toggle + nanos_to_duration(elapsed_intervals * interval_nanos));
+
+        if elapsed_intervals % 2 == 0 {
+            // Toggled an even number of times, the caret looks the same as before
+            UpdateScreen::DontRedraw
+        } else {
+            self.is_visible = !self.is_visible;
+            UpdateScreen::Redraw
+        }
+    }
+
+    /// Returns whether the caret should currently be drawn
+    pub(crate) fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+}
+
+fn duration_to_nanos(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000_000 + duration.subsec_nanos() as u64
+}
+
+fn nanos_to_duration(nanos: u64) -> Duration {
+    Duration::new(nanos / 1_000_000_000, (nanos % 1_000_000_000) as u32)
+}
+
+impl FocusState {
+
+    /// Focuses the node with the given tag, blurs the previously focused node (if any).
+    ///
+    /// Returns `UpdateScreen::DontRedraw` if the node was already focused.
+    pub(crate) fn focus(&mut self, tag: u64) -> UpdateScreen {
+        if self.focused_tag == Some(tag) {
+            return UpdateScreen::DontRedraw;
+        }
+
+        if let Some(previously_focused) = self.focused_tag.take() {
+            self.pending_events.push((previously_focused, On::FocusLost));
+        }
+
+        self.focused_tag = Some(tag);
+        self.pending_events.push((tag, On::FocusReceived));
+        self.caret.reset();
+        self.selection = None;
+        self.drag_anchor = None;
+        UpdateScreen::Redraw
+    }
+
+    /// Removes the focus from the currently focused node.
+    ///
+    /// Returns `UpdateScreen::DontRedraw` if no node was focused.
+    pub(crate) fn blur(&mut self) -> UpdateScreen {
+        self.selection = None;
+        self.drag_anchor = None;
+        match self.focused_tag.take() {
+            Some(previously_focused) => {
+                self.pending_events.push((previously_focused, On::FocusLost));
+                UpdateScreen::Redraw
+            },
+            None => UpdateScreen::DontRedraw,
+        }
+    }
+
+    /// Focuses the node that was clicked (see `find_focusable_tag`), if no focusable
+    /// node was clicked, the focused node is blurred.
+    ///
+    /// Returns `UpdateScreen::DontRedraw` if the focus didn't change.
+    pub(crate) fn focus_clicked(&mut self, clicked: Option<u64>) -> UpdateScreen {
+        match clicked {
+            Some(tag) => self.focus(tag),
+            None => self.blur(),
+        }
+    }
+
+    /// If the `input` is an Enter or Space key release, returns the tag of the focused node
+    /// together with the event that should be invoked on it (`On::MouseUp`).
+    ///
+    /// Returns `None` if no node is focused or if the key isn't an activation key.
+    pub(crate) fn keyboard_activation_event(&self, input: &KeyboardInput) -> Option<(u64, On)> {
+        use glium::glutin::VirtualKeyCode::*;
+
+        // Like a mouse click, the node is only activated once the key is released
+        if input.state != ElementState::Released {
+            return None;
+        }
+
+        match input.virtual_keycode {
+            Some(Return) | Some(NumpadEnter) | Some(Space) => self.focused_tag.map(|tag| (tag, On::MouseUp)),
+            _ => None,
+        }
+    }
+
+    /// Returns the tag of the focused node together with the `On::KeyDown` / `On::KeyUp`
+    /// event for the `input`, or `None` if no node is focused
+    pub(crate) fn keyboard_event(&self, input: &KeyboardInput) -> Option<(u64, On)> {
+        let on = match input.state {
+            ElementState::Pressed => On::KeyDown,
+            ElementState::Released => On::KeyUp,
+        };
+        self.focused_tag.map(|tag| (tag, on))
+    }
+
+    /// If the `input` is a Tab key press, returns the direction in which the focus
+    /// should move (`FocusDirection::Previous` if Shift is held down), otherwise `None`
+    pub(crate) fn tab_navigation(&self, input: &KeyboardInput) -> Option<FocusDirection> {
+        if input.state != ElementState::Pressed || input.virtual_keycode != Some(VirtualKeyCode::Tab) {
+            return None;
+        }
+
+        Some(if input.modifiers.shift { FocusDirection::Previous } else { FocusDirection::Next })
+    }
+
+    /// Focuses the next / previous node of the `tab_order` (see `get_tab_order`), wrapping
+    /// around at the end. If the focused node isn't part of the tab order (i.e. because it
+    /// is outside of a modal), the first / last node of the tab order is focused.
+    pub(crate) fn focus_in_tab_order(&mut self, tab_order: &[u64], direction: FocusDirection) -> UpdateScreen {
+        let len = tab_order.len();
+        if len == 0 {
+            return UpdateScreen::DontRedraw;
+        }
+
+        let current = self.focused_tag.and_then(|tag| tab_order.iter().position(|t| *t == tag));
+        let next = match (current, direction) {
+            (Some(idx), FocusDirection::Next) => (idx + 1) % len,
+            (Some(idx), FocusDirection::Previous) => (idx + len - 1) % len,
+            (None, FocusDirection::Next) => 0,
+            (None, FocusDirection::Previous) => len - 1,
+        };
+
+        self.focus(tab_order[next])
+    }
+
+    /// Advances the caret blink timer, see `CaretBlink::tick`. The caret only
+    /// blinks while a node is focused, otherwise nothing has to be redrawn.
+    pub(crate) fn tick_caret(&mut self, now: Instant) -> UpdateScreen {
+        if self.focused_tag.is_none() {
+            return UpdateScreen::DontRedraw;
+        }
+        self.caret.tick(now)
+    }
+
+    /// Returns whether the caret of the focused node should currently be drawn
+    pub(crate) fn is_caret_visible(&self) -> bool {
+        self.focused_tag.is_some() && self.caret.is_visible()
+    }
+
+    /// Selects the given range of the text of the focused node. Does nothing if no node is focused.
+    pub(crate) fn select(&mut self, selection: TextSelection) -> UpdateScreen {
+        if self.focused_tag.is_none() || self.selection == Some(selection) {
+            return UpdateScreen::DontRedraw;
+        }
+        self.selection = Some(selection);
+        UpdateScreen::Redraw
+    }
+
+    /// Starts selecting the text of the focused node with the mouse, at the character
+    /// `char_index`. If the text isn't selectable (`user-select: none`), the drag is ignored.
+    pub(crate) fn begin_drag_selection(&mut self, char_index: usize, user_select: UserSelect) {
+        self.drag_anchor = match user_select {
+            UserSelect::Text if self.focused_tag.is_some() => Some(char_index),
+            _ => None,
+        };
+    }
+
+    /// Extends the current drag selection to the character `char_index` (the character under the cursor).
+    ///
+    /// Returns `UpdateScreen::Redraw` if the selection changed.
+    pub(crate) fn update_drag_selection(&mut self, char_index: usize) -> UpdateScreen {
+        match self.drag_anchor {
+            Some(anchor) => self.select(TextSelection { start: anchor, end: char_index }),
+            None => UpdateScreen::DontRedraw,
+        }
+    }
+
+    /// Stops selecting text with the mouse, the selection is kept
+    pub(crate) fn end_drag_selection(&mut self) {
+        self.drag_anchor = None;
+    }
+
+    /// If the `input` is a Ctrl+A or Ctrl+C key press (Cmd on macOS) while a node
+    /// is focused, returns the shortcut, otherwise `None`
+    pub(crate) fn selection_shortcut(&self, input: &KeyboardInput) -> Option<SelectionShortcut> {
+        if self.focused_tag.is_none() || input.state != ElementState::Pressed {
+            return None;
+        }
+
+        let is_command_pressed = if cfg!(target_os = "macos") { input.modifiers.logo } else { input.modifiers.ctrl };
+        if !is_command_pressed {
+            return None;
+        }
+
+        match input.virtual_keycode {
+            Some(VirtualKeyCode::A) => Some(SelectionShortcut::SelectAll),
+            Some(VirtualKeyCode::C) => Some(SelectionShortcut::Copy),
+            _ => None,
+        }
+    }
+
+    /// Executes the selection `shortcut` on the `text` of the focused node: selects the whole
+    /// text or writes the selected part of the text to the `clipboard`.
+    ///
+    /// Returns `UpdateScreen::Redraw` if the selection changed.
+    pub(crate) fn handle_selection_shortcut<C: ClipboardWriter>(&mut self, shortcut: SelectionShortcut, text: &str, clipboard: &mut C)
+    -> Result<UpdateScreen, ClipboardError>
+    {
+        match shortcut {
+            SelectionShortcut::SelectAll => {
+                Ok(self.select(TextSelection { start: 0, end: text.chars().count() }))
+            },
+            SelectionShortcut::Copy => {
+                if let Some(selection) = self.selection {
+                    let selected_text = selection.selected_text(text);
+                    if !selected_text.is_empty() {
+                        clipboard.write_string(selected_text.to_string())?;
+                    }
+                }
+                Ok(UpdateScreen::DontRedraw)
+            },
+        }
+    }
+
+    /// Returns the focus events that haven't been dispatched yet and clears the queue
+    pub(crate) fn take_pending_events(&mut self) -> Vec<(u64, On)> {
+        ::std::mem::replace(&mut self.pending_events, Vec::new())
+    }
+}
+
+#[test]
+fn test_focus_node_and_blur() {
+    let mut focus = FocusState::default();
+
+    let text_input_tag = 5;
+    assert_eq!(focus.focus(text_input_tag), UpdateScreen::Redraw);
+    assert_eq!(focus.focused_tag, Some(text_input_tag));
+    assert_eq!(focus.take_pending_events(), vec![(text_input_tag, On::FocusReceived)]);
+
+    // Focusing the same node again doesn't fire any events
+    assert_eq!(focus.focus(text_input_tag), UpdateScreen::DontRedraw);
+    assert!(focus.take_pending_events().is_empty());
+
+    // Focusing another node blurs the old node first
+    assert_eq!(focus.focus(7), UpdateScreen::Redraw);
+    assert_eq!(focus.take_pending_events(), vec![(text_input_tag, On::FocusLost), (7, On::FocusReceived)]);
+
+    assert_eq!(focus.blur(), UpdateScreen::Redraw);
+    assert_eq!(focus.focused_tag, None);
+    assert_eq!(focus.take_pending_events(), vec![(7, On::FocusLost)]);
+    assert_eq!(focus.blur(), UpdateScreen::DontRedraw);
+}
+
+#[test]
+fn test_clicking_focuses_and_clicking_empty_space_blurs() {
+    use dom::Callback;
+    use app_state::AppState;
+    use window::WindowEvent;
+    use test_utils::TestLayout;
+
+    fn on_key_down(_: &mut AppState<TestLayout>, _: WindowEvent) -> UpdateScreen { UpdateScreen::Redraw }
+
+    // The text field has no focus callbacks, but it can still be focused to receive `KeyDown`
+    let dom = Dom::<TestLayout>::div()
+        .with_child(Dom::label("field").with_callback(On::KeyDown, Callback(on_key_down)))
+        .with_child(Dom::label("disabled").with_callback(On::MouseUp, Callback(on_key_down)).with_disabled(true));
+    let (field_tag, disabled_tag) = {
+        let arena = dom.arena.borrow();
+        let field = arena[dom.root].first_child().unwrap();
+        let disabled = arena[field].next_sibling().unwrap();
+        (arena[field].data.tag.unwrap(), arena[disabled].data.tag.unwrap())
+    };
+
+    let mut focus = FocusState::default();
+
+    let clicked = find_focusable_tag(&dom, &[field_tag]);
+    assert_eq!(clicked, Some(field_tag));
+    assert_eq!(focus.focus_clicked(clicked), UpdateScreen::Redraw);
+    assert_eq!(focus.take_pending_events(), vec![(field_tag, On::FocusReceived)]);
+
+    // Clicking the focused node again doesn't fire any events
+    assert_eq!(focus.focus_clicked(find_focusable_tag(&dom, &[field_tag])), UpdateScreen::DontRedraw);
+    assert!(focus.take_pending_events().is_empty());
+
+    // Disabled nodes can't be focused, so clicking them is the same as clicking empty space
+    let clicked = find_focusable_tag(&dom, &[disabled_tag]);
+    assert_eq!(clicked, None);
+    assert_eq!(focus.focus_clicked(clicked), UpdateScreen::Redraw);
+    assert_eq!(focus.take_pending_events(), vec![(field_tag, On::FocusLost)]);
+    assert_eq!(focus.focused_tag, None);
+
+    // Nothing is focused, clicking empty space again doesn't fire any events
+    assert_eq!(focus.focus_clicked(find_focusable_tag(&dom, &[])), UpdateScreen::DontRedraw);
+    assert!(focus.take_pending_events().is_empty());
+}
+
+#[test]
+fn test_keyboard_activation_of_focused_node() {
+    use glium::glutin::{VirtualKeyCode, ModifiersState};
+
+    let key = |state, virtual_keycode| KeyboardInput {
+        scancode: 0,
+        state: state,
+        virtual_keycode: Some(virtual_keycode),
+        modifiers: ModifiersState::default(),
+    };
+
+    let mut focus = FocusState::default();
+    let button_tag = 3;
+
+    // Nothing is focused, nothing gets activated
+    assert_eq!(focus.keyboard_activation_event(&key(ElementState::Released, VirtualKeyCode::Space)), None);
+
+    focus.focus(button_tag);
+
+    assert_eq!(focus.keyboard_activation_event(&key(ElementState::Pressed, VirtualKeyCode::Space)), None);
+    assert_eq!(focus.keyboard_activation_event(&key(ElementState::Released, VirtualKeyCode::Space)), Some((button_tag, On::MouseUp)));
+    assert_eq!(focus.keyboard_activation_event(&key(ElementState::Released, VirtualKeyCode::Return)), Some((button_tag, On::MouseUp)));
+    assert_eq!(focus.keyboard_activation_event(&key(ElementState::Released, VirtualKeyCode::A)), None);
+}
+
+#[test]
+fn test_caret_blinks_only_while_focused() {
+    let interval = Duration::from_millis(500);
+    let mut focus = FocusState { caret: CaretBlink::new(interval), .. Default::default() };
+    let t0 = Instant::now();
+
+    // Nothing focused: no caret, no redraws
+    assert_eq!(focus.tick_caret(t0), UpdateScreen::DontRedraw);
+    assert_eq!(focus.tick_caret(t0 + interval * 3), UpdateScreen::DontRedraw);
+    assert!(!focus.is_caret_visible());
+
+    focus.focus(5);
+    assert!(focus.is_caret_visible());
+
+    // The first tick after the focus change starts the timer
+    assert_eq!(focus.tick_caret(t0), UpdateScreen::DontRedraw);
+
+    let mut redraws = 0;
+    let mut visibility = Vec::new();
+
+    // Tick every 16ms (one frame) for 5 intervals
+    let mut now = t0;
+    while now < t0 + interval * 5 + Duration::from_millis(250) {
+        now += Duration::from_millis(16);
+        if focus.tick_caret(now) == UpdateScreen::Redraw {
+            redraws += 1;
+            visibility.push(focus.is_caret_visible());
+        }
+    }
+
+    // Exactly one redraw per interval, the caret alternates between hidden and visible
+    assert_eq!(redraws, 5);
+    assert_eq!(visibility, vec![false, true, false, true, false]);
+
+    // Refocusing makes the caret visible again
+    focus.blur();
+    focus.focus(7);
+    assert!(focus.is_caret_visible());
+}
+
+/// Returns the tags of the nodes that can be focused with Tab, in document order: all nodes
+/// with a callback that aren't disabled. If the DOM contains a focus trap (see `Dom::modal`),
+/// only the nodes inside of the last (topmost) focus trap can be focused.
+pub(crate) fn get_tab_order<T: Layout>(ui_description: &UiDescription<T>) -> Vec<u64> {
+    let arena = ui_description.ui_descr_arena.borrow();
+    let root = match ui_description.ui_descr_root {
+        Some(root) => root,
+        None => return Vec::new(),
+    };
+
+    let focus_trap = root.descendants(&arena)
+        .filter(|node_id| arena[*node_id].data.attributes.get(FOCUS_TRAP_ATTRIBUTE).map(|v| v == "true").unwrap_or(false))
+        .last();
+
+    focus_trap.unwrap_or(root).descendants(&arena).filter_map(|node_id| {
+        let node = &arena[node_id].data;
+        if is_focusable(node) { node.tag } else { None }
+    }).collect()
+}
+
+/// Nodes with any callback can be focused, unless they are disabled
+fn is_focusable<T: Layout>(node: &NodeData<T>) -> bool {
+    !node.disabled && !node.events.callbacks.is_empty()
+}
+
+/// Returns the first tag of the `hit_tags` (the tags of the nodes under the cursor,
+/// topmost node first) that belongs to a focusable node, `None` if no node can be focused
+pub(crate) fn find_focusable_tag<T: Layout>(dom: &Dom<T>, hit_tags: &[u64]) -> Option<u64> {
+    let mut focusable_tags = Vec::new();
+    dom.visit(|_, node| {
+        if is_focusable(node) {
+            focusable_tags.extend(node.tag);
+        }
+    });
+    hit_tags.iter().cloned().find(|tag| focusable_tags.contains(tag))
+}
+
+/// Returns the text of the node with the given tag if the text can be selected
+/// (currently only the text of a `Label` or a `TextInput` without `user-select: none`), `None` otherwise
+pub(crate) fn find_selectable_text<T: Layout>(ui_description: &UiDescription<T>, tag: u64) -> Option<String> {
+    let arena = ui_description.ui_descr_arena.borrow();
+    let node_id = arena.linear_iter().find(|node_id| arena[*node_id].data.tag == Some(tag))?;
+    let text = match arena[node_id].data.node_type {
+        NodeType::Label(ref label) => label.clone(),
+        NodeType::TextInput(ref input) => input.text.clone(),
+        _ => return None,
+    };
+    match ui_description.computed_style(node_id).user_select {
+        Some(UserSelect::None) => None,
+        _ => Some(text),
+    }
+}
+
+#[test]
+fn test_select_all_and_copy() {
+    use glium::glutin::ModifiersState;
+
+    #[derive(Default)]
+    struct MockClipboard {
+        contents: Vec<String>,
+    }
+
+    impl ClipboardWriter for MockClipboard {
+        fn write_string(&mut self, contents: String) -> Result<(), ClipboardError> {
+            self.contents.push(contents);
+            Ok(())
+        }
+    }
+
+    let text = "Hällo World";
+    let mut clipboard = MockClipboard::default();
+    let mut focus = FocusState::default();
+
+    // Nothing can be selected without focus
+    assert_eq!(focus.select(TextSelection { start: 0, end: 5 }), UpdateScreen::DontRedraw);
+    focus.focus(3);
+
+    // Copy a range of the text
+    assert_eq!(focus.select(TextSelection { start: 1, end: 5 }), UpdateScreen::Redraw);
+    assert_eq!(focus.handle_selection_shortcut(SelectionShortcut::Copy, text, &mut clipboard).unwrap(), UpdateScreen::DontRedraw);
+    assert_eq!(clipboard.contents, vec![String::from("ällo")]);
+
+    // Ctrl+A selects the whole text
+    let ctrl_a = KeyboardInput {
+        scancode: 0,
+        state: ElementState::Pressed,
+        virtual_keycode: Some(VirtualKeyCode::A),
+        modifiers: ModifiersState { ctrl: !cfg!(target_os = "macos"), logo: cfg!(target_os = "macos"), .. Default::default() },
+    };
+    let shortcut = focus.selection_shortcut(&ctrl_a).unwrap();
+    assert_eq!(shortcut, SelectionShortcut::SelectAll);
+    assert_eq!(focus.handle_selection_shortcut(shortcut, text, &mut clipboard).unwrap(), UpdateScreen::Redraw);
+    focus.handle_selection_shortcut(SelectionShortcut::Copy, text, &mut clipboard).unwrap();
+    assert_eq!(clipboard.contents.last(), Some(&String::from(text)));
+
+    // Without the modifier, "A" is just a key press
+    let a = KeyboardInput { modifiers: ModifiersState::default(), .. ctrl_a };
+    assert_eq!(focus.selection_shortcut(&a), None);
+
+    // Blurring clears the selection
+    focus.blur();
+    assert_eq!(focus.selection, None);
+}
+
+#[test]
+fn test_user_select_none_label_is_not_selected_on_drag() {
+    use dom::{Dom, Callback};
+    use app_state::AppState;
+    use css::Css;
+    use window::WindowEvent;
+    use test_utils::TestLayout;
+
+    fn on_click(_: &mut AppState<TestLayout>, _: WindowEvent) -> UpdateScreen { UpdateScreen::DontRedraw }
+
+    let selectable = Dom::new(NodeType::Label(String::from("Hello"))).with_callback(On::MouseUp, Callback(on_click));
+    let locked = Dom::new(NodeType::Label(String::from("Locked"))).with_class("locked").with_callback(On::MouseUp, Callback(on_click));
+    let selectable_tag = selectable.arena.borrow()[selectable.root].data.tag.unwrap();
+    let locked_tag = locked.arena.borrow()[locked.root].data.tag.unwrap();
+    let dom = Dom::div().with_child(selectable).with_child(locked);
+
+    let css = Css::new_from_string(".locked { user-select: none; }").unwrap();
+    let ui_description = TestLayout::style_dom(&dom, &css);
+
+    let user_select_of = |tag: u64| {
+        let arena = ui_description.ui_descr_arena.borrow();
+        let node_id = arena.linear_iter().find(|node_id| arena[*node_id].data.tag == Some(tag)).unwrap();
+        ui_description.computed_style(node_id).user_select.unwrap_or_default()
+    };
+
+    let mut focus = FocusState::default();
+
+    // Dragging over a selectable label selects the text
+    focus.focus(selectable_tag);
+    focus.begin_drag_selection(1, user_select_of(selectable_tag));
+    assert_eq!(focus.update_drag_selection(4), UpdateScreen::Redraw);
+    focus.end_drag_selection();
+    assert_eq!(focus.selection, Some(TextSelection { start: 1, end: 4 }));
+    assert_eq!(find_selectable_text(&ui_description, selectable_tag), Some(String::from("Hello")));
+
+    // A label with `user-select: none` ignores the drag
+    focus.focus(locked_tag);
+    focus.begin_drag_selection(0, user_select_of(locked_tag));
+    assert_eq!(focus.update_drag_selection(3), UpdateScreen::DontRedraw);
+    assert_eq!(focus.update_drag_selection(6), UpdateScreen::DontRedraw);
+    focus.end_drag_selection();
+    assert_eq!(focus.selection, None);
+
+    // ... and can't be selected via Ctrl+A either
+    assert_eq!(find_selectable_text(&ui_description, locked_tag), None);
+}
+
+#[test]
+fn test_tab_cycles_inside_of_modal() {
+    use glium::glutin::ModifiersState;
+    use dom::{Dom, Callback};
+    use app_state::AppState;
+    use css::Css;
+    use window::WindowEvent;
+    use widgets::text_input::TextInput;
+    use test_utils::TestLayout;
+
+    fn on_focus(_: &mut AppState<TestLayout>, _: WindowEvent) -> UpdateScreen { UpdateScreen::Redraw }
+
+    let input = |text: &str| TextInput::new(text).dom::<TestLayout>().with_callback(On::FocusReceived, Callback(on_focus));
+    let tag_of = |dom: &Dom<TestLayout>| dom.arena.borrow()[dom.root].data.tag.unwrap();
+
+    let outside = input("outside");
+    let first = input("first");
+    let second = input("second");
+    let (outside_tag, first_tag, second_tag) = (tag_of(&outside), tag_of(&first), tag_of(&second));
+
+    let dom = Dom::div()
+        .with_child(outside)
+        .with_child(Dom::div().with_child(first).with_child(second).modal());
+    let ui_description = TestLayout::style_dom(&dom, &Css::new_from_string("").unwrap());
+
+    let tab_order = get_tab_order(&ui_description);
+    assert_eq!(tab_order, vec![first_tag, second_tag]);
+
+    let tab = |shift: bool| KeyboardInput {
+        scancode: 0,
+        state: ElementState::Pressed,
+        virtual_keycode: Some(VirtualKeyCode::Tab),
+        modifiers: ModifiersState { shift: shift, .. Default::default() },
+    };
+
+    let mut focus = FocusState::default();
+    focus.focus(outside_tag);
+
+    // The focus moves into the modal and then only cycles between the two inputs
+    let mut focused = Vec::new();
+    for _ in 0..4 {
+        let direction = focus.tab_navigation(&tab(false)).unwrap();
+        focus.focus_in_tab_order(&tab_order, direction);
+        focused.push(focus.focused_tag.unwrap());
+    }
+    assert_eq!(focused, vec![first_tag, second_tag, first_tag, second_tag]);
+
+    // Shift+Tab cycles backwards
+    assert_eq!(focus.tab_navigation(&tab(true)), Some(FocusDirection::Previous));
+    focus.focus_in_tab_order(&tab_order, FocusDirection::Previous);
+    assert_eq!(focus.focused_tag, Some(first_tag));
+    focus.focus_in_tab_order(&tab_order, FocusDirection::Previous);
+    assert_eq!(focus.focused_tag, Some(second_tag));
+
+    // Without the modal, the input outside can be focused too
+    let dom = Dom::div().with_child(input("outside")).with_child(input("first"));
+    let ui_description = TestLayout::style_dom(&dom, &Css::new_from_string("").unwrap());
+    assert_eq!(get_tab_order(&ui_description).len(), 2);
+}
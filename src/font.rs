@@ -1,7 +1,8 @@
 //! Module for loading and handling fonts
-use webrender::api::FontKey;
+use webrender::api::{FontKey, FontInstancePlatformOptions};
 use rusttype::{Font, FontCollection};
 use rusttype::Error as RusttypeError;
+use css_parser::{FontId, FontWeight, FontStyle};
 
 #[derive(Debug, Clone)]
 pub(crate) enum FontState {
@@ -16,6 +17,46 @@ pub(crate) enum FontState {
     AboutToBeDeleted(Option<FontKey>),
 }
 
+/// Gamma and contrast used for rendering text. Depending on the background color, text can
+/// look too thin or too heavy, these options can be used to tune the weight of the text.
+///
+/// **NOTE:** webrender only supports configuring these on Windows,
+/// on other platforms the options currently have no effect.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TextRenderingOptions {
+    /// Gamma, in percent (i.e. `180` = gamma of 1.8)
+    pub gamma: u16,
+    /// Contrast, in percent (i.e. `100` = normal contrast)
+    pub contrast: u16,
+}
+
+impl Default for TextRenderingOptions {
+    /// Same as the defaults of webrender
+    fn default() -> Self {
+        Self {
+            gamma: 180,
+            contrast: 100,
+        }
+    }
+}
+
+impl TextRenderingOptions {
+    /// Converts the options into the platform-specific options of a webrender font instance
+    #[cfg(target_os = "windows")]
+    pub(crate) fn into_platform_options(self) -> Option<FontInstancePlatformOptions> {
+        Some(FontInstancePlatformOptions {
+            gamma: self.gamma,
+            contrast: self.contrast,
+        })
+    }
+
+    /// Converts the options into the platform-specific options of a webrender font instance
+    #[cfg(not(target_os = "windows"))]
+    pub(crate) fn into_platform_options(self) -> Option<FontInstancePlatformOptions> {
+        None
+    }
+}
+
 #[derive(Debug)]
 pub enum FontError {
     /// Font failed to upload to the GPU
@@ -41,10 +82,185 @@ pub(crate) fn rusttype_load_font<'a>(data: Vec<u8>, index: Option<i32>) -> Resul
     Ok((font, data))
 }
 
+/// A face of a font family, i.e. the bold or the italic variant of the family,
+/// see `AppState::add_font_face`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FontFace {
+    /// ID of the (already added) font that contains the glyphs of the face
+    pub(crate) font_id: FontId,
+    pub(crate) weight: FontWeight,
+    pub(crate) style: FontStyle,
+}
+
+/// The font that was selected for a `font-family`, `font-weight` and `font-style`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MatchedFontFace {
+    pub(crate) font_id: FontId,
+    /// The font isn't bold enough, the renderer has to embolden the glyphs
+    pub(crate) synthetic_bold: bool,
+    /// The font isn't slanted, the renderer has to slant the glyphs
+    pub(crate) synthetic_italic: bool,
+}
+
+/// Selects the face of a font family that matches the requested `weight` and `style` best.
+///
+/// The style takes precedence over the weight: `italic` falls back to `oblique` (and vice
+/// versa) and then to `normal`. Within the same style, the face with the closest weight is
+/// selected (for bold weights, heavier faces win ties). If the family has no registered
+/// faces, the `family` is used as the font itself. In both cases, bold and italic are
+/// synthesized if the selected font isn't bold / slanted enough.
+pub(crate) fn match_font_face(family: &FontId, faces: &[FontFace], weight: FontWeight, style: FontStyle) -> MatchedFontFace {
+
+    use self::FontStyle::*;
+
+    let style_rank = |face_style: FontStyle| match (style, face_style) {
+        (Normal, Normal) | (Italic, Italic) | (Oblique, Oblique) => 0,
+        (Italic, Oblique) | (Oblique, Italic) | (Normal, Oblique) => 1,
+        _ => 2,
+    };
+
+    let best_face = faces.iter().min_by_key(|face| {
+        let weight_distance = (face.weight.0 as i32 - weight.0 as i32).abs();
+        let weight_tie_break = if weight.is_bold() { -(face.weight.0 as i32) } else { face.weight.0 as i32 };
+        (style_rank(face.style), weight_distance, weight_tie_break)
+    });
+
+    let (font_id, face_weight, face_style) = match best_face {
+        Some(face) => (face.font_id.clone(), face.weight, face.style),
+        None => (family.clone(), FontWeight::NORMAL, Normal),
+    };
+
+    MatchedFontFace {
+        font_id: font_id,
+        synthetic_bold: weight.is_bold() && !face_weight.is_bold(),
+        synthetic_italic: style != Normal && face_style == Normal,
+    }
+}
+
+/// Metrics of a font that are needed for drawing text decorations, in font units
+/// (divide by `units_per_em` and multiply with the font size to get pixels)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct FontDecorationMetrics {
+    /// Size of the em square of the font (`head` table)
+    pub(crate) units_per_em: u16,
+    /// Distance from the baseline to the top of the ascender (`hhea` table)
+    pub(crate) ascender: i16,
+    /// Distance from the baseline to the top of the underline,
+    /// negative values are below the baseline (`post` table)
+    pub(crate) underline_position: i16,
+    /// Thickness of the underline (`post` table)
+    pub(crate) underline_thickness: i16,
+    /// Distance from the baseline to the top of the strikeout line (`OS/2` table)
+    pub(crate) strikeout_position: i16,
+    /// Thickness of the strikeout line (`OS/2` table)
+    pub(crate) strikeout_size: i16,
+}
+
+/// Reads the metrics for text decorations from the raw bytes of a TrueType / OpenType font
+/// (rusttype doesn't expose the `post` table). For font collections, the metrics of the
+/// first font in the collection are returned.
+///
+/// Returns `None` if the font is malformed or if one of the tables is missing.
+pub(crate) fn get_font_decoration_metrics(font_bytes: &[u8]) -> Option<FontDecorationMetrics> {
+
+    fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+        let b = bytes.get(offset..(offset + 2))?;
+        Some(((b[0] as u16) << 8) | (b[1] as u16))
+    }
+
+    fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+        Some(((read_u16(bytes, offset)? as u32) << 16) | (read_u16(bytes, offset + 2)? as u32))
+    }
+
+    // A font collection starts with a header that points to the offset tables of its fonts
+    let offset_table = if font_bytes.get(0..4)? == b"ttcf" { read_u32(font_bytes, 12)? as usize } else { 0 };
+    let num_tables = read_u16(font_bytes, offset_table + 4)? as usize;
+
+    // Each table record is 16 bytes: tag, checksum, offset, length
+    let find_table = |tag: &[u8]| -> Option<usize> {
+        (0..num_tables)
+            .map(|i| offset_table + 12 + i * 16)
+            .find(|record| font_bytes.get(*record..(*record + 4)) == Some(tag))
+            .and_then(|record| read_u32(font_bytes, record + 8))
+            .map(|offset| offset as usize)
+    };
+
+    let head = find_table(b"head")?;
+    let hhea = find_table(b"hhea")?;
+    let post = find_table(b"post")?;
+
+    let units_per_em = read_u16(font_bytes, head + 18)?;
+    if units_per_em == 0 {
+        return None;
+    }
+
+    let ascender = read_u16(font_bytes, hhea + 4)? as i16;
+    let underline_thickness = read_u16(font_bytes, post + 10)? as i16;
+
+    // The `OS/2` table is optional in Apple fonts: without it, the strikeout
+    // line is drawn at a third of the ascender, as thick as the underline
+    let (strikeout_size, strikeout_position) = match find_table(b"OS/2") {
+        Some(os2) => (read_u16(font_bytes, os2 + 26)? as i16, read_u16(font_bytes, os2 + 28)? as i16),
+        None => (underline_thickness, ascender / 3),
+    };
+
+    Some(FontDecorationMetrics {
+        units_per_em: units_per_em,
+        ascender: ascender,
+        underline_position: read_u16(font_bytes, post + 8)? as i16,
+        underline_thickness: underline_thickness,
+        strikeout_position: strikeout_position,
+        strikeout_size: strikeout_size,
+    })
+}
+
 // Empty test, for some reason codecov doesn't detect any files (and therefore
 // doesn't report codecov % correctly) except if they have at least one test in
 // the file. This is an empty test, which should be updated later on
 #[test]
 fn __codecov_test_font_file() {
 
+}
+
+#[test]
+fn test_font_decoration_metrics() {
+    let font_bytes = include_bytes!("../assets/fonts/weblysleekuil.ttf");
+    assert_eq!(get_font_decoration_metrics(font_bytes), Some(FontDecorationMetrics {
+        units_per_em: 2048,
+        ascender: 2210,
+        underline_position: -178,
+        underline_thickness: 119,
+        strikeout_position: 530,
+        strikeout_size: 102,
+    }));
+    assert_eq!(get_font_decoration_metrics(&[0, 1, 0, 0]), None);
+}
+
+#[test]
+fn test_match_font_face_synthesizes_missing_faces() {
+    let family = FontId::ExternalFont("Sleek".into());
+    let regular = FontFace { font_id: FontId::ExternalFont("Sleek Regular".into()), weight: FontWeight::NORMAL, style: FontStyle::Normal };
+    let light_italic = FontFace { font_id: FontId::ExternalFont("Sleek Light Italic".into()), weight: FontWeight(300), style: FontStyle::Italic };
+    let faces = [regular.clone(), light_italic.clone()];
+
+    // The style has precedence over the weight, the missing bold is synthesized
+    assert_eq!(match_font_face(&family, &faces, FontWeight::BOLD, FontStyle::Oblique), MatchedFontFace {
+        font_id: light_italic.font_id.clone(),
+        synthetic_bold: true,
+        synthetic_italic: false,
+    });
+
+    // No italic face: the regular face is slanted
+    assert_eq!(match_font_face(&family, &[regular.clone()], FontWeight::NORMAL, FontStyle::Italic), MatchedFontFace {
+        font_id: regular.font_id.clone(),
+        synthetic_bold: false,
+        synthetic_italic: true,
+    });
+
+    // A family without faces is used as the font itself
+    assert_eq!(match_font_face(&family, &[], FontWeight::NORMAL, FontStyle::Normal), MatchedFontFace {
+        font_id: family.clone(),
+        synthetic_bold: false,
+        synthetic_italic: false,
+    });
 }
\ No newline at end of file
@@ -0,0 +1,104 @@
+//! Frame time instrumentation
+//!
+//! The first display list of a window is built with cold caches: all fonts and images have
+//! to be uploaded, every text has to be laid out and all layout constraints have to be added
+//! to the solver. All following frames can reuse these caches. The time of the first frame
+//! is therefore reported separately from the time of the following ("steady-state") frames,
+//! so that the startup cost doesn't distort the steady-state frame times (and vice versa).
+
+use std::time::Duration;
+
+/// Timings of the display lists that were built for a window,
+/// see `FakeWindow::get_frame_stats`
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct FrameStats {
+    /// Time to build the first display list of the window (with cold caches),
+    /// `None` if no frame was built yet
+    pub first_frame: Option<Duration>,
+    /// Time to build the last display list after the first one (with warm caches),
+    /// `None` if only the first frame was built yet
+    pub last_frame: Option<Duration>,
+    /// Number of frames that were built after the first frame
+    pub steady_state_frames: u32,
+    /// Sum of the times of all frames after the first frame
+    steady_state_total: Duration,
+}
+
+impl FrameStats {
+
+    /// Records the time that it took to build the display list of a frame
+    pub(crate) fn record_frame(&mut self, frame_time: Duration) {
+        if self.first_frame.is_none() {
+            self.first_frame = Some(frame_time);
+            return;
+        }
+
+        self.last_frame = Some(frame_time);
+        self.steady_state_frames += 1;
+        self.steady_state_total += frame_time;
+    }
+
+    /// Returns the average time of the frames after the first frame,
+    /// `None` if only the first frame (or no frame) was built yet
+    pub fn average_steady_state_frame(&self) -> Option<Duration> {
+        if self.steady_state_frames == 0 {
+            None
+        } else {
+            Some(self.steady_state_total / self.steady_state_frames)
+        }
+    }
+}
+
+#[test]
+fn test_first_frame_is_reported_separately() {
+
+    use std::time::Instant;
+    use {
+        dom::{Dom, NodeType},
+        css::Css,
+        traits::Layout,
+        window::UiSolver,
+        window_state::WindowSize,
+        display_list::DisplayList,
+        resources::AppResources,
+        test_utils::{TestLayout, paint_frame},
+    };
+
+    let mut css = Css::new_from_string("
+        div { background-color: #ff0000; width: 100px; }
+    ").unwrap();
+    let dom = Dom::<TestLayout>::div().with_child(Dom::new(NodeType::Label("Hello".into())));
+
+    // The caches are shared between the frames, just like in a window
+    let mut ui_solver = UiSolver::new();
+    let mut app_resources = AppResources::without_system_resources();
+
+    // Builds the display list of a frame, returns how long it took
+    let mut build_frame = |is_first_frame: bool| {
+        let frame_start = Instant::now();
+        let ui_description = TestLayout::style_dom(&dom, &css);
+        let display_list = DisplayList::new_from_ui_description(&ui_description);
+        paint_frame(&display_list, &mut ui_solver, &mut css, &mut app_resources, is_first_frame, &WindowSize::default());
+        frame_start.elapsed()
+    };
+
+    let mut frame_stats = FrameStats::default();
+    assert_eq!(frame_stats.first_frame, None);
+    assert_eq!(frame_stats.average_steady_state_frame(), None);
+
+    // The first frame isn't counted as a steady-state frame
+    let first_frame = build_frame(true);
+    frame_stats.record_frame(first_frame);
+    assert_eq!(frame_stats.first_frame, Some(first_frame));
+    assert_eq!(frame_stats.last_frame, None);
+    assert_eq!(frame_stats.steady_state_frames, 0);
+    assert_eq!(frame_stats.average_steady_state_frame(), None);
+
+    // The second frame is reported as a steady-state frame, the first frame is kept
+    let second_frame = build_frame(false);
+    frame_stats.record_frame(second_frame);
+    assert_eq!(frame_stats.first_frame, Some(first_frame));
+    assert_eq!(frame_stats.last_frame, Some(second_frame));
+    assert_eq!(frame_stats.steady_state_frames, 1);
+    assert_eq!(frame_stats.average_steady_state_frame(), Some(second_frame));
+}
@@ -1,10 +1,16 @@
 //! Module for loading and handling images
 
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, atomic::{AtomicUsize, Ordering}},
+    path::PathBuf,
+    thread,
+};
 use webrender::api::ImageFormat as WebrenderImageFormat;
 use image::{ImageResult, ImageFormat, guess_format};
 use image::{self, ImageError, DynamicImage, GenericImage};
-use webrender::api::{ImageData, ImageDescriptor, ImageKey};
+use webrender::api::{ImageData, ImageDescriptor, ImageKey, Epoch};
+use FastHashMap;
 
 static IMAGE_ID_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
@@ -36,10 +42,44 @@ pub enum ImageType {
     GuessImageFormat,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// A rectangular part of an image (in pixels of the source image), i.e. a single
+/// icon of a sprite sheet. See `AppState::add_image_region()`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ImageRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A sprite atlas: a single image that contains many named sprites (regions), i.e. all
+/// states of the buttons of a game UI. See `AppState::add_sprite_atlas()`
+///
+/// The atlas image is only uploaded once, every region is an image of its own, which
+/// only draws its part of the atlas (see `AppState::add_image_region()`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AtlasId {
+    /// ID of the whole atlas image
+    pub(crate) image_id: ImageId,
+    /// IDs of the images of the regions, by the name of the region
+    pub(crate) regions: BTreeMap<String, ImageId>,
+}
+
+impl AtlasId {
+    /// Returns the image of the region with the given name, which can be used
+    /// like any other image, i.e. `NodeType::Image(atlas.region("button_hover")?)`
+    pub fn region(&self, name: &str) -> Option<ImageId> {
+        self.regions.get(name).cloned()
+    }
+}
+
+#[derive(Debug, Clone)]
 pub(crate) struct ImageInfo {
     pub(crate) key: ImageKey,
     pub(crate) descriptor: ImageDescriptor,
+    /// The decoded image data is kept around, so that the image can be
+    /// re-uploaded if it gets evicted from the `TextureCache`
+    pub(crate) data: ImageData,
 }
 
 #[derive(Debug, Clone)]
@@ -50,6 +90,168 @@ pub(crate) enum ImageState {
     ReadyForUpload((ImageData, ImageDescriptor)),
     // Image is about to get deleted in the next frame
     AboutToBeDeleted(Option<ImageKey>),
+    // Image was evicted from the GPU by the texture cache, gets
+    // re-uploaded as soon as it is displayed again
+    Evicted((ImageData, ImageDescriptor)),
+    // Image is being loaded and decoded on a background thread,
+    // a placeholder is drawn until the image is ReadyForUpload
+    Decoding(DecodingImage),
+}
+
+/// An image file that is being decoded on a background thread
+#[derive(Debug, Clone)]
+pub(crate) struct DecodingImage {
+    /// `None` while the thread is still decoding
+    result: Arc<Mutex<Option<Result<(ImageData, ImageDescriptor), ImageError>>>>,
+}
+
+impl DecodingImage {
+
+    /// Starts loading and decoding the image file on a new thread
+    pub(crate) fn new(path: PathBuf, image_type: ImageType) -> Self {
+        let result = Arc::new(Mutex::new(None));
+        let thread_result = result.clone();
+
+        thread::spawn(move || {
+            let decoded = decode_image_file(&path, image_type);
+            if let Ok(mut lock) = thread_result.lock() {
+                *lock = Some(decoded);
+            }
+        });
+
+        Self { result }
+    }
+
+    /// Returns the decoded image if the thread has finished, `None` if it is still decoding
+    fn take_result(&self) -> Option<Result<(ImageData, ImageDescriptor), ImageError>> {
+        self.result.lock().ok().and_then(|mut lock| lock.take())
+    }
+}
+
+fn decode_image_file(path: &Path, image_type: ImageType)
+-> Result<(ImageData, ImageDescriptor), ImageError>
+{
+    use std::fs;
+
+    let image_data = fs::read(path).map_err(|e| ImageError::IoError(e))?;
+    let image_format = image_type.into_image_format(&image_data)?;
+    let decoded = image::load_from_memory_with_format(&image_data, image_format)?;
+    prepare_image(decoded)
+}
+
+/// Moves all images that have finished decoding on their background thread to
+/// `ImageState::ReadyForUpload`. Images that couldn't be decoded are deleted.
+///
+/// Returns `true` if any image finished decoding, i.e. if the screen has to be redrawn
+pub(crate) fn update_decoding_images(images: &mut FastHashMap<ImageId, ImageState>) -> bool {
+
+    let mut finished_images = Vec::new();
+
+    for (image_id, state) in images.iter() {
+        if let ImageState::Decoding(ref decoding_image) = *state {
+            if let Some(result) = decoding_image.take_result() {
+                finished_images.push((*image_id, result));
+            }
+        }
+    }
+
+    let has_finished_images = !finished_images.is_empty();
+
+    for (image_id, result) in finished_images {
+        let new_state = match result {
+            Ok(image) => ImageState::ReadyForUpload(image),
+            Err(e) => {
+                error!("Error decoding image: {:?}", e);
+                ImageState::AboutToBeDeleted(None)
+            },
+        };
+        images.insert(image_id, new_state);
+    }
+
+    has_finished_images
+}
+
+/// Least-recently-used cache for the images that are resident on the GPU.
+///
+/// Uploaded images stay in GPU memory until they are deleted. If a byte budget
+/// is set, the images that weren't displayed for the longest time get deleted
+/// from the GPU until the uploaded images fit into the budget again.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct TextureCache {
+    /// Maximum number of bytes of image data that may be uploaded at the
+    /// same time. `None` (the default) means that there is no limit.
+    pub(crate) max_bytes: Option<usize>,
+    /// The last epoch in which an image was displayed
+    pub(crate) last_used: FastHashMap<ImageId, Epoch>,
+}
+
+impl TextureCache {
+
+    /// Marks the image as being displayed in the current frame
+    pub(crate) fn mark_used(&mut self, image_id: ImageId, current_epoch: Epoch) {
+        self.last_used.insert(image_id, current_epoch);
+    }
+
+    /// Evicts the least-recently-used images until the uploaded images fit into
+    /// `self.max_bytes` again. Images that were used in the `current_epoch` are never evicted.
+    ///
+    /// Returns the keys of the evicted images, which have to be deleted via `ResourceUpdate::DeleteImage`
+    pub(crate) fn evict_least_recently_used(&mut self, images: &mut FastHashMap<ImageId, ImageState>, current_epoch: Epoch)
+    -> Vec<ImageKey>
+    {
+        let max_bytes = match self.max_bytes {
+            Some(m) => m,
+            None => return Vec::new(),
+        };
+
+        let mut uploaded_bytes = 0;
+        let mut candidates = Vec::<(ImageId, u32, usize)>::new();
+
+        for (image_id, state) in images.iter() {
+            if let ImageState::Uploaded(ref image_info) = *state {
+                let image_size = image_data_size(&image_info.data);
+                uploaded_bytes += image_size;
+                let last_used = self.last_used.get(image_id).map(|e| e.0).unwrap_or(0);
+                if last_used != current_epoch.0 {
+                    candidates.push((*image_id, last_used, image_size));
+                }
+            }
+        }
+
+        // Oldest images first
+        candidates.sort_by_key(|&(_, last_used, _)| last_used);
+
+        let mut evicted_keys = Vec::new();
+
+        for (image_id, _, image_size) in candidates {
+            if uploaded_bytes <= max_bytes {
+                break;
+            }
+
+            let state = images.get_mut(&image_id).unwrap();
+            let evicted_state = match *state {
+                ImageState::Uploaded(ref image_info) => {
+                    evicted_keys.push(image_info.key);
+                    ImageState::Evicted((image_info.data.clone(), image_info.descriptor))
+                },
+                _ => continue,
+            };
+
+            *state = evicted_state;
+            self.last_used.remove(&image_id);
+            uploaded_bytes -= image_size;
+        }
+
+        evicted_keys
+    }
+}
+
+/// Returns how many bytes of image data will be uploaded to the GPU
+fn image_data_size(data: &ImageData) -> usize {
+    match *data {
+        ImageData::Raw(ref bytes) => bytes.len(),
+        _ => 0,
+    }
 }
 
 
@@ -195,4 +397,80 @@ fn test_premultiply() {
     let mut color = [255, 0, 0, 127];
     premultiply(&mut color);
     assert_eq!(color, [127, 0, 0, 127]);
+}
+
+#[test]
+fn test_texture_cache_evicts_least_recently_used() {
+    use webrender::api::IdNamespace;
+
+    fn uploaded_image(key: u32) -> ImageState {
+        ImageState::Uploaded(ImageInfo {
+            key: ImageKey::new(IdNamespace(0), key),
+            descriptor: ImageDescriptor::new(2, 2, WebrenderImageFormat::BGRA8, true, false),
+            data: ImageData::new(vec![255; 16]),
+        })
+    }
+
+    // Each image is 16 bytes large, the budget allows for two images
+    let mut texture_cache = TextureCache {
+        max_bytes: Some(32),
+        last_used: FastHashMap::default(),
+    };
+
+    let mut images = FastHashMap::default();
+    let image_ids = [new_image_id(), new_image_id(), new_image_id()];
+
+    // Cycle through the three images, displaying one image per frame
+    for (frame, image_id) in image_ids.iter().enumerate() {
+        images.insert(*image_id, uploaded_image(frame as u32));
+        texture_cache.mark_used(*image_id, Epoch(frame as u32));
+    }
+
+    let evicted = texture_cache.evict_least_recently_used(&mut images, Epoch(2));
+    assert_eq!(evicted, vec![ImageKey::new(IdNamespace(0), 0)]);
+
+    match images[&image_ids[0]] {
+        ImageState::Evicted(_) => { },
+        _ => panic!("least recently used image was not evicted"),
+    }
+
+    for image_id in &image_ids[1..] {
+        match images[image_id] {
+            ImageState::Uploaded(_) => { },
+            _ => panic!("recently used image was evicted"),
+        }
+    }
+
+    // Budget is met, nothing to evict anymore
+    assert!(texture_cache.evict_least_recently_used(&mut images, Epoch(2)).is_empty());
+}
+
+
+#[test]
+fn test_image_path_is_decoded_on_background_thread() {
+    use std::time::{Duration, Instant};
+
+    let path = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/images/azul_logo_full_min.svg.png"));
+    let image_id = new_image_id();
+
+    let mut images = FastHashMap::default();
+    images.insert(image_id, ImageState::Decoding(DecodingImage::new(path, ImageType::Png)));
+
+    let timeout = Instant::now() + Duration::from_secs(10);
+    while !update_decoding_images(&mut images) {
+        match images[&image_id] {
+            ImageState::Decoding(_) => { },
+            _ => panic!("image left the Decoding state without finishing"),
+        }
+        assert!(Instant::now() < timeout, "image wasn't decoded within 10 seconds");
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    match images[&image_id] {
+        ImageState::ReadyForUpload(_) => { },
+        _ => panic!("image was not decoded"),
+    }
+
+    // The image has already finished decoding
+    assert!(!update_decoding_images(&mut images));
 }
\ No newline at end of file
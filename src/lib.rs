@@ -52,12 +52,16 @@ extern crate harfbuzz_rs;
 extern crate tinyfiledialogs;
 extern crate clipboard2;
 extern crate font_loader;
-#[macro_use(error, log)]
+#[macro_use(error, warn, log)]
 extern crate log;
 #[cfg(feature = "logging")]
 extern crate fern;
 #[cfg(feature = "logging")]
 extern crate backtrace;
+#[cfg(feature = "serialize")]
+extern crate serde;
+#[cfg(feature = "serialize")]
+extern crate serde_json;
 
 #[cfg(not(target_os = "linux"))]
 extern crate nfd;
@@ -93,6 +97,8 @@ mod constraints;
 /// Converts the UI description (the styled HTML nodes)
 /// to an actual display list (+ layout)
 mod display_list;
+/// Captures the primitives of a display list as plain data, for golden tests
+mod display_list_capture;
 /// CSS parser
 mod css_parser;
 /// Slab allocator for nodes, based on IDs (replaces kuchiki + markup5ever)
@@ -107,6 +113,40 @@ mod images;
 mod font;
 /// Window state handling, event filtering
 mod window_state;
+/// Keyboard focus handling
+mod focus;
+/// Synthetic input events, for testing callbacks without user input
+mod synthetic_event;
+/// CSS transitions, interpolates properties across frames
+mod transition;
+/// Abstraction over the webrender API, so that display lists can be built without a GPU
+mod render_api;
+/// Tooltips of hovered nodes
+mod tooltip;
+/// Scroll offsets and scroll chaining of nested scroll containers
+mod scroll;
+/// User-resizable boxes (`resize` property)
+mod resize;
+/// Global keyboard shortcuts
+mod shortcut;
+/// Detection of double clicks
+mod double_click;
+/// Determinate and indeterminate loading spinners
+mod spinner;
+/// Checked, unchecked and indeterminate checkboxes
+mod checkbox;
+/// Text and caret of editable text inputs
+mod text_input_state;
+/// Caching of static subtrees as images
+mod render_cache;
+/// Times of the first and the following frames of a window
+mod frame_stats;
+/// Fixtures that are shared between the unit tests
+#[cfg(test)]
+mod test_utils;
+/// Renders serializable values as a tree of labels, for debugging / inspection
+#[cfg(feature = "serialize")]
+mod serialize_dom;
 /// Application / context menu handling. Currently Win32 only. Also has parsing functions
 mod menu;
 /// The compositor takes all textures (user-defined + the UI texture(s)) and draws them on
@@ -126,14 +166,22 @@ type FastHashSet<T> = ::std::collections::HashSet<T, ::std::hash::BuildHasherDef
 pub mod prelude {
     pub use app::{App, AppConfig};
     pub use app_state::AppState;
-    pub use css::{Css, FakeCss};
+    pub use css::{Css, FakeCss, Precedence};
     pub use dom::{Dom, NodeType, NodeData, Callback, On, UpdateScreen};
     pub use traits::{Layout, ModifyAppState};
-    pub use window::{MonitorIter, Window, WindowCreateOptions, WindowId,
+    pub use window::{MonitorIter, Window, WindowCreateOptions, WindowId, LayoutTree, LayoutTreeNode, FlexItemLayout,
                      MouseMode, UpdateBehaviour, UpdateMode,
                      WindowMonitorTarget, RendererType, WindowEvent, WindowInfo, ReadOnlyWindow};
     pub use window_state::WindowState;
-    pub use images::ImageType;
+    pub use synthetic_event::{SyntheticEvent, DispatchedEvents};
+    pub use scroll::ScrollBounds;
+    pub use shortcut::KeyCombo;
+    pub use spinner::SpinnerValue;
+    pub use checkbox::CheckBoxState;
+    pub use text_input_state::TextInputState;
+    pub use frame_stats::FrameStats;
+    pub use images::{ImageType, ImageRegion, ImageId, AtlasId};
+    pub use font::TextRenderingOptions;
     pub use text_cache::{TextCache, TextId};
     pub use css_parser::{
         ParsedCssProperty, BorderRadius, BackgroundColor, TextColor,
@@ -141,13 +189,20 @@ pub mod prelude {
         FontFamily, TextOverflowBehaviour, TextOverflowBehaviourInner, TextAlignmentHorz,
         BoxShadowPreDisplayItem, LayoutWidth, LayoutHeight,
         LayoutMinWidth, LayoutMinHeight, LayoutMaxWidth,
-        LayoutMaxHeight, LayoutWrap, LayoutDirection,
+        LayoutMaxHeight, LayoutAspectRatio, LayoutGap, LayoutWrap, LayoutDirection,
         LayoutJustifyContent, LayoutAlignItems, LayoutAlignContent,
-        LinearGradientPreInfo, RadialGradientPreInfo, CssImageId, FontId,
+        LinearGradientPreInfo, RadialGradientPreInfo, RadialGradientSize, RadialGradientCenter,
+        GradientPosition, CssImageId, FontId,
 
         LayoutPixel, TypedSize2D, BoxShadowClipMode, ColorU, ColorF, LayoutVector2D,
         Gradient, SideOffsets2D, RadialGradient, LayoutPoint, LayoutSize,
-        ExtendMode, PixelValue, PercentageValue,
+        ExtendMode, PixelValue, PercentageValue, RectStyle, RectLayout, TextOverflowMode,
+        ContentVisibility, BackdropFilter, Filter, Transition, TransitionTimingFunction,
+        OverflowWrap, WordBreak, TextDecoration, TextDecorationLines, LayoutPadding, Opacity, Visibility, TextDirection, UserSelect, OverscrollBehavior, TextIndent, TintColor, CaretColor, LayoutDisplay, LayoutResize, CssWideKeyword, FontWeight, FontStyle,
+        BorderEdge, BorderSideOverride, LayoutConstraintStrength, LayoutContain, LayoutBoxSizing, Appearance,
+        BackgroundSize, BackgroundSizeComponent, BackgroundRepeat, WritingMode,
+        CssPadding, PaddingValue, EnvironmentVariable, SafeAreaInsets,
+        PaddingSideOverride, CssMargin, LayoutMargin, MarginSideOverride,
     };
     pub use glium::glutin::dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize};
     pub use rusttype::Font;
@@ -165,6 +220,7 @@ pub mod errors {
         PercentageParseError,
         CssBackgroundParseError, CssColorParseError, CssBorderRadiusParseError,
         CssDirectionParseError, CssGradientStopParseError, CssShapeParseError,
+        CssTransitionParseError, CssPaddingParseError,
     };
     pub use simplecss::Error as CssSyntaxError;
     pub use css::{CssParseError, DynamicCssParseError};
@@ -0,0 +1,74 @@
+//! Abstraction over the parts of the webrender `RenderApi` that are used
+//! while building the display list (generating resource keys and uploading resources)
+//!
+//! This way, the display list can be built without a GPU context, i.e. in unit tests.
+
+use webrender::api::{RenderApi, ImageKey, FontKey, FontInstanceKey, ResourceUpdate};
+#[cfg(test)]
+use std::cell::{Cell, RefCell};
+#[cfg(test)]
+use webrender::api::IdNamespace;
+
+/// Generates the keys for images and fonts and sends the resource updates to the renderer
+pub(crate) trait FontImageApi {
+    fn new_image_key(&self) -> ImageKey;
+    fn new_font_key(&self) -> FontKey;
+    fn new_font_instance_key(&self) -> FontInstanceKey;
+    fn update_resources(&self, updates: Vec<ResourceUpdate>);
+}
+
+impl FontImageApi for RenderApi {
+    fn new_image_key(&self) -> ImageKey {
+        self.generate_image_key()
+    }
+
+    fn new_font_key(&self) -> FontKey {
+        self.generate_font_key()
+    }
+
+    fn new_font_instance_key(&self) -> FontInstanceKey {
+        self.generate_font_instance_key()
+    }
+
+    fn update_resources(&self, updates: Vec<ResourceUpdate>) {
+        RenderApi::update_resources(self, updates)
+    }
+}
+
+/// In-memory `FontImageApi` for tests: generates sequential keys and
+/// records the resource updates instead of sending them to a renderer
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct FakeRenderApi {
+    next_key: Cell<u32>,
+    /// All resource updates that were sent to this API, in the order they were sent
+    pub(crate) resource_updates: RefCell<Vec<ResourceUpdate>>,
+}
+
+#[cfg(test)]
+impl FakeRenderApi {
+    fn next_key(&self) -> u32 {
+        let key = self.next_key.get();
+        self.next_key.set(key + 1);
+        key
+    }
+}
+
+#[cfg(test)]
+impl FontImageApi for FakeRenderApi {
+    fn new_image_key(&self) -> ImageKey {
+        ImageKey::new(IdNamespace(0), self.next_key())
+    }
+
+    fn new_font_key(&self) -> FontKey {
+        FontKey(IdNamespace(0), self.next_key())
+    }
+
+    fn new_font_instance_key(&self) -> FontInstanceKey {
+        FontInstanceKey(IdNamespace(0), self.next_key())
+    }
+
+    fn update_resources(&self, updates: Vec<ResourceUpdate>) {
+        self.resource_updates.borrow_mut().extend(updates);
+    }
+}
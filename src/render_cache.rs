@@ -0,0 +1,146 @@
+//! Caching of static subtrees as images, enabled with `Dom::with_cached_rendering()`
+//!
+//! The first frame that contains a cached subtree paints it normally. After the frame is
+//! rendered, the pixels of the subtree are read back from the framebuffer and uploaded as an
+//! image. In the following frames, the whole subtree is painted as this single image, instead
+//! of the display items of all of its nodes - as long as no node of the subtree changed. If a
+//! node was changed, moved, resized or restyled (i.e. by a `:hover` rule), the subtree is
+//! painted normally again and the image is re-captured. Subtrees that contain an animated node
+//! (an indeterminate spinner or the focused node, whose caret blinks) are never cached.
+//!
+//! Since the image is a copy of the framebuffer, it contains everything that was painted
+//! inside of the bounds of the subtree, including the parent nodes behind it. Nodes inside of
+//! a cached subtree don't receive callbacks (only the root of the subtree is hit-tested), so
+//! only subtrees that are static and that aren't overlapped by other nodes should be cached.
+//!
+//! Subtrees are identified by the same key as scroll containers (see `ScrollKey`), so that
+//! the image survives DOM rebuilds.
+
+use std::collections::{BTreeMap, BTreeSet};
+use gleam::gl::{self, Gl};
+use webrender::api::{Epoch, LayoutRect, ImageKey, ImageDescriptor, ImageData, ImageFormat};
+use {
+    cache::DomHash,
+    css_parser::RectStyle,
+    scroll::ScrollKey,
+};
+
+/// Attribute that marks the root of a cached subtree, see `Dom::with_cached_rendering`
+pub(crate) const RENDER_CACHE_ATTRIBUTE: &str = "data-azul-render-cache";
+
+/// The hash, bounds and style of every node of a subtree, in depth-first order -
+/// if any of these changes, the cached image of the subtree is outdated
+pub(crate) type SubtreeContents = Vec<(DomHash, LayoutRect, RectStyle)>;
+
+/// The image of a subtree and what the subtree looked like when the image was captured
+#[derive(Debug, Clone, PartialEq)]
+struct CachedSubtree {
+    contents: SubtreeContents,
+    image_key: ImageKey,
+}
+
+/// A cached subtree that was painted normally and has to be captured after the frame was rendered
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SubtreeCapture {
+    pub(crate) key: ScrollKey,
+    /// Epoch of the display list that the subtree was painted in
+    pub(crate) epoch: Epoch,
+    pub(crate) bounds: LayoutRect,
+    pub(crate) contents: SubtreeContents,
+}
+
+/// The cached images of a window, by the key of the root of the subtree
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct RenderCache {
+    subtrees: BTreeMap<ScrollKey, CachedSubtree>,
+}
+
+impl RenderCache {
+
+    /// Returns the image of the subtree `key`, if the subtree still has the same `contents`
+    /// as when the image was captured
+    pub(crate) fn get(&self, key: &ScrollKey, contents: &SubtreeContents) -> Option<ImageKey> {
+        self.subtrees.get(key)
+            .filter(|cached| cached.contents == *contents)
+            .map(|cached| cached.image_key)
+    }
+
+    /// Stores the image of a captured subtree
+    ///
+    /// Returns the outdated image of the subtree, which has to be deleted
+    pub(crate) fn insert(&mut self, capture: SubtreeCapture, image_key: ImageKey) -> Option<ImageKey> {
+        self.subtrees.insert(capture.key, CachedSubtree { contents: capture.contents, image_key: image_key })
+            .map(|outdated| outdated.image_key)
+    }
+
+    /// Removes the images of the subtrees that aren't in the DOM anymore (i.e. that
+    /// weren't painted in the last frame)
+    ///
+    /// Returns the removed images, which have to be deleted
+    pub(crate) fn retain(&mut self, painted: &BTreeSet<ScrollKey>) -> Vec<ImageKey> {
+        let removed = self.subtrees.keys().filter(|key| !painted.contains(key)).cloned().collect::<Vec<ScrollKey>>();
+        removed.iter().filter_map(|key| self.subtrees.remove(key)).map(|cached| cached.image_key).collect()
+    }
+}
+
+/// Reads the pixels inside of the (logical) `bounds` from the current framebuffer
+/// and converts them to an image. Returns `None` if the bounds are outside of the framebuffer.
+pub(crate) fn read_framebuffer_region(
+    gl: &Gl,
+    bounds: &LayoutRect,
+    hidpi_factor: f32,
+    framebuffer_size: (u32, u32))
+-> Option<(ImageDescriptor, ImageData)>
+{
+    let (framebuffer_width, framebuffer_height) = (framebuffer_size.0 as i32, framebuffer_size.1 as i32);
+    let x0 = ((bounds.origin.x * hidpi_factor).floor() as i32).max(0);
+    let y0 = ((bounds.origin.y * hidpi_factor).floor() as i32).max(0);
+    let x1 = ((bounds.max_x() * hidpi_factor).ceil() as i32).min(framebuffer_width);
+    let y1 = ((bounds.max_y() * hidpi_factor).ceil() as i32).min(framebuffer_height);
+    if x1 <= x0 || y1 <= y0 {
+        return None;
+    }
+
+    let (width, height) = ((x1 - x0) as usize, (y1 - y0) as usize);
+    // OpenGL starts at the bottom left corner of the framebuffer
+    let pixels = gl.read_pixels(x0, framebuffer_height - y1, width as i32, height as i32, gl::RGBA, gl::UNSIGNED_BYTE);
+    let row_bytes = width * 4;
+
+    // Flip the rows and swap red and blue (webrender expects BGRA)
+    let mut image = Vec::with_capacity(pixels.len());
+    for row in pixels.chunks(row_bytes).rev() {
+        for pixel in row.chunks(4) {
+            image.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+        }
+    }
+
+    let descriptor = ImageDescriptor::new(width as u32, height as u32, ImageFormat::BGRA8, true, false);
+    Some((descriptor, ImageData::new(image)))
+}
+
+#[test]
+fn test_render_cache_invalidation() {
+    use webrender::api::{IdNamespace, LayoutPoint, LayoutSize};
+
+    let bounds = LayoutRect::new(LayoutPoint::new(10.0, 10.0), LayoutSize::new(100.0, 50.0));
+    let contents = vec![(DomHash(1), bounds, RectStyle::default())];
+    let key = ScrollKey::Id(String::from("illustration"));
+    let capture = SubtreeCapture { key: key.clone(), epoch: Epoch(0), bounds: bounds, contents: contents.clone() };
+
+    let mut cache = RenderCache::default();
+    assert_eq!(cache.get(&key, &contents), None);
+    assert_eq!(cache.insert(capture.clone(), ImageKey::new(IdNamespace(0), 1)), None);
+    assert_eq!(cache.get(&key, &contents), Some(ImageKey::new(IdNamespace(0), 1)));
+
+    // A changed node invalidates the image
+    let moved_bounds = LayoutRect::new(LayoutPoint::new(15.0, 10.0), LayoutSize::new(100.0, 50.0));
+    let moved = vec![(DomHash(1), moved_bounds, RectStyle::default())];
+    assert_eq!(cache.get(&key, &moved), None);
+
+    // Re-capturing replaces the outdated image
+    assert_eq!(cache.insert(capture, ImageKey::new(IdNamespace(0), 2)), Some(ImageKey::new(IdNamespace(0), 1)));
+
+    // Subtrees that weren't painted are removed
+    assert_eq!(cache.retain(&BTreeSet::new()), vec![ImageKey::new(IdNamespace(0), 2)]);
+    assert_eq!(cache.get(&key, &contents), None);
+}
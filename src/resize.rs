@@ -0,0 +1,172 @@
+//! User-resizable boxes (`resize: both / horizontal / vertical`)
+//!
+//! A resizable box gets a grip in its bottom-right corner. Dragging the grip with the
+//! left mouse button changes the size of the box by the distance that the mouse moved
+//! (only along the axes that the `resize` property allows). The new size overrides the
+//! `width` / `height` of the box and is kept until the window is closed.
+
+use std::collections::BTreeMap;
+use webrender::api::{LayoutRect, LayoutPoint, LayoutSize};
+use {
+    id_tree::NodeId,
+    css_parser::LayoutResize,
+};
+
+/// Width and height of the resize grip, in pixels
+pub(crate) const RESIZE_GRIP_SIZE: f32 = 12.0;
+
+/// The resize grip of a resizable node, as it was laid out in the last frame
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct ResizeGrip {
+    pub(crate) node_id: NodeId,
+    pub(crate) resize: LayoutResize,
+    /// Bounds of the grip
+    pub(crate) rect: LayoutRect,
+    /// Solved size of the node
+    pub(crate) node_size: LayoutSize,
+}
+
+/// The grip that is currently dragged
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct ResizeDrag {
+    node_id: NodeId,
+    resize: LayoutResize,
+    /// Cursor position when the drag started
+    start_cursor: LayoutPoint,
+    /// Size of the node when the drag started
+    start_size: LayoutSize,
+}
+
+/// The sizes of the nodes that were resized by the user, by the ID of the node
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct ResizeStates {
+    sizes: BTreeMap<NodeId, LayoutSize>,
+    active_drag: Option<ResizeDrag>,
+}
+
+impl ResizeStates {
+
+    /// Returns the size that the user resized the node to, `None` if the node was never resized
+    pub(crate) fn get_size(&self, node_id: NodeId) -> Option<LayoutSize> {
+        self.sizes.get(&node_id).cloned()
+    }
+
+    /// Starts dragging the topmost grip of the `grips` that contains the `cursor`.
+    /// Returns whether a grip was hit.
+    pub(crate) fn begin_drag(&mut self, grips: &[ResizeGrip], cursor: LayoutPoint) -> bool {
+        // The grips are sorted in painting order, so the last grip is the topmost one
+        self.active_drag = grips.iter().rev().find(|grip| grip.rect.contains(&cursor)).map(|grip| ResizeDrag {
+            node_id: grip.node_id,
+            resize: grip.resize,
+            start_cursor: cursor,
+            start_size: grip.node_size,
+        });
+        self.active_drag.is_some()
+    }
+
+    /// Resizes the node of the current drag, so that its size changes by the distance
+    /// between the `cursor` and the position where the drag started. Returns whether
+    /// the size of the node changed (in which case the window has to be re-laid out).
+    pub(crate) fn drag_to(&mut self, cursor: LayoutPoint) -> bool {
+        let drag = match self.active_drag {
+            Some(drag) => drag,
+            None => return false,
+        };
+
+        let mut new_size = drag.start_size;
+        if drag.resize.is_horizontal() {
+            new_size.width = (drag.start_size.width + cursor.x - drag.start_cursor.x).max(0.0);
+        }
+        if drag.resize.is_vertical() {
+            new_size.height = (drag.start_size.height + cursor.y - drag.start_cursor.y).max(0.0);
+        }
+
+        let old_size = self.sizes.insert(drag.node_id, new_size).unwrap_or(drag.start_size);
+        old_size != new_size
+    }
+
+    /// Ends the current drag (when the mouse button is released)
+    pub(crate) fn end_drag(&mut self) {
+        self.active_drag = None;
+    }
+}
+
+/// Returns the bounds of the resize grip of a node with the `bounds`
+pub(crate) fn get_resize_grip_rect(bounds: &LayoutRect) -> LayoutRect {
+    let width = RESIZE_GRIP_SIZE.min(bounds.size.width);
+    let height = RESIZE_GRIP_SIZE.min(bounds.size.height);
+    LayoutRect::new(
+        LayoutPoint::new(bounds.origin.x + bounds.size.width - width, bounds.origin.y + bounds.size.height - height),
+        LayoutSize::new(width, height))
+}
+
+#[test]
+fn test_dragging_resize_grip_resizes_both_axes() {
+
+    use {
+        dom::Dom,
+        css::{Css, RelayoutCause},
+        traits::Layout,
+        window::UiSolver,
+        window_state::WindowSize,
+        display_list::DisplayList,
+        resources::AppResources,
+        test_utils::{TestLayout, paint_frame},
+    };
+
+    fn layout_frame(dom: &Dom<TestLayout>, css: &mut Css, resize_states: &ResizeStates) -> UiSolver<TestLayout> {
+        let ui_description = TestLayout::style_dom(dom, css);
+        let mut display_list = DisplayList::new_from_ui_description(&ui_description);
+        display_list.apply_resized_sizes(resize_states);
+        let mut ui_solver = UiSolver::new();
+        paint_frame(&display_list, &mut ui_solver, css, &mut AppResources::without_system_resources(), true, &WindowSize::default());
+        ui_solver
+    }
+
+    let mut css = Css::new_from_string("
+        #box { width: 100px; height: 50px; resize: both; }
+    ").unwrap();
+    let dom = Dom::<TestLayout>::div().with_id("box");
+    let box_id = NodeId::new(0);
+    let mut resize_states = ResizeStates::default();
+
+    let ui_solver = layout_frame(&dom, &mut css, &resize_states);
+    let grips = ui_solver.solved_layout.resize_grips.clone();
+    assert_eq!(grips.len(), 1);
+    assert_eq!(grips[0].rect, LayoutRect::new(LayoutPoint::new(88.0, 38.0), LayoutSize::new(12.0, 12.0)));
+
+    // Clicking next to the grip doesn't start a drag
+    assert!(!resize_states.begin_drag(&grips, LayoutPoint::new(10.0, 10.0)));
+    assert!(!resize_states.drag_to(LayoutPoint::new(50.0, 50.0)));
+
+    // Drag the grip by (30, 20)
+    assert!(resize_states.begin_drag(&grips, LayoutPoint::new(94.0, 44.0)));
+    assert!(resize_states.drag_to(LayoutPoint::new(124.0, 64.0)));
+    resize_states.end_drag();
+    assert!(!resize_states.drag_to(LayoutPoint::new(200.0, 200.0)));
+
+    // Same as in the app: the drag changed the size, so the window is re-laid out
+    css.mark_relayout(RelayoutCause::Resize);
+    let ui_solver = layout_frame(&dom, &mut css, &resize_states);
+    assert_eq!(ui_solver.query_bounds_of_rect(box_id).unwrap().size, LayoutSize::new(130.0, 70.0));
+}
+
+#[test]
+fn test_horizontal_resize_keeps_height() {
+    let node_id = NodeId::new(0);
+    let grip = ResizeGrip {
+        node_id: node_id,
+        resize: LayoutResize::Horizontal,
+        rect: get_resize_grip_rect(&LayoutRect::new(LayoutPoint::zero(), LayoutSize::new(100.0, 50.0))),
+        node_size: LayoutSize::new(100.0, 50.0),
+    };
+
+    let mut resize_states = ResizeStates::default();
+    assert!(resize_states.begin_drag(&[grip], LayoutPoint::new(95.0, 45.0)));
+    assert!(resize_states.drag_to(LayoutPoint::new(55.0, 85.0)));
+    assert_eq!(resize_states.get_size(node_id), Some(LayoutSize::new(60.0, 50.0)));
+
+    // The size can't become negative
+    assert!(resize_states.drag_to(LayoutPoint::new(-200.0, 45.0)));
+    assert_eq!(resize_states.get_size(node_id), Some(LayoutSize::new(0.0, 50.0)));
+}
@@ -1,17 +1,18 @@
 use std::io::Read;
-use std::collections::hash_map::Entry::*;
+use std::path::Path;
+use std::collections::{HashMap, hash_map::Entry::*};
 use text_layout::{PX_TO_PT, split_text_into_words};
-use text_cache::{TextId, TextCache};
+use text_cache::{TextId, TextCache, GlyphCache};
 use webrender::api::{FontKey, FontInstanceKey};
 use FastHashMap;
-use font::{FontState, FontError};
+use font::{FontState, FontError, TextRenderingOptions, FontFace, MatchedFontFace, match_font_face};
 use image::{self, ImageError};
-use images::{ImageId, ImageState, ImageType};
+use images::{ImageId, ImageState, ImageType, ImageRegion, AtlasId, TextureCache};
 use app_units::Au;
 use clipboard2::{Clipboard, ClipboardError, SystemClipboard};
 use rusttype::Font;
 use css_parser::{
-    FontSize,
+    FontSize, FontWeight, FontStyle,
     FontId::{self, ExternalFont}
 };
 
@@ -34,6 +35,12 @@ pub struct AppResources<'a> {
     /// The actual image cache, does NOT store the image data, only stores it temporarily
     /// while it is being uploaded to the GPU via webrender.
     pub(crate) images: FastHashMap<ImageId, ImageState>,
+    /// Images that only display a part of another image (i.e. an icon of a sprite sheet),
+    /// maps the ID of the region to the ID of the source image and the region in the source image
+    pub(crate) image_regions: FastHashMap<ImageId, (ImageId, ImageRegion)>,
+    /// Tracks when the uploaded images were last displayed, so that the least-recently-used
+    /// images can be deleted from the GPU if the texture cache exceeds its byte budget
+    pub(crate) texture_cache: TextureCache,
     // Fonts are trickier to handle than images.
     // First, we duplicate the font - webrender wants the raw font data,
     // but we also need access to the font metrics. So we first parse the font
@@ -44,25 +51,31 @@ pub struct AppResources<'a> {
     // the font instance key (if there is any). If there is no font instance key,
     // we first need to create one.
     pub(crate) fonts: FastHashMap<FontKey, FastHashMap<Au, FontInstanceKey>>,
+    /// The faces (bold, italic, etc.) of the font families, by the ID of the family
+    pub(crate) font_faces: FastHashMap<FontId, Vec<FontFace>>,
     /// Stores long texts across frames
     pub(crate) text_cache: TextCache,
-    /// Keyboard clipboard storage and retrieval functionality
-    clipboard: SystemClipboard,
+    /// Shaped glyphs, shared by all texts with the same font and font size
+    pub(crate) glyph_cache: GlyphCache,
+    /// Gamma / contrast for newly created font instances, `None` = webrender defaults
+    pub(crate) text_rendering: Option<TextRenderingOptions>,
+    /// Font sizes are snapped to multiples of this size (in pixels) before a font instance
+    /// is created, so that nearly identical sizes share one instance. `None` = exact sizes
+    pub(crate) font_size_quantization: Option<f32>,
+    /// Keyboard clipboard storage and retrieval functionality,
+    /// `None` if the clipboard hasn't been connected yet
+    clipboard: Option<SystemClipboard>,
 }
 
+/// Default step (in pixels) that font sizes are snapped to, see `AppConfig::font_size_quantization`
+pub(crate) const DEFAULT_FONT_SIZE_QUANTIZATION: f32 = 0.25;
+
 impl<'a> Default for AppResources<'a> {
     fn default() -> Self {
-        let mut default_font_data = FastHashMap::default();
-        load_system_fonts(&mut default_font_data);
-
-        Self {
-            css_ids_to_image_ids: FastHashMap::default(),
-            fonts: FastHashMap::default(),
-            font_data: default_font_data,
-            images: FastHashMap::default(),
-            text_cache: TextCache::default(),
-            clipboard: SystemClipboard::new().unwrap(),
-        }
+        let mut app_resources = Self::without_system_resources();
+        load_system_fonts(&mut app_resources.font_data);
+        app_resources.clipboard = Some(SystemClipboard::new().unwrap());
+        app_resources
     }
 }
 
@@ -90,6 +103,25 @@ fn load_system_fonts<'a>(fonts: &mut FastHashMap<FontId, (::rusttype::Font<'a>,
 
 impl<'a> AppResources<'a> {
 
+    /// Creates empty resources without loading the system fonts. The system clipboard
+    /// is only connected once it is used, so this works without a display server (i.e. in tests).
+    pub(crate) fn without_system_resources() -> Self {
+        Self {
+            css_ids_to_image_ids: FastHashMap::default(),
+            fonts: FastHashMap::default(),
+            font_data: FastHashMap::default(),
+            font_faces: FastHashMap::default(),
+            images: FastHashMap::default(),
+            image_regions: FastHashMap::default(),
+            texture_cache: TextureCache::default(),
+            text_cache: TextCache::default(),
+            glyph_cache: GlyphCache::default(),
+            text_rendering: None,
+            font_size_quantization: Some(DEFAULT_FONT_SIZE_QUANTIZATION),
+            clipboard: None,
+        }
+    }
+
     /// See `AppState::add_image()`
     pub(crate) fn add_image<S: Into<String>, R: Read>(&mut self, id: S, data: &mut R, image_type: ImageType)
         -> Result<Option<()>, ImageError>
@@ -119,12 +151,120 @@ impl<'a> AppResources<'a> {
         }
     }
 
+    /// See `AppState::add_image_path()`
+    pub(crate) fn add_image_path<S: Into<String>, P: AsRef<Path>>(&mut self, id: S, path: P)
+        -> Option<()>
+    {
+        use images::{self, get_image_type_from_extension, DecodingImage};
+
+        let image_id = match self.css_ids_to_image_ids.entry(id.into()) {
+            Occupied(_) => return None,
+            Vacant(v) => {
+                let new_id = images::new_image_id();
+                v.insert(new_id)
+            },
+        };
+
+        let path = path.as_ref();
+        let image_type = get_image_type_from_extension(path).unwrap_or(ImageType::GuessImageFormat);
+
+        match self.images.entry(*image_id) {
+            Occupied(_) => None,
+            Vacant(v) => {
+                v.insert(ImageState::Decoding(DecodingImage::new(path.to_path_buf(), image_type)));
+                Some(())
+            },
+        }
+    }
+
+    /// See `AppState::add_image_region()`
+    pub(crate) fn add_image_region<S: Into<String>, R: AsRef<str>>(&mut self, id: S, source_id: R, region: ImageRegion)
+        -> Option<()>
+    {
+        use images;
+
+        let source_image_id = *self.css_ids_to_image_ids.get(source_id.as_ref())?;
+
+        // Regions of regions point to the original image
+        let (source_image_id, region) = match self.image_regions.get(&source_image_id) {
+            Some(&(source_of_source, outer)) => (source_of_source, ImageRegion {
+                x: outer.x + region.x,
+                y: outer.y + region.y,
+                width: region.width,
+                height: region.height,
+            }),
+            None => (source_image_id, region),
+        };
+
+        match self.css_ids_to_image_ids.entry(id.into()) {
+            Occupied(_) => None,
+            Vacant(v) => {
+                let new_id = images::new_image_id();
+                v.insert(new_id);
+                self.image_regions.insert(new_id, (source_image_id, region));
+                Some(())
+            },
+        }
+    }
+
+    /// See `AppState::add_sprite_atlas()`
+    pub(crate) fn add_sprite_atlas<R: Read>(&mut self, data: &mut R, image_type: ImageType, regions: HashMap<String, ImageRegion>)
+        -> Result<AtlasId, ImageError>
+    {
+        use images;
+
+        let mut image_data = Vec::<u8>::new();
+        data.read_to_end(&mut image_data).map_err(|e| ImageError::IoError(e))?;
+        let image_format = image_type.into_image_format(&image_data)?;
+        let decoded = image::load_from_memory_with_format(&image_data, image_format)?;
+
+        let atlas_image_id = images::new_image_id();
+        self.images.insert(atlas_image_id, ImageState::ReadyForUpload(images::prepare_image(decoded)?));
+
+        let regions = regions.into_iter().map(|(name, region)| {
+            let region_image_id = images::new_image_id();
+            self.image_regions.insert(region_image_id, (atlas_image_id, region));
+            (name, region_image_id)
+        }).collect();
+
+        Ok(AtlasId {
+            image_id: atlas_image_id,
+            regions: regions,
+        })
+    }
+
+    /// See `AppState::delete_sprite_atlas()`
+    pub(crate) fn delete_sprite_atlas(&mut self, atlas: &AtlasId) {
+        for region_image_id in atlas.regions.values() {
+            self.image_regions.remove(region_image_id);
+        }
+
+        if let Some(v) = self.images.get_mut(&atlas.image_id) {
+            let to_delete_image_key = match *v {
+                ImageState::Uploaded(ref image_info) => Some(image_info.key.clone()),
+                _ => None,
+            };
+            *v = ImageState::AboutToBeDeleted(to_delete_image_key);
+        }
+    }
+
+    /// Moves the images that finished decoding to `ImageState::ReadyForUpload`,
+    /// returns `true` if any image finished decoding
+    pub(crate) fn update_decoding_images(&mut self) -> bool {
+        ::images::update_decoding_images(&mut self.images)
+    }
+
     /// See `AppState::delete_image()`
     pub(crate) fn delete_image<S: AsRef<str>>(&mut self, id: S)
         -> Option<()>
     {
         let image_id = self.css_ids_to_image_ids.remove(id.as_ref())?;
 
+        // Deleting a region doesn't delete the source image
+        if self.image_regions.remove(&image_id).is_some() {
+            return Some(());
+        }
+
         match self.images.get_mut(&image_id) {
             None => None,
             Some(v) => {
@@ -149,7 +289,8 @@ impl<'a> AppResources<'a> {
             Some(s) => s,
         };
 
-        self.images.get(image_id).is_some()
+        let image_id = self.image_regions.get(image_id).map(|&(source, _)| source).unwrap_or(*image_id);
+        self.images.get(&image_id).is_some()
     }
 
     /// See `AppState::add_font()`
@@ -170,6 +311,28 @@ impl<'a> AppResources<'a> {
         }
     }
 
+    /// See `AppState::add_font_face()`
+    pub(crate) fn add_font_face<S: Into<String>, F: Into<String>>(&mut self, family: S, font_id: F, weight: FontWeight, style: FontStyle)
+        -> Option<()>
+    {
+        let font_id = ExternalFont(font_id.into());
+        if !self.font_data.contains_key(&font_id) {
+            return None;
+        }
+
+        let faces = self.font_faces.entry(ExternalFont(family.into())).or_insert_with(|| Vec::new());
+        faces.retain(|face| !(face.weight == weight && face.style == style));
+        faces.push(FontFace { font_id: font_id, weight: weight, style: style });
+        Some(())
+    }
+
+    /// Selects the font for the `family` (the first font of a `font-family`)
+    /// that matches the `weight` and `style`, see `font::match_font_face`
+    pub(crate) fn match_font_face(&self, family: &FontId, weight: FontWeight, style: FontStyle) -> MatchedFontFace {
+        let faces = self.font_faces.get(family).map(|faces| faces.as_slice()).unwrap_or(&[]);
+        match_font_face(family, faces, weight, style)
+    }
+
     pub fn get_font<'b>(&'b self, id: &FontId) -> Option<(&'b Font<'a>, &'b Vec<u8>)> {
         self.font_data.get(id).and_then(|(font, bytes, _)| Some((font, bytes)))
     }
@@ -185,8 +348,15 @@ impl<'a> AppResources<'a> {
     pub(crate) fn delete_font<S: Into<String>>(&mut self, id: S)
         -> Option<()>
     {
+        let id = ExternalFont(id.into());
+
+        // The deleted font can't be selected as a face anymore
+        for faces in self.font_faces.values_mut() {
+            faces.retain(|face| face.font_id != id);
+        }
+
         // TODO: can fonts that haven't been uploaded yet be deleted?
-        match self.font_data.get_mut(&ExternalFont(id.into())) {
+        match self.font_data.get_mut(&id) {
             None => None,
             Some(v) => {
                 let to_delete_font_key = match v.2 {
@@ -227,9 +397,10 @@ impl<'a> AppResources<'a> {
         // We need to assume that the actual string contents have already been stored in self.text_cache
         // Otherwise, how would the TextId be valid?
         let text = self.text_cache.string_cache.get(&id).expect("Invalid text Id");
-        let font_size_no_line_height = Scale::uniform(size.0.to_pixels() * PX_TO_PT);
+        let font_size_no_line_height = Scale::uniform(size.to_pixels() * PX_TO_PT);
         let rusttype_font = self.font_data.get(&font).expect("Invalid font ID");
-        let words = split_text_into_words(text.as_ref(), &rusttype_font.0, font_size_no_line_height);
+        let glyphs = self.glyph_cache.get_font_instance(&font, size);
+        let words = split_text_into_words(text.as_ref(), &rusttype_font.0, font_size_no_line_height, glyphs);
 
         self.text_cache.cached_strings
             .entry(id).or_insert_with(|| FastHashMap::default())
@@ -248,13 +419,23 @@ impl<'a> AppResources<'a> {
     pub(crate) fn get_clipboard_string(&mut self)
     -> Result<String, ClipboardError>
     {
-        self.clipboard.get_string_contents()
+        self.get_clipboard()?.get_string_contents()
     }
 
     pub(crate) fn set_clipboard_string(&mut self, contents: String)
     -> Result<(), ClipboardError>
     {
-        self.clipboard.set_string_contents(contents)
+        self.get_clipboard()?.set_string_contents(contents)
+    }
+
+    /// Connects to the system clipboard, if it isn't connected yet
+    fn get_clipboard(&mut self)
+    -> Result<&mut SystemClipboard, ClipboardError>
+    {
+        if self.clipboard.is_none() {
+            self.clipboard = Some(SystemClipboard::new()?);
+        }
+        Ok(self.clipboard.as_mut().unwrap())
     }
 }
 
@@ -264,4 +445,37 @@ impl<'a> AppResources<'a> {
 #[test]
 fn __codecov_test_resources_file() {
 
+}
+
+#[test]
+fn test_font_weight_bold_selects_bold_face() {
+    use css_parser::ParsedCssProperty;
+
+    const TEST_FONT: &[u8] = include_bytes!("../assets/fonts/weblysleekuil.ttf");
+
+    let mut app_resources = AppResources::without_system_resources();
+    app_resources.add_font("Sleek Regular", &mut TEST_FONT).unwrap();
+    app_resources.add_font("Sleek Bold", &mut TEST_FONT).unwrap();
+    assert_eq!(app_resources.add_font_face("Sleek", "Sleek Regular", FontWeight::NORMAL, FontStyle::Normal), Some(()));
+    assert_eq!(app_resources.add_font_face("Sleek", "Sleek Bold", FontWeight::BOLD, FontStyle::Normal), Some(()));
+    assert_eq!(app_resources.add_font_face("Sleek", "Sleek Unknown", FontWeight(900), FontStyle::Normal), None);
+
+    let bold = match ParsedCssProperty::from_kv("font-weight", "bold") {
+        Ok(ParsedCssProperty::FontWeight(w)) => w,
+        other => panic!("unexpected font-weight: {:?}", other),
+    };
+
+    let family = ExternalFont("Sleek".into());
+    let matched = app_resources.match_font_face(&family, bold, FontStyle::Normal);
+    assert_eq!(matched.font_id, ExternalFont("Sleek Bold".into()));
+    assert!(!matched.synthetic_bold);
+
+    let matched = app_resources.match_font_face(&family, FontWeight::NORMAL, FontStyle::Normal);
+    assert_eq!(matched.font_id, ExternalFont("Sleek Regular".into()));
+
+    // Without the bold face, the regular face is emboldened
+    app_resources.delete_font("Sleek Bold");
+    let matched = app_resources.match_font_face(&family, bold, FontStyle::Normal);
+    assert_eq!(matched.font_id, ExternalFont("Sleek Regular".into()));
+    assert!(matched.synthetic_bold);
 }
\ No newline at end of file
@@ -0,0 +1,265 @@
+//! Scrolling of nested scroll containers
+//!
+//! A scroll container is a node with scroll bounds (see `Dom::with_scroll_bounds`).
+//! A scroll event is sent to the innermost scroll container under the cursor. Once that
+//! container reaches its boundary, the rest of the scroll delta is passed on to the next
+//! outer scroll container (scroll chaining), so that i.e. scrolling a list inside of a
+//! scrollable page scrolls the page once the list is scrolled to the end. A container
+//! with `overscroll-behavior: contain` (or `none`) ends the chain, so that the outer
+//! containers are never scrolled by scroll events inside of the container.
+//!
+//! The tags of the nodes are re-generated every time the DOM is rebuilt, so the scroll
+//! offsets are stored by a `ScrollKey` instead, which stays the same across DOM rebuilds.
+
+use std::{
+    mem,
+    collections::BTreeMap,
+    hash::{Hash, Hasher},
+};
+use twox_hash::XxHash;
+use {
+    dom::NodeData,
+    id_tree::{Arena, NodeId},
+    traits::Layout,
+    ui_description::UiDescription,
+    css_parser::OverscrollBehavior,
+};
+
+/// How far the content of a scroll container can be scrolled (in pixels),
+/// i.e. the size of the content minus the size of the container
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct ScrollBounds {
+    /// Maximum horizontal scroll offset
+    pub max_x: f32,
+    /// Maximum vertical scroll offset
+    pub max_y: f32,
+}
+
+impl ScrollBounds {
+    pub fn new(max_x: f32, max_y: f32) -> Self {
+        Self { max_x, max_y }
+    }
+}
+
+/// Identifies a scroll container across DOM rebuilds
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum ScrollKey {
+    /// The container has an `id` attribute
+    Id(String),
+    /// Hash of the path from the container up to the root (or up to the first ancestor with
+    /// an `id`): the node type, the classes and the index among the siblings of every node on
+    /// the path. The children of the container aren't part of the hash, so the items of a
+    /// scrollable list can change without the list losing its scroll position.
+    Path(u64),
+}
+
+impl ScrollKey {
+
+    /// Calculates the key of the scroll container `node_id`
+    pub(crate) fn new<T: Layout>(arena: &Arena<NodeData<T>>, node_id: NodeId) -> Self {
+        if let Some(ref id) = arena[node_id].data.id {
+            return ScrollKey::Id(id.clone());
+        }
+
+        let mut hasher = XxHash::default();
+        for ancestor in node_id.ancestors(arena) {
+            let data = &arena[ancestor].data;
+            if let Some(ref id) = data.id {
+                id.hash(&mut hasher);
+                break;
+            }
+            mem::discriminant(&data.node_type).hash(&mut hasher);
+            data.classes.hash(&mut hasher);
+            ancestor.preceding_siblings(arena).count().hash(&mut hasher);
+        }
+        ScrollKey::Path(hasher.finish())
+    }
+}
+
+/// A scroll container that contains the node under the cursor
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ScrollContainer {
+    /// Tag of the container in the current DOM, for dispatching the `On::Scroll` callbacks
+    pub(crate) tag: u64,
+    pub(crate) key: ScrollKey,
+    pub(crate) bounds: ScrollBounds,
+}
+
+/// The current scroll offsets of the scroll containers of a window, by the key of the container
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct ScrollStates {
+    offsets: BTreeMap<ScrollKey, (f32, f32)>,
+}
+
+impl ScrollStates {
+
+    /// Returns the current `(x, y)` scroll offset of the container, `(0.0, 0.0)` if it was never scrolled
+    pub(crate) fn get_offset(&self, key: &ScrollKey) -> (f32, f32) {
+        self.offsets.get(key).cloned().unwrap_or((0.0, 0.0))
+    }
+
+    /// Scrolls the containers of the `chain` (innermost container first) by `delta`:
+    /// each container takes as much of the delta as it can until it reaches its boundary
+    /// and passes the rest on to the next container.
+    ///
+    /// Returns the tags of the containers that were scrolled, together with how far they were scrolled
+    pub(crate) fn scroll(&mut self, chain: &[ScrollContainer], delta: (f32, f32)) -> Vec<(u64, (f32, f32))> {
+        let mut remaining = delta;
+        let mut scrolled = Vec::new();
+
+        for ScrollContainer { tag, key, bounds } in chain {
+            if remaining == (0.0, 0.0) {
+                break;
+            }
+
+            let (offset_x, offset_y) = self.get_offset(key);
+            let new_x = (offset_x + remaining.0).max(0.0).min(bounds.max_x.max(0.0));
+            let new_y = (offset_y + remaining.1).max(0.0).min(bounds.max_y.max(0.0));
+            let consumed = (new_x - offset_x, new_y - offset_y);
+
+            if consumed != (0.0, 0.0) {
+                self.offsets.insert(key.clone(), (new_x, new_y));
+                scrolled.push((*tag, consumed));
+            }
+
+            remaining = (remaining.0 - consumed.0, remaining.1 - consumed.1);
+        }
+
+        scrolled
+    }
+}
+
+/// Returns the scroll containers that contain the node with the given tag
+/// (including the node itself), the innermost container first. The chain ends at the
+/// first container with `overscroll-behavior: contain` or `overscroll-behavior: none`.
+pub(crate) fn find_scroll_chain<T: Layout>(ui_description: &UiDescription<T>, tag: u64) -> Vec<ScrollContainer> {
+    let arena = ui_description.ui_descr_arena.borrow();
+
+    let node_id = match arena.linear_iter().find(|node_id| arena[*node_id].data.tag == Some(tag)) {
+        Some(id) => id,
+        None => return Vec::new(),
+    };
+
+    let mut chain = Vec::new();
+
+    for ancestor in node_id.ancestors(&arena) {
+        let data = &arena[ancestor].data;
+        if let (Some(tag), Some(bounds)) = (data.tag, data.scroll_bounds) {
+            chain.push(ScrollContainer { tag, key: ScrollKey::new(&arena, ancestor), bounds });
+            if ui_description.computed_style(ancestor).overscroll_behavior.unwrap_or_default() != OverscrollBehavior::Auto {
+                break;
+            }
+        }
+    }
+
+    chain
+}
+
+#[test]
+fn test_scroll_chains_to_outer_container_at_boundary() {
+    use dom::{Dom, NodeType};
+    use css::Css;
+    use test_utils::TestLayout;
+
+    let label = Dom::<TestLayout>::new(NodeType::Label(String::from("Item"))).with_title("Item");
+    let label_tag = label.arena.borrow()[label.root].data.tag.unwrap();
+    let inner = Dom::div().with_scroll_bounds(ScrollBounds::new(0.0, 50.0)).with_child(label);
+    let inner_tag = inner.arena.borrow()[inner.root].data.tag.unwrap();
+    let outer = Dom::div().with_scroll_bounds(ScrollBounds::new(0.0, 100.0)).with_child(inner);
+    let outer_tag = outer.arena.borrow()[outer.root].data.tag.unwrap();
+
+    let chain = find_scroll_chain(&TestLayout::style_dom(&outer, &Css::empty()), label_tag);
+    assert_eq!(chain.iter().map(|c| (c.tag, c.bounds)).collect::<Vec<_>>(),
+               vec![(inner_tag, ScrollBounds::new(0.0, 50.0)), (outer_tag, ScrollBounds::new(0.0, 100.0))]);
+    let (inner_key, outer_key) = (&chain[0].key, &chain[1].key);
+    assert!(inner_key != outer_key);
+
+    let mut scroll_states = ScrollStates::default();
+
+    // While the inner container isn't at its boundary, only the inner container scrolls
+    assert_eq!(scroll_states.scroll(&chain, (0.0, 40.0)), vec![(inner_tag, (0.0, 40.0))]);
+    assert_eq!(scroll_states.get_offset(outer_key), (0.0, 0.0));
+
+    // The inner container reaches its boundary, the rest scrolls the outer container
+    assert_eq!(scroll_states.scroll(&chain, (0.0, 30.0)), vec![(inner_tag, (0.0, 10.0)), (outer_tag, (0.0, 20.0))]);
+    assert_eq!(scroll_states.get_offset(inner_key), (0.0, 50.0));
+    assert_eq!(scroll_states.get_offset(outer_key), (0.0, 20.0));
+
+    // Once the inner container is at its boundary, scrolling only scrolls the outer container
+    assert_eq!(scroll_states.scroll(&chain, (0.0, 100.0)), vec![(outer_tag, (0.0, 80.0))]);
+
+    // Both containers are at their boundary
+    assert_eq!(scroll_states.scroll(&chain, (0.0, 10.0)), Vec::new());
+
+    // Scrolling back up scrolls the inner container first again
+    assert_eq!(scroll_states.scroll(&chain, (0.0, -20.0)), vec![(inner_tag, (0.0, -20.0))]);
+}
+
+#[test]
+fn test_scroll_offset_is_kept_across_dom_rebuilds() {
+    use dom::{Dom, NodeType};
+    use css::Css;
+    use test_utils::TestLayout;
+
+    // Same as `Layout::layout` in immediate mode: every frame builds a new DOM with new tags
+    fn build_dom(items: &[&str], list_id: Option<&str>) -> (Dom<TestLayout>, u64) {
+        let mut list = Dom::div().with_class("list").with_scroll_bounds(ScrollBounds::new(0.0, 200.0));
+        if let Some(id) = list_id {
+            list = list.with_id(id);
+        }
+        let mut first_item_tag = None;
+        for item in items {
+            let label = Dom::new(NodeType::Label(item.to_string())).with_title(*item);
+            first_item_tag = first_item_tag.or(label.arena.borrow()[label.root].data.tag);
+            list.add_child(label);
+        }
+        let page = Dom::div().with_child(Dom::div().with_class("header")).with_child(list);
+        (page, first_item_tag.unwrap())
+    }
+
+    for list_id in &[None, Some("list")] {
+        let mut scroll_states = ScrollStates::default();
+
+        let (first_frame, item_tag) = build_dom(&["a", "b", "c"], *list_id);
+        let chain = find_scroll_chain(&TestLayout::style_dom(&first_frame, &Css::empty()), item_tag);
+        assert_eq!(scroll_states.scroll(&chain, (0.0, 75.0)).len(), 1);
+
+        // The items of the list changed, so all tags are different in the next frame
+        let (second_frame, new_item_tag) = build_dom(&["x", "a", "b", "c", "d"], *list_id);
+        assert!(new_item_tag != item_tag);
+        let chain = find_scroll_chain(&TestLayout::style_dom(&second_frame, &Css::empty()), new_item_tag);
+        assert!(chain[0].tag != find_scroll_chain(&TestLayout::style_dom(&first_frame, &Css::empty()), item_tag)[0].tag);
+        assert_eq!(scroll_states.get_offset(&chain[0].key), (0.0, 75.0));
+
+        // ... and scrolling continues from the preserved offset
+        assert_eq!(scroll_states.scroll(&chain, (0.0, 200.0)), vec![(chain[0].tag, (0.0, 125.0))]);
+    }
+}
+
+#[test]
+fn test_overscroll_behavior_contain_stops_scroll_chaining() {
+    use dom::{Dom, NodeType};
+    use css::Css;
+    use test_utils::TestLayout;
+
+    let label = Dom::<TestLayout>::new(NodeType::Label(String::from("Item"))).with_title("Item");
+    let label_tag = label.arena.borrow()[label.root].data.tag.unwrap();
+    let inner = Dom::div().with_class("inner").with_scroll_bounds(ScrollBounds::new(0.0, 50.0)).with_child(label);
+    let inner_tag = inner.arena.borrow()[inner.root].data.tag.unwrap();
+    let outer = Dom::div().with_scroll_bounds(ScrollBounds::new(0.0, 100.0)).with_child(inner);
+
+    let css = Css::new_from_string(".inner { overscroll-behavior: contain; }").unwrap();
+    let chain = find_scroll_chain(&TestLayout::style_dom(&outer, &css), label_tag);
+    assert_eq!(chain.iter().map(|c| c.tag).collect::<Vec<_>>(), vec![inner_tag]);
+
+    let mut scroll_states = ScrollStates::default();
+
+    // The inner container reaches its boundary, the rest of the delta is dropped
+    assert_eq!(scroll_states.scroll(&chain, (0.0, 80.0)), vec![(inner_tag, (0.0, 50.0))]);
+
+    // Scrolling further at the boundary doesn't scroll the outer container either
+    assert_eq!(scroll_states.scroll(&chain, (0.0, 30.0)), Vec::new());
+
+    let outer_key = ScrollKey::new(&outer.arena.borrow(), outer.root);
+    assert_eq!(scroll_states.get_offset(&outer_key), (0.0, 0.0));
+}
@@ -0,0 +1,101 @@
+//! Renders any `serde::Serialize` value as a tree of labels and divs, i.e. to inspect
+//! the app data in the UI while debugging. Requires the `serialize` feature.
+//!
+//! The value is converted to a JSON value first:
+//!
+//! - objects (structs, maps) become a `__azul-serialized-object` div, with one
+//!   `__azul-serialized-field` div per field, which contains a `__azul-serialized-key`
+//!   label with the name of the field, followed by the value of the field
+//! - arrays (`Vec`, tuples) become a `__azul-serialized-array` div, with one child per item
+//! - strings, numbers, booleans and `null` become a `__azul-serialized-value` label
+//!
+//! The fields of an object are sorted by their name.
+
+use serde::Serialize;
+use serde_json::{self, Value};
+use {
+    dom::{Dom, NodeType},
+    traits::Layout,
+};
+
+impl<T: Layout> Dom<T> {
+
+    /// Renders a serializable value as a nested tree of labels and divs, for debugging.
+    /// If the value can't be serialized, the error is rendered as a label instead.
+    pub fn from_serializable<S: Serialize>(value: &S) -> Self {
+        match serde_json::to_value(value) {
+            Ok(value) => value_to_dom(&value),
+            Err(e) => Dom::label(format!("{}", e)).with_class("__azul-serialized-error"),
+        }
+    }
+}
+
+fn value_to_dom<T: Layout>(value: &Value) -> Dom<T> {
+    match value {
+        Value::Object(fields) => {
+            let mut object = Dom::new(NodeType::Div).with_class("__azul-serialized-object");
+            for (key, field_value) in fields {
+                object.add_child(Dom::new(NodeType::Div)
+                    .with_class("__azul-serialized-field")
+                    .with_child(Dom::label(key.clone()).with_class("__azul-serialized-key"))
+                    .with_child(value_to_dom(field_value)));
+            }
+            object
+        },
+        Value::Array(items) => {
+            let mut array = Dom::new(NodeType::Div).with_class("__azul-serialized-array");
+            for item in items {
+                array.add_child(value_to_dom(item));
+            }
+            array
+        },
+        Value::String(s) => Dom::label(s.clone()).with_class("__azul-serialized-value"),
+        // null, booleans and numbers are printed as in JSON
+        other => Dom::label(other.to_string()).with_class("__azul-serialized-value"),
+    }
+}
+
+#[test]
+fn test_from_serializable_renders_a_label_per_field() {
+
+    use serde::ser::{Serializer, SerializeStruct};
+    use test_utils::TestLayout;
+
+    struct Person {
+        name: String,
+        age: u32,
+        tags: Vec<&'static str>,
+    }
+
+    impl Serialize for Person {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut person = serializer.serialize_struct("Person", 3)?;
+            person.serialize_field("name", &self.name)?;
+            person.serialize_field("age", &self.age)?;
+            person.serialize_field("tags", &self.tags)?;
+            person.end()
+        }
+    }
+
+    let person = Person { name: String::from("Ada"), age: 36, tags: vec!["math", "code"] };
+    let dom = Dom::<TestLayout>::from_serializable(&person);
+
+    let mut keys = Vec::new();
+    let mut values = Vec::new();
+    dom.visit(|_, node| {
+        if let NodeType::Label(ref text) = node.node_type {
+            if node.classes.iter().any(|c| c == "__azul-serialized-key") {
+                keys.push(text.clone());
+            } else {
+                values.push(text.clone());
+            }
+        }
+    });
+
+    // One key label per field (sorted by name), followed by the labels of its value
+    assert_eq!(keys, vec!["age", "name", "tags"]);
+    assert_eq!(values, vec!["36", "Ada", "math", "code"]);
+
+    let arena = dom.arena.borrow();
+    assert_eq!(dom.root.children(&*arena).count(), 3);
+}
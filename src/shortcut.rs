@@ -0,0 +1,146 @@
+//! Global keyboard shortcuts, which are invoked regardless of which node is focused
+//! (see `AppState::add_shortcut`)
+
+use glium::glutin::{KeyboardInput, ElementState, VirtualKeyCode};
+use {
+    dom::Callback,
+    traits::Layout,
+};
+
+/// A key together with the modifier keys that have to be held down, i.e. Ctrl+S
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct KeyCombo {
+    /// The key that has to be pressed
+    pub key: VirtualKeyCode,
+    /// Ctrl key
+    pub ctrl: bool,
+    /// Shift key
+    pub shift: bool,
+    /// Alt key
+    pub alt: bool,
+    /// `Super / Windows / Command` key
+    pub logo: bool,
+}
+
+impl KeyCombo {
+
+    /// A key without any modifiers, i.e. Escape
+    pub fn new(key: VirtualKeyCode) -> Self {
+        Self {
+            key: key,
+            ctrl: false,
+            shift: false,
+            alt: false,
+            logo: false,
+        }
+    }
+
+    /// Shorthand for `KeyCombo::new(key).with_ctrl()`
+    pub fn ctrl(key: VirtualKeyCode) -> Self {
+        Self::new(key).with_ctrl()
+    }
+
+    #[inline]
+    pub fn with_ctrl(mut self) -> Self {
+        self.ctrl = true;
+        self
+    }
+
+    #[inline]
+    pub fn with_shift(mut self) -> Self {
+        self.shift = true;
+        self
+    }
+
+    #[inline]
+    pub fn with_alt(mut self) -> Self {
+        self.alt = true;
+        self
+    }
+
+    #[inline]
+    pub fn with_logo(mut self) -> Self {
+        self.logo = true;
+        self
+    }
+
+    /// Returns whether the `input` is a key press of this combo. The modifiers have
+    /// to match exactly, so that Ctrl+Shift+S doesn't trigger the Ctrl+S shortcut
+    pub(crate) fn matches(&self, input: &KeyboardInput) -> bool {
+        input.state == ElementState::Pressed &&
+        input.virtual_keycode == Some(self.key) &&
+        input.modifiers.ctrl == self.ctrl &&
+        input.modifiers.shift == self.shift &&
+        input.modifiers.alt == self.alt &&
+        input.modifiers.logo == self.logo
+    }
+}
+
+/// The global shortcuts of an application
+pub(crate) struct ShortcutRegistry<T: Layout> {
+    shortcuts: Vec<(KeyCombo, Callback<T>)>,
+}
+
+impl<T: Layout> Default for ShortcutRegistry<T> {
+    fn default() -> Self {
+        Self {
+            shortcuts: Vec::new(),
+        }
+    }
+}
+
+impl<T: Layout> ShortcutRegistry<T> {
+
+    /// Registers the callback for the combo, replacing the previous callback of the combo (if any)
+    pub(crate) fn add(&mut self, combo: KeyCombo, callback: Callback<T>) {
+        self.shortcuts.retain(|(c, _)| *c != combo);
+        self.shortcuts.push((combo, callback));
+    }
+
+    /// Removes the shortcut, returns whether the combo had a callback
+    pub(crate) fn remove(&mut self, combo: &KeyCombo) -> bool {
+        let previous_len = self.shortcuts.len();
+        self.shortcuts.retain(|(c, _)| c != combo);
+        self.shortcuts.len() != previous_len
+    }
+
+    /// Returns the callback of the shortcut that the `input` triggers, if any
+    pub(crate) fn find(&self, input: &KeyboardInput) -> Option<Callback<T>> {
+        self.shortcuts.iter()
+            .find(|(combo, _)| combo.matches(input))
+            .map(|(_, callback)| *callback)
+    }
+}
+
+#[test]
+fn test_global_shortcut_fires_without_focused_node() {
+    use glium::glutin::ModifiersState;
+    use app_state::AppState;
+    use dom::UpdateScreen;
+    use window::{WindowEvent, WindowId};
+    use test_utils::TestLayout;
+
+    // The data is whether the document was saved
+    fn save(app_state: &mut AppState<TestLayout<bool>>, _: WindowEvent) -> UpdateScreen {
+        app_state.data.lock().unwrap().data = true;
+        UpdateScreen::Redraw
+    }
+
+    let key_press = |key: VirtualKeyCode, ctrl: bool| KeyboardInput {
+        scancode: 0,
+        state: ElementState::Pressed,
+        virtual_keycode: Some(key),
+        modifiers: ModifiersState { ctrl: ctrl, .. ModifiersState::default() },
+    };
+
+    let mut app_state = AppState::new(TestLayout { data: false });
+    app_state.add_shortcut(KeyCombo::ctrl(VirtualKeyCode::S), Callback(save));
+    assert_eq!(app_state.get_focused_node(), None);
+
+    // S without Ctrl is not the shortcut
+    assert_eq!(app_state.dispatch_shortcut(&key_press(VirtualKeyCode::S, false), WindowId { id: 0 }), None);
+    assert!(!app_state.data.lock().unwrap().data);
+
+    assert_eq!(app_state.dispatch_shortcut(&key_press(VirtualKeyCode::S, true), WindowId { id: 0 }), Some(UpdateScreen::Redraw));
+    assert!(app_state.data.lock().unwrap().data);
+}
@@ -0,0 +1,156 @@
+//! Loading spinners, created with `Dom::spinner()` or `Dom::determinate_spinner()`
+//!
+//! A spinner is painted as an arc along the edge of the node, in the text color of the node.
+//! An indeterminate spinner rotates continuously, so the window is redrawn every frame
+//! as long as one is in the DOM. A determinate spinner doesn't rotate, its arc starts at
+//! the top and covers the fraction of the circle that corresponds to its progress.
+
+use std::time::{Duration, Instant};
+use webrender::api::{LayoutRect, LayoutPoint, LayoutSize};
+
+/// Time that an indeterminate spinner takes for one full rotation
+const SPINNER_ROTATION_DURATION: Duration = Duration::from_millis(1000);
+/// Fraction of the circle that the arc of an indeterminate spinner covers
+const INDETERMINATE_SPINNER_SWEEP: f32 = 0.25;
+/// Number of segments that a full circle is made of
+const SPINNER_SEGMENTS: usize = 60;
+/// Width of the arc, relative to the diameter of the spinner
+const SPINNER_STROKE_WIDTH: f32 = 0.1;
+
+/// Progress of a determinate spinner, from 0.0 (no arc) to 1.0 (full circle)
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct SpinnerValue {
+    /// Has to be divided by 1000.0 - the `NodeType` needs to implement Hash,
+    /// but Hash is not possible for floating-point values
+    number: u32,
+}
+
+impl SpinnerValue {
+
+    /// Creates a new progress value, clamped to the range 0.0 - 1.0
+    pub fn new(value: f32) -> Self {
+        Self {
+            number: (value.max(0.0).min(1.0) * 1000.0).round() as u32,
+        }
+    }
+
+    pub fn get(&self) -> f32 {
+        self.number as f32 / 1000.0
+    }
+}
+
+/// Measures the rotation of the indeterminate spinners of a window, so that all
+/// spinners rotate in sync and keep rotating smoothly when the DOM is re-created
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct SpinnerClock {
+    started: Instant,
+}
+
+impl Default for SpinnerClock {
+    fn default() -> Self {
+        Self::new(Instant::now())
+    }
+}
+
+impl SpinnerClock {
+
+    pub(crate) fn new(started: Instant) -> Self {
+        Self {
+            started: started,
+        }
+    }
+
+    /// Returns the rotation of the indeterminate spinners at the time `now`,
+    /// as a fraction of a full turn (0.0 - 1.0)
+    pub(crate) fn rotation(&self, now: Instant) -> f32 {
+        let elapsed = if now > self.started { now.duration_since(self.started) } else { Duration::from_secs(0) };
+        let rotation_nanos = SPINNER_ROTATION_DURATION.as_secs() * 1_000_000_000 + SPINNER_ROTATION_DURATION.subsec_nanos() as u64;
+        let elapsed_nanos = elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64;
+        (elapsed_nanos % rotation_nanos) as f32 / rotation_nanos as f32
+    }
+}
+
+/// The part of the circle that a spinner paints, in fractions of a full turn,
+/// clockwise, starting at the top of the circle
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct SpinnerArc {
+    pub(crate) start: f32,
+    pub(crate) sweep: f32,
+}
+
+impl SpinnerArc {
+
+    /// Returns the arc of a spinner with the `value` (`None` for an indeterminate
+    /// spinner), `rotation` is the current rotation of the indeterminate spinners
+    pub(crate) fn new(value: Option<SpinnerValue>, rotation: f32) -> Self {
+        match value {
+            Some(value) => Self { start: 0.0, sweep: value.get() },
+            None => Self { start: rotation, sweep: INDETERMINATE_SPINNER_SWEEP },
+        }
+    }
+}
+
+/// Returns the rectangles that make up the `arc` of a spinner with the `bounds`.
+///
+/// Webrender can't draw arcs, so the arc is approximated by small squares along
+/// the circle, which overlap each other so that the arc looks continuous.
+pub(crate) fn get_spinner_segments(bounds: &LayoutRect, arc: &SpinnerArc) -> Vec<LayoutRect> {
+    use std::f32::consts::PI;
+
+    let diameter = bounds.size.width.min(bounds.size.height);
+    let stroke_width = diameter * SPINNER_STROKE_WIDTH;
+    let radius = (diameter - stroke_width) / 2.0;
+    let center = rect_center(bounds);
+
+    let segment_count = (arc.sweep * SPINNER_SEGMENTS as f32).round() as usize;
+
+    (0..segment_count).map(|segment| {
+        // Angle of the middle of the segment, 0.0 is the top of the circle
+        let turn = arc.start + (segment as f32 + 0.5) / SPINNER_SEGMENTS as f32;
+        let angle = turn * 2.0 * PI;
+        let x = center.x + radius * angle.sin();
+        let y = center.y - radius * angle.cos();
+        LayoutRect::new(
+            LayoutPoint::new(x - stroke_width / 2.0, y - stroke_width / 2.0),
+            LayoutSize::new(stroke_width, stroke_width))
+    }).collect()
+}
+
+fn rect_center(rect: &LayoutRect) -> LayoutPoint {
+    LayoutPoint::new(rect.origin.x + rect.size.width / 2.0, rect.origin.y + rect.size.height / 2.0)
+}
+
+#[test]
+fn test_spinner_clock_rotates_once_per_rotation_duration() {
+    let started = Instant::now();
+    let clock = SpinnerClock::new(started);
+    assert_eq!(clock.rotation(started), 0.0);
+    assert_eq!(clock.rotation(started + Duration::from_millis(250)), 0.25);
+    // The rotation wraps around after a full turn
+    assert_eq!(clock.rotation(started + SPINNER_ROTATION_DURATION + Duration::from_millis(500)), 0.5);
+}
+
+#[test]
+fn test_determinate_spinner_renders_quarter_arc() {
+    let bounds = LayoutRect::new(LayoutPoint::new(0.0, 0.0), LayoutSize::new(100.0, 100.0));
+    let center = rect_center(&bounds);
+
+    // The rotation doesn't affect a determinate spinner
+    let arc = SpinnerArc::new(Some(SpinnerValue::new(0.25)), 0.7);
+    assert_eq!(arc, SpinnerArc { start: 0.0, sweep: 0.25 });
+
+    let segments = get_spinner_segments(&bounds, &arc);
+    assert_eq!(segments.len(), SPINNER_SEGMENTS / 4);
+
+    // The arc goes clockwise from the top to the right of the circle
+    for segment in &segments {
+        assert!(rect_center(segment).x >= center.x);
+        assert!(rect_center(segment).y <= center.y);
+    }
+    assert!(rect_center(segments.first().unwrap()).x - center.x < 5.0);
+    assert!(center.y - rect_center(segments.last().unwrap()).y < 5.0);
+
+    // Out-of-range values are clamped
+    assert_eq!(get_spinner_segments(&bounds, &SpinnerArc::new(Some(SpinnerValue::new(2.0)), 0.0)).len(), SPINNER_SEGMENTS);
+    assert!(get_spinner_segments(&bounds, &SpinnerArc::new(Some(SpinnerValue::new(-1.0)), 0.0)).is_empty());
+}
@@ -0,0 +1,260 @@
+//! Synthetic input events, for testing the callbacks of an application
+//! without a real window or real user input (see `App::inject_event`)
+
+use glium::glutin::{KeyboardInput, ElementState, VirtualKeyCode, ModifiersState, MouseScrollDelta};
+use webrender::api::{LayoutRect, LayoutPoint};
+use {
+    dom::{On, Callback, UpdateScreen},
+    focus::FocusState,
+    traits::Layout,
+    ui_state::UiState,
+    window::{WindowEvent, WindowId},
+};
+
+/// An input event that didn't come from the operating system, but from the application
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SyntheticEvent {
+    /// The left mouse button is pressed and released at the given position
+    /// (in logical pixels, relative to the top left corner of the window)
+    Click { x: f32, y: f32 },
+    /// The mouse wheel / touchpad is scrolled by the `delta` while the cursor is at the given
+    /// position. Only fires the `On::Scroll` callbacks, the scroll containers aren't scrolled.
+    Scroll { x: f32, y: f32, delta: MouseScrollDelta },
+    /// A key is pressed and released. The key is sent to the focused node, currently only
+    /// Enter and Space have an effect (they activate the focused node, see `AppState::focus_node`)
+    KeyPress(VirtualKeyCode),
+}
+
+/// Which callbacks were invoked by a synthetic event
+#[derive(Debug, Clone, PartialEq)]
+pub struct DispatchedEvents {
+    /// The tags of the nodes whose callbacks were invoked together with the
+    /// event that invoked the callback, in the order that they were invoked
+    pub callbacks_called: Vec<(u64, On)>,
+    /// `UpdateScreen::Redraw` if any of the invoked callbacks requested a redraw
+    pub update_screen: UpdateScreen,
+}
+
+impl SyntheticEvent {
+
+    /// Returns which events are fired on the nodes under the cursor
+    fn mouse_events(&self) -> Vec<On> {
+        match self {
+            SyntheticEvent::Click { .. } => vec![On::MouseDown, On::LeftMouseDown, On::MouseUp, On::LeftMouseUp],
+            SyntheticEvent::Scroll { .. } => vec![On::Scroll],
+            SyntheticEvent::KeyPress(_) => Vec::new(),
+        }
+    }
+}
+
+/// Runs one dispatch cycle for the synthetic `event`: hit-tests the `hit_test_rects`
+/// (the bounds of the nodes, by their tag), determines which callbacks of the `ui_state`
+/// have to be invoked and invokes them via `invoke_callback`.
+pub(crate) fn dispatch_synthetic_event<T: Layout, F>(
+    event: &SyntheticEvent,
+    window_id: WindowId,
+    hit_test_rects: &[(u64, LayoutRect)],
+    focus: &FocusState,
+    ui_state: &UiState<T>,
+    mut invoke_callback: F)
+-> DispatchedEvents
+where F: FnMut(Callback<T>, WindowEvent) -> UpdateScreen
+{
+    let mut callbacks_called = Vec::new();
+    let mut update_screen = UpdateScreen::DontRedraw;
+
+    // (tag, event, window event) of the callbacks that should be invoked
+    let mut node_events = Vec::<(u64, On, WindowEvent)>::new();
+
+    match *event {
+        SyntheticEvent::Click { x, y } | SyntheticEvent::Scroll { x, y, .. } => {
+            let scroll_delta = match *event {
+                SyntheticEvent::Scroll { delta, .. } => Some(delta),
+                _ => None,
+            };
+            let point = LayoutPoint::new(x, y);
+            for (tag, rect) in hit_test_rects.iter().filter(|(_, rect)| rect.contains(&point)) {
+                let window_event = WindowEvent {
+                    window: window_id.id,
+                    number_of_previous_siblings: None,
+                    cursor_relative_to_item: (x - rect.origin.x, y - rect.origin.y),
+                    cursor_in_viewport: (x, y),
+                    keyboard_input: None,
+                    scroll_delta: scroll_delta,
+                };
+                for on in event.mouse_events() {
+                    node_events.push((*tag, on, window_event));
+                }
+            }
+        },
+        SyntheticEvent::KeyPress(virtual_keycode) => {
+            let input = KeyboardInput {
+                scancode: 0,
+                state: ElementState::Released,
+                virtual_keycode: Some(virtual_keycode),
+                modifiers: ModifiersState::default(),
+            };
+            if let Some((tag, on)) = focus.keyboard_activation_event(&input) {
+                let window_event = WindowEvent {
+                    window: window_id.id,
+                    number_of_previous_siblings: None,
+                    cursor_relative_to_item: (0.0, 0.0),
+                    cursor_in_viewport: (0.0, 0.0),
+                    keyboard_input: Some(input),
+                    scroll_delta: None,
+                };
+                node_events.push((tag, on, window_event));
+            }
+        },
+    }
+
+    for (tag, on, window_event) in node_events {
+        let callback_id = match ui_state.node_ids_to_callbacks_list.get(&tag).and_then(|callback_ids| callback_ids.get(&on)) {
+            Some(id) => id,
+            None => continue,
+        };
+        let callback = ui_state.callback_list[callback_id].clone();
+        if invoke_callback(callback, window_event) == UpdateScreen::Redraw {
+            update_screen = UpdateScreen::Redraw;
+        }
+        callbacks_called.push((tag, on));
+    }
+
+    DispatchedEvents {
+        callbacks_called: callbacks_called,
+        update_screen: update_screen,
+    }
+}
+
+/// Returns the UI state of the `dom`, with the callbacks of the `dom` collected
+#[cfg(test)]
+fn ui_state_for<T: Layout>(dom: ::dom::Dom<T>) -> UiState<T> {
+
+    use std::collections::BTreeMap;
+
+    let mut callback_list = BTreeMap::new();
+    let mut node_ids_to_callbacks_list = BTreeMap::new();
+    dom.collect_callbacks(&mut callback_list, &mut node_ids_to_callbacks_list);
+
+    UiState {
+        dom: dom,
+        callback_list: callback_list,
+        node_ids_to_callbacks_list: node_ids_to_callbacks_list,
+    }
+}
+
+#[test]
+fn test_synthetic_click_on_button() {
+
+    use webrender::api::LayoutSize;
+    use dom::{Dom, NodeType};
+    use app_state::AppState;
+    use test_utils::TestLayout;
+
+    fn on_click(_: &mut AppState<TestLayout>, _: WindowEvent) -> UpdateScreen { UpdateScreen::Redraw }
+
+    let button = Dom::new(NodeType::Div).with_callback(On::MouseUp, Callback(on_click));
+    let button_tag = button.arena.borrow()[button.root].data.tag.unwrap();
+    let ui_state = ui_state_for(button);
+
+    let button_bounds = LayoutRect::new(LayoutPoint::new(10.0, 10.0), LayoutSize::new(100.0, 30.0));
+    let hit_test_rects = [(button_tag, button_bounds)];
+    let focus = FocusState::default();
+    let window_id = WindowId { id: 0 };
+
+    // Click on the button
+    let mut invoked_callbacks = Vec::new();
+    let dispatched = dispatch_synthetic_event(&SyntheticEvent::Click { x: 20.0, y: 20.0 }, window_id, &hit_test_rects, &focus, &ui_state, |callback, window_event| {
+        invoked_callbacks.push((callback, window_event.cursor_relative_to_item));
+        UpdateScreen::Redraw
+    });
+
+    assert_eq!(dispatched, DispatchedEvents {
+        callbacks_called: vec![(button_tag, On::MouseUp)],
+        update_screen: UpdateScreen::Redraw,
+    });
+    assert_eq!(invoked_callbacks, vec![(Callback(on_click), (10.0, 10.0))]);
+
+    // Click next to the button
+    let dispatched = dispatch_synthetic_event(&SyntheticEvent::Click { x: 200.0, y: 20.0 }, window_id, &hit_test_rects, &focus, &ui_state, |_, _| UpdateScreen::Redraw);
+    assert_eq!(dispatched, DispatchedEvents {
+        callbacks_called: Vec::new(),
+        update_screen: UpdateScreen::DontRedraw,
+    });
+}
+
+#[test]
+fn test_synthetic_click_on_disabled_button() {
+
+    use webrender::api::LayoutSize;
+    use dom::{Dom, NodeType};
+    use app_state::AppState;
+    use test_utils::TestLayout;
+
+    fn on_click(_: &mut AppState<TestLayout>, _: WindowEvent) -> UpdateScreen { UpdateScreen::Redraw }
+
+    let button = Dom::new(NodeType::Div).with_callback(On::MouseUp, Callback(on_click)).with_disabled(true);
+    let button_tag = button.arena.borrow()[button.root].data.tag.unwrap();
+    let ui_state = ui_state_for(button);
+
+    let button_bounds = LayoutRect::new(LayoutPoint::new(10.0, 10.0), LayoutSize::new(100.0, 30.0));
+    let hit_test_rects = [(button_tag, button_bounds)];
+    let mut focus = FocusState::default();
+    focus.focus(button_tag);
+
+    let mut callback_was_called = false;
+    let dispatched = dispatch_synthetic_event(&SyntheticEvent::Click { x: 20.0, y: 20.0 }, WindowId { id: 0 }, &hit_test_rects, &focus, &ui_state, |_, _| {
+        callback_was_called = true;
+        UpdateScreen::Redraw
+    });
+    assert!(!callback_was_called);
+    assert!(dispatched.callbacks_called.is_empty());
+
+    // Activating the focused button via the keyboard doesn't invoke the callback either
+    let dispatched = dispatch_synthetic_event(&SyntheticEvent::KeyPress(VirtualKeyCode::Return), WindowId { id: 0 }, &hit_test_rects, &focus, &ui_state, |_, _| UpdateScreen::Redraw);
+    assert!(dispatched.callbacks_called.is_empty());
+}
+
+#[test]
+fn test_scroll_callback_accumulates_the_scroll_delta() {
+
+    use webrender::api::LayoutSize;
+    use glium::glutin::dpi::LogicalPosition;
+    use dom::{Dom, NodeType};
+    use app_state::AppState;
+    use test_utils::TestLayout;
+
+    /// How many pixels a scrolled line is
+    const LINE_HEIGHT: f32 = 20.0;
+
+    // A custom scroll container, which moves its content (the data) by the scrolled lines / pixels
+    fn on_scroll(app_state: &mut AppState<TestLayout<f32>>, event: WindowEvent) -> UpdateScreen {
+        let delta_y = match event.scroll_delta {
+            Some(MouseScrollDelta::LineDelta(_, lines)) => lines * LINE_HEIGHT,
+            Some(MouseScrollDelta::PixelDelta(LogicalPosition { y, .. })) => y as f32,
+            None => return UpdateScreen::DontRedraw,
+        };
+        app_state.data.lock().unwrap().data += delta_y;
+        UpdateScreen::Redraw
+    }
+
+    let container = Dom::new(NodeType::Div).with_callback(On::Scroll, Callback(on_scroll));
+    let container_tag = container.arena.borrow()[container.root].data.tag.unwrap();
+    let ui_state = ui_state_for(container);
+
+    let hit_test_rects = [(container_tag, LayoutRect::new(LayoutPoint::zero(), LayoutSize::new(100.0, 100.0)))];
+    let focus = FocusState::default();
+    let mut app_state = AppState::new(TestLayout { data: 0.0 });
+
+    let scroll = |app_state: &mut AppState<TestLayout<f32>>, delta: MouseScrollDelta| {
+        dispatch_synthetic_event(&SyntheticEvent::Scroll { x: 50.0, y: 50.0, delta: delta }, WindowId { id: 0 }, &hit_test_rects, &focus, &ui_state,
+            |Callback(callback_func), window_event| (callback_func)(app_state, window_event))
+    };
+
+    let dispatched = scroll(&mut app_state, MouseScrollDelta::LineDelta(0.0, 3.0));
+    assert_eq!(dispatched.callbacks_called, vec![(container_tag, On::Scroll)]);
+    scroll(&mut app_state, MouseScrollDelta::PixelDelta(LogicalPosition::new(0.0, -15.0)));
+    scroll(&mut app_state, MouseScrollDelta::LineDelta(0.0, -1.0));
+
+    assert_eq!(app_state.data.lock().unwrap().data, 3.0 * LINE_HEIGHT - 15.0 - LINE_HEIGHT);
+}
@@ -0,0 +1,100 @@
+//! Fixtures that are shared between the unit tests of the layout and the display list,
+//! so that every test doesn't have to set up its own solver, fonts and fake render API.
+
+use webrender::api::{PipelineId, Epoch, IdNamespace, FontKey, ColorU};
+use {
+    dom::Dom,
+    css::Css,
+    traits::Layout,
+    window::UiSolver,
+    window_state::WindowSize,
+    display_list::DisplayList,
+    display_list_capture::DisplayListCapture,
+    resources::AppResources,
+    render_api::FakeRenderApi,
+    font::{self, FontState},
+    css_parser::{FontId, BackgroundColor},
+    text_layout::ScrollbarInfo,
+};
+
+/// Layout for tests that build their DOM directly instead of calling `Layout::layout`.
+/// The `data` holds the state of the tests whose callbacks modify the app data.
+#[derive(Default)]
+pub(crate) struct TestLayout<T = ()> {
+    pub(crate) data: T,
+}
+
+impl<T> Layout for TestLayout<T> {
+    fn layout(&self) -> Dom<Self> {
+        Dom::div()
+    }
+}
+
+/// The `font-family` of the font that is loaded by `app_resources_with_test_font`
+pub(crate) const TEST_FONT_FAMILY: &str = "Webly Sleeky UI";
+
+/// Returns resources that contain only the bundled Webly Sleeky UI font (marked as
+/// already uploaded), so that text can be laid out without depending on the system fonts
+pub(crate) fn app_resources_with_test_font() -> AppResources<'static> {
+    let font_bytes = include_bytes!("../assets/fonts/weblysleekuil.ttf").to_vec();
+    let (parsed_font, font_data) = font::rusttype_load_font(font_bytes, None).unwrap();
+    let font_id = FontId::ExternalFont(String::from(TEST_FONT_FAMILY));
+    let mut app_resources = AppResources::without_system_resources();
+    app_resources.font_data.insert(font_id, (parsed_font, font_data, FontState::Uploaded(FontKey(IdNamespace(0), 100))));
+    app_resources
+}
+
+/// Returns the scrollbar style that the display list uses for text, for the tests that
+/// lay out text or push scrollbars without going through `push_text`
+pub(crate) fn test_scrollbar_info() -> ScrollbarInfo {
+    ScrollbarInfo {
+        width: 17,
+        padding: 2,
+        min_thumb_length: 20,
+        background_color: BackgroundColor(ColorU { r: 241, g: 241, b: 241, a: 255 }),
+        triangle_color: BackgroundColor(ColorU { r: 163, g: 163, b: 163, a: 255 }),
+        bar_color: BackgroundColor(ColorU { r: 193, g: 193, b: 193, a: 255 }),
+    }
+}
+
+/// Lays out and paints one frame of the `display_list`, returns the pushed primitives
+///
+/// The `ui_solver` keeps the layout between the frames, like the solver of a window does.
+pub(crate) fn paint_frame(
+    display_list: &DisplayList<TestLayout>,
+    ui_solver: &mut UiSolver<TestLayout>,
+    css: &mut Css,
+    app_resources: &mut AppResources,
+    has_window_size_changed: bool,
+    window_size: &WindowSize)
+-> DisplayListCapture
+{
+    let builder = display_list.into_display_list_builder(
+        PipelineId(0, 0),
+        Epoch(0),
+        ui_solver,
+        css,
+        app_resources,
+        &FakeRenderApi::default(),
+        has_window_size_changed,
+        window_size).unwrap();
+    let (_, _, built_display_list) = builder.finalize();
+    DisplayListCapture::new(&built_display_list)
+}
+
+/// Styles the `dom` and lays it out as the first frame of a new window of the default size,
+/// returns the solver that holds the solved layout
+pub(crate) fn solve(dom: &Dom<TestLayout>, css: &mut Css) -> UiSolver<TestLayout> {
+    let ui_description = TestLayout::style_dom(dom, css);
+    let display_list = DisplayList::new_from_ui_description(&ui_description);
+    let mut ui_solver = UiSolver::new();
+    paint_frame(&display_list, &mut ui_solver, css, &mut AppResources::without_system_resources(), true, &WindowSize::default());
+    ui_solver
+}
+
+/// Styles the `dom` and paints it as the first frame of a new window of the default size
+pub(crate) fn paint_dom(dom: &Dom<TestLayout>, css: &mut Css, app_resources: &mut AppResources) -> DisplayListCapture {
+    let ui_description = TestLayout::style_dom(dom, css);
+    let display_list = DisplayList::new_from_ui_description(&ui_description);
+    paint_frame(&display_list, &mut UiSolver::new(), css, app_resources, true, &WindowSize::default())
+}
@@ -20,6 +20,47 @@ pub struct TextId {
     inner: usize,
 }
 
+/// Glyph index and horizontal advance of a character, shaped with one font instance
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct ShapedGlyph {
+    pub(crate) index: u32,
+    pub(crate) advance: f32,
+}
+
+/// The shaped characters of one font instance (font + font size)
+#[derive(Debug, Default, Clone)]
+pub(crate) struct FontInstanceGlyphs {
+    glyphs: FastHashMap<char, ShapedGlyph>,
+    /// How many characters had to be shaped, because they weren't cached yet
+    pub(crate) shape_count: usize,
+}
+
+impl FontInstanceGlyphs {
+    /// Returns the cached glyph of the character `c`, calls `shape` if it isn't cached yet
+    pub(crate) fn get_or_shape<F: FnOnce(char) -> ShapedGlyph>(&mut self, c: char, shape: F) -> ShapedGlyph {
+        let shape_count = &mut self.shape_count;
+        *self.glyphs.entry(c).or_insert_with(|| {
+            *shape_count += 1;
+            shape(c)
+        })
+    }
+}
+
+/// Shaping results of single characters, shared by all texts that use the same font
+/// instance - so that characters that occur in many texts are only shaped once,
+/// instead of once per text and frame.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct GlyphCache {
+    instances: FastHashMap<(FontId, FontSize), FontInstanceGlyphs>,
+}
+
+impl GlyphCache {
+    /// Returns the cached glyphs of a font instance, creates an empty cache if the instance is new
+    pub(crate) fn get_font_instance(&mut self, font_id: &FontId, font_size: FontSize) -> &mut FontInstanceGlyphs {
+        self.instances.entry((font_id.clone(), font_size)).or_insert_with(|| FontInstanceGlyphs::default())
+    }
+}
+
 /// Cache for accessing large amounts of text
 #[derive(Debug, Default, Clone)]
 pub struct TextCache {
@@ -0,0 +1,188 @@
+//! Editable text inputs, created with `Dom::text_input()`
+//!
+//! The text and the caret of the input are painted like a label, the caret is only
+//! drawn while the input is focused. Like the state of a checkbox, the state of a text
+//! input is part of the DOM: the `On::KeyDown` callback of the input has to pass the
+//! `WindowEvent::keyboard_input` to `TextInputState::handle_keyboard_input` and store the
+//! edited state in the app data, so that the next DOM contains the new text.
+
+use glium::glutin::{KeyboardInput, ElementState, VirtualKeyCode};
+use dom::UpdateScreen;
+
+/// Text and caret of a `NodeType::TextInput`
+#[derive(Debug, Default, Clone, Hash, PartialEq, Eq)]
+pub struct TextInputState {
+    pub text: String,
+    /// Index of the character in front of which the caret is drawn
+    /// (`text.chars().count()` if the caret is at the end of the text)
+    pub caret: usize,
+}
+
+impl TextInputState {
+
+    /// Creates a new state with the caret at the end of the text
+    pub fn new<S>(text: S)
+    -> Self where S: Into<String>
+    {
+        let text = text.into();
+        let caret = text.chars().count();
+        Self { text, caret }
+    }
+
+    /// Handles a key press of the focused input: printable keys insert a character in front
+    /// of the caret, Backspace / Delete remove the character in front of / behind the caret,
+    /// Left / Right / Home / End move the caret. Keys pressed together with Ctrl, Alt or the
+    /// logo key are left to the shortcuts.
+    ///
+    /// Returns `UpdateScreen::DontRedraw` if the key didn't change the text or the caret.
+    pub fn handle_keyboard_input(&mut self, input: &KeyboardInput) -> UpdateScreen {
+        use self::VirtualKeyCode::*;
+
+        let modifiers = input.modifiers;
+        if input.state != ElementState::Pressed || modifiers.ctrl || modifiers.alt || modifiers.logo {
+            return UpdateScreen::DontRedraw;
+        }
+
+        let char_count = self.text.chars().count();
+        // The caret may point past the end if the text was changed by the app
+        let caret = self.caret.min(char_count);
+
+        let virtual_keycode = match input.virtual_keycode {
+            Some(k) => k,
+            None => return UpdateScreen::DontRedraw,
+        };
+
+        let new_caret = match virtual_keycode {
+            Back if caret > 0 => {
+                let start = self.byte_index(caret - 1);
+                self.text.remove(start);
+                caret - 1
+            },
+            Delete if caret < char_count => {
+                let start = self.byte_index(caret);
+                self.text.remove(start);
+                caret
+            },
+            Left => caret.saturating_sub(1),
+            Right => (caret + 1).min(char_count),
+            Home => 0,
+            End => char_count,
+            other => match get_char(other, modifiers.shift) {
+                Some(c) => {
+                    let start = self.byte_index(caret);
+                    self.text.insert(start, c);
+                    caret + 1
+                },
+                None => return UpdateScreen::DontRedraw,
+            },
+        };
+
+        let is_text_changed = match virtual_keycode {
+            Left | Right | Home | End => false,
+            _ => true,
+        };
+
+        if !is_text_changed && new_caret == self.caret {
+            return UpdateScreen::DontRedraw;
+        }
+
+        self.caret = new_caret;
+        UpdateScreen::Redraw
+    }
+
+    /// Returns the number of glyphs in front of the caret. Whitespace isn't laid out as
+    /// glyphs, so whitespace in front of the caret doesn't move the caret.
+    pub(crate) fn glyphs_before_caret(&self) -> usize {
+        self.text.chars().take(self.caret).filter(|c| !c.is_whitespace()).count()
+    }
+
+    /// Returns the byte index of the character with the index `char_index`
+    fn byte_index(&self, char_index: usize) -> usize {
+        self.text.char_indices().nth(char_index).map(|(idx, _)| idx).unwrap_or(self.text.len())
+    }
+}
+
+/// Returns the character that a key inserts (with a US keyboard layout)
+fn get_char(virtual_keycode: VirtualKeyCode, shift: bool) -> Option<char> {
+    use self::VirtualKeyCode::*;
+
+    const LETTERS: [VirtualKeyCode; 26] = [A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z];
+    const DIGITS: [VirtualKeyCode; 10] = [Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9];
+    const NUMPAD_DIGITS: [VirtualKeyCode; 10] = [Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9];
+
+    if let Some(idx) = LETTERS.iter().position(|k| *k == virtual_keycode) {
+        let c = (b'a' + idx as u8) as char;
+        return Some(if shift { c.to_ascii_uppercase() } else { c });
+    }
+    if let Some(idx) = DIGITS.iter().position(|k| *k == virtual_keycode) {
+        return Some(if shift { ")!@#$%^&*(".chars().nth(idx)? } else { (b'0' + idx as u8) as char });
+    }
+    if let Some(idx) = NUMPAD_DIGITS.iter().position(|k| *k == virtual_keycode) {
+        return Some((b'0' + idx as u8) as char);
+    }
+
+    let (c, shifted) = match virtual_keycode {
+        Space => (' ', ' '),
+        Apostrophe => ('\'', '"'),
+        Comma => (',', '<'),
+        Minus => ('-', '_'),
+        Period => ('.', '>'),
+        Slash => ('/', '?'),
+        Semicolon => (';', ':'),
+        Equals => ('=', '+'),
+        LBracket => ('[', '{'),
+        RBracket => (']', '}'),
+        Backslash => ('\\', '|'),
+        Grave => ('`', '~'),
+        _ => return None,
+    };
+
+    Some(if shift { shifted } else { c })
+}
+
+#[test]
+fn test_text_input_editing() {
+
+    use glium::glutin::ModifiersState;
+
+    fn key(virtual_keycode: VirtualKeyCode, shift: bool) -> KeyboardInput {
+        KeyboardInput {
+            scancode: 0,
+            state: ElementState::Pressed,
+            virtual_keycode: Some(virtual_keycode),
+            modifiers: ModifiersState { shift: shift, .. Default::default() },
+        }
+    }
+
+    let mut input = TextInputState::new("ac");
+    assert_eq!(input.caret, 2);
+
+    // Move the caret between "a" and "c", insert an uppercase "B"
+    assert_eq!(input.handle_keyboard_input(&key(VirtualKeyCode::Left, false)), UpdateScreen::Redraw);
+    assert_eq!(input.handle_keyboard_input(&key(VirtualKeyCode::B, true)), UpdateScreen::Redraw);
+    assert_eq!(input, TextInputState { text: String::from("aBc"), caret: 2 });
+
+    // Backspace removes the character in front of the caret, Delete the one behind it
+    input.handle_keyboard_input(&key(VirtualKeyCode::Back, false));
+    input.handle_keyboard_input(&key(VirtualKeyCode::Delete, false));
+    assert_eq!(input, TextInputState { text: String::from("a"), caret: 1 });
+
+    // The caret can't move past the end of the text
+    assert_eq!(input.handle_keyboard_input(&key(VirtualKeyCode::Right, false)), UpdateScreen::DontRedraw);
+    assert_eq!(input.handle_keyboard_input(&key(VirtualKeyCode::Delete, false)), UpdateScreen::DontRedraw);
+
+    input.handle_keyboard_input(&key(VirtualKeyCode::Space, false));
+    input.handle_keyboard_input(&key(VirtualKeyCode::Key1, true));
+    input.handle_keyboard_input(&key(VirtualKeyCode::Home, false));
+    input.handle_keyboard_input(&key(VirtualKeyCode::Key2, false));
+    assert_eq!(input, TextInputState { text: String::from("2a !"), caret: 1 });
+
+    // Keys pressed together with Ctrl are shortcuts, not text
+    let ctrl_a = KeyboardInput { modifiers: ModifiersState { ctrl: true, .. Default::default() }, .. key(VirtualKeyCode::A, false) };
+    assert_eq!(input.handle_keyboard_input(&ctrl_a), UpdateScreen::DontRedraw);
+    assert_eq!(input.text, "2a !");
+
+    // The space isn't laid out as a glyph
+    input.caret = 4;
+    assert_eq!(input.glyphs_before_caret(), 3);
+}
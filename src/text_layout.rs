@@ -1,5 +1,6 @@
 #![allow(unused_variables, dead_code)]
 
+use std::borrow::Cow;
 use webrender::api::LayoutPixel;
 use euclid::{TypedRect, TypedSize2D, TypedPoint2D};
 use rusttype::{Font, Scale, GlyphId};
@@ -8,9 +9,12 @@ use {
     display_list::TextInfo,
     css_parser::{
         TextAlignmentHorz, FontSize, BackgroundColor,
-        FontId, TextAlignmentVert, LineHeight, LayoutOverflow
+        FontId, TextAlignmentVert, LineHeight, TextIndent, LayoutOverflow,
+        OverflowWrap, WordBreak, TextDecoration, TextDecorationLines,
+        WritingMode,
     },
-    text_cache::{TextId, TextCache},
+    font::{self, FontDecorationMetrics},
+    text_cache::{TextId, TextCache, FontInstanceGlyphs, ShapedGlyph},
 };
 
 pub use webrender::api::GlyphInstance;
@@ -60,6 +64,109 @@ impl SemanticWordItem {
     }
 }
 
+/// How a word that doesn't fit into the remaining space of a line is broken,
+/// resolved from the `overflow-wrap` and `word-break` properties
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum LineBreakMode {
+    /// The word is moved to the next line, but never broken
+    Normal,
+    /// `overflow-wrap: break-word`: The word is moved to the next line,
+    /// if it still doesn't fit, it is broken at the character that overflows
+    BreakLongWords,
+    /// `word-break: break-all`: The word fills up the current line
+    /// and is broken at the character that overflows
+    BreakAll,
+}
+
+impl Default for LineBreakMode {
+    fn default() -> Self {
+        LineBreakMode::Normal
+    }
+}
+
+impl LineBreakMode {
+    /// `word-break: break-all` takes precedence over `overflow-wrap`
+    pub(crate) fn from_style(overflow_wrap: Option<OverflowWrap>, word_break: Option<WordBreak>) -> Self {
+        match (overflow_wrap, word_break) {
+            (_, Some(WordBreak::BreakAll)) => LineBreakMode::BreakAll,
+            (Some(OverflowWrap::BreakWord), _) => LineBreakMode::BreakLongWords,
+            _ => LineBreakMode::Normal,
+        }
+    }
+}
+
+/// Where a single word has to be broken, so that it fits into the rectangle
+#[derive(Debug, Clone, PartialEq)]
+struct WordLineBreaks {
+    /// Whether the word has to start on a new line
+    wrap_before: bool,
+    /// The indices of the glyphs (in `Word::glyphs`) that start a new line
+    mid_word_breaks: Vec<usize>,
+    /// The width of the part of the word that is on the last line
+    last_line_width: f32,
+}
+
+/// Determines where the `word` has to be broken, given that the word starts at
+/// `word_caret` and the lines are `max_width` wide. With `LineBreakMode::Normal`, the
+/// word is only moved to the next line if it doesn't fit (but may overflow the next line).
+/// A word that already starts at the beginning of a line is never moved.
+///
+/// A single glyph that is wider than `max_width` is never broken and overflows the line.
+fn break_word(word: &Word, word_caret: f32, max_width: f32, line_break_mode: LineBreakMode) -> WordLineBreaks {
+
+    let mut line_breaks = WordLineBreaks {
+        wrap_before: false,
+        mid_word_breaks: Vec::new(),
+        last_line_width: word.total_width,
+    };
+
+    if word_caret + word.total_width <= max_width {
+        return line_breaks;
+    }
+
+    // Caret position of the first glyph on the current line
+    let mut line_caret = word_caret;
+
+    match line_break_mode {
+        LineBreakMode::Normal => {
+            line_breaks.wrap_before = word_caret > 0.0;
+            return line_breaks;
+        },
+        LineBreakMode::BreakLongWords => {
+            if word_caret > 0.0 {
+                line_breaks.wrap_before = true;
+                line_caret = 0.0;
+            }
+        },
+        LineBreakMode::BreakAll => { },
+    }
+
+    // Index and x offset (relative to the word) of the first glyph on the current line
+    let mut line_start_idx = 0;
+    let mut line_start_x = 0.0;
+
+    for (glyph_idx, glyph) in word.glyphs.iter().enumerate() {
+        let glyph_end = word.glyphs.get(glyph_idx + 1).map(|g| g.point.x).unwrap_or(word.total_width);
+        if line_caret + (glyph_end - line_start_x) <= max_width {
+            continue;
+        }
+
+        if glyph_idx != line_start_idx {
+            line_breaks.mid_word_breaks.push(glyph_idx);
+            line_start_idx = glyph_idx;
+            line_start_x = glyph.point.x;
+            line_caret = 0.0;
+        } else if line_caret > 0.0 {
+            // Not even the first glyph fits into the rest of the line
+            line_breaks.wrap_before = true;
+            line_caret = 0.0;
+        }
+    }
+
+    line_breaks.last_line_width = word.total_width - line_start_x;
+    line_breaks
+}
+
 /// Returned struct for the pass-1 text run test.
 ///
 /// Once the text is parsed and split into words + normalized, we can calculate
@@ -119,6 +226,9 @@ pub(crate) struct ScrollbarInfo {
     pub(crate) width: usize,
     /// Padding of the scrollbar, in pixels. The inner bar is `width - padding` pixels wide.
     pub(crate) padding: usize,
+    /// Minimum length of the thumb in pixels, so that the thumb stays usable
+    /// even if the content is much longer than the rectangle
+    pub(crate) min_thumb_length: usize,
     /// Style of the scrollbar (how to draw it)
     pub(crate) bar_color: BackgroundColor,
     /// How to draw the "up / down" arrows
@@ -135,7 +245,8 @@ pub struct FontMetrics {
     space_width: f32,
     /// Usually 4 * space_width
     tab_width: f32,
-    /// font_size * line_height
+    /// Distance between two baselines: the line spacing of the font (see
+    /// `get_normal_line_height`), multiplied by the `line-height`
     vertical_advance: f32,
     /// Offset of the font from the top of the bounding rectangle
     offset_top: f32,
@@ -147,6 +258,123 @@ pub struct FontMetrics {
     font_size_no_line_height: Scale,
 }
 
+/// Position and thickness of the text decorations of a font, scaled to the font size.
+/// The offsets are relative to the baseline, positive values point down.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct TextDecorationMetrics {
+    /// Offset of the top edge of the underline
+    pub(crate) underline_offset: f32,
+    /// Offset of the top edge of the overline (at the ascender of the font)
+    pub(crate) overline_offset: f32,
+    /// Thickness of the underline and the overline (in pixels)
+    pub(crate) thickness: f32,
+    /// Offset of the top edge of the line-through (at the strikeout position of the font)
+    pub(crate) line_through_offset: f32,
+    /// Thickness of the line-through (in pixels)
+    pub(crate) line_through_thickness: f32,
+}
+
+impl TextDecorationMetrics {
+    /// Scales the metrics of the font (in font units) to the font size
+    pub(crate) fn new(metrics: &FontDecorationMetrics, font_size: &FontSize) -> Self {
+        let scale = font_size.to_pixels() / metrics.units_per_em as f32;
+        Self {
+            underline_offset: -(metrics.underline_position as f32) * scale,
+            overline_offset: -(metrics.ascender as f32) * scale,
+            thickness: metrics.underline_thickness as f32 * scale,
+            line_through_offset: -(metrics.strikeout_position as f32) * scale,
+            line_through_thickness: metrics.strikeout_size as f32 * scale,
+        }
+    }
+}
+
+/// Returns the rectangles of the `text-decoration` lines for the laid-out `glyphs`: for each
+/// decoration line (from top to bottom), one rectangle per line of text (empty for `TextDecoration::None`)
+pub(crate) fn get_text_decoration_rects<D: Into<TextDecorationLines>>(
+    app_resources: &AppResources,
+    glyphs: &[GlyphInstance],
+    font_id: &FontId,
+    font_size: &FontSize,
+    text_decoration: D)
+-> Vec<TypedRect<f32, LayoutPixel>>
+{
+    let decoration_lines = text_decoration.into().lines();
+    if decoration_lines.is_empty() {
+        return Vec::new();
+    }
+
+    let (font, font_bytes) = match app_resources.get_font(font_id) {
+        Some(f) => f,
+        None => return Vec::new(),
+    };
+
+    let metrics = match font::get_font_decoration_metrics(font_bytes) {
+        Some(m) => TextDecorationMetrics::new(&m, font_size),
+        None => return Vec::new(),
+    };
+
+    // Same scale that the glyphs were laid out with, see `calculate_font_metrics`
+    let scale = Scale::uniform(font_size.to_pixels() * PX_TO_PT);
+    let glyph_right_edge = |glyph: &GlyphInstance| {
+        glyph.point.x + font.glyph(GlyphId(glyph.index)).scaled(scale).h_metrics().advance_width
+    };
+
+    // (baseline, left edge, right edge) of each line of text
+    let mut lines = Vec::<(f32, f32, f32)>::new();
+    for glyph in glyphs {
+        let is_same_line = lines.last().map(|line| line.0 == glyph.point.y).unwrap_or(false);
+        if is_same_line {
+            let line = lines.last_mut().unwrap();
+            line.2 = line.2.max(glyph_right_edge(glyph));
+        } else {
+            lines.push((glyph.point.y, glyph.point.x, glyph_right_edge(glyph)));
+        }
+    }
+
+    decoration_lines.into_iter().flat_map(|decoration_line| {
+        let (offset, thickness) = match decoration_line {
+            TextDecoration::Underline => (metrics.underline_offset, metrics.thickness),
+            TextDecoration::Overline => (metrics.overline_offset, metrics.thickness),
+            TextDecoration::LineThrough | TextDecoration::None => (metrics.line_through_offset, metrics.line_through_thickness),
+        };
+        lines.iter().map(move |&(baseline, left, right)| {
+            TypedRect::new(TypedPoint2D::new(left, baseline + offset), TypedSize2D::new(right - left, thickness))
+        })
+    }).collect()
+}
+
+/// Returns the rectangle of the text caret after the first `caret_glyph` glyphs of the `glyphs`
+/// (after the last glyph if there are fewer glyphs), which spans from the ascent to the descent
+/// of the font. Returns `None` if there are no glyphs.
+pub(crate) fn get_caret_rect(
+    app_resources: &AppResources,
+    glyphs: &[GlyphInstance],
+    caret_glyph: usize,
+    font_id: &FontId,
+    font_size: &FontSize,
+    caret_width: f32)
+-> Option<TypedRect<f32, LayoutPixel>>
+{
+    let (font, _) = app_resources.get_font(font_id)?;
+
+    // Same scale that the glyphs were laid out with, see `calculate_font_metrics`
+    let scale = Scale::uniform(font_size.to_pixels() * PX_TO_PT);
+    let v_metrics = font.v_metrics(scale);
+
+    // The caret is drawn after the glyph in front of it, or before the first glyph
+    let (x, y) = match caret_glyph.min(glyphs.len()).checked_sub(1).and_then(|idx| glyphs.get(idx)) {
+        Some(glyph) => (glyph.point.x + font.glyph(GlyphId(glyph.index)).scaled(scale).h_metrics().advance_width, glyph.point.y),
+        None => {
+            let first_glyph = glyphs.first()?;
+            (first_glyph.point.x, first_glyph.point.y)
+        },
+    };
+
+    Some(TypedRect::new(
+        TypedPoint2D::new(x, y - v_metrics.ascent),
+        TypedSize2D::new(caret_width, v_metrics.ascent - v_metrics.descent)))
+}
+
 /// ## Inputs
 ///
 /// - `app_resources`: This is only used for caching - if you already have a `LargeString`, which
@@ -158,10 +386,13 @@ pub struct FontMetrics {
 /// - `font`: The font to use for layouting (only the ID)
 /// - `font_size`: The font size (without line height)
 /// - `line_height`: The line height (100% = 1.0). I.e. `line-height = 1.2;` scales the text vertically by 1.2x
+/// - `text_indent`: Horizontal offset of the first line, parsed from the `text-indent` attribute
 /// - `text`: The actual text to layout. Will be unicode-normalized after the Unicode Normalization Form C
 ///   (canonical decomposition followed by canonical composition).
 /// - `overflow`: If the scrollbars should be show, parsed from the `overflow-{x / y}` fields
 /// - `scrollbar_info`: Mostly used to reserve space for the scrollbar, if necessary.
+/// - `writing_mode`: Parsed from the `writing-mode` attribute. Vertical text is laid out in
+///   columns, the `horiz_alignment` then aligns the text along the columns.
 ///
 /// ## Returns
 ///
@@ -178,48 +409,99 @@ pub(crate) fn get_glyphs(
     target_font_id: &FontId,
     target_font_size: &FontSize,
     line_height: Option<LineHeight>,
+    text_indent: Option<TextIndent>,
     text: &TextInfo,
     overflow: &LayoutOverflow,
-    scrollbar_info: &ScrollbarInfo)
+    scrollbar_info: &ScrollbarInfo,
+    line_break_mode: LineBreakMode,
+    writing_mode: WritingMode)
 -> (Vec<GlyphInstance>, TextOverflowPass2)
 {
-    let target_font = app_resources.font_data.get(target_font_id).expect("Drawing with invalid font!");
+    if !writing_mode.is_vertical() {
+        return get_horizontal_glyphs(
+            app_resources, bounds, horiz_alignment, vert_alignment, target_font_id, target_font_size,
+            line_height, text_indent, text, overflow, scrollbar_info, line_break_mode);
+    }
 
-    let font_metrics = calculate_font_metrics(&target_font.0, target_font_size, line_height);
+    let font_metrics = {
+        let target_font = app_resources.font_data.get(target_font_id).expect("Drawing with invalid font!");
+        calculate_font_metrics(&target_font.0, target_font_size, line_height)
+    };
+
+    // Vertical text is laid out as horizontal text in the transposed bounds (the lines become
+    // the columns), then each glyph is moved into its column
+    let transposed_bounds = TypedRect::new(TypedPoint2D::zero(), TypedSize2D::new(bounds.size.height, bounds.size.width));
+    let (mut glyphs, overflow_pass_2) = get_horizontal_glyphs(
+        app_resources, &transposed_bounds, horiz_alignment, vert_alignment, target_font_id, target_font_size,
+        line_height, text_indent, text, overflow, scrollbar_info, line_break_mode);
+
+    transpose_glyphs(&mut glyphs, bounds, &font_metrics, writing_mode);
+
+    let overflow_pass_2 = TextOverflowPass2 {
+        horizontal: overflow_pass_2.vertical,
+        vertical: overflow_pass_2.horizontal,
+    };
+
+    (glyphs, overflow_pass_2)
+}
 
+/// Moves the glyphs of a text that was laid out horizontally in the transposed `bounds`
+/// (relative to the origin) into columns: the lines advance downwards, starting at the
+/// right edge (`vertical-rl`) or the left edge (`vertical-lr`) of the `bounds`
+fn transpose_glyphs(glyphs: &mut [GlyphInstance], bounds: &TypedRect<f32, LayoutPixel>, font_metrics: &FontMetrics, writing_mode: WritingMode) {
+
+    let FontMetrics { vertical_advance, offset_top, .. } = *font_metrics;
+
+    for glyph in glyphs {
+        // The horizontal layout puts the baseline of a line `offset_top` below the top of the line
+        let line_top = glyph.point.y - offset_top;
+        let column_left = match writing_mode {
+            WritingMode::VerticalRl => bounds.size.width - line_top - vertical_advance,
+            _ => line_top,
+        };
+        // The upright glyph is drawn below its position along the column
+        let y = glyph.point.x + offset_top;
+        glyph.point = TypedPoint2D::new(bounds.origin.x + column_left, bounds.origin.y + y);
+    }
+}
+
+fn get_horizontal_glyphs(
+    app_resources: &mut AppResources,
+    bounds: &TypedRect<f32, LayoutPixel>,
+    horiz_alignment: TextAlignmentHorz,
+    vert_alignment: TextAlignmentVert,
+    target_font_id: &FontId,
+    target_font_size: &FontSize,
+    line_height: Option<LineHeight>,
+    text_indent: Option<TextIndent>,
+    text: &TextInfo,
+    overflow: &LayoutOverflow,
+    scrollbar_info: &ScrollbarInfo,
+    line_break_mode: LineBreakMode)
+-> (Vec<GlyphInstance>, TextOverflowPass2)
+{
     // (1) Split the text into semantic items (word, tab or newline) OR get the cached
     // text and scale it accordingly.
-    //
-    // This function also normalizes the unicode characters and calculates kerning.
-    //
-    // NOTE: This should be revisited, the caching does unnecessary cloning.
-    let words_owned;
-    let words = match text {
-        TextInfo::Cached(text_id) => {
-            get_words_cached(text_id, &target_font.0, target_font_id, target_font_size, font_metrics.font_size_no_line_height, &mut app_resources.text_cache)
-        },
-        TextInfo::Uncached(s) => {
-            words_owned = split_text_into_words(s, &target_font.0, font_metrics.font_size_no_line_height);
-            &words_owned
-        },
-    };
+    let (target_font, font_metrics, words) = get_words(app_resources, target_font_id, target_font_size, line_height, text);
+    // A percentage is relative to the width of the text box, as in CSS
+    let text_indent = text_indent.map(|indent| indent.0.to_pixels_relative_to(bounds.size.width)).unwrap_or(0.0);
 
     // (2) Calculate the additions / subtractions that have to be take into account
     // let harfbuzz_adjustments = calculate_harfbuzz_adjustments(&text, &target_font.0);
 
     // (3) Determine if the words will overflow the bounding rectangle
-    let overflow_pass_1 = estimate_overflow_pass_1(&words, &bounds.size, &font_metrics, &overflow);
+    let overflow_pass_1 = estimate_overflow_pass_1(&words, &bounds.size, &font_metrics, text_indent, &overflow, line_break_mode);
 
     // (4) If the lines overflow, subtract the space needed for the scrollbars and calculate the length
     // again (TODO: already layout characters here?)
     let (new_size, overflow_pass_2) =
-        estimate_overflow_pass_2(&words, &bounds.size, &font_metrics, &overflow, scrollbar_info, overflow_pass_1);
+        estimate_overflow_pass_2(&words, &bounds.size, &font_metrics, text_indent, &overflow, scrollbar_info, overflow_pass_1, line_break_mode);
 
     let max_horizontal_text_width = if overflow.allows_horizontal_overflow() { None } else { Some(new_size.width) };
 
     // (5) Align text to the left, initial layout of glyphs
     let (mut positioned_glyphs, line_break_offsets, _, _) =
-        words_to_left_aligned_glyphs(words, &target_font.0, max_horizontal_text_width, &font_metrics);
+        words_to_left_aligned_glyphs(&words, target_font, max_horizontal_text_width, &font_metrics, text_indent, line_break_mode);
 
     // (6) Add the harfbuzz adjustments to the positioned glyphs
     // apply_harfbuzz_adjustments(&mut positioned_glyphs, harfbuzz_adjustments);
@@ -242,6 +524,62 @@ pub(crate) fn get_glyphs(
     (positioned_glyphs, overflow_pass_2)
 }
 
+/// Returns how high the lines of the `text` are when the text is laid out in a box that is
+/// `width` pixels wide - i.e. the height of a text box that is only as high as its content
+pub(crate) fn get_text_height(
+    app_resources: &mut AppResources,
+    width: f32,
+    target_font_id: &FontId,
+    target_font_size: &FontSize,
+    line_height: Option<LineHeight>,
+    text_indent: Option<TextIndent>,
+    text: &TextInfo,
+    overflow: &LayoutOverflow,
+    line_break_mode: LineBreakMode)
+-> f32
+{
+    let (_, font_metrics, words) = get_words(app_resources, target_font_id, target_font_size, line_height, text);
+    let text_indent = text_indent.map(|indent| indent.0.to_pixels_relative_to(width)).unwrap_or(0.0);
+
+    // In a box without any height, the text overflows by exactly the height of its lines
+    let rect_dimensions = TypedSize2D::new(width, 0.0);
+    match estimate_overflow_pass_1(&words, &rect_dimensions, &font_metrics, text_indent, overflow, line_break_mode).vertical {
+        TextOverflow::IsOverflowing(overflowing_px) => overflowing_px,
+        TextOverflow::InBounds(_) => 0.0,
+    }
+}
+
+/// Splits the `text` into words (or looks up the words of a cached text) and calculates the
+/// metrics of the font - the part of the text layout that is shared by `get_horizontal_glyphs`
+/// and `get_text_height`. Returns the font, so that the words can be laid out with it.
+///
+/// This function also normalizes the unicode characters and calculates kerning.
+///
+/// NOTE: This should be revisited, the caching does unnecessary cloning.
+fn get_words<'b, 'a: 'b>(
+    app_resources: &'b mut AppResources<'a>,
+    target_font_id: &FontId,
+    target_font_size: &FontSize,
+    line_height: Option<LineHeight>,
+    text: &TextInfo)
+-> (&'b Font<'a>, FontMetrics, Cow<'b, Words>)
+{
+    let target_font = app_resources.font_data.get(target_font_id).expect("Drawing with invalid font!");
+    let font_metrics = calculate_font_metrics(&target_font.0, target_font_size, line_height);
+
+    let glyphs = app_resources.glyph_cache.get_font_instance(target_font_id, *target_font_size);
+    let words = match text {
+        TextInfo::Cached(text_id) => {
+            Cow::Borrowed(get_words_cached(text_id, &target_font.0, target_font_id, target_font_size, font_metrics.font_size_no_line_height, &mut app_resources.text_cache, glyphs))
+        },
+        TextInfo::Uncached(s) => {
+            Cow::Owned(split_text_into_words(s, &target_font.0, font_metrics.font_size_no_line_height, glyphs))
+        },
+    };
+
+    (&target_font.0, font_metrics, words)
+}
+
 impl FontMetrics {
     /// Given a font, font size and line height, calculates the `FontMetrics` necessary
     /// which are later used to layout a block of text
@@ -252,7 +590,7 @@ impl FontMetrics {
 
 fn calculate_font_metrics<'a>(font: &Font<'a>, font_size: &FontSize, line_height: Option<LineHeight>) -> FontMetrics {
 
-    let font_size_f32 = font_size.0.to_pixels() * PX_TO_PT;
+    let font_size_f32 = font_size.to_pixels() * PX_TO_PT;
     let line_height = match line_height { Some(lh) => (lh.0).number, None => 1.0 };
     let font_size_with_line_height = Scale::uniform(font_size_f32 * line_height);
     let font_size_no_line_height = Scale::uniform(font_size_f32);
@@ -260,8 +598,8 @@ fn calculate_font_metrics<'a>(font: &Font<'a>, font_size: &FontSize, line_height
     let space_width = font.glyph(' ').scaled(font_size_no_line_height).h_metrics().advance_width;
     let tab_width = 4.0 * space_width; // TODO: make this configurable
 
-    let v_metrics_scaled = font.v_metrics(font_size_with_line_height);
-    let v_advance_scaled = v_metrics_scaled.ascent - v_metrics_scaled.descent + v_metrics_scaled.line_gap;
+    // Without a `line-height`, the lines are spaced as the font was designed
+    let v_advance_scaled = get_normal_line_height(font, font_size_no_line_height) * line_height;
     let offset_top = v_advance_scaled / 2.0;
 
     FontMetrics {
@@ -274,13 +612,22 @@ fn calculate_font_metrics<'a>(font: &Font<'a>, font_size: &FontSize, line_height
     }
 }
 
+/// Returns the distance between two baselines that the font was designed for
+/// (ascent + descent + line gap), i.e. the line height if no `line-height` is set
+/// (like `line-height: normal` in CSS)
+pub(crate) fn get_normal_line_height<'a>(font: &Font<'a>, font_size: Scale) -> f32 {
+    let v_metrics = font.v_metrics(font_size);
+    v_metrics.ascent - v_metrics.descent + v_metrics.line_gap
+}
+
 fn get_words_cached<'a>(
     text_id: &TextId,
     font: &Font<'a>,
     font_id: &FontId,
     font_size: &FontSize,
     font_size_no_line_height: Scale,
-    text_cache: &'a mut TextCache)
+    text_cache: &'a mut TextCache,
+    glyphs: &mut FontInstanceGlyphs)
 -> &'a Words
 {
     use std::collections::hash_map::Entry::*;
@@ -298,7 +645,7 @@ fn get_words_cached<'a>(
                 Occupied(existing_font_size_words) => { }
                 Vacant(v) => {
                     if is_new_font {
-                        v.insert(split_text_into_words(&text_cache.string_cache[text_id], font, font_size_no_line_height));
+                        v.insert(split_text_into_words(&text_cache.string_cache[text_id], font, font_size_no_line_height, glyphs));
                     } else {
                         // If we can get the words from any other size, we can just scale them here
                         // ex. if an existing font size gets scaled.
@@ -317,7 +664,7 @@ fn get_words_cached<'a>(
             let font_size_map = &text_cache.cached_strings[&text_id][&font_id];
             let (old_font_size, next_words_for_font) = font_size_map.iter().next().unwrap();
             let mut words_cloned: Words = next_words_for_font.clone();
-            let scale_factor = font_size.0.to_pixels() / old_font_size.0.to_pixels();
+            let scale_factor = font_size.to_pixels() / old_font_size.to_pixels();
 
             scale_words(&mut words_cloned, scale_factor);
             words_cloned
@@ -349,8 +696,10 @@ fn scale_words(words: &mut Words, scale_factor: f32) {
 
 /// This function is also used in the `text_cache` module for caching large strings.
 ///
-/// It is one of the most expensive functions, use with care.
-pub(crate) fn split_text_into_words<'a>(text: &str, font: &Font<'a>, font_size: Scale)
+/// It is one of the most expensive functions, use with care. The glyph indices and advances
+/// of the characters are looked up in (and added to) the `glyphs` of the font instance, so
+/// that each character is only shaped once per font instance.
+pub(crate) fn split_text_into_words<'a>(text: &str, font: &Font<'a>, font_size: Scale, glyphs: &mut FontInstanceGlyphs)
 -> Words
 {
     use unicode_normalization::UnicodeNormalization;
@@ -364,6 +713,7 @@ pub(crate) fn split_text_into_words<'a>(text: &str, font: &Font<'a>, font_size:
     let mut last_glyph = None;
 
     fn end_word(words: &mut Vec<SemanticWordItem>,
+                chars_in_this_word: &mut Vec<char>,
                 glyphs_in_this_word: &mut Vec<GlyphInstance>,
                 cur_word_length: &mut f32,
                 word_caret: &mut f32,
@@ -376,6 +726,7 @@ pub(crate) fn split_text_into_words<'a>(text: &str, font: &Font<'a>, font_size:
         }));
 
         // Reset everything
+        chars_in_this_word.clear();
         *last_glyph = None;
         *word_caret = 0.0;
         *cur_word_length = 0.0;
@@ -393,6 +744,7 @@ pub(crate) fn split_text_into_words<'a>(text: &str, font: &Font<'a>, font_size:
                 if !chars_in_this_word.is_empty() {
                     end_word(
                         &mut words,
+                        &mut chars_in_this_word,
                         &mut glyphs_in_this_word,
                         &mut cur_word_length,
                         &mut word_caret,
@@ -405,6 +757,7 @@ pub(crate) fn split_text_into_words<'a>(text: &str, font: &Font<'a>, font_size:
                 if !chars_in_this_word.is_empty() {
                     end_word(
                         &mut words,
+                        &mut chars_in_this_word,
                         &mut glyphs_in_this_word,
                         &mut cur_word_length,
                         &mut word_caret,
@@ -412,10 +765,13 @@ pub(crate) fn split_text_into_words<'a>(text: &str, font: &Font<'a>, font_size:
                 }
                 words.push(SemanticWordItem::Return);
             },
+            // "\r\n" line endings: the "\n" already ends the line
+            '\r' => { },
             ' ' => {
                 if !chars_in_this_word.is_empty() {
                     end_word(
                         &mut words,
+                        &mut chars_in_this_word,
                         &mut glyphs_in_this_word,
                         &mut cur_word_length,
                         &mut word_caret,
@@ -424,17 +780,23 @@ pub(crate) fn split_text_into_words<'a>(text: &str, font: &Font<'a>, font_size:
             },
             cur_char =>  {
                 // Regular character
-                let g = font.glyph(cur_char);
-                let id = g.id();
+                let shaped_glyph = glyphs.get_or_shape(cur_char, |c| {
+                    let g = font.glyph(c);
 
-                // calculate the real width
-                let glyph_metrics = g.standalone().get_data().unwrap();
-                let h_metrics = g.scaled(v_metrics_height_unscaled).h_metrics();
-                let mut horiz_advance = h_metrics.advance_width
-                                    * glyph_metrics.scale_for_1_pixel
-                                    * (font_size.x * (96.0 / 72.0));
+                    // calculate the real width
+                    let glyph_metrics = g.standalone().get_data().unwrap();
+                    let h_metrics = g.scaled(v_metrics_height_unscaled).h_metrics();
+                    let horiz_advance = h_metrics.advance_width
+                                        * glyph_metrics.scale_for_1_pixel
+                                        * (font_size.x * (96.0 / 72.0));
+
+                    // horiz_advance *= 96.0 / 72.0;
+
+                    ShapedGlyph { index: g.id().0, advance: horiz_advance }
+                });
 
-                // horiz_advance *= 96.0 / 72.0;
+                let id = GlyphId(shaped_glyph.index);
+                let horiz_advance = shaped_glyph.advance;
 
                 if let Some(last) = last_glyph {
                     word_caret += font.pair_kerning(font_size, last, id);
@@ -460,6 +822,7 @@ pub(crate) fn split_text_into_words<'a>(text: &str, font: &Font<'a>, font_size:
     if !chars_in_this_word.is_empty() {
         end_word(
             &mut words,
+            &mut chars_in_this_word,
             &mut glyphs_in_this_word,
             &mut cur_word_length,
             &mut word_caret,
@@ -475,7 +838,9 @@ fn estimate_overflow_pass_1(
     words: &Words,
     rect_dimensions: &TypedSize2D<f32, LayoutPixel>,
     font_metrics: &FontMetrics,
-    overflow: &LayoutOverflow)
+    text_indent: f32,
+    overflow: &LayoutOverflow,
+    line_break_mode: LineBreakMode)
 -> TextOverflowPass1
 {
     use self::SemanticWordItem::*;
@@ -511,18 +876,24 @@ fn estimate_overflow_pass_1(
             // TODO: should this be cached? The calculation is probably quick, but this
             // is essentially the same thing as we do in the actual text layout stage
             let mut max_line_cursor: f32 = 0.0;
-            let mut cur_line_cursor = 0.0;
+            // The first line starts at the `text-indent`
+            let mut cur_line_cursor = text_indent;
             let mut cur_line = 0;
 
             for w in words {
                 match w {
                     Word(w) => {
-                        if cur_line_cursor + w.total_width > rect_dimensions.width {
+                        let line_breaks = break_word(w, cur_line_cursor, rect_dimensions.width, line_break_mode);
+                        if line_breaks.wrap_before {
                             max_line_cursor = max_line_cursor.max(cur_line_cursor);
                             cur_line_cursor = 0.0;
                             cur_line += 1;
                         }
-                        cur_line_cursor += w.total_width + space_width;
+                        if !line_breaks.mid_word_breaks.is_empty() {
+                            cur_line_cursor = 0.0;
+                            cur_line += line_breaks.mid_word_breaks.len();
+                        }
+                        cur_line_cursor += line_breaks.last_line_width + space_width;
                     },
                     // TODO: also check for rect break after tabs? Kinda pointless, isn't it?
                     Tab => cur_line_cursor += tab_width,
@@ -552,7 +923,7 @@ fn estimate_overflow_pass_1(
 
         let horz_max = if overflow.allows_horizontal_overflow() {
 
-            let mut cur_line_cursor = 0.0;
+            let mut cur_line_cursor = text_indent;
             let mut max_line_cursor: f32 = 0.0;
 
             for w in words {
@@ -589,9 +960,11 @@ fn estimate_overflow_pass_2(
     words: &Words,
     rect_dimensions: &TypedSize2D<f32, LayoutPixel>,
     font_metrics: &FontMetrics,
+    text_indent: f32,
     overflow: &LayoutOverflow,
     scrollbar_info: &ScrollbarInfo,
-    pass1: TextOverflowPass1)
+    pass1: TextOverflowPass1,
+    line_break_mode: LineBreakMode)
 -> (TypedSize2D<f32, LayoutPixel>, TextOverflowPass2)
 {
     let FontMetrics { space_width, tab_width, vertical_advance, offset_top, .. } = *font_metrics;
@@ -606,17 +979,24 @@ fn estimate_overflow_pass_2(
     // scrollbar gets shown on the right edge, so we need to subtract from the
     // **width** of the rectangle.
 
-    if pass1.horizontal.is_overflowing() {
+    //
+    // With `overflow: auto`, the space is only reserved if the text actually overflows,
+    // `overflow: scroll` always reserves the space for the scrollbar.
+
+    let show_horizontal_scrollbar = overflow.needs_horizontal_scrollbar(pass1.horizontal.is_overflowing());
+    let show_vertical_scrollbar = overflow.needs_vertical_scrollbar(pass1.vertical.is_overflowing());
+
+    if show_horizontal_scrollbar {
         new_size.height -= scrollbar_info.width as f32;
     }
 
-    if pass1.vertical.is_overflowing() {
+    if show_vertical_scrollbar {
         new_size.width -= scrollbar_info.width as f32;
     }
 
-    // If the words are not overflowing, just take the result from the first pass
-    let recalc_scrollbar_info = if pass1.horizontal.is_overflowing() || pass1.vertical.is_overflowing() {
-        estimate_overflow_pass_1(words, &new_size, font_metrics, overflow)
+    // If no scrollbars are shown, just take the result from the first pass
+    let recalc_scrollbar_info = if show_horizontal_scrollbar || show_vertical_scrollbar {
+        estimate_overflow_pass_1(words, &new_size, font_metrics, text_indent, overflow, line_break_mode)
     } else {
         pass1
     };
@@ -661,7 +1041,9 @@ fn words_to_left_aligned_glyphs<'a>(
     words: &Words,
     font: &Font<'a>,
     max_horizontal_width: Option<f32>,
-    font_metrics: &FontMetrics)
+    font_metrics: &FontMetrics,
+    text_indent: f32,
+    line_break_mode: LineBreakMode)
 -> (Vec<GlyphInstance>, Vec<(usize, f32)>, f32, f32)
 {
     let words = &words.0;
@@ -683,8 +1065,9 @@ fn words_to_left_aligned_glyphs<'a>(
     // - How much space each line has (to the right edge of the containing rectangle)
     let mut line_break_offsets = Vec::<(usize, WordCaretMax)>::new();
 
-    // word_caret is the current X position of the "pen" we are writing with
-    let mut word_caret = 0.0;
+    // word_caret is the current X position of the "pen" we are writing with,
+    // the first line starts at the `text-indent` (which can be negative)
+    let mut word_caret = text_indent;
     let mut current_line_num = 0;
     let mut max_word_caret = 0.0;
 
@@ -692,14 +1075,18 @@ fn words_to_left_aligned_glyphs<'a>(
         use self::SemanticWordItem::*;
         match word {
             Word(word) => {
-                let text_overflows_rect = match max_horizontal_width {
-                    Some(max) => word_caret + word.total_width > max,
+                let line_breaks = match max_horizontal_width {
+                    Some(max) => break_word(word, word_caret, max, line_break_mode),
                     // If we don't have a maximum horizontal width, the text can overflow the
                     // bounding rectangle in the horizontal direction
-                    None => false,
+                    None => WordLineBreaks {
+                        wrap_before: false,
+                        mid_word_breaks: Vec::new(),
+                        last_line_width: word.total_width,
+                    },
                 };
 
-                if text_overflows_rect {
+                if line_breaks.wrap_before {
                     let space_until_horz_return = match max_horizontal_width {
                         Some(s) => WordCaretMax::SomeMaxWidth(s - word_caret),
                         None => WordCaretMax::NoMaxWidth(word_caret),
@@ -712,17 +1099,35 @@ fn words_to_left_aligned_glyphs<'a>(
                     current_line_num += 1;
                 }
 
-                for glyph in &word.glyphs {
+                // x offset (relative to the word) of the first glyph on the current line
+                let mut line_start_x = 0.0;
+
+                for (glyph_idx, glyph) in word.glyphs.iter().enumerate() {
+                    if line_breaks.mid_word_breaks.contains(&glyph_idx) {
+                        let line_width = word_caret + (glyph.point.x - line_start_x);
+                        let space_until_horz_return = match max_horizontal_width {
+                            Some(s) => WordCaretMax::SomeMaxWidth(s - line_width),
+                            None => WordCaretMax::NoMaxWidth(line_width),
+                        };
+                        line_break_offsets.push((left_aligned_glyphs.len() - 1, space_until_horz_return));
+                        if line_width > max_word_caret {
+                            max_word_caret = line_width;
+                        }
+                        word_caret = 0.0;
+                        line_start_x = glyph.point.x;
+                        current_line_num += 1;
+                    }
+
                     let mut new_glyph = *glyph;
-                    let push_x = word_caret;
+                    let push_x = word_caret - line_start_x;
                     let push_y = (current_line_num as f32 * vertical_advance) + offset_top;
                     new_glyph.point.x += push_x;
                     new_glyph.point.y += push_y;
                     left_aligned_glyphs.push(new_glyph);
                 }
 
-                // Add the word width to the current word_caret
-                word_caret += word.total_width + space_width;
+                // Add the width of the (last part of the) word to the current word_caret
+                word_caret += line_breaks.last_line_width + space_width;
             },
             Tab => {
                 word_caret += tab_width;
@@ -733,7 +1138,10 @@ fn words_to_left_aligned_glyphs<'a>(
                     Some(s) => WordCaretMax::SomeMaxWidth(s - word_caret),
                     None => WordCaretMax::NoMaxWidth(word_caret),
                 };
-                line_break_offsets.push((left_aligned_glyphs.len() - 1, space_until_horz_return));
+                // Empty lines at the start of the text have no glyph to break after
+                if !left_aligned_glyphs.is_empty() {
+                    line_break_offsets.push((left_aligned_glyphs.len() - 1, space_until_horz_return));
+                }
                 if word_caret > max_word_caret {
                     max_word_caret = word_caret;
                 }
@@ -837,7 +1245,8 @@ fn align_text_horz(alignment: TextAlignmentHorz, glyphs: &mut [GlyphInstance], l
 
     let mut current_line_num = 0;
     for (glyph_idx, glyph) in glyphs.iter_mut().enumerate() {
-        if glyph_idx > line_breaks[current_line_num].0 {
+        // `while`, not `if`: empty lines (i.e. "a\n\nb") have the same line break index
+        while glyph_idx > line_breaks[current_line_num].0 {
             current_line_num += 1;
         }
         let space_added_full = line_breaks[current_line_num].1;
@@ -851,6 +1260,10 @@ fn align_text_vert(alignment: TextAlignmentVert, glyphs: &mut [GlyphInstance], l
     use self::TextOverflow::*;
     use self::TextAlignmentVert::*;
 
+    if line_breaks.is_empty() {
+        return;
+    }
+
     assert!(glyphs.len() - 1 == line_breaks[line_breaks.len() - 1].0);
 
     let multiply_factor = match alignment {
@@ -912,15 +1325,63 @@ pub fn layout_text<'a>(
     //
     // This function simply lays out a text, without trying to fit it into a rectangle.
     // This function does not calculate any overflow.
-    let words = split_text_into_words(text, font, font_metrics.font_size_no_line_height);
+    let words = split_text_into_words(text, font, font_metrics.font_size_no_line_height, &mut FontInstanceGlyphs::default());
     let (layouted_glyphs, line_breaks, min_width, min_height) =
-        words_to_left_aligned_glyphs(&words, font, None, font_metrics);
+        words_to_left_aligned_glyphs(&words, font, None, font_metrics, 0.0, LineBreakMode::Normal);
 
     LayoutTextResult {
         words, layouted_glyphs, line_breaks, min_width, min_height
     }
 }
 
+/// How `layout_test_text` lays out its text - by default at 20px, without a `line-height`
+/// or `text-indent`, horizontally from the top left of a 500x500px box
+#[cfg(test)]
+struct TestTextLayout {
+    bounds: TypedRect<f32, LayoutPixel>,
+    font_size: FontSize,
+    line_height: Option<LineHeight>,
+    text_indent: Option<TextIndent>,
+    line_break_mode: LineBreakMode,
+    writing_mode: WritingMode,
+}
+
+#[cfg(test)]
+impl Default for TestTextLayout {
+    fn default() -> Self {
+        Self {
+            bounds: TypedRect::new(TypedPoint2D::zero(), TypedSize2D::new(500.0, 500.0)),
+            font_size: FontSize::px(20.0),
+            line_height: None,
+            text_indent: None,
+            line_break_mode: LineBreakMode::Normal,
+            writing_mode: WritingMode::HorizontalTb,
+        }
+    }
+}
+
+/// Lays out the `text` in the font of `test_utils::app_resources_with_test_font`, returns the glyphs
+#[cfg(test)]
+fn layout_test_text(app_resources: &mut AppResources, text: &str, layout: &TestTextLayout) -> Vec<GlyphInstance> {
+
+    use test_utils::{TEST_FONT_FAMILY, test_scrollbar_info};
+
+    get_glyphs(
+        app_resources,
+        &layout.bounds,
+        TextAlignmentHorz::Left,
+        TextAlignmentVert::Top,
+        &FontId::ExternalFont(String::from(TEST_FONT_FAMILY)),
+        &layout.font_size,
+        layout.line_height,
+        layout.text_indent,
+        &TextInfo::Uncached(String::from(text)),
+        &LayoutOverflow::default(),
+        &test_scrollbar_info(),
+        layout.line_break_mode,
+        layout.writing_mode).0
+}
+
 #[test]
 fn test_it_should_add_origin() {
     let mut instances = vec![
@@ -940,4 +1401,309 @@ fn test_it_should_add_origin() {
     assert_eq!(instances[0].point.y as usize, 0);
     assert_eq!(instances[1].point.x as usize, 33);
     assert_eq!(instances[1].point.y as usize, 10);
+}
+
+#[test]
+fn test_overflow_auto_only_reserves_scrollbar_when_overflowing() {
+
+    use css_parser::{TextOverflowBehaviour, TextOverflowBehaviourInner};
+    use test_utils::test_scrollbar_info;
+
+    let font_metrics = FontMetrics {
+        space_width: 5.0,
+        tab_width: 20.0,
+        vertical_advance: 20.0,
+        offset_top: 10.0,
+        font_size_with_line_height: Scale::uniform(12.0),
+        font_size_no_line_height: Scale::uniform(12.0),
+    };
+
+    let scrollbar_info = test_scrollbar_info();
+
+    let make_words = |count: usize| Words((0..count).map(|_| SemanticWordItem::Word(Word {
+        glyphs: Vec::new(),
+        total_width: 50.0,
+    })).collect());
+
+    let overflow_with = |behaviour| LayoutOverflow {
+        horizontal: TextOverflowBehaviour::Modified(TextOverflowBehaviourInner::Hidden),
+        vertical: TextOverflowBehaviour::Modified(behaviour),
+    };
+
+    let rect = TypedSize2D::new(100.0, 100.0);
+
+    let estimate = |words: &Words, overflow: &LayoutOverflow| {
+        let pass1 = estimate_overflow_pass_1(words, &rect, &font_metrics, 0.0, overflow, LineBreakMode::Normal);
+        estimate_overflow_pass_2(words, &rect, &font_metrics, 0.0, overflow, &scrollbar_info, pass1, LineBreakMode::Normal)
+    };
+
+    let fitting_words = make_words(1);
+    let overflowing_words = make_words(20);
+
+    // overflow: auto, content fits: no scrollbar, no space reserved
+    let auto = overflow_with(TextOverflowBehaviourInner::Auto);
+    let (size, pass2) = estimate(&fitting_words, &auto);
+    assert_eq!(size.width, 100.0);
+    assert!(!auto.needs_vertical_scrollbar(pass2.vertical.is_overflowing()));
+
+    // overflow: auto, content overflows: scrollbar is shown
+    let (size, pass2) = estimate(&overflowing_words, &auto);
+    assert_eq!(size.width, 83.0);
+    assert!(auto.needs_vertical_scrollbar(pass2.vertical.is_overflowing()));
+
+    // overflow: scroll always shows the scrollbar
+    let scroll = overflow_with(TextOverflowBehaviourInner::Scroll);
+    let (size, pass2) = estimate(&fitting_words, &scroll);
+    assert_eq!(size.width, 83.0);
+    assert!(scroll.needs_vertical_scrollbar(pass2.vertical.is_overflowing()));
+}
+
+#[test]
+fn test_text_height_grows_with_the_wrapped_lines() {
+
+    use test_utils::{TEST_FONT_FAMILY, app_resources_with_test_font};
+
+    let font_id = FontId::ExternalFont(String::from(TEST_FONT_FAMILY));
+    let mut app_resources = app_resources_with_test_font();
+
+    let overflow = LayoutOverflow::default();
+    let mut text_height = |text: &str, width: f32| get_text_height(
+        &mut app_resources,
+        width,
+        &font_id,
+        &FontSize::px(20.0),
+        None,
+        None,
+        &TextInfo::Uncached(String::from(text)),
+        &overflow,
+        LineBreakMode::Normal);
+
+    let one_line = text_height("hello", 500.0);
+    assert!(one_line > 0.0);
+
+    // Each line adds the same advance
+    let three_lines = text_height("hello\nhello\nhello", 500.0);
+    let two_lines = text_height("hello\nhello", 500.0);
+    assert!(three_lines > two_lines && two_lines > one_line);
+    assert!(((three_lines - two_lines) - (two_lines - one_line)).abs() < 0.001);
+
+    // The words wrap in a narrow box, so the text gets higher
+    assert_eq!(text_height("hello hello hello", 500.0), one_line);
+    assert_eq!(text_height("hello hello hello", 60.0), three_lines);
+}
+
+#[test]
+fn test_multi_line_label() {
+
+    use test_utils::app_resources_with_test_font;
+
+    let glyphs = layout_test_text(&mut app_resources_with_test_font(), "a\nb\nc", &TestTextLayout::default());
+
+    // One glyph per line, each line starts at the left edge and is below the previous line
+    assert_eq!(glyphs.len(), 3);
+    assert!(glyphs[0].point.y < glyphs[1].point.y);
+    assert!(glyphs[1].point.y < glyphs[2].point.y);
+    let line_height = glyphs[1].point.y - glyphs[0].point.y;
+    assert!((glyphs[2].point.y - glyphs[1].point.y - line_height).abs() < 0.001);
+    assert!(glyphs.iter().all(|g| g.point.x == glyphs[0].point.x));
+}
+
+#[test]
+fn test_vertical_text_is_laid_out_in_columns() {
+
+    use test_utils::app_resources_with_test_font;
+
+    let mut app_resources = app_resources_with_test_font();
+    let bounds = TypedRect::new(TypedPoint2D::new(10.0, 10.0), TypedSize2D::new(300.0, 200.0));
+
+    let mut layout = |writing_mode| layout_test_text(&mut app_resources, "ab\ncd", &TestTextLayout {
+        bounds: bounds,
+        writing_mode: writing_mode,
+        .. Default::default()
+    });
+
+    // vertical-rl: the glyphs of a column advance downward, the second column is left of the first
+    let glyphs = layout(WritingMode::VerticalRl);
+    assert_eq!(glyphs.len(), 4);
+    assert_eq!(glyphs[0].point.x, glyphs[1].point.x);
+    assert!(glyphs[0].point.y < glyphs[1].point.y);
+    assert_eq!(glyphs[2].point.x, glyphs[3].point.x);
+    assert!(glyphs[2].point.y < glyphs[3].point.y);
+    assert!(glyphs[2].point.x < glyphs[0].point.x);
+    // Both columns start at the top, the first column is at the right edge
+    assert_eq!(glyphs[0].point.y, glyphs[2].point.y);
+    assert!(glyphs[0].point.x > bounds.origin.x + bounds.size.width / 2.0);
+    assert!(glyphs[0].point.x < bounds.origin.x + bounds.size.width);
+
+    // vertical-lr: the columns progress to the right, starting at the left edge
+    let glyphs = layout(WritingMode::VerticalLr);
+    assert!(glyphs[0].point.y < glyphs[1].point.y);
+    assert!(glyphs[2].point.x > glyphs[0].point.x);
+    assert!(glyphs[0].point.x >= bounds.origin.x);
+    assert!(glyphs[0].point.x < bounds.origin.x + bounds.size.width / 2.0);
+}
+
+#[test]
+fn test_overflow_wrap_breaks_long_word() {
+
+    use test_utils::app_resources_with_test_font;
+
+    let mut app_resources = app_resources_with_test_font();
+    let bounds = TypedRect::new(TypedPoint2D::new(0.0, 0.0), TypedSize2D::new(100.0, 500.0));
+    let long_word = "x".repeat(50);
+
+    let mut layout_word = |line_break_mode| layout_test_text(&mut app_resources, &long_word, &TestTextLayout {
+        bounds: bounds,
+        line_break_mode: line_break_mode,
+        .. Default::default()
+    });
+
+    let count_lines = |glyphs: &[GlyphInstance]| {
+        let mut lines = Vec::<f32>::new();
+        for g in glyphs {
+            if !lines.iter().any(|y| (y - g.point.y).abs() < 0.001) {
+                lines.push(g.point.y);
+            }
+        }
+        lines.len()
+    };
+
+    // Without `overflow-wrap`, the word overflows the box on a single line
+    let glyphs = layout_word(LineBreakMode::Normal);
+    assert_eq!(glyphs.len(), 50);
+    assert_eq!(count_lines(&glyphs), 1);
+    assert!(glyphs.iter().any(|g| g.point.x > 100.0));
+
+    // `overflow-wrap: break-word` breaks the word across multiple lines, nothing overflows
+    let mode = LineBreakMode::from_style(Some(OverflowWrap::BreakWord), None);
+    assert_eq!(mode, LineBreakMode::BreakLongWords);
+    let glyphs = layout_word(mode);
+    assert_eq!(glyphs.len(), 50);
+    assert!(count_lines(&glyphs) > 1);
+    assert!(glyphs.iter().all(|g| g.point.x < 100.0));
+    // Every line starts at the left edge
+    assert!(glyphs.windows(2).filter(|w| w[1].point.y > w[0].point.y).all(|w| w[1].point.x == glyphs[0].point.x));
+}
+
+#[test]
+fn test_underline_uses_font_metrics() {
+
+    use test_utils::{TEST_FONT_FAMILY, app_resources_with_test_font};
+
+    let font_id = FontId::ExternalFont(String::from(TEST_FONT_FAMILY));
+    let app_resources = app_resources_with_test_font();
+
+    // Two lines of text, with the baselines at y = 50 and y = 70
+    let glyphs = [
+        GlyphInstance { index: 36, point: TypedPoint2D::new(10.0, 50.0) },
+        GlyphInstance { index: 37, point: TypedPoint2D::new(20.0, 50.0) },
+        GlyphInstance { index: 38, point: TypedPoint2D::new(10.0, 70.0) },
+    ];
+
+    // weblysleekuil.ttf: 2048 units per em, underline position -178, thickness 119
+    let font_size = FontSize::px(16.0);
+    let scale = 16.0 / 2048.0;
+
+    let underlines = get_text_decoration_rects(&app_resources, &glyphs, &font_id, &font_size, TextDecoration::Underline);
+    assert_eq!(underlines.len(), 2);
+    assert_eq!(underlines[0].origin, TypedPoint2D::new(10.0, 50.0 + 178.0 * scale));
+    assert_eq!(underlines[0].size.height, 119.0 * scale);
+    assert!(underlines[0].size.width > 10.0);
+    assert_eq!(underlines[1].origin, TypedPoint2D::new(10.0, 70.0 + 178.0 * scale));
+
+    // The overline is at the ascender (2210 units)
+    let overlines = get_text_decoration_rects(&app_resources, &glyphs, &font_id, &font_size, TextDecoration::Overline);
+    assert_eq!(overlines[0].origin.y, 50.0 - 2210.0 * scale);
+
+    assert!(get_text_decoration_rects(&app_resources, &glyphs, &font_id, &font_size, TextDecoration::None).is_empty());
+}
+
+#[test]
+fn test_text_indent_offsets_first_line() {
+
+    use css_parser::{PixelValue, CssMetric};
+    use test_utils::app_resources_with_test_font;
+
+    let mut app_resources = app_resources_with_test_font();
+
+    let mut layout_with_indent = |indent: f32| layout_test_text(&mut app_resources, "a\na\na", &TestTextLayout {
+        text_indent: Some(TextIndent(PixelValue::from_metric(CssMetric::Px, indent))),
+        .. Default::default()
+    });
+
+    // Only the first line is indented
+    let glyphs = layout_with_indent(20.0);
+    assert_eq!(glyphs.len(), 3);
+    assert!((glyphs[0].point.x - glyphs[1].point.x - 20.0).abs() < 0.001);
+    assert_eq!(glyphs[1].point.x, glyphs[2].point.x);
+
+    // A negative indent creates a hanging indent
+    let glyphs = layout_with_indent(-10.0);
+    assert!((glyphs[1].point.x - glyphs[0].point.x - 10.0).abs() < 0.001);
+    assert_eq!(glyphs[1].point.x, glyphs[2].point.x);
+}
+
+#[test]
+fn test_default_line_height_is_derived_from_font_metrics() {
+
+    use css_parser::PercentageValue;
+    use test_utils::{TEST_FONT_FAMILY, app_resources_with_test_font};
+
+    let mut app_resources = app_resources_with_test_font();
+
+    let metric_line_height = {
+        let font_id = FontId::ExternalFont(String::from(TEST_FONT_FAMILY));
+        let v_metrics = app_resources.font_data[&font_id].0.v_metrics(Scale::uniform(20.0 * PX_TO_PT));
+        v_metrics.ascent - v_metrics.descent + v_metrics.line_gap
+    };
+
+    let mut line_spacing = |line_height: Option<LineHeight>| {
+        let glyphs = layout_test_text(&mut app_resources, "a\na", &TestTextLayout {
+            line_height: line_height,
+            .. Default::default()
+        });
+        glyphs[1].point.y - glyphs[0].point.y
+    };
+
+    // The lines are spaced by the ascent, descent and line gap of the font, not by the font size
+    let default_spacing = line_spacing(None);
+    assert!((default_spacing - metric_line_height).abs() < 0.001);
+    assert!((default_spacing - 20.0).abs() > 0.1);
+
+    // `line-height` scales the line spacing of the font
+    let spacing_150_percent = line_spacing(Some(LineHeight(PercentageValue { number: 1.5 })));
+    assert!((spacing_150_percent - 1.5 * metric_line_height).abs() < 0.001);
+}
+
+#[test]
+fn test_glyph_cache_shapes_shared_characters_once() {
+
+    use test_utils::{TEST_FONT_FAMILY, app_resources_with_test_font};
+
+    let font_id = FontId::ExternalFont(String::from(TEST_FONT_FAMILY));
+    let mut app_resources = app_resources_with_test_font();
+
+    let layout_label = |app_resources: &mut AppResources, text: &str, font_size: f32| layout_test_text(app_resources, text, &TestTextLayout {
+        font_size: FontSize::px(font_size),
+        .. Default::default()
+    });
+
+    let hello = layout_label(&mut app_resources, "hello", 20.0);
+    let world = layout_label(&mut app_resources, "world", 20.0);
+
+    // The glyphs are the same, whether they were shaped or looked up in the cache
+    assert_eq!(hello[2].index, world[3].index);
+    assert_eq!(hello[4].index, world[1].index);
+
+    // Unique characters: h, e, l, o, w, r, d
+    assert_eq!(app_resources.glyph_cache.get_font_instance(&font_id, FontSize::px(20.0)).shape_count, 7);
+
+    // Rendering the labels again doesn't shape anything
+    layout_label(&mut app_resources, "hello", 20.0);
+    layout_label(&mut app_resources, "world", 20.0);
+    assert_eq!(app_resources.glyph_cache.get_font_instance(&font_id, FontSize::px(20.0)).shape_count, 7);
+
+    // A different font size is a different font instance: h, e, l, o
+    layout_label(&mut app_resources, "hello", 30.0);
+    assert_eq!(app_resources.glyph_cache.get_font_instance(&font_id, FontSize::px(30.0)).shape_count, 4);
 }
\ No newline at end of file
@@ -0,0 +1,191 @@
+//! Tooltips, shown when the mouse hovers over a node with a `title` for a while
+//!
+//! The hovered node is determined by hit-testing on every mouse move. Once the mouse
+//! has stayed on the same node for the tooltip delay of the window, the tooltip is
+//! shown as an overlay near the cursor. It is hidden as soon as the mouse leaves the
+//! node (or the window).
+
+use std::time::{Duration, Instant};
+use webrender::api::{LayoutRect, LayoutPoint, LayoutSize};
+use {
+    dom::{Dom, UpdateScreen},
+    traits::Layout,
+};
+
+/// Default delay until the tooltip of a hovered node is shown
+pub(crate) const DEFAULT_TOOLTIP_DELAY: Duration = Duration::from_millis(500);
+
+/// Font size of the tooltip text, in pixels
+pub(crate) const TOOLTIP_FONT_SIZE: f32 = 12.0;
+/// Space between the tooltip text and the edge of the tooltip, in pixels
+const TOOLTIP_PADDING: f32 = 4.0;
+/// Offset of the tooltip from the cursor, so that it isn't covered by the mouse cursor
+const TOOLTIP_CURSOR_OFFSET: (f32, f32) = (12.0, 16.0);
+
+/// The node that the mouse is currently hovering over
+#[derive(Debug, Clone, PartialEq)]
+struct HoveredNode {
+    /// Tag of the node
+    tag: u64,
+    /// The `title` of the node
+    title: String,
+    /// When the mouse entered the node
+    since: Instant,
+    /// Position of the cursor (in logical pixels), the tooltip is shown next to it
+    cursor: (f32, f32),
+}
+
+/// The tooltip of a window
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TooltipState {
+    /// How long the mouse has to hover over a node until its tooltip is shown
+    delay: Duration,
+    hovered: Option<HoveredNode>,
+    is_visible: bool,
+}
+
+/// A tooltip that has to be drawn on top of the UI
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TooltipOverlay {
+    pub(crate) text: String,
+    /// Bounds of the tooltip background, in logical pixels
+    pub(crate) bounds: LayoutRect,
+}
+
+impl Default for TooltipState {
+    fn default() -> Self {
+        Self::new(DEFAULT_TOOLTIP_DELAY)
+    }
+}
+
+impl TooltipState {
+
+    pub(crate) fn new(delay: Duration) -> Self {
+        Self {
+            delay: delay,
+            hovered: None,
+            is_visible: false,
+        }
+    }
+
+    /// Called when the mouse moved. `hovered` is the tag and the title of the topmost node
+    /// under the cursor that has a title, `None` if the cursor isn't over such a node.
+    ///
+    /// Returns `UpdateScreen::Redraw` if a visible tooltip was hidden.
+    pub(crate) fn on_mouse_move(&mut self, hovered: Option<(u64, String)>, cursor: (f32, f32), now: Instant) -> UpdateScreen {
+        let (tag, title) = match hovered {
+            Some(h) => h,
+            None => return self.hide(),
+        };
+
+        let is_same_node = self.hovered.as_ref().map(|h| h.tag == tag && h.title == title).unwrap_or(false);
+        if !is_same_node {
+            let update_screen = self.hide();
+            self.hovered = Some(HoveredNode { tag, title, since: now, cursor });
+            return update_screen;
+        }
+
+        // Once the tooltip is visible, it stays where it was opened
+        if !self.is_visible {
+            if let Some(ref mut h) = self.hovered {
+                h.cursor = cursor;
+            }
+        }
+
+        UpdateScreen::DontRedraw
+    }
+
+    /// Hides the tooltip, i.e. when the mouse leaves the window.
+    ///
+    /// Returns `UpdateScreen::Redraw` if the tooltip was visible.
+    pub(crate) fn hide(&mut self) -> UpdateScreen {
+        self.hovered = None;
+        if ::std::mem::replace(&mut self.is_visible, false) {
+            UpdateScreen::Redraw
+        } else {
+            UpdateScreen::DontRedraw
+        }
+    }
+
+    /// Shows the tooltip if the mouse has hovered over the node for longer than the delay.
+    ///
+    /// Returns `UpdateScreen::Redraw` if the tooltip became visible.
+    pub(crate) fn tick(&mut self, now: Instant) -> UpdateScreen {
+        if self.is_visible {
+            return UpdateScreen::DontRedraw;
+        }
+
+        match self.hovered {
+            Some(ref h) if now >= h.since + self.delay => {
+                self.is_visible = true;
+                UpdateScreen::Redraw
+            },
+            _ => UpdateScreen::DontRedraw,
+        }
+    }
+
+    /// Returns the tooltip that has to be drawn (if any), placed next to the
+    /// cursor and moved inside of the window if it would overflow the window
+    pub(crate) fn overlay(&self, window_size: LayoutSize) -> Option<TooltipOverlay> {
+        if !self.is_visible {
+            return None;
+        }
+
+        let hovered = self.hovered.as_ref()?;
+
+        // The text is not laid out yet, so estimate the width from the number of characters
+        let text_width = hovered.title.chars().count() as f32 * TOOLTIP_FONT_SIZE * 0.6;
+        let size = LayoutSize::new(text_width + 2.0 * TOOLTIP_PADDING, TOOLTIP_FONT_SIZE * 1.5 + 2.0 * TOOLTIP_PADDING);
+
+        let (cursor_x, cursor_y) = hovered.cursor;
+        let x = (cursor_x + TOOLTIP_CURSOR_OFFSET.0).min(window_size.width - size.width).max(0.0);
+        let y = (cursor_y + TOOLTIP_CURSOR_OFFSET.1).min(window_size.height - size.height).max(0.0);
+
+        Some(TooltipOverlay {
+            text: hovered.title.clone(),
+            bounds: LayoutRect::new(LayoutPoint::new(x, y), size),
+        })
+    }
+}
+
+/// Returns the `title` of the node with the given tag, `None` if the node has no title
+pub(crate) fn find_title<T: Layout>(dom: &Dom<T>, tag: u64) -> Option<String> {
+    let mut title = None;
+    dom.visit(|_, node| {
+        if node.tag == Some(tag) {
+            title = node.title.clone();
+        }
+    });
+    title
+}
+
+#[test]
+fn test_tooltip_is_shown_after_delay() {
+    let delay = Duration::from_millis(500);
+    let window_size = LayoutSize::new(800.0, 600.0);
+    let mut tooltip = TooltipState::new(delay);
+    let t0 = Instant::now();
+    let button = Some((3, String::from("Saves the file")));
+
+    tooltip.on_mouse_move(button.clone(), (100.0, 100.0), t0);
+
+    // Before the delay, nothing is shown
+    assert_eq!(tooltip.tick(t0 + Duration::from_millis(200)), UpdateScreen::DontRedraw);
+    assert_eq!(tooltip.overlay(window_size), None);
+
+    // Moving within the same node doesn't restart the timer
+    tooltip.on_mouse_move(button.clone(), (110.0, 105.0), t0 + Duration::from_millis(300));
+
+    assert_eq!(tooltip.tick(t0 + Duration::from_millis(600)), UpdateScreen::Redraw);
+    let overlay = tooltip.overlay(window_size).unwrap();
+    assert_eq!(overlay.text, "Saves the file");
+    assert_eq!(overlay.bounds.origin, LayoutPoint::new(110.0 + TOOLTIP_CURSOR_OFFSET.0, 105.0 + TOOLTIP_CURSOR_OFFSET.1));
+
+    // The tooltip is only shown once
+    assert_eq!(tooltip.tick(t0 + Duration::from_millis(700)), UpdateScreen::DontRedraw);
+
+    // Leaving the node hides the tooltip
+    assert_eq!(tooltip.on_mouse_move(None, (500.0, 500.0), t0 + Duration::from_millis(800)), UpdateScreen::Redraw);
+    assert_eq!(tooltip.overlay(window_size), None);
+    assert_eq!(tooltip.tick(t0 + Duration::from_millis(2000)), UpdateScreen::DontRedraw);
+}
@@ -7,9 +7,9 @@ use std::{
 use {
     dom::{NodeData, Dom},
     ui_description::{StyledNode, CssConstraintList, UiDescription},
-    css::{Css, CssRule},
+    css::{Css, CssRule, CssDeclaration},
     id_tree::{NodeId, Arena},
-    css_parser::{ParsedCssProperty, CssParsingError},
+    css_parser::{ParsedCssProperty, CssParsingError, Appearance},
 };
 #[cfg(not(test))]
 use window::WindowInfo;
@@ -42,6 +42,8 @@ pub(crate) struct ParsedCss<'a> {
     pub(crate) pure_div_rules: Vec<&'a CssRule>,
     pub(crate) pure_class_rules: Vec<&'a CssRule>,
     pub(crate) pure_id_rules: Vec<&'a CssRule>,
+    pub(crate) attribute_rules: Vec<&'a CssRule>,
+    pub(crate) pseudo_class_rules: Vec<&'a CssRule>,
 }
 
 /// Convenience trait for the `css.set_dynamic_property()` function.
@@ -105,6 +107,7 @@ impl<'a> ParsedCss<'a> {
         // 2. div-type ("html { }") specific rules
         // 3. class-based rules
         // 4. ID-based rules
+        // 5. pseudo-class rules (i.e. `button:disabled`)
 
         /*
             CssRule { html_type: "div", id: Some("main"), classes: [], declaration: ("direction", "row") }
@@ -120,7 +123,7 @@ impl<'a> ParsedCss<'a> {
         //    background-color: blue;
         // }
         let pure_global_rules: Vec<&CssRule> = css.rules.iter().filter(|rule|
-            rule.html_type == "*" && rule.id.is_none() && rule.classes.is_empty()
+            rule.html_type == "*" && rule.id.is_none() && rule.classes.is_empty() && rule.pseudo_class.is_none() && rule.attributes.is_empty()
         ).collect();
 
         // Pure-div-type specific rules
@@ -128,7 +131,7 @@ impl<'a> ParsedCss<'a> {
         //    justify-content: center;
         // }
         let pure_div_rules: Vec<&CssRule> = css.rules.iter().filter(|rule|
-            rule.html_type != "*" && rule.id.is_none() && rule.classes.is_empty()
+            rule.html_type != "*" && rule.id.is_none() && rule.classes.is_empty() && rule.pseudo_class.is_none() && rule.attributes.is_empty()
         ).collect();
 
         // Pure-class rules
@@ -138,7 +141,7 @@ impl<'a> ParsedCss<'a> {
         //    text-color: red;
         // }
         let pure_class_rules: Vec<&CssRule> = css.rules.iter().filter(|rule|
-            rule.id.is_none() && !rule.classes.is_empty()
+            rule.id.is_none() && !rule.classes.is_empty() && rule.pseudo_class.is_none() && rule.attributes.is_empty()
         ).collect();
 
         // Pure-id rules
@@ -146,7 +149,23 @@ impl<'a> ParsedCss<'a> {
         //    background-color: red;
         // }
         let pure_id_rules: Vec<&CssRule> = css.rules.iter().filter(|rule|
-            rule.id.is_some() && rule.classes.is_empty()
+            rule.id.is_some() && rule.classes.is_empty() && rule.pseudo_class.is_none() && rule.attributes.is_empty()
+        ).collect();
+
+        // Attribute rules, override the pure rules if the node has the given attributes
+        // div[data-state="open"] {
+        //    height: 200px;
+        // }
+        let attribute_rules: Vec<&CssRule> = css.rules.iter().filter(|rule|
+            rule.pseudo_class.is_none() && !rule.attributes.is_empty()
+        ).collect();
+
+        // Pseudo-class rules, override all other rules if the node is in the given state
+        // button:disabled {
+        //    background-color: grey;
+        // }
+        let pseudo_class_rules: Vec<&CssRule> = css.rules.iter().filter(|rule|
+            rule.pseudo_class.is_some()
         ).collect();
 
         Self {
@@ -154,11 +173,13 @@ impl<'a> ParsedCss<'a> {
             pure_div_rules: pure_div_rules,
             pure_class_rules: pure_class_rules,
             pure_id_rules: pure_id_rules,
+            attribute_rules: attribute_rules,
+            pseudo_class_rules: pseudo_class_rules,
         }
     }
 }
 
-fn match_dom_css_selectors<'a, T: Layout>(
+pub(crate) fn match_dom_css_selectors<'a, T: Layout>(
     root: NodeId,
     arena: &Rc<RefCell<Arena<NodeData<T>>>>,
     parsed_css: &ParsedCss<'a>,
@@ -189,6 +210,7 @@ fn match_dom_css_selectors<'a, T: Layout>(
         styled_nodes: styled_nodes,
         default_style_of_node: StyledNode::default(),
         dynamic_css_overrides: css.dynamic_css_overrides.clone(),
+        safe_area_insets: css.safe_area_insets,
     }
 }
 
@@ -228,9 +250,11 @@ fn cascade_constraints<'a, T: Layout>(
     parsed_css: &ParsedCss<'a>,
     css: &Css)
 {
+    let mut matched_rules = Vec::<&CssRule>::new();
+
     for div_rule in &parsed_css.pure_div_rules {
         if *node.node_type.get_css_id() == div_rule.html_type {
-            push_rule(list, div_rule);
+            matched_rules.push(div_rule);
         }
     }
 
@@ -256,7 +280,7 @@ fn cascade_constraints<'a, T: Layout>(
         }
 
         if should_insert_rule {
-            push_rule(list, class_rule);
+            matched_rules.push(class_rule);
         }
     }
 
@@ -267,12 +291,61 @@ fn cascade_constraints<'a, T: Layout>(
         // if the node has an ID
         for id_rule in &parsed_css.pure_id_rules {
             if *id_rule.id.as_ref().unwrap() == *node_id {
-                push_rule(list, id_rule);
+                matched_rules.push(id_rule);
             }
         }
     }
 
+    for attribute_rule in &parsed_css.attribute_rules {
+        if selector_matches(node, attribute_rule) {
+            matched_rules.push(attribute_rule);
+        }
+    }
+
+    for pseudo_class_rule in &parsed_css.pseudo_class_rules {
+        if pseudo_class_rule_matches(node, pseudo_class_rule, css) {
+            matched_rules.push(pseudo_class_rule);
+        }
+    }
+
     // TODO: all the mixed rules
+
+    // `appearance: none` removes the native widget styling, but keeps the user CSS.
+    // The last matched rule wins, like for any other property.
+    let has_no_appearance = matched_rules.iter().rev().filter_map(|rule| match rule.declaration.1 {
+        CssDeclaration::Static(ParsedCssProperty::Appearance(appearance)) => Some(appearance),
+        _ => None,
+    }).next() == Some(Appearance::None);
+
+    for rule in matched_rules {
+        if !(has_no_appearance && rule.is_native) {
+            push_rule(list, rule);
+        }
+    }
+}
+
+/// Returns whether the node is in the state of the pseudo-class of the rule
+/// and matches the rest of the selector (type, ID, classes and attributes) of the rule
+fn pseudo_class_rule_matches<T: Layout>(node: &NodeData<T>, rule: &CssRule, css: &Css) -> bool {
+    use css::CssPseudoClass;
+
+    let is_in_state = match rule.pseudo_class {
+        Some(CssPseudoClass::Disabled) => node.disabled,
+        Some(CssPseudoClass::Hover) => node.tag.map(|tag| css.hovered_tags.contains(&tag)).unwrap_or(false),
+        Some(CssPseudoClass::Focus) => node.tag.is_some() && node.tag == css.focused_tag,
+        None => false,
+    };
+
+    is_in_state && selector_matches(node, rule)
+}
+
+/// Returns whether the type, ID, classes and attributes of the node match the selector of
+/// the rule (ignoring the pseudo-class). The node may have more classes than the selector.
+fn selector_matches<T: Layout>(node: &NodeData<T>, rule: &CssRule) -> bool {
+    (rule.html_type == "*" || rule.html_type == node.node_type.get_css_id()) &&
+    rule.id.as_ref().map(|id| node.id.as_ref() == Some(id)).unwrap_or(true) &&
+    rule.classes.iter().all(|class| node.classes.contains(class)) &&
+    rule.attributes.iter().all(|attribute| attribute.matches(&node.attributes))
 }
 
 #[inline]
@@ -286,4 +359,69 @@ fn push_rule(list: &mut CssConstraintList, rule: &CssRule) {
 #[test]
 fn __codecov_test_traits_file() {
 
+}
+
+#[test]
+fn test_disabled_pseudo_class_style() {
+    use css::CssDeclaration;
+    use css_parser::{BackgroundColor, ColorU};
+    use dom::NodeType;
+    use test_utils::TestLayout;
+
+    let css = Css::new_from_string("
+        .button { background-color: #ffffff; }
+        .button:disabled { background-color: #888888; }
+    ").unwrap();
+
+    let get_background_colors = |dom: &Dom<TestLayout>| {
+        let ui_description = TestLayout::style_dom(dom, &css);
+        ui_description.styled_nodes[&dom.root].css_constraints.list.iter().filter_map(|declaration| match declaration {
+            CssDeclaration::Static(ParsedCssProperty::BackgroundColor(c)) => Some(*c),
+            _ => None,
+        }).collect::<Vec<BackgroundColor>>()
+    };
+
+    let white = BackgroundColor(ColorU { r: 255, g: 255, b: 255, a: 255 });
+    let grey = BackgroundColor(ColorU { r: 136, g: 136, b: 136, a: 255 });
+
+    let enabled_button = Dom::new(NodeType::Div).with_class("button");
+    assert_eq!(get_background_colors(&enabled_button), vec![white]);
+
+    // The `:disabled` rule is applied after (and therefore overrides) the regular rule
+    let disabled_button = Dom::new(NodeType::Div).with_class("button").with_disabled(true);
+    assert_eq!(get_background_colors(&disabled_button), vec![white, grey]);
+}
+
+#[test]
+fn test_attribute_selector_style() {
+    use css::CssDeclaration;
+    use css_parser::{BackgroundColor, ColorU};
+    use dom::NodeType;
+    use test_utils::TestLayout;
+
+    let css = Css::new_from_string("
+        .tab { background-color: #ffffff; }
+        [data-active=\"true\"] { background-color: #0000ff; }
+    ").unwrap();
+
+    let get_background_colors = |dom: &Dom<TestLayout>| {
+        let ui_description = TestLayout::style_dom(dom, &css);
+        ui_description.styled_nodes[&dom.root].css_constraints.list.iter().filter_map(|declaration| match declaration {
+            CssDeclaration::Static(ParsedCssProperty::BackgroundColor(c)) => Some(*c),
+            _ => None,
+        }).collect::<Vec<BackgroundColor>>()
+    };
+
+    let white = BackgroundColor(ColorU { r: 255, g: 255, b: 255, a: 255 });
+    let blue = BackgroundColor(ColorU { r: 0, g: 0, b: 255, a: 255 });
+
+    let inactive_tab = Dom::new(NodeType::Div).with_class("tab").with_attribute("data-active", "false");
+    assert_eq!(get_background_colors(&inactive_tab), vec![white]);
+
+    let tab_without_attribute = Dom::new(NodeType::Div).with_class("tab");
+    assert_eq!(get_background_colors(&tab_without_attribute), vec![white]);
+
+    // The attribute rule is applied after (and therefore overrides) the class rule
+    let active_tab = Dom::new(NodeType::Div).with_class("tab").with_attribute("data-active", "true");
+    assert_eq!(get_background_colors(&active_tab), vec![white, blue]);
 }
\ No newline at end of file
@@ -0,0 +1,201 @@
+//! Interpolation of CSS properties that have a `transition` attached to them
+//!
+//! Every frame, the display list compares the resolved value of a transitioned property
+//! with the value it had in the last frame. If the value changed (for example, because
+//! a dynamic CSS property was overridden in a callback), the property is animated from the
+//! old to the new value over the `duration` of the transition, instead of changing instantly.
+//!
+//! Currently only `background-color`, `color`, `width` and `height` can be transitioned.
+
+use std::time::{Duration, Instant};
+use webrender::api::ColorU;
+use {
+    FastHashMap,
+    id_tree::NodeId,
+    css_parser::{
+        Transition, RectStyle, RectLayout, LayoutWidth, LayoutHeight, PixelValue, CssMetric,
+    },
+};
+
+/// A value of a property that can be transitioned
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum TransitionValue {
+    BackgroundColor(ColorU),
+    TextColor(ColorU),
+    Width(f32),
+    Height(f32),
+}
+
+/// The transition state of a single property on a single node
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct TransitionState {
+    /// The resolved value of the property (from the CSS), i.e. where the transition ends
+    target: TransitionValue,
+    /// The value that was displayed in the last frame
+    displayed: TransitionValue,
+    /// The value that was displayed when the transition started
+    from: TransitionValue,
+    /// When the currently running transition started, `None` if no transition is running
+    start: Option<Instant>,
+    /// Whether the property still existed in the current frame
+    is_used: bool,
+}
+
+/// Stores the values of all transitioned properties across frames
+#[derive(Debug, Default, Clone)]
+pub(crate) struct TransitionCache {
+    states: FastHashMap<(NodeId, String), TransitionState>,
+}
+
+impl TransitionCache {
+
+    /// Replaces the value of the `transition.property` in the `style` / `layout` with the
+    /// interpolated value at the time `now`. Starts a new transition if the value of the
+    /// property differs from the value in the last frame.
+    ///
+    /// Returns `true` if the transition is still running, i.e. if the next frame has to be redrawn.
+    pub(crate) fn apply(
+        &mut self,
+        node_id: NodeId,
+        transition: &Transition,
+        style: &mut RectStyle,
+        layout: &mut RectLayout,
+        now: Instant)
+    -> bool
+    {
+        let target = match get_value(&transition.property, style, layout) {
+            Some(v) => v,
+            None => return false,
+        };
+
+        let state = self.states.entry((node_id, transition.property.clone())).or_insert(TransitionState {
+            target: target,
+            displayed: target,
+            from: target,
+            start: None,
+            is_used: true,
+        });
+
+        state.is_used = true;
+
+        if state.target != target {
+            // Start from the currently displayed value, so that interrupted
+            // transitions don't jump back to the old value
+            state.from = state.displayed;
+            state.target = target;
+            state.start = Some(now);
+        }
+
+        if let Some(start) = state.start {
+            let elapsed = if now > start { now.duration_since(start) } else { Duration::from_secs(0) };
+            let t = if transition.duration == Duration::from_secs(0) {
+                1.0
+            } else {
+                duration_to_secs(elapsed) / duration_to_secs(transition.duration)
+            };
+
+            if t >= 1.0 {
+                state.start = None;
+                state.displayed = state.target;
+            } else {
+                state.displayed = interpolate(state.from, state.target, transition.timing_function.apply(t));
+            }
+        }
+
+        set_value(state.displayed, style, layout);
+        state.start.is_some()
+    }
+
+    /// Removes the properties that weren't transitioned since the last call
+    /// to this function (i.e. because the node was removed from the DOM)
+    pub(crate) fn remove_unused_transitions(&mut self) {
+        self.states.retain(|_, state| state.is_used);
+        for state in self.states.values_mut() {
+            state.is_used = false;
+        }
+    }
+}
+
+fn duration_to_secs(duration: Duration) -> f32 {
+    duration.as_secs() as f32 + duration.subsec_nanos() as f32 / 1_000_000_000.0
+}
+
+fn get_value(property: &str, style: &RectStyle, layout: &RectLayout) -> Option<TransitionValue> {
+    match property {
+        "background-color" => style.background_color.map(|c| TransitionValue::BackgroundColor(c.0)),
+        "color" => style.font_color.map(|c| TransitionValue::TextColor(c.0)),
+        // Only pixel sizes are interpolated, percentages depend on the size of the parent
+        "width" => layout.width.and_then(|w| w.0.to_pixels()).map(TransitionValue::Width),
+        "height" => layout.height.and_then(|h| h.0.to_pixels()).map(TransitionValue::Height),
+        _ => None,
+    }
+}
+
+fn set_value(value: TransitionValue, style: &mut RectStyle, layout: &mut RectLayout) {
+    use self::TransitionValue::*;
+    match value {
+        BackgroundColor(c) => style.background_color = Some(::css_parser::BackgroundColor(c)),
+        TextColor(c) => style.font_color = Some(::css_parser::TextColor(c)),
+        Width(w) => layout.width = Some(LayoutWidth(PixelValue::from_metric(CssMetric::Px, w))),
+        Height(h) => layout.height = Some(LayoutHeight(PixelValue::from_metric(CssMetric::Px, h))),
+    }
+}
+
+/// Interpolates between `from` and `to`, `progress` goes from 0.0 (`from`) to 1.0 (`to`)
+fn interpolate(from: TransitionValue, to: TransitionValue, progress: f32) -> TransitionValue {
+    use self::TransitionValue::*;
+    match (from, to) {
+        (BackgroundColor(a), BackgroundColor(b)) => BackgroundColor(interpolate_color(a, b, progress)),
+        (TextColor(a), TextColor(b)) => TextColor(interpolate_color(a, b, progress)),
+        (Width(a), Width(b)) => Width(interpolate_f32(a, b, progress)),
+        (Height(a), Height(b)) => Height(interpolate_f32(a, b, progress)),
+        // the values are always stored under the same property name, so they are always of the same type
+        (_, to) => to,
+    }
+}
+
+fn interpolate_f32(from: f32, to: f32, progress: f32) -> f32 {
+    from + (to - from) * progress
+}
+
+fn interpolate_color(from: ColorU, to: ColorU, progress: f32) -> ColorU {
+    let channel = |a: u8, b: u8| interpolate_f32(a as f32, b as f32, progress).round() as u8;
+    ColorU {
+        r: channel(from.r, to.r),
+        g: channel(from.g, to.g),
+        b: channel(from.b, to.b),
+        a: channel(from.a, to.a),
+    }
+}
+
+#[test]
+fn test_background_color_transition() {
+    let transition = Transition {
+        property: String::from("background-color"),
+        duration: Duration::from_millis(200),
+        timing_function: ::css_parser::TransitionTimingFunction::Linear,
+    };
+
+    let red = ColorU { r: 255, g: 0, b: 0, a: 255 };
+    let blue = ColorU { r: 0, g: 0, b: 255, a: 255 };
+
+    let mut cache = TransitionCache::default();
+    let node_id = NodeId::new(0);
+    let t0 = Instant::now();
+
+    let frame = |cache: &mut TransitionCache, color: ColorU, now: Instant| {
+        let mut style = RectStyle { background_color: Some(::css_parser::BackgroundColor(color)), .. Default::default() };
+        let mut layout = RectLayout::default();
+        let is_running = cache.apply(node_id, &transition, &mut style, &mut layout, now);
+        (style.background_color.unwrap().0, is_running)
+    };
+
+    // The first frame doesn't transition
+    assert_eq!(frame(&mut cache, red, t0), (red, false));
+
+    // The color changes from red to blue, at the start of the transition, the color is still red
+    assert_eq!(frame(&mut cache, blue, t0), (red, true));
+    assert_eq!(frame(&mut cache, blue, t0 + Duration::from_millis(100)), (ColorU { r: 128, g: 0, b: 128, a: 255 }, true));
+    assert_eq!(frame(&mut cache, blue, t0 + Duration::from_millis(200)), (blue, false));
+    assert_eq!(frame(&mut cache, blue, t0 + Duration::from_millis(300)), (blue, false));
+}
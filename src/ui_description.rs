@@ -1,16 +1,17 @@
 use std::{
     cell::RefCell,
     rc::Rc,
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
 };
 use {
     FastHashMap,
-    css_parser::ParsedCssProperty,
+    css_parser::{ParsedCssProperty, RectStyle, RectLayout, SafeAreaInsets},
     id_tree::{Arena, NodeId},
-    traits::Layout,
+    traits::{Layout, ParsedCss, match_dom_css_selectors},
     ui_state::UiState,
     css::{Css, CssDeclaration},
     dom::NodeData,
+    display_list::{DisplayList, DisplayRectangle, populate_css_properties},
 };
 
 pub struct UiDescription<T: Layout> {
@@ -27,6 +28,8 @@ pub struct UiDescription<T: Layout> {
     pub(crate) default_style_of_node: StyledNode,
     /// The CSS properties that should be overridden for this frame, cloned from the `Css`
     pub(crate) dynamic_css_overrides: FastHashMap<String, ParsedCssProperty>,
+    /// The insets for `env(safe-area-inset-*)`, cloned from the `Css`
+    pub(crate) safe_area_insets: SafeAreaInsets,
 }
 
 impl<T: Layout> Clone for UiDescription<T> {
@@ -37,6 +40,7 @@ impl<T: Layout> Clone for UiDescription<T> {
             styled_nodes: self.styled_nodes.clone(),
             default_style_of_node: self.default_style_of_node.clone(),
             dynamic_css_overrides: self.dynamic_css_overrides.clone(),
+            safe_area_insets: self.safe_area_insets,
         }
     }
 }
@@ -49,6 +53,7 @@ impl<T: Layout> Default for UiDescription<T> {
             styled_nodes: BTreeMap::new(),
             default_style_of_node: StyledNode::default(),
             dynamic_css_overrides: FastHashMap::default(),
+            safe_area_insets: SafeAreaInsets::default(),
         }
     }
 }
@@ -58,6 +63,76 @@ impl<T: Layout> UiDescription<T> {
     {
         T::style_dom(&ui_state.dom, style)
     }
+
+    /// Returns the fully resolved style of a node, i.e. the style after the
+    /// cascading step and after the dynamic CSS overrides have been applied.
+    ///
+    /// This is the same style that the display list would use for drawing
+    /// the node, so it can be used for testing without rendering anything.
+    pub fn computed_style(&self, node_id: NodeId) -> RectStyle {
+        self.resolve_node(node_id).style
+    }
+
+    /// Same as `computed_style`, but returns the resolved layout properties
+    /// (`width`, `height`, `flex-direction`, etc.) of the node instead.
+    pub fn computed_layout(&self, node_id: NodeId) -> RectLayout {
+        self.resolve_node(node_id).layout
+    }
+
+    /// Styles the same DOM again with another stylesheet
+    pub(crate) fn restyle(&self, css: &Css) -> Self {
+        match self.ui_descr_root {
+            Some(root) => match_dom_css_selectors(root, &self.ui_descr_arena, &ParsedCss::from_css(css), css, 0),
+            None => Self::default(),
+        }
+    }
+
+    /// Compares the resolved styles of all nodes with the resolved styles of `other`,
+    /// which has to be a styling of the same DOM (i.e. with another stylesheet).
+    pub fn diff_styles(&self, other: &UiDescription<T>) -> StyleChanges {
+        let old_rects = DisplayList::new_from_ui_description(self).rectangles;
+        let new_rects = DisplayList::new_from_ui_description(other).rectangles;
+        let mut changes = StyleChanges::default();
+
+        for node_id in old_rects.linear_iter() {
+            let (old_rect, new_rect) = (&old_rects[node_id].data, &new_rects[node_id].data);
+            if old_rect.layout != new_rect.layout {
+                changes.relayout_nodes.insert(node_id);
+            } else if old_rect.style != new_rect.style {
+                changes.repaint_nodes.insert(node_id);
+            }
+        }
+
+        changes
+    }
+
+    fn resolve_node(&self, node_id: NodeId) -> DisplayRectangle {
+        // The parent has to be resolved first, for properties that `inherit` from it
+        let parent_id = self.ui_descr_arena.borrow()[node_id].parent();
+        let parent = parent_id.map(|parent_id| self.resolve_node(parent_id));
+        let style = self.styled_nodes.get(&node_id).unwrap_or(&self.default_style_of_node);
+        let mut rect = DisplayRectangle::new(None, style);
+        populate_css_properties(&mut rect, node_id, &*self.ui_descr_arena.borrow(), &self.dynamic_css_overrides, &self.safe_area_insets,
+                                parent.as_ref().map(|parent| (&parent.style, &parent.layout)));
+        rect
+    }
+}
+
+/// The nodes whose resolved styles differ between two stylings of the same DOM,
+/// see `UiDescription::diff_styles` and `Css::switch_theme`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StyleChanges {
+    /// Nodes where only paint properties (i.e. colors) changed, they only have to be repainted
+    pub repaint_nodes: BTreeSet<NodeId>,
+    /// Nodes where a layout property (i.e. a `width`) changed, the frame has to be re-laid out
+    pub relayout_nodes: BTreeSet<NodeId>,
+}
+
+impl StyleChanges {
+    /// Returns whether the layout of any node changed
+    pub fn needs_relayout(&self) -> bool {
+        !self.relayout_nodes.is_empty()
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq)]
@@ -79,4 +154,102 @@ pub(crate) struct CssConstraintList {
 #[test]
 fn __codecov_test_ui_description_file() {
 
+}
+
+#[test]
+fn test_ui_description_computed_style() {
+
+    use dom::{Dom, NodeType};
+    use css_parser::{BackgroundColor, LayoutWidth, LayoutHeight, PixelValue, CssMetric};
+    use webrender::api::ColorU;
+    use test_utils::TestLayout;
+
+    let mut css = Css::new_from_string("
+        div { background-color: #ff0000; width: 100px; }
+        .wide { width: 200px; height: [[ my_height | 50px ]]; }
+        #main { background-color: #00ff00; }
+    ").unwrap();
+
+    css.dynamic_css_overrides.insert("my_height".into(), ParsedCssProperty::Height(
+        LayoutHeight(PixelValue::from_metric(CssMetric::Px, 70.0))));
+
+    let dom = Dom::<TestLayout>::new(NodeType::Div).with_id("main").with_class("wide");
+    let ui_description = TestLayout::style_dom(&dom, &css);
+
+    let style = ui_description.computed_style(dom.root);
+    let layout = ui_description.computed_layout(dom.root);
+
+    assert_eq!(style.background_color, Some(BackgroundColor(ColorU { r: 0, g: 255, b: 0, a: 255 })));
+    assert_eq!(layout.width, Some(LayoutWidth(PixelValue::from_metric(CssMetric::Px, 200.0))));
+    assert_eq!(layout.height, Some(LayoutHeight(PixelValue::from_metric(CssMetric::Px, 70.0))));
+}
+
+
+#[test]
+fn test_scoped_dynamic_css_overrides() {
+
+    use dom::{Dom, NodeType};
+    use css_parser::{LayoutHeight, PixelValue, CssMetric};
+    use test_utils::TestLayout;
+
+    let px = |value| LayoutHeight(PixelValue::from_metric(CssMetric::Px, value));
+
+    let mut css = Css::new_from_string("
+        .row { height: [[ row_height | 50px ]]; }
+    ").unwrap();
+
+    css.dynamic_css_overrides.insert("row_height".into(), ParsedCssProperty::Height(px(70.0)));
+
+    // Two instances of the same "component", each providing a different value for
+    // `row_height`, plus one row outside of any component (uses the global override)
+    let dom = Dom::<TestLayout>::new(NodeType::Div)
+        .with_child(Dom::new(NodeType::Div)
+            .with_css_override("row_height", ParsedCssProperty::Height(px(10.0)))
+            .with_child(Dom::new(NodeType::Div).with_id("first").with_class("row")))
+        .with_child(Dom::new(NodeType::Div)
+            .with_css_override("row_height", ParsedCssProperty::Height(px(20.0)))
+            .with_child(Dom::new(NodeType::Div)
+                .with_child(Dom::new(NodeType::Div).with_id("second").with_class("row"))))
+        .with_child(Dom::new(NodeType::Div).with_id("third").with_class("row"));
+
+    let ui_description = TestLayout::style_dom(&dom, &css);
+
+    let find_node = |id: &str| {
+        let arena = dom.arena.borrow();
+        let node_id = arena.linear_iter().find(|node_id| arena[*node_id].data.id == Some(id.into()));
+        node_id.unwrap()
+    };
+
+    assert_eq!(ui_description.computed_layout(find_node("first")).height, Some(px(10.0)));
+    assert_eq!(ui_description.computed_layout(find_node("second")).height, Some(px(20.0)));
+    assert_eq!(ui_description.computed_layout(find_node("third")).height, Some(px(70.0)));
+}
+#[test]
+fn test_ui_description_css_wide_keywords() {
+
+    use dom::{Dom, NodeType};
+    use css_parser::{TextColor, LayoutWidth, PixelValue, CssMetric};
+    use webrender::api::ColorU;
+    use test_utils::TestLayout;
+
+    let css = Css::new_from_string("
+        div { width: 100px; }
+        #parent { color: #0000ff; }
+        #child { color: inherit; }
+        #reset { width: initial; }
+    ").unwrap();
+
+    let mut dom = Dom::<TestLayout>::new(NodeType::Div).with_id("parent");
+    dom.add_child(Dom::new(NodeType::Div).with_id("child"));
+    dom.add_child(Dom::new(NodeType::Div).with_id("reset"));
+    let ui_description = TestLayout::style_dom(&dom, &css);
+
+    let child = NodeId::new(1);
+    let reset = NodeId::new(2);
+
+    assert_eq!(ui_description.computed_style(child).font_color, Some(TextColor(ColorU { r: 0, g: 0, b: 255, a: 255 })));
+    assert_eq!(ui_description.computed_layout(child).width, Some(LayoutWidth(PixelValue::from_metric(CssMetric::Px, 100.0))));
+    // `initial` resets the width to the default (no width set)
+    assert_eq!(ui_description.computed_layout(reset).width, None);
+    assert_eq!(ui_description.computed_style(reset).font_color, None);
 }
\ No newline at end of file
@@ -5,7 +5,7 @@ use std::{
 use {
     window::{WindowInfo, ReadOnlyWindow, WindowId},
     traits::Layout,
-    dom::{NODE_ID, CALLBACK_ID, Callback, Dom, On},
+    dom::{self, Callback, Dom, On},
     app_state::AppState,
 };
 
@@ -34,14 +34,17 @@ impl<T: Layout> UiState<T> {
     pub(crate) fn from_app_state(app_state: &AppState<T>, window_id: WindowId, read_only_window: ReadOnlyWindow) -> Self
     {
         use dom::{Dom, On, NodeType};
-        use std::sync::atomic::Ordering;
 
         let window_info = WindowInfo {
             window_id,
             window: read_only_window,
             resources: &app_state.resources,
+            is_caret_visible: app_state.focus.is_caret_visible(),
         };
 
+        // Tags are allocated per frame, starting from 0
+        dom::reset_tag_counters();
+
         // Only shortly lock the data to get the dom out
          let dom: Dom<T> = {
             let dom_lock = app_state.data.lock().unwrap();
@@ -54,9 +57,6 @@ impl<T: Layout> UiState<T> {
             }
         };
 
-        NODE_ID.swap(0, Ordering::SeqCst);
-        CALLBACK_ID.swap(0, Ordering::SeqCst);
-
         let mut callback_list = BTreeMap::<u64, Callback<T>>::new();
         let mut node_ids_to_callbacks_list = BTreeMap::<u64, BTreeMap<On, u64>>::new();
         dom.collect_callbacks(&mut callback_list, &mut node_ids_to_callbacks_list);
@@ -0,0 +1,156 @@
+use glium::glutin::{KeyboardInput, ElementState};
+use {
+    traits::Layout,
+    dom::{Dom, NodeType, UpdateScreen},
+};
+
+/// A dropdown (select box). The state of the dropdown is stored in the app data,
+/// like the text of a `TextInput`: while the dropdown is open, the keyboard input of the
+/// focused dropdown should be passed to `Dropdown::handle_keyboard_input`, which moves
+/// the highlighted option (Up / Down), selects it (Enter) or closes the dropdown (Escape).
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Dropdown {
+    pub options: Vec<String>,
+    /// Index of the selected option, `None` if no option is selected yet
+    pub selected: Option<usize>,
+    /// Index of the option that is highlighted in the open dropdown
+    pub highlighted: Option<usize>,
+    pub is_open: bool,
+}
+
+impl Dropdown {
+    pub fn new<S>(options: Vec<S>)
+    -> Self where S: Into<String>
+    {
+        Self {
+            options: options.into_iter().map(|o| o.into()).collect(),
+            selected: None,
+            highlighted: None,
+            is_open: false,
+        }
+    }
+
+    /// Opens the dropdown and highlights the selected option (or the first option)
+    pub fn open(&mut self) {
+        self.is_open = true;
+        self.highlighted = if self.options.is_empty() { None } else { Some(self.selected.unwrap_or(0)) };
+    }
+
+    pub fn close(&mut self) {
+        self.is_open = false;
+        self.highlighted = None;
+    }
+
+    /// Returns the text of the selected option
+    pub fn selected_option(&self) -> Option<&str> {
+        self.selected.and_then(|idx| self.options.get(idx)).map(|s| s.as_str())
+    }
+
+    /// Handles a key press while the dropdown is open: Up / Down move the highlight (without
+    /// wrapping around), Enter selects the highlighted option and closes the dropdown,
+    /// Escape closes the dropdown without changing the selection.
+    ///
+    /// Returns `UpdateScreen::DontRedraw` if the dropdown is closed or the key didn't
+    /// change anything, so that the app can pass every keyboard input to this function.
+    pub fn handle_keyboard_input(&mut self, input: &KeyboardInput) -> UpdateScreen {
+        use glium::glutin::VirtualKeyCode::*;
+
+        if !self.is_open || input.state != ElementState::Pressed {
+            return UpdateScreen::DontRedraw;
+        }
+
+        let last_option = match self.options.len() {
+            0 => None,
+            len => Some(len - 1),
+        };
+
+        let new_highlight = match input.virtual_keycode {
+            Some(Up) => self.highlighted.map(|h| h.saturating_sub(1)),
+            Some(Down) => self.highlighted.map(|h| h + 1).into_iter().chain(last_option).min(),
+            Some(Return) | Some(NumpadEnter) => {
+                if self.highlighted.is_some() {
+                    self.selected = self.highlighted;
+                }
+                self.close();
+                return UpdateScreen::Redraw;
+            },
+            Some(Escape) => {
+                self.close();
+                return UpdateScreen::Redraw;
+            },
+            _ => return UpdateScreen::DontRedraw,
+        };
+
+        if new_highlight == self.highlighted {
+            UpdateScreen::DontRedraw
+        } else {
+            self.highlighted = new_highlight;
+            UpdateScreen::Redraw
+        }
+    }
+
+    pub fn dom<T>(self)
+    -> Dom<T> where T: Layout
+    {
+        let selected_text = self.selected_option().unwrap_or("").to_string();
+        let mut dropdown = Dom::new(NodeType::Div).with_class("__azul-native-dropdown");
+        dropdown.add_child(Dom::new(NodeType::Label(selected_text)).with_class("__azul-native-dropdown-selected"));
+
+        if self.is_open {
+            let mut option_list = Dom::new(NodeType::Div).with_class("__azul-native-dropdown-options");
+            for (idx, option) in self.options.into_iter().enumerate() {
+                let mut option_dom = Dom::new(NodeType::Label(option)).with_class("__azul-native-dropdown-option");
+                if self.highlighted == Some(idx) {
+                    option_dom = option_dom.with_class("__azul-native-dropdown-option-highlighted");
+                }
+                option_list.add_child(option_dom);
+            }
+            dropdown.add_child(option_list);
+        }
+
+        dropdown
+    }
+}
+
+#[test]
+fn test_dropdown_keyboard_navigation() {
+
+    use glium::glutin::VirtualKeyCode;
+
+    fn key(virtual_keycode: VirtualKeyCode) -> KeyboardInput {
+        use glium::glutin::ModifiersState;
+        KeyboardInput {
+            scancode: 0,
+            state: ElementState::Pressed,
+            virtual_keycode: Some(virtual_keycode),
+            modifiers: ModifiersState::default(),
+        }
+    }
+
+    let mut dropdown = Dropdown::new(vec!["Red", "Green", "Blue", "Yellow"]);
+
+    // A closed dropdown ignores the keyboard
+    assert_eq!(dropdown.handle_keyboard_input(&key(VirtualKeyCode::Down)), UpdateScreen::DontRedraw);
+
+    dropdown.open();
+    assert_eq!(dropdown.highlighted, Some(0));
+    assert_eq!(dropdown.handle_keyboard_input(&key(VirtualKeyCode::Down)), UpdateScreen::Redraw);
+    assert_eq!(dropdown.handle_keyboard_input(&key(VirtualKeyCode::Down)), UpdateScreen::Redraw);
+    assert_eq!(dropdown.handle_keyboard_input(&key(VirtualKeyCode::Return)), UpdateScreen::Redraw);
+
+    assert_eq!(dropdown.selected, Some(2));
+    assert_eq!(dropdown.selected_option(), Some("Blue"));
+    assert!(!dropdown.is_open);
+
+    // Reopening highlights the selected option, the highlight stops at the last option
+    dropdown.open();
+    assert_eq!(dropdown.highlighted, Some(2));
+    dropdown.handle_keyboard_input(&key(VirtualKeyCode::Down));
+    assert_eq!(dropdown.handle_keyboard_input(&key(VirtualKeyCode::Down)), UpdateScreen::DontRedraw);
+    assert_eq!(dropdown.highlighted, Some(3));
+
+    // Escape closes the dropdown without changing the selection
+    dropdown.handle_keyboard_input(&key(VirtualKeyCode::Escape));
+    assert!(!dropdown.is_open);
+    assert_eq!(dropdown.selected, Some(2));
+}
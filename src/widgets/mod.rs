@@ -1,6 +1,9 @@
 pub mod svg;
 pub mod button;
 pub mod label;
+pub mod text_input;
+pub mod dropdown;
+pub mod virtual_list;
 
 // Re-export widgets
 pub use self::svg::{
@@ -18,4 +21,7 @@ pub use self::svg::{
     quadratic_interpolate_bezier,
 };
 pub use self::button::{Button, ButtonContent};
-pub use self::label::Label;
\ No newline at end of file
+pub use self::label::Label;
+pub use self::text_input::TextInput;
+pub use self::dropdown::Dropdown;
+pub use self::virtual_list::{VirtualList, VIRTUAL_LIST_SPACER_HEIGHT_ID};
\ No newline at end of file
@@ -0,0 +1,42 @@
+use {
+    traits::Layout,
+    dom::{Dom, NodeType},
+};
+
+/// A single-line text input. While the text is empty,
+/// the (optional) placeholder is shown with a dimmed color
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct TextInput {
+    pub text: String,
+    pub placeholder: Option<String>,
+}
+
+impl TextInput {
+    pub fn new<S>(text: S)
+    -> Self where S: Into<String>
+    {
+        Self {
+            text: text.into(),
+            placeholder: None,
+        }
+    }
+
+    pub fn with_placeholder<S>(self, placeholder: S)
+    -> Self where S: Into<String>
+    {
+        Self {
+            placeholder: Some(placeholder.into()),
+            .. self
+        }
+    }
+
+    pub fn dom<T>(self)
+    -> Dom<T> where T: Layout
+    {
+        let mut input = Dom::new(NodeType::Label(self.text)).with_class("__azul-native-input");
+        if let Some(placeholder) = self.placeholder {
+            input.set_placeholder(placeholder);
+        }
+        input
+    }
+}
@@ -0,0 +1,127 @@
+use std::ops::Range;
+use {
+    traits::Layout,
+    dom::{Dom, NodeType},
+    css_parser::{ParsedCssProperty, LayoutHeight, PixelValue, CssMetric},
+};
+
+/// ID of the dynamic CSS property that sets the height of the spacers of a `VirtualList`.
+/// The native styles use it for the `.__azul-native-virtual-list-spacer` class, custom styles
+/// have to declare `height: [[ __azul_virtual_list_spacer_height | 0px ]]` for this class.
+pub const VIRTUAL_LIST_SPACER_HEIGHT_ID: &str = "__azul_virtual_list_spacer_height";
+
+/// A list of a large number of items with a fixed (or estimated) height,
+/// where only the visible items are turned into DOM nodes.
+///
+/// The items above and below the viewport are replaced by two spacer divs,
+/// so that the list still has its full height. When the list is scrolled,
+/// the new `scroll_offset` has to be set and the DOM has to be re-created.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct VirtualList {
+    /// Total number of items in the list
+    pub item_count: usize,
+    /// Height of a single item, in pixels
+    pub item_height: f32,
+    /// Height of the visible part of the list, in pixels
+    pub viewport_height: f32,
+    /// How far the list is scrolled down, in pixels
+    pub scroll_offset: f32,
+}
+
+impl VirtualList {
+    pub fn new(item_count: usize, item_height: f32, viewport_height: f32)
+    -> Self
+    {
+        Self {
+            item_count: item_count,
+            item_height: item_height,
+            viewport_height: viewport_height,
+            scroll_offset: 0.0,
+        }
+    }
+
+    pub fn with_scroll_offset(self, scroll_offset: f32)
+    -> Self
+    {
+        Self {
+            scroll_offset: scroll_offset,
+            .. self
+        }
+    }
+
+    /// Returns the indices of the items that are (at least partially) visible.
+    /// The scroll offset is clamped, so that the list can't be scrolled past its end.
+    pub fn visible_range(&self) -> Range<usize> {
+        if self.item_count == 0 || self.item_height <= 0.0 {
+            return 0..0;
+        }
+
+        let max_scroll_offset = (self.item_count as f32 * self.item_height - self.viewport_height).max(0.0);
+        let scroll_offset = self.scroll_offset.max(0.0).min(max_scroll_offset);
+
+        let first = (scroll_offset / self.item_height).floor() as usize;
+        let last = ((scroll_offset + self.viewport_height) / self.item_height).ceil() as usize;
+        first.min(self.item_count)..last.min(self.item_count)
+    }
+
+    /// Creates the DOM of the list: a spacer for the items above the viewport,
+    /// the visible items (created by `render_item`, which gets the index of the item)
+    /// and a spacer for the items below the viewport.
+    pub fn dom<T, F>(self, render_item: F)
+    -> Dom<T> where T: Layout, F: Fn(usize) -> Dom<T>
+    {
+        let visible_range = self.visible_range();
+        let height_above = visible_range.start as f32 * self.item_height;
+        let height_below = (self.item_count - visible_range.end) as f32 * self.item_height;
+
+        let mut list = Dom::new(NodeType::Div).with_class("__azul-native-virtual-list");
+        list.add_child(spacer(height_above));
+        for index in visible_range {
+            list.add_child(render_item(index));
+        }
+        list.add_child(spacer(height_below));
+        list
+    }
+}
+
+/// Empty div that takes up the space of the items that aren't rendered
+fn spacer<T: Layout>(height: f32) -> Dom<T> {
+    let height = ParsedCssProperty::Height(LayoutHeight(PixelValue::from_metric(CssMetric::Px, height)));
+    Dom::new(NodeType::Div)
+        .with_class("__azul-native-virtual-list-spacer")
+        .with_css_override(VIRTUAL_LIST_SPACER_HEIGHT_ID, height)
+}
+
+#[test]
+fn test_virtual_list_only_creates_visible_items() {
+
+    use test_utils::TestLayout;
+    let spacer_height = |px: f32| ParsedCssProperty::Height(LayoutHeight(PixelValue::from_metric(CssMetric::Px, px)));
+
+    // 10k items of 20px each, 25 of them fit into the 500px viewport
+    let list = VirtualList::new(10_000, 20.0, 500.0).with_scroll_offset(1000.0);
+    assert_eq!(list.visible_range(), 50..75);
+
+    let dom: Dom<TestLayout> = list.dom(|index| Dom::label(format!("Item {}", index)));
+
+    let mut items = Vec::new();
+    let mut spacer_heights = Vec::new();
+    dom.visit(|_, node| {
+        if let NodeType::Label(ref text) = node.node_type {
+            items.push(text.clone());
+        }
+        if let Some(height) = node.dynamic_css_overrides.get(VIRTUAL_LIST_SPACER_HEIGHT_ID) {
+            spacer_heights.push(height.clone());
+        }
+    });
+
+    assert_eq!(items.len(), 25);
+    assert_eq!(items.first(), Some(&String::from("Item 50")));
+    assert_eq!(items.last(), Some(&String::from("Item 74")));
+    // The spacers still take up the height of all the other items
+    assert_eq!(spacer_heights, vec![spacer_height(50.0 * 20.0), spacer_height(9925.0 * 20.0)]);
+
+    // Scrolling past the end shows the last items
+    let list = VirtualList::new(10_000, 20.0, 500.0).with_scroll_offset(1_000_000.0);
+    assert_eq!(list.visible_range(), 9975..10_000);
+}
@@ -14,7 +14,8 @@ use glium::{
     IncompatibleOpenGl, Display,
     debug::DebugCallbackBehavior,
     glutin::{self, EventsLoop, AvailableMonitorsIter, GlProfile, GlContext, GlWindow, CreationError,
-             MonitorId, EventsLoopProxy, ContextError, ContextBuilder, WindowBuilder},
+             MonitorId, EventsLoopProxy, ContextError, ContextBuilder, WindowBuilder,
+             dpi::LogicalSize},
     backend::{Context, Facade, glutin::DisplayCreationError},
 };
 use gleam::gl::{self, Gl};
@@ -25,16 +26,25 @@ use cassowary::{
 };
 
 use {
-    dom::Texture,
+    dom::{Dom, Texture},
     css::{Css, FakeCss},
-    window_state::{WindowState, MouseState, KeyboardState},
-    display_list::SolvedLayout,
+    window_state::{WindowState, WindowSize, MouseState, KeyboardState},
+    display_list::{SolvedLayout, DisplayList, measure_content_size},
     traits::Layout,
     cache::{EditVariableCache, DomTreeCache},
     id_tree::NodeId,
     compositor::Compositor,
     app::FrameEventInfo,
     resources::AppResources,
+    transition::TransitionCache,
+    tooltip::{TooltipState, DEFAULT_TOOLTIP_DELAY},
+    double_click::{DoubleClickState, DEFAULT_DOUBLE_CLICK_INTERVAL},
+    scroll::ScrollStates,
+    resize::ResizeStates,
+    render_cache::RenderCache,
+    frame_stats::FrameStats,
+    spinner::SpinnerClock,
+    css_parser::LayoutDirection,
 };
 
 /// azul-internal ID for a window
@@ -59,6 +69,10 @@ pub struct FakeWindow {
     /// but not change any window properties from underneath - this would
     /// lead to mismatch between the
     pub(crate) read_only_window: Rc<Display>,
+    /// The frame times of the window, updated after every frame
+    pub(crate) frame_stats: FrameStats,
+    /// The region of the window that changed in the last frame, updated after every frame
+    pub(crate) dirty_rect: Option<LayoutRect>,
 }
 
 impl FakeWindow {
@@ -70,6 +84,22 @@ impl FakeWindow {
         }
     }
 
+    /// Returns how long it took to build the first display list of the
+    /// window (with cold caches) and the display lists after that
+    pub fn get_frame_stats(&self) -> FrameStats {
+        self.frame_stats
+    }
+
+    /// Returns the region of the window that changed in the last frame (the whole
+    /// window if the window was resized), or `None` if the last frame looked the
+    /// same as the frame before it
+    ///
+    /// This is only reported to the application: webrender (at the version that azul
+    /// uses) has no partial present, so the whole window is still rendered every frame.
+    pub fn get_dirty_rect(&self) -> Option<LayoutRect> {
+        self.dirty_rect
+    }
+
     pub(crate) fn set_keyboard_state(&mut self, kb: &KeyboardState) {
         self.state.keyboard_state = kb.clone();
     }
@@ -148,6 +178,9 @@ pub struct WindowInfo<'a> {
     pub window_id: WindowId,
     pub window: ReadOnlyWindow,
     pub resources: &'a AppResources<'a>,
+    /// Whether the text caret of the focused node should be drawn in this frame
+    /// (the caret blinks, the screen is redrawn every time it toggles)
+    pub is_caret_visible: bool,
 }
 
 impl fmt::Debug for FakeWindow {
@@ -177,6 +210,14 @@ pub struct WindowEvent {
     pub cursor_relative_to_item: (f32, f32),
     /// The (x, y) position of the mouse cursor, **relative to top left of the window**.
     pub cursor_in_viewport: (f32, f32),
+    /// The key (scancode, virtual key code and modifiers) that was pressed or released,
+    /// for `On::KeyDown` / `On::KeyUp` callbacks and shortcuts. `None` for all other events.
+    pub keyboard_input: Option<glutin::KeyboardInput>,
+    /// How far the mouse wheel / touchpad was scrolled, in lines or in pixels, for `On::Scroll`
+    /// callbacks. This is the delta as reported by the operating system, the part of the delta
+    /// that scrolled the container of the callback is in `MouseState::scroll_x / scroll_y`.
+    /// `None` for all other events.
+    pub scroll_delta: Option<glutin::MouseScrollDelta>,
 }
 
 impl WindowEvent {
@@ -187,6 +228,8 @@ impl WindowEvent {
             number_of_previous_siblings: None,
             cursor_relative_to_item: (0.0, 0.0),
             cursor_in_viewport: (0.0, 0.0),
+            keyboard_input: None,
+            scroll_delta: None,
         }
     }
 }
@@ -196,7 +239,7 @@ impl WindowEvent {
 pub struct WindowCreateOptions {
     /// State of the window, set the initial title / width / height here.
     pub state: WindowState,
-    /// OpenGL clear color
+    /// OpenGL clear color, only used if the root node of the DOM has no `background-color`
     pub background: ColorF,
     /// Clear the stencil buffer with the given value. If not set, stencil buffer is not cleared
     pub clear_stencil: Option<i32>,
@@ -214,6 +257,10 @@ pub struct WindowCreateOptions {
     pub update_behaviour: UpdateBehaviour,
     /// Renderer type: Hardware-with-software-fallback, pure software or pure hardware renderer?
     pub renderer_type: RendererType,
+    /// How long the mouse has to hover over a node with a `title` until the tooltip is shown
+    pub tooltip_delay: Duration,
+    /// Maximum time between the two clicks of a double click (`On::DoubleClick`), 400ms by default
+    pub double_click_interval: Duration,
 }
 
 impl Default for WindowCreateOptions {
@@ -228,6 +275,8 @@ impl Default for WindowCreateOptions {
             mouse_mode: MouseMode::default(),
             update_behaviour: UpdateBehaviour::default(),
             renderer_type: RendererType::default(),
+            tooltip_delay: DEFAULT_TOOLTIP_DELAY,
+            double_click_interval: DEFAULT_DOUBLE_CLICK_INTERVAL,
         }
     }
 }
@@ -459,6 +508,22 @@ pub struct Window<T: Layout> {
     // pub(crate) background_thread: Option<JoinHandle<()>>,
     /// The css (how the current window is styled)
     pub css: Css,
+    /// The state of the CSS transitions that are running in this window
+    pub(crate) transitions: TransitionCache,
+    /// The tooltip of the node that the mouse is hovering over
+    pub(crate) tooltip: TooltipState,
+    /// The last click, for detecting double clicks
+    pub(crate) double_click: DoubleClickState,
+    /// The rotation of the indeterminate spinners in this window
+    pub(crate) spinner_clock: SpinnerClock,
+    /// The scroll offsets of the scroll containers in this window
+    pub(crate) scroll_states: ScrollStates,
+    /// The sizes of the boxes that the user resized by dragging their resize grip
+    pub(crate) resize_states: ResizeStates,
+    /// The images of the subtrees that are painted with `Dom::with_cached_rendering`
+    pub(crate) render_cache: RenderCache,
+    /// The time it took to build the display lists of this window
+    pub(crate) frame_stats: FrameStats,
 }
 
 /// Used in the solver, for the root constraint
@@ -497,15 +562,167 @@ pub(crate) struct UiSolver<T: Layout> {
     pub(crate) edit_variable_cache: EditVariableCache,
     /// The cache of the previous frames DOM tree
     pub(crate) dom_tree_cache: DomTreeCache,
+    /// Region of the window that changed in the last frame (`None` if nothing changed),
+    /// see `display_list::get_dirty_rect` and `FakeWindow::get_dirty_rect`
+    pub(crate) dirty_rect: Option<LayoutRect>,
+}
+
+/// The solved layout of a window, see `UiSolver::dump_layout`.
+///
+/// The `Display` implementation prints one line per node (indented by the depth of the node),
+/// so the layout can be stored in a file and compared between changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutTree {
+    /// All nodes of the DOM, in tree order (parents before their children)
+    pub nodes: Vec<LayoutTreeNode>,
+}
+
+/// A single node of a `LayoutTree`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutTreeNode {
+    pub node_id: NodeId,
+    /// How many ancestors the node has (0 for the root node)
+    pub depth: usize,
+    /// The CSS type of the node, i.e. `div` or `p`
+    pub node_type: &'static str,
+    /// The solved bounds of the node
+    pub rect: LayoutRect,
+}
+
+/// Position and size of a flex item along the main axis of its parent
+/// (the horizontal axis in a row, the vertical axis in a column), see `UiSolver::query_flex_items`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FlexItemLayout {
+    pub node_id: NodeId,
+    /// Offset of the item from the start of the parent
+    pub main_axis_offset: f32,
+    /// Solved width (in a row) or height (in a column) of the item
+    pub main_axis_size: f32,
+}
+
+impl fmt::Display for LayoutTree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for node in &self.nodes {
+            writeln!(f, "{}{} #{}: {}x{} at ({}, {})",
+                "  ".repeat(node.depth), node.node_type, node.node_id,
+                node.rect.size.width, node.rect.size.height, node.rect.origin.x, node.rect.origin.y)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Layout> Default for UiSolver<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<T: Layout> UiSolver<T> {
-    pub(crate) fn query_bounds_of_rect(&self, rect_id: NodeId) {
-        // TODO: After solving the UI, use this function to get the actual coordinates of an item in the UI.
-        // This function should cache values accordingly
+
+    /// Creates a solver without any constraints, for a window that wasn't laid out yet
+    pub(crate) fn new() -> Self {
+        Self {
+            solver: Solver::new(),
+            solved_layout: SolvedLayout::empty(),
+            edit_variable_cache: EditVariableCache::empty(),
+            dom_tree_cache: DomTreeCache::empty(),
+            dirty_rect: None,
+        }
+    }
+
+    /// Returns the solved bounds of a node of the last frame,
+    /// `None` if the node wasn't laid out in the last frame
+    pub(crate) fn query_bounds_of_rect(&self, rect_id: NodeId) -> Option<LayoutRect> {
+        let arena = &self.dom_tree_cache.previous_layout.arena;
+        if rect_id.index() >= arena.nodes_len() {
+            return None;
+        }
+
+        let dom_hash = arena[rect_id].data;
+        let display_rect = self.edit_variable_cache.map.get(&dom_hash)?.1;
+
+        Some(LayoutRect::new(
+            LayoutPoint::new(self.solver.get_value(display_rect.left) as f32, self.solver.get_value(display_rect.top) as f32),
+            LayoutSize::new(self.solver.get_value(display_rect.width) as f32, self.solver.get_value(display_rect.height) as f32)))
+    }
+
+    /// Returns the solved bounds of all nodes of the last frame, i.e. for golden-file tests
+    /// or for comparing the layout before and after a change.
+    ///
+    /// NOTE: Currently siblings are only placed next to each other in `justify-content: center`
+    /// containers, where they are separated by the `gap` and centered along the main axis. All
+    /// other rectangles are placed at the top left corner of the content box of their parent,
+    /// inset by their `margin`, so their siblings overlap them.
+    pub fn dump_layout(&self) -> LayoutTree {
+        let arena = &self.dom_tree_cache.previous_layout.arena;
+
+        let root = match self.dom_tree_cache.previous_layout.root {
+            Some(root) => root,
+            None => return LayoutTree { nodes: Vec::new() },
+        };
+
+        let nodes = root.descendants(arena).filter_map(|node_id| {
+            let node_data = self.solved_layout.solved_constraints.get(&node_id)?;
+            Some(LayoutTreeNode {
+                node_id: node_id,
+                // `ancestors` includes the node itself
+                depth: node_id.ancestors(arena).count() - 1,
+                node_type: node_data.node_type.get_css_id(),
+                rect: self.query_bounds_of_rect(node_id)?,
+            })
+        }).collect();
+
+        LayoutTree { nodes }
+    }
+
+    /// Returns the main-axis offsets and sizes of the children of the flex container `parent`
+    /// of the last frame, in DOM order - i.e. for finding the index where an item that is dragged
+    /// within the container should be inserted. Returns `None` if the node wasn't laid out in
+    /// the last frame.
+    ///
+    /// The offsets are read from the solved positions of the items, so they only reflect
+    /// the `gap` of the container where the items are placed next to each other (see the
+    /// NOTE on `dump_layout`).
+    pub fn query_flex_items(&self, parent: NodeId) -> Option<Vec<FlexItemLayout>> {
+        let arena = &self.dom_tree_cache.previous_layout.arena;
+        self.solved_layout.solved_constraints.get(&parent)?;
+
+        let is_column = self.solved_layout.flex_directions.get(&parent) == Some(&LayoutDirection::Vertical);
+        let parent_rect = self.query_bounds_of_rect(parent)?;
+
+        parent.children(arena).map(|child| {
+            let rect = self.query_bounds_of_rect(child)?;
+            let (main_axis_offset, main_axis_size) = if is_column {
+                (rect.origin.y - parent_rect.origin.y, rect.size.height)
+            } else {
+                (rect.origin.x - parent_rect.origin.x, rect.size.width)
+            };
+            Some(FlexItemLayout {
+                node_id: child,
+                main_axis_offset: main_axis_offset,
+                main_axis_size: main_axis_size,
+            })
+        }).collect()
     }
 }
 
+/// Returns the size that the `dom` has when it is styled with the `css` and laid out inside
+/// of the `available` size, without adding it to the layout of a window - i.e. to calculate
+/// the heights of the items of a virtualized list.
+///
+/// The `dom` is laid out with the same constraints as the DOM of a window, but if its root
+/// node has no `width` / `height`, the root is only as large as its content (the margin
+/// boxes of its children plus its padding). The returned size includes the margin of the root.
+/// The `app_resources` contain the fonts of the text nodes.
+pub fn measure_subtree<T: Layout>(dom: &Dom<T>, css: &Css, app_resources: &mut AppResources, available: LogicalSize) -> LayoutSize {
+    let ui_description = T::style_dom(dom, css);
+    let display_list = DisplayList::new_from_ui_description(&ui_description);
+    let window_size = WindowSize { dimensions: available, .. Default::default() };
+    let mut ui_solver = UiSolver::new();
+    display_list.solve_layout(&mut ui_solver, css, app_resources, true, &window_size);
+    measure_content_size(&display_list, &ui_solver)
+}
+
 pub(crate) struct WindowInternal {
     pub(crate) last_display_list_builder: BuiltDisplayList,
     pub(crate) api: RenderApi,
@@ -681,6 +898,14 @@ impl<T: Layout> Window<T> {
             renderer: Some(renderer),
             display: Rc::new(display),
             css: css,
+            transitions: TransitionCache::default(),
+            tooltip: TooltipState::new(options.tooltip_delay),
+            double_click: DoubleClickState::new(options.double_click_interval),
+            spinner_clock: SpinnerClock::default(),
+            scroll_states: ScrollStates::default(),
+            resize_states: ResizeStates::default(),
+            render_cache: RenderCache::default(),
+            frame_stats: FrameStats::default(),
             internal: WindowInternal {
                 api: api,
                 epoch: epoch,
@@ -690,9 +915,7 @@ impl<T: Layout> Window<T> {
             },
             solver: UiSolver {
                 solver: solver,
-                solved_layout: SolvedLayout::empty(),
-                edit_variable_cache: EditVariableCache::empty(),
-                dom_tree_cache: DomTreeCache::empty(),
+                .. UiSolver::new()
             }
         };
 
@@ -767,6 +990,9 @@ impl<T: Layout> Window<T> {
             window.set_max_dimensions(new_state.size.max_dimensions.and_then(|dim| Some(dim.into())));
             old_state.size.max_dimensions = new_state.size.max_dimensions;
         }
+
+        // The platform doesn't report any insets on desktop, but they can be set by the user
+        old_state.size.safe_area_insets = new_state.size.safe_area_insets;
     }
 
     pub(crate) fn update_from_external_window_state(&mut self, frame_event_info: &mut FrameEventInfo) {
@@ -815,4 +1041,162 @@ impl<T: Layout> Drop for Window<T> {
 #[test]
 fn __codecov_test_window_file() {
 
+}
+
+#[test]
+fn test_measure_subtree_of_column() {
+
+    use test_utils::TestLayout;
+
+    let css = Css::new_from_string("
+        #list { flex-direction: column; justify-content: center; gap: 10px; padding: 5px; }
+        #first { width: 100px; height: 30px; }
+        #second { width: 150px; height: 50px; margin-left: 20px; }
+    ").unwrap();
+
+    let dom = Dom::<TestLayout>::div().with_id("list")
+        .with_child(Dom::div().with_id("first"))
+        .with_child(Dom::div().with_id("second"));
+
+    let size = measure_subtree(&dom, &css, &mut AppResources::without_system_resources(), LogicalSize::new(800.0, 600.0));
+
+    // The column is as high as its items plus the gap between them and as wide as the
+    // widest item (including its margin), plus the padding on both sides
+    assert!((size.height - (30.0 + 10.0 + 50.0 + 2.0 * 5.0)).abs() < 0.01);
+    assert!((size.width - (20.0 + 150.0 + 2.0 * 5.0)).abs() < 0.01);
+}
+
+#[test]
+fn test_measure_subtree_with_margin() {
+
+    use test_utils::TestLayout;
+
+    let css = Css::new_from_string("
+        .item { width: 300px; height: 40px; margin: 5px; }
+    ").unwrap();
+
+    let dom = Dom::<TestLayout>::div().with_class("item")
+        .with_child(Dom::div());
+
+    // A node with a width / height doesn't grow to its content, but its margin is added
+    let size = measure_subtree(&dom, &css, &mut AppResources::without_system_resources(), LogicalSize::new(800.0, 600.0));
+    assert!((size.width - 310.0).abs() < 0.01);
+    assert!((size.height - 50.0).abs() < 0.01);
+}
+
+#[test]
+fn test_dump_solved_layout() {
+
+    use test_utils::{TestLayout, solve};
+
+    let mut css = Css::new_from_string("
+        #root { width: 400px; height: 300px; }
+        #first { width: 100px; height: 50px; }
+        #second { width: 200px; height: 80px; }
+    ").unwrap();
+
+    let dom = Dom::<TestLayout>::div().with_id("root")
+        .with_child(Dom::div().with_id("first"))
+        .with_child(Dom::div().with_id("second"));
+
+    let layout = solve(&dom, &mut css).dump_layout();
+    let rect = |w, h| LayoutRect::new(LayoutPoint::zero(), LayoutSize::new(w, h));
+
+    assert_eq!(layout.nodes.iter().map(|n| (n.node_id.index(), n.depth, n.node_type)).collect::<Vec<_>>(),
+               vec![(0, 0, "div"), (1, 1, "div"), (2, 1, "div")]);
+    assert_eq!(layout.nodes.iter().map(|n| n.rect).collect::<Vec<_>>(),
+               vec![rect(400.0, 300.0), rect(100.0, 50.0), rect(200.0, 80.0)]);
+
+    assert_eq!(layout.to_string(), "div #0: 400x300 at (0, 0)\n  div #1: 100x50 at (0, 0)\n  div #2: 200x80 at (0, 0)\n");
+}
+
+#[test]
+fn test_query_flex_items_of_row() {
+
+    use test_utils::{TestLayout, solve};
+
+    let mut css = Css::new_from_string("
+        #row { flex-direction: row; justify-content: center; width: 600px; height: 100px; }
+        #first { width: 100px; height: 50px; }
+        #second { width: 200px; height: 50px; }
+        #third { width: 150px; height: 50px; }
+    ").unwrap();
+
+    let dom = Dom::<TestLayout>::div().with_id("row")
+        .with_child(Dom::div().with_id("first"))
+        .with_child(Dom::div().with_id("second"))
+        .with_child(Dom::div().with_id("third"));
+
+    let ui_solver = solve(&dom, &mut css);
+
+    let items = ui_solver.query_flex_items(NodeId::new(0)).unwrap();
+    assert_eq!(items.iter().map(|item| item.node_id.index()).collect::<Vec<_>>(), vec![1, 2, 3]);
+
+    // The main-axis sizes are the solved widths, the items are placed one after another,
+    // starting 75px (half of the 150px of leftover space) into the container
+    for item in &items {
+        assert_eq!(item.main_axis_size, ui_solver.query_bounds_of_rect(item.node_id).unwrap().size.width);
+    }
+    assert_eq!(items.iter().map(|item| item.main_axis_offset).collect::<Vec<_>>(), vec![75.0, 175.0, 375.0]);
+
+    // The items have no children
+    assert_eq!(ui_solver.query_flex_items(NodeId::new(1)), Some(Vec::new()));
+    // The node doesn't exist
+    assert_eq!(ui_solver.query_flex_items(NodeId::new(10)), None);
+}
+
+#[test]
+fn test_query_flex_items_with_percentage_gap() {
+
+    use test_utils::{TestLayout, solve};
+
+    let mut css = Css::new_from_string("
+        #row { flex-direction: row; justify-content: center; width: 200px; height: 100px; gap: 10%; }
+        #first { width: 50px; height: 50px; }
+        #second { width: 30px; height: 50px; }
+        #third { width: 40px; height: 50px; }
+    ").unwrap();
+
+    let dom = Dom::<TestLayout>::div().with_id("row")
+        .with_child(Dom::div().with_id("first"))
+        .with_child(Dom::div().with_id("second"))
+        .with_child(Dom::div().with_id("third"));
+
+    let ui_solver = solve(&dom, &mut css);
+
+    assert_eq!(ui_solver.query_bounds_of_rect(NodeId::new(0)).unwrap().size.width, 200.0);
+
+    // 10% of the 200px wide container = 20px between the items, which leaves 20px of space
+    // on each side
+    let items = ui_solver.query_flex_items(NodeId::new(0)).unwrap();
+    assert_eq!(items.iter().map(|item| item.main_axis_offset).collect::<Vec<_>>(), vec![20.0, 90.0, 140.0]);
+}
+
+#[test]
+fn test_justify_content_center_splits_leftover_space_equally() {
+
+    use test_utils::{TestLayout, solve};
+
+    let mut css = Css::new_from_string("
+        #row { flex-direction: row; justify-content: center; width: 300px; height: 100px; gap: 10px; }
+        #first { width: 50px; height: 50px; }
+        #second { width: 70px; height: 50px; }
+    ").unwrap();
+
+    let dom = Dom::<TestLayout>::div().with_id("row")
+        .with_child(Dom::div().with_id("first"))
+        .with_child(Dom::div().with_id("second"));
+
+    let ui_solver = solve(&dom, &mut css);
+
+    let row = ui_solver.query_bounds_of_rect(NodeId::new(0)).unwrap();
+    let first = ui_solver.query_bounds_of_rect(NodeId::new(1)).unwrap();
+    let second = ui_solver.query_bounds_of_rect(NodeId::new(2)).unwrap();
+
+    // 300px - 50px - 10px - 70px = 170px of leftover space, 85px on each side
+    let leading_space = first.origin.x - row.origin.x;
+    let trailing_space = row.max_x() - second.max_x();
+    assert_eq!(leading_space, 85.0);
+    assert_eq!(trailing_space, 85.0);
+    assert_eq!(second.origin.x - first.max_x(), 10.0);
 }
\ No newline at end of file
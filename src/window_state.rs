@@ -10,6 +10,7 @@ use std::collections::HashSet;
 use {
     dom::On,
     menu::{ApplicationMenu, ContextMenu},
+    css_parser::SafeAreaInsets,
 };
 
 const DEFAULT_TITLE: &str = "Azul App";
@@ -131,6 +132,9 @@ pub struct WindowSize {
     pub min_dimensions: Option<LogicalSize>,
     /// Maximum dimensions of the window
     pub max_dimensions: Option<LogicalSize>,
+    /// Area of the window that is covered by system UI (notches, status bars), available
+    /// in CSS as `env(safe-area-inset-top)`, etc. - always zero on desktop platforms
+    pub safe_area_insets: SafeAreaInsets,
 }
 
 impl Default for WindowSize {
@@ -140,6 +144,7 @@ impl Default for WindowSize {
             hidpi_factor: 1.0,
             min_dimensions: None,
             max_dimensions: None,
+            safe_area_insets: SafeAreaInsets::default(),
         }
     }
 }